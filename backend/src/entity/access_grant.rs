@@ -0,0 +1,28 @@
+use sea_orm::entity::prelude::*;
+
+/// Time-limited, read-only access for an external accountant to one workspace.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "access_grant")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Uuid,
+    pub workspace_id: Uuid,
+    /// Address the access link was sent to.
+    pub email: String,
+    /// SHA-256 of the bearer token sent to `email`.
+    #[sea_orm(unique)]
+    pub token_hash: String,
+    /// Only documents dated within `period_start..=period_end` are visible.
+    pub period_start: Date,
+    pub period_end: Date,
+    pub expires_at: DateTimeUtc,
+    pub revoked_at: Option<DateTimeUtc>,
+    pub last_used_at: Option<DateTimeUtc>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
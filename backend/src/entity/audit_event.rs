@@ -0,0 +1,21 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "audit_event")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Uuid,
+    /// Unset when the event could not be tied to an account, e.g. a login for an unknown email.
+    pub user_id: Option<Uuid>,
+    pub event: String,
+    pub ip_address: Option<String>,
+    pub detail: Option<String>,
+    /// Set for reads made through an accountant access grant.
+    pub access_grant_id: Option<Uuid>,
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
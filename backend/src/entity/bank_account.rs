@@ -0,0 +1,23 @@
+use sea_orm::entity::prelude::*;
+
+/// A bank account of the workspace that statements are imported into.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "bank_account")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Uuid,
+    pub workspace_id: Uuid,
+    pub name: String,
+    /// Stored without spaces, upper case.
+    pub iban: Option<String>,
+    pub bic: Option<String>,
+    pub currency: String,
+    /// Default column mapping for CSV imports, a serialized `CsvMapping`.
+    pub csv_mapping: Option<Json>,
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,25 @@
+use sea_orm::entity::prelude::*;
+
+/// One uploaded statement file and how many of its bookings were new.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "bank_import")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Uuid,
+    pub workspace_id: Uuid,
+    pub bank_account_id: Uuid,
+    /// Who uploaded the file.
+    pub user_id: Uuid,
+    /// `camt053`, `mt940`, `ofx` or `csv`.
+    pub format: String,
+    pub filename: String,
+    pub imported_count: i32,
+    /// Bookings skipped because an earlier import already contained them.
+    pub duplicate_count: i32,
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
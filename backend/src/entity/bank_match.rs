@@ -0,0 +1,30 @@
+use sea_orm::entity::prelude::*;
+
+/// A proposed, accepted or rejected link between a bank transaction and the invoice it pays
+/// or the expense it paid.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "bank_match")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Uuid,
+    pub workspace_id: Uuid,
+    pub bank_transaction_id: Uuid,
+    /// Set for incoming transactions.
+    pub invoice_id: Option<Uuid>,
+    /// Set for outgoing transactions.
+    pub expense_id: Option<Uuid>,
+    /// Between 0 and 1.
+    pub confidence: f64,
+    /// What the match is based on, a list of strings.
+    pub reasons: Json,
+    /// `proposed`, `accepted` or `rejected`.
+    pub status: String,
+    pub decided_by: Option<Uuid>,
+    pub decided_at: Option<DateTimeUtc>,
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,32 @@
+use sea_orm::entity::prelude::*;
+
+/// A booking on a bank account, as imported from a statement.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "bank_transaction")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Uuid,
+    pub workspace_id: Uuid,
+    pub bank_account_id: Uuid,
+    pub import_id: Uuid,
+    pub booking_date: Date,
+    pub value_date: Option<Date>,
+    /// Positive for incoming, negative for outgoing payments.
+    pub amount: f64,
+    pub currency: String,
+    /// The payer of incoming and the payee of outgoing payments.
+    pub counterparty_name: Option<String>,
+    pub counterparty_iban: Option<String>,
+    /// Purpose of payment (Verwendungszweck).
+    pub remittance_info: Option<String>,
+    /// Reference assigned by the bank or the payer, e.g. an end-to-end id.
+    pub reference: Option<String>,
+    /// Identifies the booking across imports; unique per account.
+    pub fingerprint: String,
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
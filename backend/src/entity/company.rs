@@ -6,6 +6,7 @@ pub struct Model {
     #[sea_orm(primary_key)]
     pub id: Uuid,
     pub user_id: Option<Uuid>,
+    pub workspace_id: Option<Uuid>,
     pub name: String,
     pub address: String,
     pub registration_number: String,
@@ -6,13 +6,47 @@ pub struct Model {
     #[sea_orm(primary_key)]
     pub id: Uuid,
     pub user_id: Uuid,
+    pub workspace_id: Uuid,
     pub vendor: String,
     pub description: String,
+    /// Gross amount paid.
     pub amount: f64,
+    pub net_amount: Option<f64>,
+    /// VAT rate in percent; for reverse charge the rate the recipient self-assesses.
+    pub vat_rate: Option<f64>,
+    pub vat_amount: Option<f64>,
+    pub input_tax_deductible: bool,
+    /// The vendor charged no VAT and the tax is owed (and deducted) by the recipient.
+    pub reverse_charge: bool,
     pub currency: String,
     pub date: Date,
+    /// Name of `category_id`, kept in sync on rename for filtering and reports.
     pub category: Option<String>,
+    pub category_id: Option<Uuid>,
+    pub receipt_id: Option<Uuid>,
+    /// Link to an externally stored receipt, or a legacy public upload URL.
     pub receipt_url: Option<String>,
+    /// `receipt`, `mileage` or `per_diem`; the amount of the latter two is computed.
+    pub kind: String,
+    /// Mileage: start and destination of the trip.
+    pub trip_from: Option<String>,
+    pub trip_to: Option<String>,
+    pub distance_km: Option<f64>,
+    pub vehicle: Option<String>,
+    /// Mileage: rate applied, from the workspace's mileage rates.
+    pub rate_per_km: Option<f64>,
+    /// Per diem: ISO 3166 country code of the destination.
+    pub country: Option<String>,
+    /// Per diem: local times of leaving and coming back.
+    pub departure_at: Option<DateTime>,
+    pub return_at: Option<DateTime>,
+    /// Per diem: days away for 24 hours, and arrival, departure or single days over 8 hours.
+    pub full_days: Option<i32>,
+    pub partial_days: Option<i32>,
+    /// Per diem: meals paid by someone else, which reduce the allowance.
+    pub breakfasts_provided: Option<i32>,
+    pub lunches_provided: Option<i32>,
+    pub dinners_provided: Option<i32>,
     pub created_at: DateTimeUtc,
 }
 
@@ -0,0 +1,23 @@
+use sea_orm::entity::prelude::*;
+
+/// A workspace's expense category and where it is booked in the German standard charts of
+/// accounts.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "expense_category")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Uuid,
+    pub workspace_id: Uuid,
+    /// Unique per workspace, ignoring case.
+    pub name: String,
+    pub skr03_account: Option<String>,
+    pub skr04_account: Option<String>,
+    /// Share of the amount that is tax deductible, e.g. 70 for business meals.
+    pub deductible_percent: i32,
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
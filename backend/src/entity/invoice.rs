@@ -7,8 +7,10 @@ pub struct Model {
     pub id: Uuid,
     pub invoice_number: String,
     pub user_id: Option<Uuid>,
+    pub workspace_id: Option<Uuid>,
     pub company_id: Option<Uuid>,
     pub template_id: Option<Uuid>,
+    pub contact_id: Option<Uuid>,
     pub client_name: String,
     pub client_address: String,
     pub description: String,
@@ -17,6 +19,9 @@ pub struct Model {
     pub user_address: String,
     pub total_amount: f64,
     pub date: Date,
+    pub due_date: Option<Date>,
+    pub status: String,
+    pub sent_at: Option<DateTimeUtc>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
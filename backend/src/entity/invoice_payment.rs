@@ -0,0 +1,22 @@
+use sea_orm::entity::prelude::*;
+
+/// Money received for an invoice.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "invoice_payment")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Uuid,
+    pub workspace_id: Uuid,
+    pub invoice_id: Uuid,
+    /// The bank booking the payment was reconciled from.
+    pub bank_transaction_id: Option<Uuid>,
+    pub amount: f64,
+    pub currency: String,
+    pub paid_on: Date,
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,25 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "invoice_reminder")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Uuid,
+    pub invoice_id: Uuid,
+    pub level: i32,
+    pub name: String,
+    pub days_overdue: i32,
+    pub fee: f64,
+    pub interest: f64,
+    pub total_due: f64,
+    pub email_status: String,
+    pub email_error: Option<String>,
+    /// Delivery attempts so far; failed emails are retried up to a limit.
+    pub email_attempts: i32,
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
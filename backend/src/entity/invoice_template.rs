@@ -6,6 +6,7 @@ pub struct Model {
     #[sea_orm(primary_key)]
     pub id: Uuid,
     pub user_id: Uuid,
+    pub workspace_id: Uuid,
     pub name: String,
     pub html: String,
     pub created_at: DateTimeUtc,
@@ -0,0 +1,22 @@
+use sea_orm::entity::prelude::*;
+
+/// Per-kilometre allowance for a vehicle type, effective from `valid_from` until the next
+/// rate for the same vehicle.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "mileage_rate")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Uuid,
+    pub workspace_id: Uuid,
+    /// Lowercase, e.g. `car` or `motorcycle`.
+    pub vehicle: String,
+    pub rate_per_km: f64,
+    pub currency: String,
+    pub valid_from: Date,
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,7 +1,32 @@
+pub mod access_grant;
+pub mod api_token;
+pub mod audit_event;
+pub mod auth_token;
+pub mod bank_account;
+pub mod bank_match;
+pub mod bank_import;
+pub mod bank_transaction;
 pub mod company;
+pub mod company_contact;
+pub mod dunning_level;
+pub mod email_template;
 pub mod expense;
+pub mod expense_category;
 pub mod invoice;
+pub mod invoice_delivery;
 pub mod invoice_line_item;
+pub mod invoice_payment;
+pub mod invoice_reminder;
 pub mod invoice_template;
+pub mod login_throttle;
+pub mod mileage_rate;
+pub mod oidc_login;
+pub mod per_diem_rate;
+pub mod receipt;
+pub mod recovery_code;
 pub mod session;
 pub mod user;
+pub mod user_identity;
+pub mod workspace;
+pub mod workspace_invitation;
+pub mod workspace_member;
@@ -0,0 +1,22 @@
+use sea_orm::entity::prelude::*;
+
+/// A pending authorization-code login, consumed by the provider's callback.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "oidc_login")]
+pub struct Model {
+    /// SHA-256 of the `state` parameter sent to the provider.
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub state_hash: String,
+    pub provider: String,
+    /// PKCE verifier; only its S256 challenge leaves the server.
+    pub code_verifier: String,
+    pub nonce: String,
+    /// Frontend path to open after signing in
+    pub redirect_to: String,
+    pub expires_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,25 @@
+use sea_orm::entity::prelude::*;
+
+/// Daily meal allowance for trips to a country, effective from `valid_from` until the next
+/// rate for the same country.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "per_diem_rate")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Uuid,
+    pub workspace_id: Uuid,
+    /// ISO 3166 alpha-2 code, uppercase.
+    pub country: String,
+    /// Allowance for a day away for the full 24 hours.
+    pub full_day: f64,
+    /// Allowance for the arrival and departure days, and single-day trips over 8 hours.
+    pub partial_day: f64,
+    pub currency: String,
+    pub valid_from: Date,
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,28 @@
+use sea_orm::entity::prelude::*;
+
+/// An uploaded receipt file. The bytes live in receipt storage under `storage_key`.
+/// Direct uploads stay pending, without size and hash, until they are confirmed.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "receipt")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Uuid,
+    pub workspace_id: Uuid,
+    /// Who uploaded the file.
+    pub user_id: Uuid,
+    pub storage_key: String,
+    /// Original file name, used for downloads only.
+    pub filename: String,
+    /// Detected from the file contents, not taken from the client.
+    pub content_type: String,
+    pub size_bytes: Option<i64>,
+    /// Hex-encoded SHA-256 of the contents.
+    pub sha256: Option<String>,
+    pub confirmed_at: Option<DateTimeUtc>,
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -11,6 +11,14 @@ pub struct Model {
     pub address: Option<String>,
     pub company_id: Option<Uuid>,
     pub created_at: DateTimeUtc,
+    pub email_verified_at: Option<DateTimeUtc>,
+    /// Hex-encoded TOTP secret; set during enrollment, before `totp_enabled_at`.
+    pub totp_secret: Option<String>,
+    pub totp_enabled_at: Option<DateTimeUtc>,
+    /// Last accepted TOTP time step, so a code cannot be replayed.
+    pub totp_last_step: Option<i64>,
+    /// Set when the account was deleted; the row is kept, anonymised, for invoice references.
+    pub deleted_at: Option<DateTimeUtc>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
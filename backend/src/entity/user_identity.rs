@@ -0,0 +1,23 @@
+use sea_orm::entity::prelude::*;
+
+/// A login at an external OpenID Connect provider, linked to a local user.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "user_identity")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Uuid,
+    pub user_id: Uuid,
+    /// Provider id from `OIDC_PROVIDERS`
+    pub provider: String,
+    /// The provider's stable `sub` claim; unique per provider.
+    pub subject: String,
+    /// Email reported by the provider at the last login
+    pub email: Option<String>,
+    pub created_at: DateTimeUtc,
+    pub last_login_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
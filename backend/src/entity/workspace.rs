@@ -0,0 +1,20 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "workspace")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Uuid,
+    pub name: String,
+    pub created_at: DateTimeUtc,
+    /// Set when the last member deleted their account but fiscal records must be kept.
+    /// Archived workspaces have no members and are read only.
+    pub archived_at: Option<DateTimeUtc>,
+    /// Archived workspaces are purged after this day.
+    pub retain_until: Option<Date>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,4 +1,4 @@
-use axum::{routing::{get, post}, Router};
+use axum::{extract::DefaultBodyLimit, routing::{get, post}, Router};
 use migration::{Migrator, MigratorTrait};
 use sea_orm::Database;
 use std::net::SocketAddr;
@@ -10,35 +10,158 @@ mod entity;
 mod migration;
 mod modules;
 
+use modules::access_grants::{
+    __path_create_access_grant, __path_get_accountant_grant, __path_get_accountant_invoice_pdf,
+    __path_get_accountant_report, __path_list_access_grant_events, __path_list_access_grants,
+    __path_get_accountant_receipt, __path_list_accountant_expenses,
+    __path_list_accountant_invoices, __path_revoke_access_grant, create_access_grant,
+    get_accountant_grant, get_accountant_invoice_pdf, get_accountant_receipt,
+    get_accountant_report, list_access_grant_events, list_access_grants, list_accountant_expenses,
+    list_accountant_invoices, revoke_access_grant, AccessGrantEventResponse, AccessGrantResponse,
+    AccountantGrantResponse, AccountantReportResponse, CategoryTotal, CreateAccessGrantRequest,
+    CurrencyTotal, KindTotal,
+};
+use modules::account::{
+    __path_change_password, __path_request_password_reset, __path_reset_password,
+    __path_send_email_verification, __path_verify_email, change_password,
+    request_password_reset, reset_password, send_email_verification, verify_email,
+    ChangePasswordRequest, PasswordResetConfirmRequest, PasswordResetRequest, TokenRequest,
+};
+use modules::api_tokens::{
+    __path_create_api_token, __path_list_api_tokens, __path_revoke_api_token, create_api_token,
+    list_api_tokens, revoke_api_token, ApiScope, ApiTokenResponse, CreateApiTokenRequest,
+    CreatedApiTokenResponse,
+};
 use modules::auth::{
     __path_login, __path_logout, __path_me, __path_register, __path_update_profile, login,
-    logout, me, register, update_profile, LoginRequest, RegisterRequest, SessionResponse,
-    UpdateProfileRequest, UserResponse,
+    logout, me, register, session_cookie_renewal, update_profile, LoginRequest, RegisterRequest,
+    SessionResponse, UpdateProfileRequest, UserResponse,
 };
 use modules::ai::{
     __path_improve_line_item, __path_last_line_item, improve_line_item, last_line_item,
     ImproveLineItemRequest, ImproveLineItemResponse, LastLineItemResponse,
 };
+use modules::banking::{
+    __path_create_bank_account, __path_delete_bank_account, __path_import_bank_statement,
+    __path_list_bank_accounts, __path_list_bank_imports, __path_list_bank_transactions,
+    __path_update_bank_account, create_bank_account, delete_bank_account, import_bank_statement,
+    list_bank_accounts, list_bank_imports, list_bank_transactions, max_statement_bytes,
+    update_bank_account, BankAccountCreateRequest, BankAccountResponse, BankAccountUpdateRequest,
+    BankImportForm, BankImportResponse, BankTransactionResponse, TransactionDirection,
+    TransactionSort,
+};
+use modules::bank_statements::{CsvMapping, StatementFormat};
+use modules::reconciliation::{
+    __path_accept_bank_match, __path_list_bank_matches, __path_list_invoice_payments,
+    __path_reject_bank_match, __path_run_reconciliation, accept_bank_match, list_bank_matches,
+    list_invoice_payments, reject_bank_match, run_reconciliation, BankMatchResponse,
+    InvoicePaymentResponse, MatchStatus, MatchedExpense, MatchedInvoice,
+    ReconciliationRunResponse,
+};
 use modules::company::{
-    __path_create_company, __path_get_my_company, __path_list_companies, __path_update_company,
-    create_company, get_my_company, list_companies, update_company, CompanyCreateRequest,
-    CompanyResponse, CompanyUpdateRequest,
+    __path_create_company, __path_create_contact, __path_delete_contact, __path_get_my_company,
+    __path_list_companies, __path_list_contacts, __path_update_company, __path_update_contact,
+    create_company, create_contact, delete_contact, get_my_company, list_companies,
+    list_contacts, update_company, update_contact, CompanyCreateRequest, CompanyResponse,
+    CompanySort, CompanyUpdateRequest, ContactCreateRequest, ContactResponse,
+    ContactUpdateRequest,
+};
+use modules::dunning::{
+    __path_get_reminder_pdf, __path_list_dunning_levels, __path_replace_dunning_levels,
+    __path_run_dunning_now, get_reminder_pdf, list_dunning_levels, replace_dunning_levels,
+    run_dunning_now, spawn_dunning_job, DunningLevelInput, DunningLevelResponse,
+    DunningRunResponse, ReminderResponse,
+};
+use modules::expense_categories::{
+    __path_create_expense_category, __path_delete_expense_category,
+    __path_list_expense_categories, __path_update_expense_category, create_expense_category,
+    delete_expense_category, list_expense_categories, update_expense_category,
+    ExpenseCategoryCreateRequest, ExpenseCategoryResponse, ExpenseCategoryUpdateRequest,
 };
 use modules::expenses::{
     __path_create_expense, __path_create_receipt_upload_url, __path_delete_expense,
     __path_list_expenses, __path_update_expense, create_expense, create_receipt_upload_url,
-    delete_expense, list_expenses, update_expense, ExpenseCreateRequest, ExpenseResponse,
-    ExpenseUpdateRequest, ReceiptUploadRequest, ReceiptUploadResponse,
+    delete_expense, list_expenses, update_expense, ExpenseCreateRequest, ExpenseKind,
+    ExpenseResponse, ExpenseSort, ExpenseUpdateRequest, ReceiptUploadRequest,
+    ReceiptUploadResponse,
 };
 use modules::invoices::{
-    __path_create_invoice, __path_get_invoice, __path_get_invoice_pdf, __path_list_invoices,
-    __path_update_invoice, __path_create_template, __path_list_templates, __path_update_template,
+    __path_create_invoice, __path_get_invoice, __path_get_invoice_pdf,
+    __path_get_invoice_recipients, __path_list_invoice_deliveries, __path_list_invoices,
+    __path_send_invoice, __path_update_invoice,
+    __path_create_template, __path_list_templates, __path_update_template,
     __path_delete_template, create_invoice, create_template, delete_template, get_invoice,
-    get_invoice_pdf, list_invoices, list_templates, update_invoice, update_template,
-    InvoiceResponse, LineItemInput, LineItemResponse, NewInvoice, TemplateCreateRequest,
-    TemplateResponse, UpdateInvoiceRequest,
+    get_invoice_pdf, get_invoice_recipients, list_invoice_deliveries, list_invoices,
+    list_templates, send_invoice, update_invoice, update_template, DeliveryResponse,
+    SendInvoiceRequest,
+    InvoiceResponse, InvoiceSort, LineItemInput, LineItemResponse, NewInvoice,
+    TemplateCreateRequest, TemplateResponse, TemplateSort, UpdateInvoiceRequest,
+};
+use modules::mail::{
+    __path_list_email_templates, __path_reset_email_template, __path_save_email_template,
+    list_email_templates, reset_email_template, save_email_template, EmailTemplateRequest,
+    EmailTemplateResponse, Mailer,
+};
+use modules::csrf::csrf_protection;
+use modules::oidc::{
+    __path_list_identities, __path_list_oidc_providers, __path_oidc_callback,
+    __path_start_oidc_login, __path_unlink_identity, list_identities, list_oidc_providers,
+    oidc_callback, start_oidc_login, unlink_identity, IdentityResponse, OidcProviderResponse,
+};
+use modules::privacy::{
+    __path_delete_account, __path_export_account, delete_account, export_account,
+    spawn_retention_purge_job, DeleteAccountRequest,
+};
+use modules::ocr::ocr_from_env;
+use modules::rate_limit::{rate_limit, spawn_rate_limit_cleanup, RateLimiter};
+use modules::receipt_extraction::{
+    __path_extract_receipt, extract_receipt, ExtractedAmount, ExtractedDate, ExtractedFields,
+    ExtractedText, ReceiptExtractionResponse,
+};
+use modules::receipts::{
+    __path_confirm_receipt, __path_delete_receipt, __path_download_receipt, __path_get_receipt,
+    __path_sweep_receipts, __path_upload_receipt, confirm_receipt, delete_receipt,
+    download_receipt, get_receipt, max_receipt_bytes, spawn_receipt_sweep_job, sweep_receipts,
+    upload_receipt, ReceiptResponse, ReceiptSweepReport, ReceiptSweepRequest, ReceiptUploadForm,
+    SweptReceipt,
+};
+use modules::sessions::{
+    __path_list_sessions, __path_logout_everywhere, __path_revoke_other_sessions,
+    __path_revoke_session, list_sessions, logout_everywhere, revoke_other_sessions,
+    revoke_session, spawn_session_purge_job, ActiveSessionResponse,
+};
+use modules::shared::{
+    client_ip, clone_db, frontend_origin, request_id, AppState, BankMatchPage, BankTransactionPage, CompanyPage, ErrorResponse, ExpensePage,
+    FieldError, InvoicePage, SortOrder, TemplatePage, REQUEST_ID_HEADER,
+};
+use modules::storage::storage_from_env;
+use modules::travel::{
+    __path_create_mileage_expense, __path_create_mileage_rate, __path_create_per_diem_expense,
+    __path_create_per_diem_rate, __path_delete_mileage_rate, __path_delete_per_diem_rate,
+    __path_list_mileage_rates, __path_list_per_diem_rates, __path_update_mileage_expense,
+    __path_update_per_diem_expense, create_mileage_expense, create_mileage_rate,
+    create_per_diem_expense, create_per_diem_rate, delete_mileage_rate, delete_per_diem_rate,
+    list_mileage_rates, list_per_diem_rates, update_mileage_expense, update_per_diem_expense,
+    MileageDetails, MileageExpenseRequest, MileageRateCreateRequest, MileageRateResponse,
+    PerDiemDetails, PerDiemExpenseRequest, PerDiemRateCreateRequest, PerDiemRateResponse,
+};
+use modules::two_factor::{
+    __path_complete_two_factor_login, __path_disable_two_factor, __path_enable_two_factor,
+    __path_regenerate_recovery_codes, __path_setup_two_factor, complete_two_factor_login,
+    disable_two_factor, enable_two_factor, regenerate_recovery_codes, setup_two_factor,
+    DisableTwoFactorRequest, RecoveryCodesResponse, TotpSetupResponse, TwoFactorChallengeResponse,
+    TwoFactorCodeRequest, TwoFactorLoginRequest,
+};
+use modules::workspaces::{
+    __path_accept_invitation, __path_create_workspace, __path_invite_member,
+    __path_list_invitations, __path_list_members, __path_list_workspaces, __path_remove_member,
+    __path_revoke_invitation, __path_update_member, __path_update_workspace, accept_invitation,
+    create_workspace, invite_member, list_invitations, list_members, list_workspaces,
+    remove_member, revoke_invitation, update_member, update_workspace, AcceptInvitationRequest,
+    InviteMemberRequest, UpdateMemberRequest, WorkspaceInvitationResponse,
+    WorkspaceMemberResponse, WorkspaceRequest, WorkspaceResponse, WorkspaceRole,
+    WORKSPACE_HEADER,
 };
-use modules::shared::AppState;
 
 #[derive(OpenApi)]
 #[openapi(
@@ -49,26 +172,117 @@ use modules::shared::AppState;
         get_invoice,
         update_invoice,
         get_invoice_pdf,
+        get_invoice_recipients,
+        send_invoice,
+        list_invoice_deliveries,
+        list_dunning_levels,
+        replace_dunning_levels,
+        run_dunning_now,
+        get_reminder_pdf,
         list_templates,
         create_template,
         update_template,
         delete_template,
+        list_email_templates,
+        save_email_template,
+        reset_email_template,
         create_company,
         update_company,
         get_my_company,
         list_companies,
+        list_contacts,
+        create_contact,
+        update_contact,
+        delete_contact,
         list_expenses,
         create_expense,
         update_expense,
         delete_expense,
         create_receipt_upload_url,
+        create_mileage_expense,
+        update_mileage_expense,
+        create_per_diem_expense,
+        update_per_diem_expense,
+        list_mileage_rates,
+        create_mileage_rate,
+        delete_mileage_rate,
+        list_per_diem_rates,
+        create_per_diem_rate,
+        delete_per_diem_rate,
+        list_expense_categories,
+        create_expense_category,
+        update_expense_category,
+        delete_expense_category,
+        upload_receipt,
+        get_receipt,
+        confirm_receipt,
+        extract_receipt,
+        download_receipt,
+        delete_receipt,
+        sweep_receipts,
+        list_bank_accounts,
+        create_bank_account,
+        update_bank_account,
+        delete_bank_account,
+        import_bank_statement,
+        list_bank_imports,
+        list_bank_transactions,
+        run_reconciliation,
+        list_bank_matches,
+        accept_bank_match,
+        reject_bank_match,
+        list_invoice_payments,
         improve_line_item,
         last_line_item,
         register,
         update_profile,
         login,
         logout,
-        me
+        me,
+        send_email_verification,
+        verify_email,
+        request_password_reset,
+        reset_password,
+        change_password,
+        setup_two_factor,
+        enable_two_factor,
+        disable_two_factor,
+        regenerate_recovery_codes,
+        complete_two_factor_login,
+        create_api_token,
+        list_api_tokens,
+        revoke_api_token,
+        list_sessions,
+        revoke_session,
+        revoke_other_sessions,
+        logout_everywhere,
+        export_account,
+        delete_account,
+        list_oidc_providers,
+        start_oidc_login,
+        oidc_callback,
+        list_identities,
+        unlink_identity,
+        list_workspaces,
+        create_workspace,
+        update_workspace,
+        list_members,
+        update_member,
+        remove_member,
+        invite_member,
+        list_invitations,
+        revoke_invitation,
+        accept_invitation,
+        create_access_grant,
+        list_access_grants,
+        revoke_access_grant,
+        list_access_grant_events,
+        get_accountant_grant,
+        list_accountant_invoices,
+        get_accountant_invoice_pdf,
+        list_accountant_expenses,
+        get_accountant_receipt,
+        get_accountant_report
     ),
     components(schemas(
         NewInvoice,
@@ -78,14 +292,63 @@ use modules::shared::AppState;
         UpdateInvoiceRequest,
         TemplateCreateRequest,
         TemplateResponse,
+        SendInvoiceRequest,
+        DeliveryResponse,
+        DunningLevelInput,
+        DunningLevelResponse,
+        DunningRunResponse,
+        ReminderResponse,
+        EmailTemplateRequest,
+        EmailTemplateResponse,
         CompanyCreateRequest,
         CompanyUpdateRequest,
         CompanyResponse,
+        ContactCreateRequest,
+        ContactUpdateRequest,
+        ContactResponse,
         ExpenseCreateRequest,
         ExpenseUpdateRequest,
         ExpenseResponse,
+        ExpenseKind,
+        MileageDetails,
+        PerDiemDetails,
+        MileageExpenseRequest,
+        PerDiemExpenseRequest,
+        MileageRateCreateRequest,
+        MileageRateResponse,
+        PerDiemRateCreateRequest,
+        PerDiemRateResponse,
+        ExpenseCategoryCreateRequest,
+        ExpenseCategoryUpdateRequest,
+        ExpenseCategoryResponse,
+        ReceiptUploadForm,
+        ReceiptResponse,
+        ReceiptSweepRequest,
+        ReceiptSweepReport,
+        SweptReceipt,
+        ReceiptExtractionResponse,
+        ExtractedFields,
+        ExtractedText,
+        ExtractedDate,
+        ExtractedAmount,
         ReceiptUploadRequest,
         ReceiptUploadResponse,
+        BankAccountCreateRequest,
+        BankAccountUpdateRequest,
+        BankAccountResponse,
+        CsvMapping,
+        StatementFormat,
+        BankImportForm,
+        BankImportResponse,
+        BankTransactionResponse,
+        TransactionDirection,
+        TransactionSort,
+        BankMatchResponse,
+        MatchStatus,
+        MatchedInvoice,
+        MatchedExpense,
+        InvoicePaymentResponse,
+        ReconciliationRunResponse,
         ImproveLineItemRequest,
         ImproveLineItemResponse,
         LastLineItemResponse,
@@ -93,14 +356,66 @@ use modules::shared::AppState;
         LoginRequest,
         UpdateProfileRequest,
         UserResponse,
-        SessionResponse
+        SessionResponse,
+        TokenRequest,
+        PasswordResetRequest,
+        PasswordResetConfirmRequest,
+        ChangePasswordRequest,
+        TotpSetupResponse,
+        RecoveryCodesResponse,
+        TwoFactorChallengeResponse,
+        TwoFactorCodeRequest,
+        TwoFactorLoginRequest,
+        DisableTwoFactorRequest,
+        ApiScope,
+        CreateApiTokenRequest,
+        ApiTokenResponse,
+        CreatedApiTokenResponse,
+        ActiveSessionResponse,
+        DeleteAccountRequest,
+        OidcProviderResponse,
+        IdentityResponse,
+        WorkspaceRole,
+        WorkspaceRequest,
+        WorkspaceResponse,
+        WorkspaceMemberResponse,
+        UpdateMemberRequest,
+        InviteMemberRequest,
+        WorkspaceInvitationResponse,
+        AcceptInvitationRequest,
+        CreateAccessGrantRequest,
+        AccessGrantResponse,
+        AccessGrantEventResponse,
+        AccountantGrantResponse,
+        CurrencyTotal,
+        CategoryTotal,
+        KindTotal,
+        AccountantReportResponse,
+        ErrorResponse,
+        FieldError,
+        SortOrder,
+        InvoiceSort,
+        TemplateSort,
+        ExpenseSort,
+        CompanySort,
+        InvoicePage,
+        TemplatePage,
+        ExpensePage,
+        CompanyPage,
+        BankTransactionPage,
+        BankMatchPage
     )),
     tags(
         (name = "health", description = "Health check"),
         (name = "invoices", description = "Invoice management"),
         (name = "auth", description = "Authentication"),
+        (name = "workspaces", description = "Workspaces, members and invitations"),
+        (name = "accountant", description = "Read-only access links for accountants"),
         (name = "company", description = "Company onboarding"),
         (name = "expenses", description = "Expense management"),
+        (name = "mail", description = "Outbound email templates"),
+        (name = "dunning", description = "Payment reminders and dunning"),
+        (name = "banking", description = "Bank accounts and imported statements"),
         (name = "ai", description = "AI helpers")
     )
 )]
@@ -116,6 +431,25 @@ async fn main() -> anyhow::Result<()> {
 
     Migrator::up(&db, None).await?;
 
+    let mailer = Mailer::from_env()?;
+    let rate_limiter = RateLimiter::from_env(&db)?;
+    spawn_rate_limit_cleanup(rate_limiter.clone(), clone_db(&db));
+    let receipts = storage_from_env()?;
+    println!("Receipt storage: {}", receipts.name());
+    let ocr = ocr_from_env()?;
+    println!("Receipt OCR: {}", ocr.name());
+    let state = AppState {
+        db,
+        mailer,
+        rate_limiter,
+        receipts,
+        ocr,
+    };
+    spawn_dunning_job(state.clone());
+    spawn_session_purge_job(clone_db(&state.db));
+    spawn_retention_purge_job(state.clone());
+    spawn_receipt_sweep_job(state.clone());
+
     let app = Router::new()
         .route("/", get(root))
         .route("/invoices", post(create_invoice))
@@ -123,19 +457,86 @@ async fn main() -> anyhow::Result<()> {
         .route("/invoices/:id", get(get_invoice))
         .route("/invoices/:id", axum::routing::patch(update_invoice))
         .route("/invoices/:id/pdf", get(get_invoice_pdf))
+        .route("/invoices/:id/recipients", get(get_invoice_recipients))
+        .route("/invoices/:id/send", post(send_invoice))
+        .route("/invoices/:id/deliveries", get(list_invoice_deliveries))
+        .route("/invoices/:id/payments", get(list_invoice_payments))
+        .route("/invoices/:id/reminders/:reminder_id/pdf", get(get_reminder_pdf))
+        .route("/dunning-levels", get(list_dunning_levels))
+        .route("/dunning-levels", axum::routing::put(replace_dunning_levels))
+        .route("/dunning/run", post(run_dunning_now))
         .route("/invoice-templates", get(list_templates))
         .route("/invoice-templates", post(create_template))
         .route("/invoice-templates/:id", axum::routing::patch(update_template))
         .route("/invoice-templates/:id", axum::routing::delete(delete_template))
+        .route("/email-templates", get(list_email_templates))
+        .route("/email-templates/:kind", axum::routing::put(save_email_template))
+        .route("/email-templates/:kind", axum::routing::delete(reset_email_template))
         .route("/company", post(create_company))
         .route("/company", axum::routing::patch(update_company))
         .route("/company", get(list_companies))
         .route("/company/me", get(get_my_company))
+        .route("/company/:id/contacts", get(list_contacts))
+        .route("/company/:id/contacts", post(create_contact))
+        .route("/company/:id/contacts/:contact_id", axum::routing::patch(update_contact))
+        .route("/company/:id/contacts/:contact_id", axum::routing::delete(delete_contact))
         .route("/expenses", get(list_expenses))
         .route("/expenses", post(create_expense))
         .route("/expenses/:id", axum::routing::patch(update_expense))
         .route("/expenses/:id", axum::routing::delete(delete_expense))
         .route("/expenses/receipt-url", post(create_receipt_upload_url))
+        .route("/expenses/mileage", post(create_mileage_expense))
+        .route("/expenses/per-diem", post(create_per_diem_expense))
+        .route("/expenses/:id/mileage", axum::routing::put(update_mileage_expense))
+        .route("/expenses/:id/per-diem", axum::routing::put(update_per_diem_expense))
+        .route("/travel-rates/mileage", get(list_mileage_rates))
+        .route("/travel-rates/mileage", post(create_mileage_rate))
+        .route(
+            "/travel-rates/mileage/:id",
+            axum::routing::delete(delete_mileage_rate),
+        )
+        .route("/travel-rates/per-diem", get(list_per_diem_rates))
+        .route("/travel-rates/per-diem", post(create_per_diem_rate))
+        .route(
+            "/travel-rates/per-diem/:id",
+            axum::routing::delete(delete_per_diem_rate),
+        )
+        .route(
+            "/receipts",
+            post(upload_receipt)
+                .layer(DefaultBodyLimit::max(max_receipt_bytes() + 64 * 1024)),
+        )
+        .route("/receipts/sweep", post(sweep_receipts))
+        .route("/receipts/:id", get(get_receipt))
+        .route("/receipts/:id", axum::routing::delete(delete_receipt))
+        .route("/receipts/:id/confirm", post(confirm_receipt))
+        .route("/receipts/:id/extract", post(extract_receipt))
+        .route("/receipts/:id/download", get(download_receipt))
+        .route("/expense-categories", get(list_expense_categories))
+        .route("/expense-categories", post(create_expense_category))
+        .route(
+            "/expense-categories/:id",
+            axum::routing::patch(update_expense_category),
+        )
+        .route(
+            "/expense-categories/:id",
+            axum::routing::delete(delete_expense_category),
+        )
+        .route("/bank-accounts", get(list_bank_accounts))
+        .route("/bank-accounts", post(create_bank_account))
+        .route("/bank-accounts/:id", axum::routing::patch(update_bank_account))
+        .route("/bank-accounts/:id", axum::routing::delete(delete_bank_account))
+        .route("/bank-accounts/:id/imports", get(list_bank_imports))
+        .route(
+            "/bank-accounts/:id/imports",
+            post(import_bank_statement)
+                .layer(DefaultBodyLimit::max(max_statement_bytes() + 64 * 1024)),
+        )
+        .route("/bank-transactions", get(list_bank_transactions))
+        .route("/bank-matches", get(list_bank_matches))
+        .route("/bank-matches/run", post(run_reconciliation))
+        .route("/bank-matches/:id/accept", post(accept_bank_match))
+        .route("/bank-matches/:id/reject", post(reject_bank_match))
         .route("/ai/line-item-improve", post(improve_line_item))
         .route("/ai/line-item-last", get(last_line_item))
         .route("/auth/register", post(register))
@@ -143,14 +544,73 @@ async fn main() -> anyhow::Result<()> {
         .route("/auth/logout", post(logout))
         .route("/auth/me", get(me))
         .route("/auth/profile", axum::routing::patch(update_profile))
+        .route("/auth/verify-email/send", post(send_email_verification))
+        .route("/auth/verify-email", post(verify_email))
+        .route("/auth/password-reset/request", post(request_password_reset))
+        .route("/auth/password-reset", post(reset_password))
+        .route("/auth/password", post(change_password))
+        .route("/auth/login/2fa", post(complete_two_factor_login))
+        .route("/auth/2fa/setup", post(setup_two_factor))
+        .route("/auth/2fa/enable", post(enable_two_factor))
+        .route("/auth/2fa/disable", post(disable_two_factor))
+        .route("/auth/2fa/recovery-codes", post(regenerate_recovery_codes))
+        .route("/auth/api-tokens", post(create_api_token))
+        .route("/auth/api-tokens", get(list_api_tokens))
+        .route("/auth/api-tokens/:id", axum::routing::delete(revoke_api_token))
+        .route("/auth/sessions", get(list_sessions))
+        .route("/auth/sessions/:id", axum::routing::delete(revoke_session))
+        .route("/auth/sessions/revoke-others", post(revoke_other_sessions))
+        .route("/auth/logout-all", post(logout_everywhere))
+        .route("/auth/account/export", get(export_account))
+        .route("/auth/account", axum::routing::delete(delete_account))
+        .route("/auth/oidc/providers", get(list_oidc_providers))
+        .route("/auth/oidc/:provider/login", get(start_oidc_login))
+        .route("/auth/oidc/:provider/callback", get(oidc_callback))
+        .route("/auth/identities", get(list_identities))
+        .route("/auth/identities/:id", axum::routing::delete(unlink_identity))
+        .route("/workspaces", get(list_workspaces))
+        .route("/workspaces", post(create_workspace))
+        .route("/workspaces/:id", axum::routing::patch(update_workspace))
+        .route("/workspaces/:id/members", get(list_members))
+        .route("/workspaces/:id/members/:user_id", axum::routing::patch(update_member))
+        .route("/workspaces/:id/members/:user_id", axum::routing::delete(remove_member))
+        .route("/workspaces/:id/invitations", post(invite_member))
+        .route("/workspaces/:id/invitations", get(list_invitations))
+        .route(
+            "/workspaces/:id/invitations/:invitation_id",
+            axum::routing::delete(revoke_invitation),
+        )
+        .route("/workspaces/invitations/accept", post(accept_invitation))
+        .route("/workspaces/:id/access-grants", post(create_access_grant))
+        .route("/workspaces/:id/access-grants", get(list_access_grants))
+        .route(
+            "/workspaces/:id/access-grants/:grant_id",
+            axum::routing::delete(revoke_access_grant),
+        )
+        .route("/workspaces/:id/access-grants/:grant_id/audit", get(list_access_grant_events))
+        .route("/accountant/grant", get(get_accountant_grant))
+        .route("/accountant/invoices", get(list_accountant_invoices))
+        .route("/accountant/invoices/:id/pdf", get(get_accountant_invoice_pdf))
+        .route("/accountant/expenses", get(list_accountant_expenses))
+        .route("/accountant/receipts/:id", get(get_accountant_receipt))
+        .route("/accountant/report", get(get_accountant_report))
         .merge(SwaggerUi::new("/docs").url("/api-doc/openapi.json", ApiDoc::openapi()))
+        .layer(axum::middleware::from_fn(session_cookie_renewal))
+        .layer(axum::middleware::from_fn(csrf_protection))
+        .layer(axum::middleware::from_fn_with_state(state.clone(), rate_limit))
+        .layer(axum::middleware::from_fn(client_ip))
+        .layer(axum::middleware::from_fn(request_id))
         .layer(build_cors())
-        .with_state(AppState { db });
+        .with_state(state);
 
     let addr = SocketAddr::from(([0, 0, 0, 0], 3000));
     println!("🚀 Running at http://{}", addr);
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
 
     Ok(())
 }
@@ -168,10 +628,7 @@ async fn root() -> &'static str {
 }
 
 fn build_cors() -> CorsLayer {
-    let origin = std::env::var("CORS_ORIGIN")
-        .or_else(|_| std::env::var("FRONTEND_ORIGIN"))
-        .unwrap_or_else(|_| "http://localhost:5173".to_string());
-    let allowed_origin = origin
+    let allowed_origin = frontend_origin()
         .parse::<axum::http::HeaderValue>()
         .unwrap_or_else(|_| axum::http::HeaderValue::from_static("http://localhost:5173"));
 
@@ -180,9 +637,15 @@ fn build_cors() -> CorsLayer {
         .allow_methods([
             axum::http::Method::GET,
             axum::http::Method::POST,
+            axum::http::Method::PUT,
             axum::http::Method::PATCH,
             axum::http::Method::DELETE,
         ])
-        .allow_headers([axum::http::header::CONTENT_TYPE])
+        .allow_headers([
+            axum::http::header::CONTENT_TYPE,
+            axum::http::header::AUTHORIZATION,
+            axum::http::HeaderName::from_static(WORKSPACE_HEADER),
+        ])
+        .expose_headers([axum::http::HeaderName::from_static(REQUEST_ID_HEADER)])
         .allow_credentials(true)
 }
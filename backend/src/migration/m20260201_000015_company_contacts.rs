@@ -0,0 +1,120 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(CompanyContact::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(CompanyContact::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(CompanyContact::CompanyId).uuid().not_null())
+                    .col(ColumnDef::new(CompanyContact::Name).text().not_null())
+                    .col(ColumnDef::new(CompanyContact::Role).text().null())
+                    .col(ColumnDef::new(CompanyContact::Email).text().null())
+                    .col(ColumnDef::new(CompanyContact::Phone).text().null())
+                    .col(
+                        ColumnDef::new(CompanyContact::IsBilling)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(
+                        ColumnDef::new(CompanyContact::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_company_contact_company")
+                            .from(CompanyContact::Table, CompanyContact::CompanyId)
+                            .to(Company::Table, Company::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_company_contact_company")
+                    .table(CompanyContact::Table)
+                    .col(CompanyContact::CompanyId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Invoice::Table)
+                    .add_column(ColumnDef::new(Invoice::ContactId).uuid().null())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_invoice_contact")
+                            .from_tbl(Invoice::Table)
+                            .from_col(Invoice::ContactId)
+                            .to_tbl(CompanyContact::Table)
+                            .to_col(CompanyContact::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Invoice::Table)
+                    .drop_foreign_key(Alias::new("fk_invoice_contact"))
+                    .drop_column(Invoice::ContactId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_index(Index::drop().name("idx_company_contact_company").to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(CompanyContact::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum CompanyContact {
+    Table,
+    Id,
+    CompanyId,
+    Name,
+    Role,
+    Email,
+    Phone,
+    IsBilling,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Company {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Invoice {
+    Table,
+    ContactId,
+}
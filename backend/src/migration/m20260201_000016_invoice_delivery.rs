@@ -0,0 +1,180 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Invoice::Table)
+                    .add_column(
+                        ColumnDef::new(Invoice::Status)
+                            .text()
+                            .not_null()
+                            .default("draft"),
+                    )
+                    .add_column(
+                        ColumnDef::new(Invoice::SentAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(EmailTemplate::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(EmailTemplate::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(EmailTemplate::UserId).uuid().not_null())
+                    .col(ColumnDef::new(EmailTemplate::Kind).text().not_null())
+                    .col(ColumnDef::new(EmailTemplate::Subject).text().not_null())
+                    .col(ColumnDef::new(EmailTemplate::Html).text().not_null())
+                    .col(ColumnDef::new(EmailTemplate::Text).text().not_null())
+                    .col(
+                        ColumnDef::new(EmailTemplate::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_email_template_user")
+                            .from(EmailTemplate::Table, EmailTemplate::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_email_template_user_kind")
+                    .table(EmailTemplate::Table)
+                    .col(EmailTemplate::UserId)
+                    .col(EmailTemplate::Kind)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(InvoiceDelivery::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(InvoiceDelivery::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(InvoiceDelivery::InvoiceId).uuid().not_null())
+                    .col(ColumnDef::new(InvoiceDelivery::Recipients).text().not_null())
+                    .col(ColumnDef::new(InvoiceDelivery::Subject).text().not_null())
+                    .col(ColumnDef::new(InvoiceDelivery::Status).text().not_null())
+                    .col(ColumnDef::new(InvoiceDelivery::Error).text().null())
+                    .col(
+                        ColumnDef::new(InvoiceDelivery::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_invoice_delivery_invoice")
+                            .from(InvoiceDelivery::Table, InvoiceDelivery::InvoiceId)
+                            .to(Invoice::Table, Invoice::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_invoice_delivery_invoice")
+                    .table(InvoiceDelivery::Table)
+                    .col(InvoiceDelivery::InvoiceId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(Index::drop().name("idx_invoice_delivery_invoice").to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(InvoiceDelivery::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_index(Index::drop().name("idx_email_template_user_kind").to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(EmailTemplate::Table).to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Invoice::Table)
+                    .drop_column(Invoice::SentAt)
+                    .drop_column(Invoice::Status)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Invoice {
+    Table,
+    Id,
+    Status,
+    SentAt,
+}
+
+#[derive(DeriveIden)]
+enum EmailTemplate {
+    Table,
+    Id,
+    UserId,
+    Kind,
+    Subject,
+    Html,
+    Text,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum InvoiceDelivery {
+    Table,
+    Id,
+    InvoiceId,
+    Recipients,
+    Subject,
+    Status,
+    Error,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+}
@@ -0,0 +1,183 @@
+use sea_orm_migration::prelude::*;
+use sea_orm::Statement;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Invoice::Table)
+                    .add_column(ColumnDef::new(Invoice::DueDate).date().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .get_connection()
+            .execute(Statement::from_string(
+                manager.get_database_backend(),
+                "UPDATE invoice SET due_date = date + 14",
+            ))
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(DunningLevel::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(DunningLevel::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(DunningLevel::UserId).uuid().not_null())
+                    .col(ColumnDef::new(DunningLevel::Level).integer().not_null())
+                    .col(ColumnDef::new(DunningLevel::Name).text().not_null())
+                    .col(ColumnDef::new(DunningLevel::DaysAfterDue).integer().not_null())
+                    .col(
+                        ColumnDef::new(DunningLevel::Fee)
+                            .double()
+                            .not_null()
+                            .default(0.0),
+                    )
+                    .col(ColumnDef::new(DunningLevel::InterestRate).double().null())
+                    .col(
+                        ColumnDef::new(DunningLevel::SendEmail)
+                            .boolean()
+                            .not_null()
+                            .default(true),
+                    )
+                    .col(
+                        ColumnDef::new(DunningLevel::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_dunning_level_user")
+                            .from(DunningLevel::Table, DunningLevel::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(InvoiceReminder::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(InvoiceReminder::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(InvoiceReminder::InvoiceId).uuid().not_null())
+                    .col(ColumnDef::new(InvoiceReminder::Level).integer().not_null())
+                    .col(ColumnDef::new(InvoiceReminder::Name).text().not_null())
+                    .col(ColumnDef::new(InvoiceReminder::DaysOverdue).integer().not_null())
+                    .col(ColumnDef::new(InvoiceReminder::Fee).double().not_null())
+                    .col(ColumnDef::new(InvoiceReminder::Interest).double().not_null())
+                    .col(ColumnDef::new(InvoiceReminder::TotalDue).double().not_null())
+                    .col(ColumnDef::new(InvoiceReminder::EmailStatus).text().not_null())
+                    .col(ColumnDef::new(InvoiceReminder::EmailError).text().null())
+                    .col(
+                        ColumnDef::new(InvoiceReminder::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_invoice_reminder_invoice")
+                            .from(InvoiceReminder::Table, InvoiceReminder::InvoiceId)
+                            .to(Invoice::Table, Invoice::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_invoice_reminder_invoice")
+                    .table(InvoiceReminder::Table)
+                    .col(InvoiceReminder::InvoiceId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(Index::drop().name("idx_invoice_reminder_invoice").to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(InvoiceReminder::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(DunningLevel::Table).to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Invoice::Table)
+                    .drop_column(Invoice::DueDate)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Invoice {
+    Table,
+    Id,
+    DueDate,
+}
+
+#[derive(DeriveIden)]
+enum DunningLevel {
+    Table,
+    Id,
+    UserId,
+    Level,
+    Name,
+    DaysAfterDue,
+    Fee,
+    InterestRate,
+    SendEmail,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum InvoiceReminder {
+    Table,
+    Id,
+    InvoiceId,
+    Level,
+    Name,
+    DaysOverdue,
+    Fee,
+    Interest,
+    TotalDue,
+    EmailStatus,
+    EmailError,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+}
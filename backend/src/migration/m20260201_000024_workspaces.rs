@@ -0,0 +1,327 @@
+use sea_orm::Statement;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Tables whose rows move from being owned by a user to being owned by a workspace,
+/// and whether their existing `user_id` (and therefore `workspace_id`) may be null.
+const SCOPED_TABLES: [(&str, bool); 6] = [
+    ("company", true),
+    ("invoice", true),
+    ("expense", false),
+    ("invoice_template", false),
+    ("email_template", false),
+    ("dunning_level", false),
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Workspace::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Workspace::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Workspace::Name).text().not_null())
+                    .col(
+                        ColumnDef::new(Workspace::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(WorkspaceMember::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(WorkspaceMember::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(WorkspaceMember::WorkspaceId).uuid().not_null())
+                    .col(ColumnDef::new(WorkspaceMember::UserId).uuid().not_null())
+                    .col(ColumnDef::new(WorkspaceMember::Role).text().not_null())
+                    .col(
+                        ColumnDef::new(WorkspaceMember::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_workspace_member_workspace")
+                            .from(WorkspaceMember::Table, WorkspaceMember::WorkspaceId)
+                            .to(Workspace::Table, Workspace::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_workspace_member_user")
+                            .from(WorkspaceMember::Table, WorkspaceMember::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_workspace_member_workspace_user")
+                    .table(WorkspaceMember::Table)
+                    .col(WorkspaceMember::WorkspaceId)
+                    .col(WorkspaceMember::UserId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_workspace_member_user")
+                    .table(WorkspaceMember::Table)
+                    .col(WorkspaceMember::UserId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(WorkspaceInvitation::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(WorkspaceInvitation::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(WorkspaceInvitation::WorkspaceId).uuid().not_null())
+                    .col(ColumnDef::new(WorkspaceInvitation::Email).text().not_null())
+                    .col(ColumnDef::new(WorkspaceInvitation::Role).text().not_null())
+                    .col(
+                        ColumnDef::new(WorkspaceInvitation::TokenHash)
+                            .text()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(WorkspaceInvitation::InvitedBy).uuid().null())
+                    .col(
+                        ColumnDef::new(WorkspaceInvitation::ExpiresAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(WorkspaceInvitation::AcceptedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(WorkspaceInvitation::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_workspace_invitation_workspace")
+                            .from(WorkspaceInvitation::Table, WorkspaceInvitation::WorkspaceId)
+                            .to(Workspace::Table, Workspace::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_workspace_invitation_invited_by")
+                            .from(WorkspaceInvitation::Table, WorkspaceInvitation::InvitedBy)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_workspace_invitation_workspace")
+                    .table(WorkspaceInvitation::Table)
+                    .col(WorkspaceInvitation::WorkspaceId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Every existing user gets a personal workspace that reuses their id, which makes
+        // moving their data over a plain `workspace_id = user_id`.
+        let db = manager.get_connection();
+        let backend = manager.get_database_backend();
+        db.execute(Statement::from_string(
+            backend,
+            "INSERT INTO workspace (id, name, created_at) SELECT id, email, created_at FROM \"user\"",
+        ))
+        .await?;
+        db.execute(Statement::from_string(
+            backend,
+            "INSERT INTO workspace_member (id, workspace_id, user_id, role, created_at) \
+             SELECT gen_random_uuid(), id, id, 'owner', created_at FROM \"user\"",
+        ))
+        .await?;
+
+        for (table, nullable) in SCOPED_TABLES {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Alias::new(table))
+                        .add_column(ColumnDef::new(Alias::new("workspace_id")).uuid().null())
+                        .add_foreign_key(
+                            TableForeignKey::new()
+                                .name(format!("fk_{table}_workspace"))
+                                .from_tbl(Alias::new(table))
+                                .from_col(Alias::new("workspace_id"))
+                                .to_tbl(Workspace::Table)
+                                .to_col(Workspace::Id)
+                                .on_delete(ForeignKeyAction::Cascade),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+
+            db.execute(Statement::from_string(
+                backend,
+                format!("UPDATE {table} SET workspace_id = user_id"),
+            ))
+            .await?;
+
+            if !nullable {
+                db.execute(Statement::from_string(
+                    backend,
+                    format!("ALTER TABLE {table} ALTER COLUMN workspace_id SET NOT NULL"),
+                ))
+                .await?;
+            }
+
+            manager
+                .create_index(
+                    Index::create()
+                        .name(format!("idx_{table}_workspace"))
+                        .table(Alias::new(table))
+                        .col(Alias::new("workspace_id"))
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        // Email template overrides are now per workspace rather than per user.
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_email_template_user_kind")
+                    .table(Alias::new("email_template"))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_email_template_workspace_kind")
+                    .table(Alias::new("email_template"))
+                    .col(Alias::new("workspace_id"))
+                    .col(Alias::new("kind"))
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_email_template_workspace_kind")
+                    .table(Alias::new("email_template"))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_email_template_user_kind")
+                    .table(Alias::new("email_template"))
+                    .col(Alias::new("user_id"))
+                    .col(Alias::new("kind"))
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        for (table, _) in SCOPED_TABLES {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Alias::new(table))
+                        .drop_column(Alias::new("workspace_id"))
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .drop_table(Table::drop().table(WorkspaceInvitation::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(WorkspaceMember::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Workspace::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Workspace {
+    Table,
+    Id,
+    Name,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum WorkspaceMember {
+    Table,
+    Id,
+    WorkspaceId,
+    UserId,
+    Role,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum WorkspaceInvitation {
+    Table,
+    Id,
+    WorkspaceId,
+    Email,
+    Role,
+    TokenHash,
+    InvitedBy,
+    ExpiresAt,
+    AcceptedAt,
+    CreatedAt,
+}
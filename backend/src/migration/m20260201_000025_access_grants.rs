@@ -0,0 +1,157 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AccessGrant::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AccessGrant::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(AccessGrant::WorkspaceId).uuid().not_null())
+                    .col(ColumnDef::new(AccessGrant::Email).text().not_null())
+                    .col(
+                        ColumnDef::new(AccessGrant::TokenHash)
+                            .text()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(AccessGrant::PeriodStart).date().not_null())
+                    .col(ColumnDef::new(AccessGrant::PeriodEnd).date().not_null())
+                    .col(
+                        ColumnDef::new(AccessGrant::ExpiresAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(AccessGrant::RevokedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(AccessGrant::LastUsedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .col(ColumnDef::new(AccessGrant::CreatedBy).uuid().null())
+                    .col(
+                        ColumnDef::new(AccessGrant::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_access_grant_workspace")
+                            .from(AccessGrant::Table, AccessGrant::WorkspaceId)
+                            .to(Workspace::Table, Workspace::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_access_grant_created_by")
+                            .from(AccessGrant::Table, AccessGrant::CreatedBy)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_access_grant_workspace")
+                    .table(AccessGrant::Table)
+                    .col(AccessGrant::WorkspaceId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AuditEvent::Table)
+                    .add_column(ColumnDef::new(AuditEvent::AccessGrantId).uuid().null())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_audit_event_access_grant")
+                            .from_tbl(AuditEvent::Table)
+                            .from_col(AuditEvent::AccessGrantId)
+                            .to_tbl(AccessGrant::Table)
+                            .to_col(AccessGrant::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_audit_event_access_grant")
+                    .table(AuditEvent::Table)
+                    .col(AuditEvent::AccessGrantId)
+                    .col(AuditEvent::CreatedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AuditEvent::Table)
+                    .drop_column(AuditEvent::AccessGrantId)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(Table::drop().table(AccessGrant::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Workspace {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum AuditEvent {
+    Table,
+    AccessGrantId,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum AccessGrant {
+    Table,
+    Id,
+    WorkspaceId,
+    Email,
+    TokenHash,
+    PeriodStart,
+    PeriodEnd,
+    ExpiresAt,
+    RevokedAt,
+    LastUsedAt,
+    CreatedBy,
+    CreatedAt,
+}
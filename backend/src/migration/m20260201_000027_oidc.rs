@@ -0,0 +1,129 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserIdentity::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(UserIdentity::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(UserIdentity::UserId).uuid().not_null())
+                    .col(ColumnDef::new(UserIdentity::Provider).text().not_null())
+                    .col(ColumnDef::new(UserIdentity::Subject).text().not_null())
+                    .col(ColumnDef::new(UserIdentity::Email).text().null())
+                    .col(
+                        ColumnDef::new(UserIdentity::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(UserIdentity::LastLoginAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_user_identity_user")
+                            .from(UserIdentity::Table, UserIdentity::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_user_identity_provider_subject")
+                    .table(UserIdentity::Table)
+                    .col(UserIdentity::Provider)
+                    .col(UserIdentity::Subject)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_user_identity_user")
+                    .table(UserIdentity::Table)
+                    .col(UserIdentity::UserId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(OidcLogin::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(OidcLogin::StateHash)
+                            .text()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(OidcLogin::Provider).text().not_null())
+                    .col(ColumnDef::new(OidcLogin::CodeVerifier).text().not_null())
+                    .col(ColumnDef::new(OidcLogin::Nonce).text().not_null())
+                    .col(ColumnDef::new(OidcLogin::RedirectTo).text().not_null())
+                    .col(
+                        ColumnDef::new(OidcLogin::ExpiresAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(OidcLogin::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(UserIdentity::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum UserIdentity {
+    Table,
+    Id,
+    UserId,
+    Provider,
+    Subject,
+    Email,
+    CreatedAt,
+    LastLoginAt,
+}
+
+#[derive(DeriveIden)]
+enum OidcLogin {
+    Table,
+    StateHash,
+    Provider,
+    CodeVerifier,
+    Nonce,
+    RedirectTo,
+    ExpiresAt,
+}
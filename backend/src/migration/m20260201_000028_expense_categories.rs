@@ -0,0 +1,204 @@
+use sea_orm::Statement;
+use sea_orm_migration::prelude::*;
+
+/// Default categories as of this migration: name, SKR03 account, SKR04 account and the
+/// deductible share in percent.
+const DEFAULT_CATEGORIES: &str = "('Office', '4930', '6815', 100), \
+    ('Software', '4964', '6837', 100), \
+    ('Hardware', '0480', '0670', 100), \
+    ('Phone & internet', '4920', '6805', 100), \
+    ('Travel', '4670', '6670', 100), \
+    ('Business meals', '4650', '6640', 70), \
+    ('Gifts', '4630', '6610', 100), \
+    ('Marketing', '4600', '6600', 100), \
+    ('Services', '3100', '5900', 100), \
+    ('Training', '4945', '6821', 100), \
+    ('Books & media', '4940', '6820', 100), \
+    ('Legal & tax advice', '4950', '6825', 100), \
+    ('Insurance', '4360', '6400', 100), \
+    ('Rent', '4210', '6310', 100), \
+    ('Bank fees', '4970', '6855', 100), \
+    ('Other', '4900', '6300', 100)";
+
+/// Common spellings of the defaults found in free-text categories.
+const ALIASES: &str = "('saas', 'Software'), ('subscriptions', 'Software'), \
+    ('licenses', 'Software'), ('office supplies', 'Office'), ('büro', 'Office'), \
+    ('bürobedarf', 'Office'), ('phone', 'Phone & internet'), \
+    ('internet', 'Phone & internet'), ('telefon', 'Phone & internet'), \
+    ('reisekosten', 'Travel'), ('meals', 'Business meals'), ('bewirtung', 'Business meals'), \
+    ('advertising', 'Marketing'), ('werbung', 'Marketing'), ('contractors', 'Services'), \
+    ('fremdleistungen', 'Services'), ('books', 'Books & media'), ('fortbildung', 'Training'), \
+    ('education', 'Training'), ('steuerberatung', 'Legal & tax advice'), \
+    ('miete', 'Rent'), ('sonstiges', 'Other'), ('misc', 'Other')";
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ExpenseCategory::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ExpenseCategory::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ExpenseCategory::WorkspaceId).uuid().not_null())
+                    .col(ColumnDef::new(ExpenseCategory::Name).text().not_null())
+                    .col(ColumnDef::new(ExpenseCategory::Skr03Account).text().null())
+                    .col(ColumnDef::new(ExpenseCategory::Skr04Account).text().null())
+                    .col(
+                        ColumnDef::new(ExpenseCategory::DeductiblePercent)
+                            .integer()
+                            .not_null()
+                            .default(100),
+                    )
+                    .col(
+                        ColumnDef::new(ExpenseCategory::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_expense_category_workspace")
+                            .from(ExpenseCategory::Table, ExpenseCategory::WorkspaceId)
+                            .to(Workspace::Table, Workspace::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        let db = manager.get_connection();
+        let backend = manager.get_database_backend();
+        // Names are unique per workspace regardless of case, so "Software" and "software"
+        // cannot both exist.
+        db.execute(Statement::from_string(
+            backend,
+            "CREATE UNIQUE INDEX idx_expense_category_workspace_name \
+             ON expense_category (workspace_id, lower(name))",
+        ))
+        .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Expense::Table)
+                    .add_column(ColumnDef::new(Expense::CategoryId).uuid().null())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_expense_category")
+                            .from_tbl(Expense::Table)
+                            .from_col(Expense::CategoryId)
+                            .to_tbl(ExpenseCategory::Table)
+                            .to_col(ExpenseCategory::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_expense_category_id")
+                    .table(Expense::Table)
+                    .col(Expense::CategoryId)
+                    .to_owned(),
+            )
+            .await?;
+
+        db.execute(Statement::from_string(
+            backend,
+            format!(
+                "INSERT INTO expense_category (id, workspace_id, name, skr03_account, \
+                 skr04_account, deductible_percent, created_at) \
+                 SELECT gen_random_uuid(), w.id, d.name, d.skr03, d.skr04, d.percent, now() \
+                 FROM workspace w CROSS JOIN (VALUES {DEFAULT_CATEGORIES}) \
+                 AS d(name, skr03, skr04, percent)"
+            ),
+        ))
+        .await?;
+        db.execute(Statement::from_string(
+            backend,
+            format!(
+                "UPDATE expense SET category = a.target \
+                 FROM (VALUES {ALIASES}) AS a(alias, target) \
+                 WHERE lower(trim(expense.category)) = a.alias"
+            ),
+        ))
+        .await?;
+        // Remaining free-text values become categories of their own, spelled as first seen
+        // and left without accounts for the user to map.
+        db.execute(Statement::from_string(
+            backend,
+            "INSERT INTO expense_category (id, workspace_id, name, deductible_percent, created_at) \
+             SELECT gen_random_uuid(), e.workspace_id, min(trim(e.category)), 100, now() \
+             FROM expense e \
+             WHERE trim(coalesce(e.category, '')) <> '' AND NOT EXISTS ( \
+                 SELECT 1 FROM expense_category c \
+                 WHERE c.workspace_id = e.workspace_id \
+                 AND lower(c.name) = lower(trim(e.category))) \
+             GROUP BY e.workspace_id, lower(trim(e.category))",
+        ))
+        .await?;
+        db.execute(Statement::from_string(
+            backend,
+            "UPDATE expense e SET category_id = c.id, category = c.name \
+             FROM expense_category c \
+             WHERE c.workspace_id = e.workspace_id AND lower(c.name) = lower(trim(e.category))",
+        ))
+        .await?;
+        db.execute(Statement::from_string(
+            backend,
+            "UPDATE expense SET category = NULL WHERE category_id IS NULL",
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Expense::Table)
+                    .drop_foreign_key(Alias::new("fk_expense_category"))
+                    .drop_column(Expense::CategoryId)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(Table::drop().table(ExpenseCategory::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Workspace {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Expense {
+    Table,
+    CategoryId,
+}
+
+#[derive(DeriveIden)]
+enum ExpenseCategory {
+    Table,
+    Id,
+    WorkspaceId,
+    Name,
+    Skr03Account,
+    Skr04Account,
+    DeductiblePercent,
+    CreatedAt,
+}
@@ -0,0 +1,57 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Expense::Table)
+                    .add_column(ColumnDef::new(Expense::NetAmount).double().null())
+                    .add_column(ColumnDef::new(Expense::VatRate).double().null())
+                    .add_column(ColumnDef::new(Expense::VatAmount).double().null())
+                    .add_column(
+                        ColumnDef::new(Expense::InputTaxDeductible)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .add_column(
+                        ColumnDef::new(Expense::ReverseCharge)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Expense::Table)
+                    .drop_column(Expense::NetAmount)
+                    .drop_column(Expense::VatRate)
+                    .drop_column(Expense::VatAmount)
+                    .drop_column(Expense::InputTaxDeductible)
+                    .drop_column(Expense::ReverseCharge)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Expense {
+    Table,
+    NetAmount,
+    VatRate,
+    VatAmount,
+    InputTaxDeductible,
+    ReverseCharge,
+}
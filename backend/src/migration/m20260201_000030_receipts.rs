@@ -0,0 +1,140 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Receipt::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Receipt::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Receipt::WorkspaceId).uuid().not_null())
+                    .col(ColumnDef::new(Receipt::UserId).uuid().not_null())
+                    .col(
+                        ColumnDef::new(Receipt::StorageKey)
+                            .text()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(Receipt::Filename).text().not_null())
+                    .col(ColumnDef::new(Receipt::ContentType).text().not_null())
+                    .col(ColumnDef::new(Receipt::SizeBytes).big_integer().not_null())
+                    .col(ColumnDef::new(Receipt::Sha256).text().not_null())
+                    .col(
+                        ColumnDef::new(Receipt::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_receipt_workspace")
+                            .from(Receipt::Table, Receipt::WorkspaceId)
+                            .to(Workspace::Table, Workspace::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_receipt_user")
+                            .from(Receipt::Table, Receipt::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_receipt_workspace")
+                    .table(Receipt::Table)
+                    .col(Receipt::WorkspaceId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Expense::Table)
+                    .add_column(ColumnDef::new(Expense::ReceiptId).uuid().null())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_expense_receipt")
+                            .from_tbl(Expense::Table)
+                            .from_col(Expense::ReceiptId)
+                            .to_tbl(Receipt::Table)
+                            .to_col(Receipt::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_expense_receipt_id")
+                    .table(Expense::Table)
+                    .col(Expense::ReceiptId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Expense::Table)
+                    .drop_foreign_key(Alias::new("fk_expense_receipt"))
+                    .drop_column(Expense::ReceiptId)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Receipt::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Receipt {
+    Table,
+    Id,
+    WorkspaceId,
+    UserId,
+    StorageKey,
+    Filename,
+    ContentType,
+    SizeBytes,
+    Sha256,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Expense {
+    Table,
+    ReceiptId,
+}
+
+#[derive(DeriveIden)]
+enum Workspace {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+}
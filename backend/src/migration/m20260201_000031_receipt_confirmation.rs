@@ -0,0 +1,77 @@
+use sea_orm::Statement;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Direct uploads get a row before the file exists; size and hash are filled in when
+        // the upload is confirmed.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Receipt::Table)
+                    .add_column(
+                        ColumnDef::new(Receipt::ConfirmedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .modify_column(ColumnDef::new(Receipt::SizeBytes).big_integer().null())
+                    .modify_column(ColumnDef::new(Receipt::Sha256).text().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        let db = manager.get_connection();
+        let backend = manager.get_database_backend();
+        db.execute(Statement::from_string(
+            backend,
+            "UPDATE receipt SET confirmed_at = created_at",
+        ))
+        .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_receipt_created_at")
+                    .table(Receipt::Table)
+                    .col(Receipt::CreatedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        let backend = manager.get_database_backend();
+        db.execute(Statement::from_string(
+            backend,
+            "DELETE FROM receipt WHERE confirmed_at IS NULL",
+        ))
+        .await?;
+        manager
+            .drop_index(Index::drop().name("idx_receipt_created_at").to_owned())
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Receipt::Table)
+                    .drop_column(Receipt::ConfirmedAt)
+                    .modify_column(ColumnDef::new(Receipt::SizeBytes).big_integer().not_null())
+                    .modify_column(ColumnDef::new(Receipt::Sha256).text().not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Receipt {
+    Table,
+    SizeBytes,
+    Sha256,
+    ConfirmedAt,
+    CreatedAt,
+}
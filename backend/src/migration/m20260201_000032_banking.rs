@@ -0,0 +1,233 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(BankAccount::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(BankAccount::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(BankAccount::WorkspaceId).uuid().not_null())
+                    .col(ColumnDef::new(BankAccount::Name).text().not_null())
+                    .col(ColumnDef::new(BankAccount::Iban).text().null())
+                    .col(ColumnDef::new(BankAccount::Bic).text().null())
+                    .col(ColumnDef::new(BankAccount::Currency).text().not_null())
+                    .col(ColumnDef::new(BankAccount::CsvMapping).json_binary().null())
+                    .col(
+                        ColumnDef::new(BankAccount::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_bank_account_workspace")
+                            .from(BankAccount::Table, BankAccount::WorkspaceId)
+                            .to(Workspace::Table, Workspace::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_bank_account_workspace")
+                    .table(BankAccount::Table)
+                    .col(BankAccount::WorkspaceId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(BankImport::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(BankImport::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(BankImport::WorkspaceId).uuid().not_null())
+                    .col(ColumnDef::new(BankImport::BankAccountId).uuid().not_null())
+                    .col(ColumnDef::new(BankImport::UserId).uuid().not_null())
+                    .col(ColumnDef::new(BankImport::Format).text().not_null())
+                    .col(ColumnDef::new(BankImport::Filename).text().not_null())
+                    .col(ColumnDef::new(BankImport::ImportedCount).integer().not_null())
+                    .col(ColumnDef::new(BankImport::DuplicateCount).integer().not_null())
+                    .col(
+                        ColumnDef::new(BankImport::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_bank_import_account")
+                            .from(BankImport::Table, BankImport::BankAccountId)
+                            .to(BankAccount::Table, BankAccount::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_bank_import_user")
+                            .from(BankImport::Table, BankImport::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(BankTransaction::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(BankTransaction::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(BankTransaction::WorkspaceId).uuid().not_null())
+                    .col(ColumnDef::new(BankTransaction::BankAccountId).uuid().not_null())
+                    .col(ColumnDef::new(BankTransaction::ImportId).uuid().not_null())
+                    .col(ColumnDef::new(BankTransaction::BookingDate).date().not_null())
+                    .col(ColumnDef::new(BankTransaction::ValueDate).date().null())
+                    .col(ColumnDef::new(BankTransaction::Amount).double().not_null())
+                    .col(ColumnDef::new(BankTransaction::Currency).text().not_null())
+                    .col(ColumnDef::new(BankTransaction::CounterpartyName).text().null())
+                    .col(ColumnDef::new(BankTransaction::CounterpartyIban).text().null())
+                    .col(ColumnDef::new(BankTransaction::RemittanceInfo).text().null())
+                    .col(ColumnDef::new(BankTransaction::Reference).text().null())
+                    .col(ColumnDef::new(BankTransaction::Fingerprint).text().not_null())
+                    .col(
+                        ColumnDef::new(BankTransaction::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_bank_transaction_account")
+                            .from(BankTransaction::Table, BankTransaction::BankAccountId)
+                            .to(BankAccount::Table, BankAccount::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_bank_transaction_import")
+                            .from(BankTransaction::Table, BankTransaction::ImportId)
+                            .to(BankImport::Table, BankImport::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Re-importing an overlapping statement must not create the same booking twice.
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_bank_transaction_fingerprint")
+                    .table(BankTransaction::Table)
+                    .col(BankTransaction::BankAccountId)
+                    .col(BankTransaction::Fingerprint)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_bank_transaction_workspace_date")
+                    .table(BankTransaction::Table)
+                    .col(BankTransaction::WorkspaceId)
+                    .col(BankTransaction::BookingDate)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(BankTransaction::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(BankImport::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(BankAccount::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum BankAccount {
+    Table,
+    Id,
+    WorkspaceId,
+    Name,
+    Iban,
+    Bic,
+    Currency,
+    CsvMapping,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum BankImport {
+    Table,
+    Id,
+    WorkspaceId,
+    BankAccountId,
+    UserId,
+    Format,
+    Filename,
+    ImportedCount,
+    DuplicateCount,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum BankTransaction {
+    Table,
+    Id,
+    WorkspaceId,
+    BankAccountId,
+    ImportId,
+    BookingDate,
+    ValueDate,
+    Amount,
+    Currency,
+    CounterpartyName,
+    CounterpartyIban,
+    RemittanceInfo,
+    Reference,
+    Fingerprint,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Workspace {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+}
@@ -0,0 +1,217 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(BankMatch::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(BankMatch::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(BankMatch::WorkspaceId).uuid().not_null())
+                    .col(ColumnDef::new(BankMatch::BankTransactionId).uuid().not_null())
+                    .col(ColumnDef::new(BankMatch::InvoiceId).uuid().null())
+                    .col(ColumnDef::new(BankMatch::ExpenseId).uuid().null())
+                    .col(ColumnDef::new(BankMatch::Confidence).double().not_null())
+                    .col(ColumnDef::new(BankMatch::Reasons).json_binary().not_null())
+                    .col(ColumnDef::new(BankMatch::Status).text().not_null())
+                    .col(ColumnDef::new(BankMatch::DecidedBy).uuid().null())
+                    .col(
+                        ColumnDef::new(BankMatch::DecidedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(BankMatch::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_bank_match_transaction")
+                            .from(BankMatch::Table, BankMatch::BankTransactionId)
+                            .to(BankTransaction::Table, BankTransaction::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_bank_match_invoice")
+                            .from(BankMatch::Table, BankMatch::InvoiceId)
+                            .to(Invoice::Table, Invoice::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_bank_match_expense")
+                            .from(BankMatch::Table, BankMatch::ExpenseId)
+                            .to(Expense::Table, Expense::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_bank_match_decided_by")
+                            .from(BankMatch::Table, BankMatch::DecidedBy)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // A transaction is proposed for each invoice or expense at most once; NULLs are
+        // distinct, so each index only constrains its own kind of match.
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_bank_match_invoice")
+                    .table(BankMatch::Table)
+                    .col(BankMatch::BankTransactionId)
+                    .col(BankMatch::InvoiceId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_bank_match_expense")
+                    .table(BankMatch::Table)
+                    .col(BankMatch::BankTransactionId)
+                    .col(BankMatch::ExpenseId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_bank_match_workspace_status")
+                    .table(BankMatch::Table)
+                    .col(BankMatch::WorkspaceId)
+                    .col(BankMatch::Status)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(InvoicePayment::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(InvoicePayment::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(InvoicePayment::WorkspaceId).uuid().not_null())
+                    .col(ColumnDef::new(InvoicePayment::InvoiceId).uuid().not_null())
+                    .col(ColumnDef::new(InvoicePayment::BankTransactionId).uuid().null())
+                    .col(ColumnDef::new(InvoicePayment::Amount).double().not_null())
+                    .col(ColumnDef::new(InvoicePayment::Currency).text().not_null())
+                    .col(ColumnDef::new(InvoicePayment::PaidOn).date().not_null())
+                    .col(
+                        ColumnDef::new(InvoicePayment::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_invoice_payment_invoice")
+                            .from(InvoicePayment::Table, InvoicePayment::InvoiceId)
+                            .to(Invoice::Table, Invoice::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_invoice_payment_transaction")
+                            .from(InvoicePayment::Table, InvoicePayment::BankTransactionId)
+                            .to(BankTransaction::Table, BankTransaction::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_invoice_payment_invoice")
+                    .table(InvoicePayment::Table)
+                    .col(InvoicePayment::InvoiceId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(InvoicePayment::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(BankMatch::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum BankMatch {
+    Table,
+    Id,
+    WorkspaceId,
+    BankTransactionId,
+    InvoiceId,
+    ExpenseId,
+    Confidence,
+    Reasons,
+    Status,
+    DecidedBy,
+    DecidedAt,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum InvoicePayment {
+    Table,
+    Id,
+    WorkspaceId,
+    InvoiceId,
+    BankTransactionId,
+    Amount,
+    Currency,
+    PaidOn,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum BankTransaction {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Invoice {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Expense {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+}
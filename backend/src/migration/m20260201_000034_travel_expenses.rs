@@ -0,0 +1,243 @@
+use sea_orm::Statement;
+use sea_orm_migration::prelude::*;
+
+/// Default rates as of this migration (German tax law): vehicle, rate per km and the day
+/// it applies from.
+const DEFAULT_MILEAGE_RATES: &str = "('car', 0.30, DATE '2001-01-01'), \
+    ('motorcycle', 0.20, DATE '2014-01-01')";
+
+/// Country, full-day and partial-day allowance, and the day they apply from.
+const DEFAULT_PER_DIEM_RATES: &str = "('DE', 24.0, 12.0, DATE '2014-01-01'), \
+    ('DE', 28.0, 14.0, DATE '2020-01-01')";
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Expense::Table)
+                    .add_column(
+                        ColumnDef::new(Expense::Kind)
+                            .text()
+                            .not_null()
+                            .default("receipt"),
+                    )
+                    .add_column(ColumnDef::new(Expense::TripFrom).text().null())
+                    .add_column(ColumnDef::new(Expense::TripTo).text().null())
+                    .add_column(ColumnDef::new(Expense::DistanceKm).double().null())
+                    .add_column(ColumnDef::new(Expense::Vehicle).text().null())
+                    .add_column(ColumnDef::new(Expense::RatePerKm).double().null())
+                    .add_column(ColumnDef::new(Expense::Country).text().null())
+                    .add_column(ColumnDef::new(Expense::DepartureAt).timestamp().null())
+                    .add_column(ColumnDef::new(Expense::ReturnAt).timestamp().null())
+                    .add_column(ColumnDef::new(Expense::FullDays).integer().null())
+                    .add_column(ColumnDef::new(Expense::PartialDays).integer().null())
+                    .add_column(ColumnDef::new(Expense::BreakfastsProvided).integer().null())
+                    .add_column(ColumnDef::new(Expense::LunchesProvided).integer().null())
+                    .add_column(ColumnDef::new(Expense::DinnersProvided).integer().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(MileageRate::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(MileageRate::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(MileageRate::WorkspaceId).uuid().not_null())
+                    .col(ColumnDef::new(MileageRate::Vehicle).text().not_null())
+                    .col(ColumnDef::new(MileageRate::RatePerKm).double().not_null())
+                    .col(ColumnDef::new(MileageRate::Currency).text().not_null())
+                    .col(ColumnDef::new(MileageRate::ValidFrom).date().not_null())
+                    .col(
+                        ColumnDef::new(MileageRate::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_mileage_rate_workspace")
+                            .from(MileageRate::Table, MileageRate::WorkspaceId)
+                            .to(Workspace::Table, Workspace::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_mileage_rate_vehicle")
+                    .table(MileageRate::Table)
+                    .col(MileageRate::WorkspaceId)
+                    .col(MileageRate::Vehicle)
+                    .col(MileageRate::ValidFrom)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(PerDiemRate::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PerDiemRate::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(PerDiemRate::WorkspaceId).uuid().not_null())
+                    .col(ColumnDef::new(PerDiemRate::Country).text().not_null())
+                    .col(ColumnDef::new(PerDiemRate::FullDay).double().not_null())
+                    .col(ColumnDef::new(PerDiemRate::PartialDay).double().not_null())
+                    .col(ColumnDef::new(PerDiemRate::Currency).text().not_null())
+                    .col(ColumnDef::new(PerDiemRate::ValidFrom).date().not_null())
+                    .col(
+                        ColumnDef::new(PerDiemRate::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_per_diem_rate_workspace")
+                            .from(PerDiemRate::Table, PerDiemRate::WorkspaceId)
+                            .to(Workspace::Table, Workspace::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_per_diem_rate_country")
+                    .table(PerDiemRate::Table)
+                    .col(PerDiemRate::WorkspaceId)
+                    .col(PerDiemRate::Country)
+                    .col(PerDiemRate::ValidFrom)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        let db = manager.get_connection();
+        let backend = manager.get_database_backend();
+        db.execute(Statement::from_string(
+            backend,
+            format!(
+                "INSERT INTO mileage_rate (id, workspace_id, vehicle, rate_per_km, currency, \
+                 valid_from, created_at) \
+                 SELECT gen_random_uuid(), w.id, d.vehicle, d.rate, 'EUR', d.valid_from, now() \
+                 FROM workspace w CROSS JOIN (VALUES {DEFAULT_MILEAGE_RATES}) \
+                 AS d(vehicle, rate, valid_from)"
+            ),
+        ))
+        .await?;
+        db.execute(Statement::from_string(
+            backend,
+            format!(
+                "INSERT INTO per_diem_rate (id, workspace_id, country, full_day, partial_day, \
+                 currency, valid_from, created_at) \
+                 SELECT gen_random_uuid(), w.id, d.country, d.full_day, d.partial_day, 'EUR', \
+                 d.valid_from, now() \
+                 FROM workspace w CROSS JOIN (VALUES {DEFAULT_PER_DIEM_RATES}) \
+                 AS d(country, full_day, partial_day, valid_from)"
+            ),
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PerDiemRate::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(MileageRate::Table).to_owned())
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Expense::Table)
+                    .drop_column(Expense::Kind)
+                    .drop_column(Expense::TripFrom)
+                    .drop_column(Expense::TripTo)
+                    .drop_column(Expense::DistanceKm)
+                    .drop_column(Expense::Vehicle)
+                    .drop_column(Expense::RatePerKm)
+                    .drop_column(Expense::Country)
+                    .drop_column(Expense::DepartureAt)
+                    .drop_column(Expense::ReturnAt)
+                    .drop_column(Expense::FullDays)
+                    .drop_column(Expense::PartialDays)
+                    .drop_column(Expense::BreakfastsProvided)
+                    .drop_column(Expense::LunchesProvided)
+                    .drop_column(Expense::DinnersProvided)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Workspace {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Expense {
+    Table,
+    Kind,
+    TripFrom,
+    TripTo,
+    DistanceKm,
+    Vehicle,
+    RatePerKm,
+    Country,
+    DepartureAt,
+    ReturnAt,
+    FullDays,
+    PartialDays,
+    BreakfastsProvided,
+    LunchesProvided,
+    DinnersProvided,
+}
+
+#[derive(DeriveIden)]
+enum MileageRate {
+    Table,
+    Id,
+    WorkspaceId,
+    Vehicle,
+    RatePerKm,
+    Currency,
+    ValidFrom,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum PerDiemRate {
+    Table,
+    Id,
+    WorkspaceId,
+    Country,
+    FullDay,
+    PartialDay,
+    Currency,
+    ValidFrom,
+    CreatedAt,
+}
@@ -0,0 +1,69 @@
+use sea_orm::Statement;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Concurrent dunning runs could issue a level twice; keep the earliest reminder.
+        manager
+            .get_connection()
+            .execute(Statement::from_string(
+                manager.get_database_backend(),
+                "DELETE FROM invoice_reminder a USING invoice_reminder b \
+                 WHERE a.invoice_id = b.invoice_id AND a.level = b.level \
+                 AND (a.created_at, a.id) > (b.created_at, b.id)",
+            ))
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_invoice_reminder_level")
+                    .table(InvoiceReminder::Table)
+                    .col(InvoiceReminder::InvoiceId)
+                    .col(InvoiceReminder::Level)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(InvoiceReminder::Table)
+                    .add_column(
+                        ColumnDef::new(InvoiceReminder::EmailAttempts)
+                            .integer()
+                            .not_null()
+                            .default(1),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(InvoiceReminder::Table)
+                    .drop_column(InvoiceReminder::EmailAttempts)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_index(Index::drop().name("idx_invoice_reminder_level").to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum InvoiceReminder {
+    Table,
+    InvoiceId,
+    Level,
+    EmailAttempts,
+}
@@ -0,0 +1,52 @@
+use sea_orm::Statement;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        let backend = manager.get_database_backend();
+        // Concurrent accepts could reconcile a transaction or expense twice; keep the first
+        // decision and put the others back up for review.
+        for column in ["bank_transaction_id", "expense_id"] {
+            db.execute(Statement::from_string(
+                backend,
+                format!(
+                    "UPDATE bank_match a SET status = 'proposed', decided_by = NULL, decided_at = NULL \
+                     FROM bank_match b \
+                     WHERE a.{column} = b.{column} \
+                     AND a.status = 'accepted' AND b.status = 'accepted' \
+                     AND (a.decided_at, a.id) > (b.decided_at, b.id)"
+                ),
+            ))
+            .await?;
+        }
+
+        // Partial indexes are not expressible with the schema builder.
+        db.execute(Statement::from_string(
+            backend,
+            "CREATE UNIQUE INDEX idx_bank_match_accepted_transaction \
+             ON bank_match (bank_transaction_id) WHERE status = 'accepted'",
+        ))
+        .await?;
+        db.execute(Statement::from_string(
+            backend,
+            "CREATE UNIQUE INDEX idx_bank_match_accepted_expense \
+             ON bank_match (expense_id) WHERE status = 'accepted'",
+        ))
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(Index::drop().name("idx_bank_match_accepted_expense").to_owned())
+            .await?;
+        manager
+            .drop_index(Index::drop().name("idx_bank_match_accepted_transaction").to_owned())
+            .await
+    }
+}
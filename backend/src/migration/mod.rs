@@ -2,11 +2,14 @@ pub use sea_orm_migration::prelude::*;
 
 mod m20260201_000001_create_invoices;
 mod m20260201_000002_create_auth;
+#[allow(clippy::needless_borrow)] // shipped migrations stay as applied
 mod m20260201_000003_company_and_addresses;
 mod m20260201_000004_company_registration_number;
 mod m20260201_000005_invoice_line_items;
 mod m20260201_000006_invoice_client_address;
+#[allow(clippy::needless_borrow)] // shipped migrations stay as applied
 mod m20260201_000007_company_invoice_owner;
+#[allow(clippy::needless_borrow)] // shipped migrations stay as applied
 mod m20260201_000008_invoice_templates;
 mod m20260201_000009_invoice_template_layout;
 mod m20260201_000010_invoice_template_note_default;
@@ -14,6 +17,28 @@ mod m20260201_000011_invoice_template_html;
 mod m20260201_000012_invoice_line_item_mode;
 mod m20260201_000013_invoice_number;
 mod m20260201_000014_expenses;
+mod m20260201_000015_company_contacts;
+mod m20260201_000016_invoice_delivery;
+mod m20260201_000017_dunning;
+mod m20260201_000018_account_tokens;
+mod m20260201_000019_two_factor;
+mod m20260201_000020_api_tokens;
+mod m20260201_000021_session_metadata;
+mod m20260201_000022_rate_limits;
+mod m20260201_000023_session_token_hash;
+mod m20260201_000024_workspaces;
+mod m20260201_000025_access_grants;
+mod m20260201_000026_account_deletion;
+mod m20260201_000027_oidc;
+mod m20260201_000028_expense_categories;
+mod m20260201_000029_expense_vat;
+mod m20260201_000030_receipts;
+mod m20260201_000031_receipt_confirmation;
+mod m20260201_000032_banking;
+mod m20260201_000033_reconciliation;
+mod m20260201_000034_travel_expenses;
+mod m20260201_000035_invoice_reminder_unique_level;
+mod m20260201_000036_bank_match_unique_accepted;
 
 pub struct Migrator;
 
@@ -35,6 +60,28 @@ impl MigratorTrait for Migrator {
             Box::new(m20260201_000012_invoice_line_item_mode::Migration),
             Box::new(m20260201_000013_invoice_number::Migration),
             Box::new(m20260201_000014_expenses::Migration),
+            Box::new(m20260201_000015_company_contacts::Migration),
+            Box::new(m20260201_000016_invoice_delivery::Migration),
+            Box::new(m20260201_000017_dunning::Migration),
+            Box::new(m20260201_000018_account_tokens::Migration),
+            Box::new(m20260201_000019_two_factor::Migration),
+            Box::new(m20260201_000020_api_tokens::Migration),
+            Box::new(m20260201_000021_session_metadata::Migration),
+            Box::new(m20260201_000022_rate_limits::Migration),
+            Box::new(m20260201_000023_session_token_hash::Migration),
+            Box::new(m20260201_000024_workspaces::Migration),
+            Box::new(m20260201_000025_access_grants::Migration),
+            Box::new(m20260201_000026_account_deletion::Migration),
+            Box::new(m20260201_000027_oidc::Migration),
+            Box::new(m20260201_000028_expense_categories::Migration),
+            Box::new(m20260201_000029_expense_vat::Migration),
+            Box::new(m20260201_000030_receipts::Migration),
+            Box::new(m20260201_000031_receipt_confirmation::Migration),
+            Box::new(m20260201_000032_banking::Migration),
+            Box::new(m20260201_000033_reconciliation::Migration),
+            Box::new(m20260201_000034_travel_expenses::Migration),
+            Box::new(m20260201_000035_invoice_reminder_unique_level::Migration),
+            Box::new(m20260201_000036_bank_match_unique_accepted::Migration),
         ]
     }
 }
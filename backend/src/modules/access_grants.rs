@@ -0,0 +1,846 @@
+use crate::entity::{access_grant, audit_event, expense, expense_category, invoice, workspace};
+use crate::modules::account::app_url;
+use crate::modules::audit::record_grant_access;
+use crate::modules::auth::{extract_bearer_token, generate_token, hash_token, require_session_user};
+use crate::modules::expenses::{ExpenseKind, ExpenseResponse};
+use crate::modules::invoices::{invoice_pdf_response, load_items_for_invoices, InvoiceResponse};
+use crate::modules::mail::{render_account_email, OutgoingMail, ACCOUNTANT_ACCESS_EMAIL};
+use crate::modules::receipts::{find_receipt, receipt_response, ReceiptResponse};
+use crate::modules::shared::{fetch_page, AppError, AppState, FieldError, Page, PageParams};
+use crate::modules::validation::{Path, Query, ValidatedJson};
+use crate::modules::workspaces::require_manager;
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::Response,
+    Json,
+};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use sea_orm::sea_query::Expr;
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, Set};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+/// Distinguishes accountant links from personal API tokens (`ffk_`).
+const TOKEN_PREFIX: &str = "ffa_";
+const DEFAULT_EXPIRY_DAYS: u32 = 30;
+const LAST_USED_RESOLUTION_SECS: i64 = 60;
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct CreateAccessGrantRequest {
+    /// Where the access link is sent
+    #[validate(email(message = "must be a valid email address"))]
+    pub email: String,
+    /// First day of the visible period
+    pub period_start: NaiveDate,
+    /// Last day of the visible period (inclusive)
+    pub period_end: NaiveDate,
+    /// Lifetime of the link in days (default 30, max 365)
+    #[validate(range(min = 1, max = 365))]
+    pub expires_in_days: Option<u32>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AccessGrantResponse {
+    pub id: Uuid,
+    pub email: String,
+    pub period_start: NaiveDate,
+    pub period_end: NaiveDate,
+    pub expires_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<access_grant::Model> for AccessGrantResponse {
+    fn from(model: access_grant::Model) -> Self {
+        Self {
+            id: model.id,
+            email: model.email,
+            period_start: model.period_start,
+            period_end: model.period_end,
+            expires_at: model.expires_at,
+            revoked_at: model.revoked_at,
+            last_used_at: model.last_used_at,
+            created_at: model.created_at,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AccessGrantEventResponse {
+    pub id: Uuid,
+    /// What was accessed, e.g. `invoices page 1` or `invoice <id> pdf`
+    pub detail: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AccountantGrantResponse {
+    pub workspace: String,
+    pub email: String,
+    pub period_start: NaiveDate,
+    pub period_end: NaiveDate,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CurrencyTotal {
+    pub currency: String,
+    pub count: u64,
+    pub total: f64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct KindTotal {
+    pub kind: ExpenseKind,
+    pub currency: String,
+    pub count: u64,
+    pub total: f64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CategoryTotal {
+    /// `null` for uncategorised expenses
+    pub category: Option<String>,
+    pub skr03_account: Option<String>,
+    pub skr04_account: Option<String>,
+    /// Tax deductible share of the category, `null` for uncategorised expenses
+    pub deductible_percent: Option<i32>,
+    pub currency: String,
+    pub count: u64,
+    pub total: f64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AccountantReportResponse {
+    pub period_start: NaiveDate,
+    pub period_end: NaiveDate,
+    /// Invoiced totals (gross) per currency, excluding cancelled invoices
+    pub invoices: Vec<CurrencyTotal>,
+    /// Expense totals per currency
+    pub expenses: Vec<CurrencyTotal>,
+    pub expenses_by_category: Vec<CategoryTotal>,
+    /// Receipt, mileage and per diem totals per currency
+    pub expenses_by_kind: Vec<KindTotal>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/workspaces/{id}/access-grants",
+    params(
+        ("id" = String, Path, description = "Workspace id (UUID)")
+    ),
+    request_body = CreateAccessGrantRequest,
+    responses(
+        (status = 201, description = "Access granted and link emailed", body = AccessGrantResponse),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Requires the owner or admin role", body = ErrorResponse),
+        (status = 404, description = "Workspace not found", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 502, description = "Mail delivery failed", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "accountant"
+)]
+pub async fn create_access_grant(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    ValidatedJson(payload): ValidatedJson<CreateAccessGrantRequest>,
+) -> Result<(StatusCode, Json<AccessGrantResponse>), AppError> {
+    let current_user = require_session_user(&state, &headers).await?;
+    let id = Uuid::parse_str(&id).map_err(|_| AppError::bad_request("Invalid id"))?;
+    require_manager(&state.db, id, current_user.id).await?;
+    if payload.period_end < payload.period_start {
+        return Err(AppError::Validation(vec![FieldError::new(
+            "period_end",
+            "range",
+            "must not be before period_start",
+        )]));
+    }
+    let workspace = workspace::Entity::find_by_id(id)
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Workspace not found"))?;
+
+    let email = payload.email.trim().to_lowercase();
+    let token = format!("{}{}", TOKEN_PREFIX, generate_token());
+    let now = Utc::now();
+    let days = payload.expires_in_days.unwrap_or(DEFAULT_EXPIRY_DAYS);
+    let grant = access_grant::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        workspace_id: Set(id),
+        email: Set(email.clone()),
+        token_hash: Set(hash_token(&token)),
+        period_start: Set(payload.period_start),
+        period_end: Set(payload.period_end),
+        expires_at: Set(now + Duration::days(i64::from(days))),
+        revoked_at: Set(None),
+        last_used_at: Set(None),
+        created_by: Set(Some(current_user.id)),
+        created_at: Set(now),
+    }
+    .insert(&state.db)
+    .await?;
+
+    let ctx = json!({
+        "email": email,
+        "inviter": current_user.email,
+        "workspace": workspace.name,
+        "period_start": grant.period_start.to_string(),
+        "period_end": grant.period_end.to_string(),
+        "expires_at": grant.expires_at.date_naive().to_string(),
+        "link": format!("{}/accountant?token={}", app_url(), token),
+    });
+    let rendered = render_account_email(ACCOUNTANT_ACCESS_EMAIL, &ctx)?;
+    state
+        .mailer
+        .send(OutgoingMail {
+            to: vec![email],
+            subject: rendered.subject,
+            html: rendered.html,
+            text: rendered.text,
+            attachments: Vec::new(),
+        })
+        .await
+        .map_err(AppError::bad_gateway)?;
+
+    Ok((StatusCode::CREATED, Json(grant.into())))
+}
+
+#[utoipa::path(
+    get,
+    path = "/workspaces/{id}/access-grants",
+    params(
+        ("id" = String, Path, description = "Workspace id (UUID)")
+    ),
+    responses(
+        (status = 200, description = "Access grants, newest first", body = [AccessGrantResponse]),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Requires the owner or admin role", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "accountant"
+)]
+pub async fn list_access_grants(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<AccessGrantResponse>>, AppError> {
+    let current_user = require_session_user(&state, &headers).await?;
+    let id = Uuid::parse_str(&id).map_err(|_| AppError::bad_request("Invalid id"))?;
+    require_manager(&state.db, id, current_user.id).await?;
+
+    let grants = access_grant::Entity::find()
+        .filter(access_grant::Column::WorkspaceId.eq(id))
+        .order_by_desc(access_grant::Column::CreatedAt)
+        .all(&state.db)
+        .await?;
+
+    Ok(Json(grants.into_iter().map(AccessGrantResponse::from).collect()))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/workspaces/{id}/access-grants/{grant_id}",
+    params(
+        ("id" = String, Path, description = "Workspace id (UUID)"),
+        ("grant_id" = String, Path, description = "Access grant id (UUID)")
+    ),
+    responses(
+        (status = 204, description = "Access revoked"),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Requires the owner or admin role", body = ErrorResponse),
+        (status = 404, description = "Access grant not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "accountant"
+)]
+pub async fn revoke_access_grant(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((id, grant_id)): Path<(String, String)>,
+) -> Result<StatusCode, AppError> {
+    let current_user = require_session_user(&state, &headers).await?;
+    let id = Uuid::parse_str(&id).map_err(|_| AppError::bad_request("Invalid id"))?;
+    let grant_id = Uuid::parse_str(&grant_id).map_err(|_| AppError::bad_request("Invalid id"))?;
+    require_manager(&state.db, id, current_user.id).await?;
+
+    let result = access_grant::Entity::update_many()
+        .col_expr(access_grant::Column::RevokedAt, Expr::value(Utc::now()))
+        .filter(access_grant::Column::Id.eq(grant_id))
+        .filter(access_grant::Column::WorkspaceId.eq(id))
+        .filter(access_grant::Column::RevokedAt.is_null())
+        .exec(&state.db)
+        .await?;
+    if result.rows_affected == 0 {
+        return Err(AppError::not_found("Access grant not found"));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/workspaces/{id}/access-grants/{grant_id}/audit",
+    params(
+        ("id" = String, Path, description = "Workspace id (UUID)"),
+        ("grant_id" = String, Path, description = "Access grant id (UUID)")
+    ),
+    responses(
+        (status = 200, description = "Every access made with the grant, newest first", body = [AccessGrantEventResponse]),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Requires the owner or admin role", body = ErrorResponse),
+        (status = 404, description = "Access grant not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "accountant"
+)]
+pub async fn list_access_grant_events(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((id, grant_id)): Path<(String, String)>,
+) -> Result<Json<Vec<AccessGrantEventResponse>>, AppError> {
+    let current_user = require_session_user(&state, &headers).await?;
+    let id = Uuid::parse_str(&id).map_err(|_| AppError::bad_request("Invalid id"))?;
+    let grant_id = Uuid::parse_str(&grant_id).map_err(|_| AppError::bad_request("Invalid id"))?;
+    require_manager(&state.db, id, current_user.id).await?;
+
+    access_grant::Entity::find_by_id(grant_id)
+        .filter(access_grant::Column::WorkspaceId.eq(id))
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Access grant not found"))?;
+    let events = audit_event::Entity::find()
+        .filter(audit_event::Column::AccessGrantId.eq(grant_id))
+        .order_by_desc(audit_event::Column::CreatedAt)
+        .all(&state.db)
+        .await?;
+
+    Ok(Json(
+        events
+            .into_iter()
+            .map(|event| AccessGrantEventResponse {
+                id: event.id,
+                detail: event.detail,
+                ip_address: event.ip_address,
+                created_at: event.created_at,
+            })
+            .collect(),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/accountant/grant",
+    responses(
+        (status = 200, description = "Workspace and period the access link covers", body = AccountantGrantResponse),
+        (status = 401, description = "Missing, revoked or expired access link", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "accountant"
+)]
+pub async fn get_accountant_grant(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<AccountantGrantResponse>, AppError> {
+    let grant = authenticate_grant(&state, &headers).await?;
+    let workspace = workspace::Entity::find_by_id(grant.workspace_id)
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::unauthorized("Invalid access link"))?;
+    record_grant_access(&state.db, &grant, "grant".to_string()).await?;
+
+    Ok(Json(AccountantGrantResponse {
+        workspace: workspace.name,
+        email: grant.email,
+        period_start: grant.period_start,
+        period_end: grant.period_end,
+        expires_at: grant.expires_at,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/accountant/invoices",
+    params(PageParams),
+    responses(
+        (status = 200, description = "Invoices dated within the granted period", body = InvoicePage),
+        (status = 400, description = "Invalid query", body = ErrorResponse),
+        (status = 401, description = "Missing, revoked or expired access link", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "accountant"
+)]
+pub async fn list_accountant_invoices(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(paging): Query<PageParams>,
+) -> Result<Json<Page<InvoiceResponse>>, AppError> {
+    let grant = authenticate_grant(&state, &headers).await?;
+    let select = invoice::Entity::find()
+        .filter(invoice::Column::WorkspaceId.eq(grant.workspace_id))
+        .filter(invoice::Column::Date.between(grant.period_start, grant.period_end))
+        .order_by_asc(invoice::Column::Date)
+        .order_by_asc(invoice::Column::InvoiceNumber);
+
+    let page = fetch_page(&state.db, select, &paging).await?;
+    let ids: Vec<Uuid> = page.items.iter().map(|item| item.id).collect();
+    let mut items_by_invoice = load_items_for_invoices(&state.db, &ids).await?;
+    record_grant_access(&state.db, &grant, format!("invoices page {}", page.page)).await?;
+
+    Ok(Json(page.map(|item| {
+        let items = items_by_invoice.remove(&item.id).unwrap_or_default();
+        InvoiceResponse::with_items(item, Some(items))
+    })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/accountant/invoices/{id}/pdf",
+    params(
+        ("id" = String, Path, description = "Invoice id (UUID)")
+    ),
+    responses(
+        (status = 200, description = "Invoice PDF"),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 401, description = "Missing, revoked or expired access link", body = ErrorResponse),
+        (status = 404, description = "Invoice not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "accountant"
+)]
+pub async fn get_accountant_invoice_pdf(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Response, AppError> {
+    let grant = authenticate_grant(&state, &headers).await?;
+    let id = Uuid::parse_str(&id).map_err(|_| AppError::bad_request("Invalid id"))?;
+    let invoice = invoice::Entity::find_by_id(id)
+        .filter(invoice::Column::WorkspaceId.eq(grant.workspace_id))
+        .filter(invoice::Column::Date.between(grant.period_start, grant.period_end))
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Invoice not found"))?;
+
+    let response = invoice_pdf_response(&state.db, &invoice).await?;
+    record_grant_access(&state.db, &grant, format!("invoice {} pdf", invoice.invoice_number))
+        .await?;
+    Ok(response)
+}
+
+#[utoipa::path(
+    get,
+    path = "/accountant/expenses",
+    params(PageParams),
+    responses(
+        (status = 200, description = "Expenses dated within the granted period, with receipt links", body = ExpensePage),
+        (status = 400, description = "Invalid query", body = ErrorResponse),
+        (status = 401, description = "Missing, revoked or expired access link", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "accountant"
+)]
+pub async fn list_accountant_expenses(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(paging): Query<PageParams>,
+) -> Result<Json<Page<ExpenseResponse>>, AppError> {
+    let grant = authenticate_grant(&state, &headers).await?;
+    let select = expense::Entity::find()
+        .filter(expense::Column::WorkspaceId.eq(grant.workspace_id))
+        .filter(expense::Column::Date.between(grant.period_start, grant.period_end))
+        .order_by_asc(expense::Column::Date)
+        .order_by_asc(expense::Column::Id);
+
+    let page = fetch_page(&state.db, select, &paging).await?;
+    record_grant_access(&state.db, &grant, format!("expenses page {}", page.page)).await?;
+    Ok(Json(page.map(ExpenseResponse::from)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/accountant/receipts/{id}",
+    params(
+        ("id" = String, Path, description = "Receipt id (UUID)")
+    ),
+    responses(
+        (status = 200, description = "Receipt of an expense within the granted period, with a download link", body = ReceiptResponse),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 401, description = "Missing, revoked or expired access link", body = ErrorResponse),
+        (status = 404, description = "Receipt not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "accountant"
+)]
+pub async fn get_accountant_receipt(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ReceiptResponse>, AppError> {
+    let grant = authenticate_grant(&state, &headers).await?;
+    let id = Uuid::parse_str(&id).map_err(|_| AppError::bad_request("Invalid id"))?;
+    let expense = expense::Entity::find()
+        .filter(expense::Column::WorkspaceId.eq(grant.workspace_id))
+        .filter(expense::Column::ReceiptId.eq(id))
+        .filter(expense::Column::Date.between(grant.period_start, grant.period_end))
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Receipt not found"))?;
+    let receipt = find_receipt(&state.db, grant.workspace_id, id).await?;
+
+    let response = receipt_response(state.receipts.as_ref(), receipt).await?;
+    record_grant_access(
+        &state.db,
+        &grant,
+        format!("receipt of expense {} ({})", expense.id, expense.vendor),
+    )
+    .await?;
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    get,
+    path = "/accountant/report",
+    responses(
+        (status = 200, description = "Income and expense totals for the granted period", body = AccountantReportResponse),
+        (status = 401, description = "Missing, revoked or expired access link", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "accountant"
+)]
+pub async fn get_accountant_report(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<AccountantReportResponse>, AppError> {
+    let grant = authenticate_grant(&state, &headers).await?;
+    let invoices = invoice::Entity::find()
+        .filter(invoice::Column::WorkspaceId.eq(grant.workspace_id))
+        .filter(invoice::Column::Date.between(grant.period_start, grant.period_end))
+        .filter(invoice::Column::Status.ne("cancelled"))
+        .all(&state.db)
+        .await?;
+    let expenses = expense::Entity::find()
+        .filter(expense::Column::WorkspaceId.eq(grant.workspace_id))
+        .filter(expense::Column::Date.between(grant.period_start, grant.period_end))
+        .all(&state.db)
+        .await?;
+    let categories: HashMap<String, expense_category::Model> = expense_category::Entity::find()
+        .filter(expense_category::Column::WorkspaceId.eq(grant.workspace_id))
+        .all(&state.db)
+        .await?
+        .into_iter()
+        .map(|category| (category.name.clone(), category))
+        .collect();
+
+    let mut invoice_totals: BTreeMap<String, (u64, f64)> = BTreeMap::new();
+    for invoice in &invoices {
+        let entry = invoice_totals.entry(invoice.currency.clone()).or_default();
+        entry.0 += 1;
+        entry.1 += invoice.total_amount;
+    }
+    let mut expense_totals: BTreeMap<String, (u64, f64)> = BTreeMap::new();
+    let mut category_totals: BTreeMap<(Option<String>, String), (u64, f64)> = BTreeMap::new();
+    let mut kind_totals: BTreeMap<(String, String), (u64, f64)> = BTreeMap::new();
+    for expense in &expenses {
+        let entry = expense_totals.entry(expense.currency.clone()).or_default();
+        entry.0 += 1;
+        entry.1 += expense.amount;
+        let entry = category_totals
+            .entry((expense.category.clone(), expense.currency.clone()))
+            .or_default();
+        entry.0 += 1;
+        entry.1 += expense.amount;
+        let entry = kind_totals
+            .entry((expense.kind.clone(), expense.currency.clone()))
+            .or_default();
+        entry.0 += 1;
+        entry.1 += expense.amount;
+    }
+    record_grant_access(&state.db, &grant, "report".to_string()).await?;
+
+    Ok(Json(AccountantReportResponse {
+        period_start: grant.period_start,
+        period_end: grant.period_end,
+        invoices: currency_totals(invoice_totals),
+        expenses: currency_totals(expense_totals),
+        expenses_by_category: category_totals
+            .into_iter()
+            .map(|((category, currency), (count, total))| {
+                let mapped = category.as_ref().and_then(|name| categories.get(name));
+                CategoryTotal {
+                    skr03_account: mapped.and_then(|c| c.skr03_account.clone()),
+                    skr04_account: mapped.and_then(|c| c.skr04_account.clone()),
+                    deductible_percent: mapped.map(|c| c.deductible_percent),
+                    category,
+                    currency,
+                    count,
+                    total: round_cents(total),
+                }
+            })
+            .collect(),
+        expenses_by_kind: kind_totals
+            .into_iter()
+            .map(|((kind, currency), (count, total))| KindTotal {
+                kind: ExpenseKind::from_stored(&kind),
+                currency,
+                count,
+                total: round_cents(total),
+            })
+            .collect(),
+    }))
+}
+
+/// Resolves an `Authorization: Bearer ffa_...` header to a live access grant.
+async fn authenticate_grant(
+    state: &AppState,
+    headers: &HeaderMap,
+) -> Result<access_grant::Model, AppError> {
+    let token = extract_bearer_token(headers)
+        .filter(|token| token.starts_with(TOKEN_PREFIX))
+        .ok_or_else(|| AppError::unauthorized("Access link required"))?;
+    let now = Utc::now();
+    let grant = access_grant::Entity::find()
+        .filter(access_grant::Column::TokenHash.eq(hash_token(&token)))
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::unauthorized("Invalid access link"))?;
+    if grant.revoked_at.is_some() {
+        return Err(AppError::unauthorized("Access link revoked"));
+    }
+    if grant.expires_at <= now {
+        return Err(AppError::unauthorized("Access link expired"));
+    }
+
+    let stale = grant
+        .last_used_at
+        .is_none_or(|used| now - used >= Duration::seconds(LAST_USED_RESOLUTION_SECS));
+    if stale {
+        access_grant::Entity::update_many()
+            .col_expr(access_grant::Column::LastUsedAt, Expr::value(now))
+            .filter(access_grant::Column::Id.eq(grant.id))
+            .exec(&state.db)
+            .await?;
+    }
+
+    Ok(grant)
+}
+
+fn currency_totals(totals: BTreeMap<String, (u64, f64)>) -> Vec<CurrencyTotal> {
+    totals
+        .into_iter()
+        .map(|(currency, (count, total))| CurrencyTotal {
+            currency,
+            count,
+            total: round_cents(total),
+        })
+        .collect()
+}
+
+fn round_cents(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::{header::AUTHORIZATION, HeaderValue};
+    use sea_orm::{DatabaseBackend, MockDatabase, QueryTrait, Transaction};
+
+    const TOKEN: &str = "ffa_test-link";
+
+    fn workspace_id() -> Uuid {
+        Uuid::from_u128(10)
+    }
+
+    fn period() -> (NaiveDate, NaiveDate) {
+        (
+            NaiveDate::from_ymd_opt(2026, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2026, 3, 31).unwrap(),
+        )
+    }
+
+    fn grant() -> access_grant::Model {
+        let now = Utc::now();
+        let (period_start, period_end) = period();
+        access_grant::Model {
+            id: Uuid::from_u128(20),
+            workspace_id: workspace_id(),
+            email: "accountant@example.com".to_string(),
+            token_hash: hash_token(TOKEN),
+            period_start,
+            period_end,
+            expires_at: now + Duration::days(30),
+            revoked_at: None,
+            // Recently used, so authenticating does not write `last_used_at`.
+            last_used_at: Some(now),
+            created_by: None,
+            created_at: now,
+        }
+    }
+
+    fn headers(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        let value = format!("Bearer {token}");
+        headers.insert(AUTHORIZATION, HeaderValue::from_str(&value).unwrap());
+        headers
+    }
+
+    fn audit_event() -> audit_event::Model {
+        audit_event::Model {
+            id: Uuid::from_u128(30),
+            user_id: None,
+            event: "accountant_access".to_string(),
+            ip_address: None,
+            detail: None,
+            access_grant_id: Some(grant().id),
+            created_at: Utc::now(),
+        }
+    }
+
+    /// `SELECT <all columns> FROM "<table>"` for `E`.
+    fn select_all<E: EntityTrait>() -> String {
+        E::find().build(DatabaseBackend::Postgres).sql
+    }
+
+    fn query(sql: String, values: Vec<sea_orm::Value>) -> Transaction {
+        Transaction::from_sql_and_values(DatabaseBackend::Postgres, sql, values)
+    }
+
+    fn take_log(state: AppState) -> Vec<Transaction> {
+        let AppState { db, .. } = state;
+        db.into_transaction_log()
+    }
+
+    #[tokio::test]
+    async fn invoices_outside_the_granted_period_are_not_found() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([[grant()]])
+            .append_query_results([Vec::<invoice::Model>::new()])
+            .into_connection();
+        let state = AppState::for_tests(db);
+        let id = Uuid::from_u128(40);
+        let result = get_accountant_invoice_pdf(State(state.clone()), headers(TOKEN), Path(id.to_string()));
+        let error = result.await.err().unwrap();
+
+        assert_eq!(error.status(), StatusCode::NOT_FOUND);
+        let (start, end) = period();
+        assert_eq!(
+            take_log(state)[1],
+            query(
+                format!(
+                    r#"{} WHERE "invoice"."id" = $1 AND "invoice"."workspace_id" = $2 AND ("invoice"."date" BETWEEN $3 AND $4) LIMIT $5"#,
+                    select_all::<invoice::Entity>()
+                ),
+                vec![id.into(), workspace_id().into(), start.into(), end.into(), 1u64.into()],
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn receipts_of_expenses_outside_the_granted_period_are_not_found() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([[grant()]])
+            .append_query_results([Vec::<expense::Model>::new()])
+            .into_connection();
+        let state = AppState::for_tests(db);
+        let id = Uuid::from_u128(50);
+        let result =
+            get_accountant_receipt(State(state.clone()), headers(TOKEN), Path(id.to_string()));
+        let error = result.await.err().unwrap();
+
+        assert_eq!(error.status(), StatusCode::NOT_FOUND);
+        let (start, end) = period();
+        assert_eq!(
+            take_log(state)[1],
+            query(
+                format!(
+                    r#"{} WHERE "expense"."workspace_id" = $1 AND "expense"."receipt_id" = $2 AND ("expense"."date" BETWEEN $3 AND $4) LIMIT $5"#,
+                    select_all::<expense::Entity>()
+                ),
+                vec![workspace_id().into(), id.into(), start.into(), end.into(), 1u64.into()],
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn the_report_only_totals_documents_within_the_granted_period() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([[grant()]])
+            .append_query_results([Vec::<invoice::Model>::new()])
+            .append_query_results([Vec::<expense::Model>::new()])
+            .append_query_results([Vec::<expense_category::Model>::new()])
+            .append_query_results([[audit_event()]])
+            .into_connection();
+        let state = AppState::for_tests(db);
+        let Json(report) = get_accountant_report(State(state.clone()), headers(TOKEN))
+            .await
+            .ok()
+            .unwrap();
+
+        let (start, end) = period();
+        assert_eq!((report.period_start, report.period_end), (start, end));
+        assert!(report.invoices.is_empty() && report.expenses.is_empty());
+        let log = take_log(state);
+        assert_eq!(
+            log[1],
+            query(
+                format!(
+                    r#"{} WHERE "invoice"."workspace_id" = $1 AND ("invoice"."date" BETWEEN $2 AND $3) AND "invoice"."status" <> $4"#,
+                    select_all::<invoice::Entity>()
+                ),
+                vec![workspace_id().into(), start.into(), end.into(), "cancelled".into()],
+            )
+        );
+        assert_eq!(
+            log[2],
+            query(
+                format!(
+                    r#"{} WHERE "expense"."workspace_id" = $1 AND ("expense"."date" BETWEEN $2 AND $3)"#,
+                    select_all::<expense::Entity>()
+                ),
+                vec![workspace_id().into(), start.into(), end.into()],
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn revoked_expired_and_foreign_tokens_are_rejected() {
+        let revoked = access_grant::Model {
+            revoked_at: Some(Utc::now()),
+            ..grant()
+        };
+        let expired = access_grant::Model {
+            expires_at: Utc::now() - Duration::minutes(1),
+            ..grant()
+        };
+        let cases = [(revoked, "Access link revoked"), (expired, "Access link expired")];
+        for (grant, message) in cases {
+            let db = MockDatabase::new(DatabaseBackend::Postgres)
+                .append_query_results([[grant]])
+                .into_connection();
+            let error = get_accountant_grant(State(AppState::for_tests(db)), headers(TOKEN))
+                .await
+                .err()
+                .unwrap();
+            assert_eq!(error.status(), StatusCode::UNAUTHORIZED);
+            assert_eq!(error.to_string(), message);
+        }
+
+        let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+        let result = get_accountant_grant(State(AppState::for_tests(db)), headers("ffk_api-token"));
+        let error = result.await.err().unwrap();
+        assert_eq!(error.to_string(), "Access link required");
+    }
+}
@@ -0,0 +1,395 @@
+use crate::entity::{api_token, auth_token, session, user};
+use crate::modules::auth::{
+    create_session, generate_token, hash_password, hash_token, require_session_user,
+    verify_password,
+};
+use crate::modules::mail::{render_account_email, OutgoingMail, PASSWORD_RESET_EMAIL, VERIFY_EMAIL};
+use crate::modules::shared::{AppError, AppState};
+use crate::modules::validation::{not_blank, ValidatedJson};
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+};
+use chrono::{Duration, Utc};
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, Set,
+    TransactionTrait,
+};
+use serde::Deserialize;
+use serde_json::json;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+const VERIFY_EMAIL_PURPOSE: &str = "verify_email";
+const PASSWORD_RESET_PURPOSE: &str = "password_reset";
+const VERIFY_EMAIL_TTL_HOURS: i64 = 48;
+const PASSWORD_RESET_TTL_HOURS: i64 = 1;
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct TokenRequest {
+    #[validate(custom(function = not_blank))]
+    pub token: String,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct PasswordResetRequest {
+    #[validate(email(message = "must be a valid email address"))]
+    pub email: String,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct PasswordResetConfirmRequest {
+    #[validate(custom(function = not_blank))]
+    pub token: String,
+    #[validate(length(min = 8, max = 128, message = "must be between 8 and 128 characters"))]
+    pub password: String,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct ChangePasswordRequest {
+    #[validate(custom(function = not_blank))]
+    pub current_password: String,
+    #[validate(length(min = 8, max = 128, message = "must be between 8 and 128 characters"))]
+    pub new_password: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/verify-email/send",
+    responses(
+        (status = 204, description = "Verification email sent"),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Requires a browser session", body = ErrorResponse),
+        (status = 409, description = "Email already verified", body = ErrorResponse),
+        (status = 502, description = "Mail delivery failed", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "auth"
+)]
+pub async fn send_email_verification(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<StatusCode, AppError> {
+    let current_user = require_session_user(&state, &headers).await?;
+    if current_user.email_verified_at.is_some() {
+        return Err(AppError::conflict("Email already verified"));
+    }
+    send_verification_email(&state, &current_user).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/verify-email",
+    request_body = TokenRequest,
+    responses(
+        (status = 204, description = "Email verified"),
+        (status = 400, description = "Invalid or expired token", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "auth"
+)]
+pub async fn verify_email(
+    State(state): State<AppState>,
+    ValidatedJson(payload): ValidatedJson<TokenRequest>,
+) -> Result<StatusCode, AppError> {
+    let txn = state.db.begin().await?;
+    let token = consume_token(&txn, VERIFY_EMAIL_PURPOSE, &payload.token).await?;
+    let user = user::Entity::find_by_id(token.user_id)
+        .one(&txn)
+        .await?
+        .ok_or_else(|| AppError::bad_request("Invalid or expired token"))?;
+    if user.email_verified_at.is_none() {
+        let mut active: user::ActiveModel = user.into();
+        active.email_verified_at = Set(Some(Utc::now()));
+        active.update(&txn).await?;
+    }
+    txn.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/password-reset/request",
+    request_body = PasswordResetRequest,
+    responses(
+        (status = 202, description = "A reset link is sent if the account exists"),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "auth"
+)]
+pub async fn request_password_reset(
+    State(state): State<AppState>,
+    ValidatedJson(payload): ValidatedJson<PasswordResetRequest>,
+) -> Result<StatusCode, AppError> {
+    let email = payload.email.trim().to_lowercase();
+    let user = user::Entity::find()
+        .filter(user::Column::Email.eq(email))
+        .one(&state.db)
+        .await?;
+
+    // Always answer the same way, and equally fast, so the endpoint cannot be used to
+    // probe for accounts: the token and the mail are handled off the request path.
+    if let Some(user) = user {
+        let state = state.clone();
+        tokio::spawn(async move {
+            if let Err(error) = send_password_reset_email(&state, &user).await {
+                eprintln!("Password reset email for {} failed: {error}", user.id);
+            }
+        });
+    }
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+async fn send_password_reset_email(state: &AppState, user: &user::Model) -> Result<(), AppError> {
+    let token = issue_token(
+        &state.db,
+        user.id,
+        PASSWORD_RESET_PURPOSE,
+        Duration::hours(PASSWORD_RESET_TTL_HOURS),
+    )
+    .await?;
+    let ctx = json!({
+        "email": user.email,
+        "link": format!("{}/reset-password?token={}", app_url(), token),
+        "valid_hours": PASSWORD_RESET_TTL_HOURS,
+    });
+    send_account_email(state, &user.email, PASSWORD_RESET_EMAIL, &ctx).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/password-reset",
+    request_body = PasswordResetConfirmRequest,
+    responses(
+        (status = 204, description = "Password changed; all sessions were signed out and API tokens revoked"),
+        (status = 400, description = "Invalid or expired token", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "auth"
+)]
+pub async fn reset_password(
+    State(state): State<AppState>,
+    ValidatedJson(payload): ValidatedJson<PasswordResetConfirmRequest>,
+) -> Result<StatusCode, AppError> {
+    let password_hash = hash_password(&payload.password).map_err(AppError::internal)?;
+
+    let txn = state.db.begin().await?;
+    let token = consume_token(&txn, PASSWORD_RESET_PURPOSE, &payload.token).await?;
+    let user = user::Entity::find_by_id(token.user_id)
+        .one(&txn)
+        .await?
+        .ok_or_else(|| AppError::bad_request("Invalid or expired token"))?;
+
+    let verified_at = user.email_verified_at.unwrap_or_else(Utc::now);
+    let mut active: user::ActiveModel = user.into();
+    active.password_hash = Set(password_hash);
+    // Following the emailed link proves the user controls the address.
+    active.email_verified_at = Set(Some(verified_at));
+    let user = active.update(&txn).await?;
+    end_all_sessions(&txn, user.id).await?;
+    revoke_api_tokens(&txn, user.id).await?;
+    auth_token::Entity::delete_many()
+        .filter(auth_token::Column::UserId.eq(user.id))
+        .filter(auth_token::Column::Purpose.eq(PASSWORD_RESET_PURPOSE))
+        .filter(auth_token::Column::UsedAt.is_null())
+        .exec(&txn)
+        .await?;
+    txn.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/password",
+    request_body = ChangePasswordRequest,
+    responses(
+        (status = 204, description = "Password changed; other sessions were signed out and API tokens revoked"),
+        (status = 400, description = "Current password is wrong", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Requires a browser session", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "auth"
+)]
+pub async fn change_password(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidatedJson(payload): ValidatedJson<ChangePasswordRequest>,
+) -> Result<(HeaderMap, StatusCode), AppError> {
+    let current_user = require_session_user(&state, &headers).await?;
+    verify_password(&payload.current_password, &current_user.password_hash)
+        .map_err(|_| AppError::bad_request("Current password is wrong"))?;
+    let password_hash = hash_password(&payload.new_password).map_err(AppError::internal)?;
+
+    let txn = state.db.begin().await?;
+    let user_id = current_user.id;
+    let mut active: user::ActiveModel = current_user.into();
+    active.password_hash = Set(password_hash);
+    active.update(&txn).await?;
+    end_all_sessions(&txn, user_id).await?;
+    revoke_api_tokens(&txn, user_id).await?;
+    txn.commit().await?;
+
+    // The caller keeps working with a fresh session; every other device is signed out.
+    let (_session, cookie) = create_session(&state.db, user_id, &headers).await?;
+    let mut response_headers = HeaderMap::new();
+    response_headers.insert(axum::http::header::SET_COOKIE, cookie);
+    Ok((response_headers, StatusCode::NO_CONTENT))
+}
+
+/// Issues a fresh verification token for `user` and emails the confirmation link.
+pub(crate) async fn send_verification_email(
+    state: &AppState,
+    user: &user::Model,
+) -> Result<(), AppError> {
+    let token = issue_token(
+        &state.db,
+        user.id,
+        VERIFY_EMAIL_PURPOSE,
+        Duration::hours(VERIFY_EMAIL_TTL_HOURS),
+    )
+    .await?;
+    let ctx = json!({
+        "email": user.email,
+        "link": format!("{}/verify-email?token={}", app_url(), token),
+        "valid_hours": VERIFY_EMAIL_TTL_HOURS,
+    });
+    send_account_email(state, &user.email, VERIFY_EMAIL, &ctx).await
+}
+
+pub(crate) async fn end_all_sessions<C: ConnectionTrait>(
+    db: &C,
+    user_id: Uuid,
+) -> Result<(), AppError> {
+    session::Entity::delete_many()
+        .filter(session::Column::UserId.eq(user_id))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// API tokens were issued under the old password, so a password change revokes them
+/// like it ends sessions; integrations need a new token afterwards.
+async fn revoke_api_tokens<C: ConnectionTrait>(db: &C, user_id: Uuid) -> Result<(), AppError> {
+    api_token::Entity::delete_many()
+        .filter(api_token::Column::UserId.eq(user_id))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+async fn send_account_email(
+    state: &AppState,
+    to: &str,
+    kind: &str,
+    ctx: &serde_json::Value,
+) -> Result<(), AppError> {
+    let email = render_account_email(kind, ctx)?;
+    state
+        .mailer
+        .send(OutgoingMail {
+            to: vec![to.to_string()],
+            subject: email.subject,
+            html: email.html,
+            text: email.text,
+            attachments: Vec::new(),
+        })
+        .await
+        .map_err(AppError::bad_gateway)
+}
+
+/// Replaces any outstanding token of the same purpose and returns the new plain token.
+pub(crate) async fn issue_token<C: ConnectionTrait>(
+    db: &C,
+    user_id: Uuid,
+    purpose: &str,
+    ttl: Duration,
+) -> Result<String, AppError> {
+    auth_token::Entity::delete_many()
+        .filter(auth_token::Column::UserId.eq(user_id))
+        .filter(auth_token::Column::Purpose.eq(purpose))
+        .filter(auth_token::Column::UsedAt.is_null())
+        .exec(db)
+        .await?;
+
+    let token = generate_token();
+    let now = Utc::now();
+    auth_token::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        user_id: Set(user_id),
+        purpose: Set(purpose.to_string()),
+        token_hash: Set(hash_token(&token)),
+        expires_at: Set(now + ttl),
+        used_at: Set(None),
+        created_at: Set(now),
+    }
+    .insert(db)
+    .await?;
+
+    Ok(token)
+}
+
+/// Looks up an unexpired, unused token without redeeming it.
+pub(crate) async fn find_valid_token<C: ConnectionTrait>(
+    db: &C,
+    purpose: &str,
+    token: &str,
+) -> Result<auth_token::Model, AppError> {
+    let now = Utc::now();
+    auth_token::Entity::find()
+        .filter(auth_token::Column::TokenHash.eq(hash_token(token.trim())))
+        .filter(auth_token::Column::Purpose.eq(purpose))
+        .one(db)
+        .await?
+        .filter(|record| record.used_at.is_none() && record.expires_at > now)
+        .ok_or_else(|| AppError::bad_request("Invalid or expired token"))
+}
+
+/// Marks an unexpired, unused token as used. The conditional update makes
+/// concurrent redemptions of the same token fail.
+pub(crate) async fn consume_token<C: ConnectionTrait>(
+    db: &C,
+    purpose: &str,
+    token: &str,
+) -> Result<auth_token::Model, AppError> {
+    let record = find_valid_token(db, purpose, token).await?;
+    let result = auth_token::Entity::update_many()
+        .col_expr(auth_token::Column::UsedAt, Expr::value(Utc::now()))
+        .filter(auth_token::Column::Id.eq(record.id))
+        .filter(auth_token::Column::UsedAt.is_null())
+        .exec(db)
+        .await?;
+    if result.rows_affected != 1 {
+        return Err(AppError::bad_request("Invalid or expired token"));
+    }
+
+    Ok(record)
+}
+
+/// Base URL of the frontend, used to build links in account emails.
+pub(crate) fn app_url() -> String {
+    std::env::var("APP_URL")
+        .or_else(|_| std::env::var("FRONTEND_ORIGIN"))
+        .or_else(|_| std::env::var("CORS_ORIGIN"))
+        .unwrap_or_else(|_| "http://localhost:5173".to_string())
+        .trim_end_matches('/')
+        .to_string()
+}
+
+/// Public base URL of this API, for links that point back at the backend.
+pub(crate) fn api_url() -> String {
+    std::env::var("API_URL")
+        .unwrap_or_else(|_| "http://localhost:3000".to_string())
+        .trim_end_matches('/')
+        .to_string()
+}
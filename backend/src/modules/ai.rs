@@ -1,14 +1,21 @@
 use crate::entity::{invoice, invoice_line_item};
-use crate::modules::auth::require_user;
-use crate::modules::shared::AppState;
-use axum::{extract::State, http::HeaderMap, http::StatusCode, Json};
+use crate::modules::api_tokens::ApiScope;
+use crate::modules::auth::require_access;
+use crate::modules::ocr::OcrEngine;
+use crate::modules::shared::{AppError, AppState};
+use crate::modules::validation::{not_blank, ValidatedJson};
+use async_trait::async_trait;
+use axum::{extract::State, http::HeaderMap, Json};
+use base64::Engine;
 use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use uuid::Uuid;
+use validator::Validate;
 
-#[derive(Deserialize, ToSchema)]
+#[derive(Deserialize, ToSchema, Validate)]
 pub struct ImproveLineItemRequest {
+    #[validate(custom(function = not_blank), length(max = 1000))]
     pub description: String,
 }
 
@@ -29,23 +36,21 @@ pub struct LastLineItemResponse {
     request_body = ImproveLineItemRequest,
     responses(
         (status = 200, description = "Improved description", body = ImproveLineItemResponse),
-        (status = 400, description = "Invalid input"),
-        (status = 401, description = "Not authenticated"),
-        (status = 500, description = "Server error")
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
     ),
     tag = "ai"
 )]
 pub async fn improve_line_item(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Json(payload): Json<ImproveLineItemRequest>,
-) -> Result<Json<ImproveLineItemResponse>, (StatusCode, String)> {
-    let current_user = require_user(&state, &headers).await?;
-    if payload.description.trim().is_empty() {
-        return Err((StatusCode::BAD_REQUEST, "Description is required".to_string()));
-    }
-
-    let last_description = load_last_line_item_description(&state.db, current_user.id).await?;
+    ValidatedJson(payload): ValidatedJson<ImproveLineItemRequest>,
+) -> Result<Json<ImproveLineItemResponse>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::InvoicesWrite).await?;
+    let last_description = load_last_line_item_description(&state.db, access.workspace_id).await?;
     let suggestion = call_openai(&payload.description, last_description.as_deref()).await?;
 
     Ok(Json(ImproveLineItemResponse {
@@ -59,17 +64,18 @@ pub async fn improve_line_item(
     path = "/ai/line-item-last",
     responses(
         (status = 200, description = "Last line item description", body = LastLineItemResponse),
-        (status = 401, description = "Not authenticated"),
-        (status = 500, description = "Server error")
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
     ),
     tag = "ai"
 )]
 pub async fn last_line_item(
     State(state): State<AppState>,
     headers: HeaderMap,
-) -> Result<Json<LastLineItemResponse>, (StatusCode, String)> {
-    let current_user = require_user(&state, &headers).await?;
-    let last_description = load_last_line_item_description(&state.db, current_user.id).await?;
+) -> Result<Json<LastLineItemResponse>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::InvoicesRead).await?;
+    let last_description = load_last_line_item_description(&state.db, access.workspace_id).await?;
     Ok(Json(LastLineItemResponse {
         description: last_description,
     }))
@@ -77,15 +83,15 @@ pub async fn last_line_item(
 
 async fn load_last_line_item_description(
     db: &sea_orm::DatabaseConnection,
-    user_id: Uuid,
-) -> Result<Option<String>, (StatusCode, String)> {
+    workspace_id: Uuid,
+) -> Result<Option<String>, AppError> {
     let latest_invoice = invoice::Entity::find()
-        .filter(invoice::Column::UserId.eq(user_id))
+        .filter(invoice::Column::WorkspaceId.eq(workspace_id))
         .order_by_desc(invoice::Column::Date)
         .order_by_desc(invoice::Column::Id)
         .one(db)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(AppError::internal)?;
 
     let Some(latest_invoice) = latest_invoice else {
         return Ok(None);
@@ -96,7 +102,7 @@ async fn load_last_line_item_description(
         .order_by_desc(invoice_line_item::Column::Id)
         .one(db)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(AppError::internal)?;
 
     Ok(latest_item.map(|item| item.description))
 }
@@ -104,9 +110,9 @@ async fn load_last_line_item_description(
 async fn call_openai(
     description: &str,
     last_description: Option<&str>,
-) -> Result<String, (StatusCode, String)> {
+) -> Result<String, AppError> {
     let api_key = std::env::var("OPENAI_API_KEY")
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "OPENAI_API_KEY missing".to_string()))?;
+        .map_err(|_| AppError::internal("OPENAI_API_KEY missing"))?;
 
     let system = "You improve a single invoice line-item description. Keep it concise, professional, and specific. Return only the improved description without quotes.";
     let context = last_description
@@ -125,29 +131,94 @@ async fn call_openai(
         "temperature": 0.3
     });
 
+    let suggestion = chat_completion(&api_key, &body)
+        .await?
+        .unwrap_or_else(|| description.to_string());
+
+    Ok(suggestion)
+}
+
+/// Transcribes receipts with an OpenAI vision model (`OPENAI_OCR_MODEL`, default
+/// `gpt-4o-mini`). PDFs are sent as files, images inline.
+pub struct OpenAiOcr {
+    api_key: String,
+    model: String,
+}
+
+impl OpenAiOcr {
+    pub fn from_env() -> anyhow::Result<Self> {
+        let api_key = std::env::var("OPENAI_API_KEY")
+            .map_err(|_| anyhow::anyhow!("OPENAI_API_KEY missing"))?;
+        let model =
+            std::env::var("OPENAI_OCR_MODEL").unwrap_or_else(|_| "gpt-4o-mini".to_string());
+        Ok(Self { api_key, model })
+    }
+}
+
+#[async_trait]
+impl OcrEngine for OpenAiOcr {
+    fn name(&self) -> &'static str {
+        "openai"
+    }
+
+    async fn recognize(&self, content_type: &str, body: &[u8]) -> Result<String, AppError> {
+        let data_url = format!(
+            "data:{};base64,{}",
+            content_type,
+            base64::engine::general_purpose::STANDARD.encode(body)
+        );
+        let document = if content_type == "application/pdf" {
+            serde_json::json!({
+                "type": "file",
+                "file": { "filename": "receipt.pdf", "file_data": data_url }
+            })
+        } else {
+            serde_json::json!({ "type": "image_url", "image_url": { "url": data_url } })
+        };
+        let system = "You transcribe receipts. Return all printed text exactly as it appears, one printed line per line, keeping numbers, dates and currency symbols unchanged. Return only the text.";
+        let body = serde_json::json!({
+            "model": self.model,
+            "messages": [
+                { "role": "system", "content": system },
+                {
+                    "role": "user",
+                    "content": [
+                        { "type": "text", "text": "Transcribe this receipt." },
+                        document
+                    ]
+                }
+            ],
+            "temperature": 0
+        });
+
+        Ok(chat_completion(&self.api_key, &body).await?.unwrap_or_default())
+    }
+}
+
+/// Sends a chat completion request and returns the trimmed content of the first choice.
+async fn chat_completion(
+    api_key: &str,
+    body: &serde_json::Value,
+) -> Result<Option<String>, AppError> {
     let client = reqwest::Client::new();
     let response = client
         .post("https://api.openai.com/v1/chat/completions")
         .bearer_auth(api_key)
-        .json(&body)
+        .json(body)
         .send()
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(AppError::internal)?;
 
     if !response.status().is_success() {
         let text = response.text().await.unwrap_or_default();
-        return Err((StatusCode::INTERNAL_SERVER_ERROR, text));
+        return Err(AppError::bad_gateway(text));
     }
 
     let value: serde_json::Value = response
         .json()
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-    let suggestion = value["choices"][0]["message"]["content"]
+        .map_err(AppError::internal)?;
+    Ok(value["choices"][0]["message"]["content"]
         .as_str()
-        .unwrap_or(description)
-        .trim()
-        .to_string();
-
-    Ok(suggestion)
+        .map(|content| content.trim().to_string()))
 }
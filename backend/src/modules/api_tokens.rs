@@ -0,0 +1,462 @@
+use crate::entity::{api_token, user};
+use crate::modules::auth::{generate_token, hash_token, require_session_user};
+use crate::modules::shared::{AppError, AppState};
+use crate::modules::validation::{not_blank, Path, ValidatedJson};
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::sea_query::Expr;
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, Set};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+/// Prefix that makes personal API tokens recognisable, e.g. in secret scanners.
+const TOKEN_PREFIX: &str = "ffk_";
+/// Characters of the token kept in clear text so users can tell tokens apart.
+const DISPLAY_PREFIX_LENGTH: usize = 12;
+const DEFAULT_EXPIRY_DAYS: u32 = 90;
+/// `last_used_at` is only written when it is older than this, to avoid a write per request.
+const LAST_USED_RESOLUTION_SECS: i64 = 60;
+
+/// Access granted to an API token. Write access to a resource includes read access.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+pub enum ApiScope {
+    #[serde(rename = "invoices:read")]
+    InvoicesRead,
+    #[serde(rename = "invoices:write")]
+    InvoicesWrite,
+    #[serde(rename = "expenses:read")]
+    ExpensesRead,
+    #[serde(rename = "expenses:write")]
+    ExpensesWrite,
+    #[serde(rename = "companies:read")]
+    CompaniesRead,
+    #[serde(rename = "companies:write")]
+    CompaniesWrite,
+    #[serde(rename = "templates:read")]
+    TemplatesRead,
+    #[serde(rename = "templates:write")]
+    TemplatesWrite,
+    #[serde(rename = "banking:read")]
+    BankingRead,
+    #[serde(rename = "banking:write")]
+    BankingWrite,
+}
+
+impl ApiScope {
+    const ALL: [ApiScope; 10] = [
+        ApiScope::InvoicesRead,
+        ApiScope::InvoicesWrite,
+        ApiScope::ExpensesRead,
+        ApiScope::ExpensesWrite,
+        ApiScope::CompaniesRead,
+        ApiScope::CompaniesWrite,
+        ApiScope::TemplatesRead,
+        ApiScope::TemplatesWrite,
+        ApiScope::BankingRead,
+        ApiScope::BankingWrite,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ApiScope::InvoicesRead => "invoices:read",
+            ApiScope::InvoicesWrite => "invoices:write",
+            ApiScope::ExpensesRead => "expenses:read",
+            ApiScope::ExpensesWrite => "expenses:write",
+            ApiScope::CompaniesRead => "companies:read",
+            ApiScope::CompaniesWrite => "companies:write",
+            ApiScope::TemplatesRead => "templates:read",
+            ApiScope::TemplatesWrite => "templates:write",
+            ApiScope::BankingRead => "banking:read",
+            ApiScope::BankingWrite => "banking:write",
+        }
+    }
+
+    pub fn is_write(self) -> bool {
+        matches!(
+            self,
+            ApiScope::InvoicesWrite
+                | ApiScope::ExpensesWrite
+                | ApiScope::CompaniesWrite
+                | ApiScope::TemplatesWrite
+                | ApiScope::BankingWrite
+        )
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|scope| scope.as_str() == value)
+    }
+
+    /// The write scope that implies this read scope, if any.
+    fn write_variant(self) -> Option<Self> {
+        match self {
+            ApiScope::InvoicesRead => Some(ApiScope::InvoicesWrite),
+            ApiScope::ExpensesRead => Some(ApiScope::ExpensesWrite),
+            ApiScope::CompaniesRead => Some(ApiScope::CompaniesWrite),
+            ApiScope::TemplatesRead => Some(ApiScope::TemplatesWrite),
+            ApiScope::BankingRead => Some(ApiScope::BankingWrite),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ApiScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct CreateApiTokenRequest {
+    #[validate(custom(function = not_blank), length(max = 100))]
+    pub name: String,
+    #[validate(length(min = 1, message = "must contain at least one scope"))]
+    pub scopes: Vec<ApiScope>,
+    /// Lifetime in days (default 90, max 365)
+    #[validate(range(min = 1, max = 365))]
+    pub expires_in_days: Option<u32>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ApiTokenResponse {
+    pub id: Uuid,
+    pub name: String,
+    /// Leading characters of the token
+    pub prefix: String,
+    pub scopes: Vec<ApiScope>,
+    pub expires_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CreatedApiTokenResponse {
+    /// Send as `Authorization: Bearer <token>`. It is shown only once.
+    pub token: String,
+    pub api_token: ApiTokenResponse,
+}
+
+impl From<api_token::Model> for ApiTokenResponse {
+    fn from(model: api_token::Model) -> Self {
+        Self {
+            scopes: parse_scopes(&model.scopes),
+            id: model.id,
+            name: model.name,
+            prefix: model.token_prefix,
+            expires_at: model.expires_at,
+            last_used_at: model.last_used_at,
+            created_at: model.created_at,
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/api-tokens",
+    request_body = CreateApiTokenRequest,
+    responses(
+        (status = 201, description = "Token created", body = CreatedApiTokenResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Requires a browser session", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "auth"
+)]
+pub async fn create_api_token(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidatedJson(payload): ValidatedJson<CreateApiTokenRequest>,
+) -> Result<(StatusCode, Json<CreatedApiTokenResponse>), AppError> {
+    let current_user = require_session_user(&state, &headers).await?;
+
+    let mut scopes: Vec<ApiScope> = Vec::new();
+    for scope in payload.scopes {
+        if !scopes.contains(&scope) {
+            scopes.push(scope);
+        }
+    }
+    let scopes: Vec<&str> = scopes.into_iter().map(ApiScope::as_str).collect();
+
+    let token = format!("{}{}", TOKEN_PREFIX, generate_token());
+    let now = Utc::now();
+    let expires_in_days = payload.expires_in_days.unwrap_or(DEFAULT_EXPIRY_DAYS);
+    let model = api_token::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        user_id: Set(current_user.id),
+        name: Set(payload.name.trim().to_string()),
+        token_prefix: Set(token[..DISPLAY_PREFIX_LENGTH].to_string()),
+        token_hash: Set(hash_token(&token)),
+        scopes: Set(scopes.join(",")),
+        expires_at: Set(now + Duration::days(i64::from(expires_in_days))),
+        last_used_at: Set(None),
+        created_at: Set(now),
+    }
+    .insert(&state.db)
+    .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreatedApiTokenResponse {
+            token,
+            api_token: model.into(),
+        }),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/auth/api-tokens",
+    responses(
+        (status = 200, description = "API tokens of the current user", body = [ApiTokenResponse]),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Requires a browser session", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "auth"
+)]
+pub async fn list_api_tokens(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<ApiTokenResponse>>, AppError> {
+    let current_user = require_session_user(&state, &headers).await?;
+    let tokens = api_token::Entity::find()
+        .filter(api_token::Column::UserId.eq(current_user.id))
+        .order_by_desc(api_token::Column::CreatedAt)
+        .all(&state.db)
+        .await?;
+
+    Ok(Json(tokens.into_iter().map(ApiTokenResponse::from).collect()))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/auth/api-tokens/{id}",
+    params(
+        ("id" = String, Path, description = "API token id (UUID)")
+    ),
+    responses(
+        (status = 204, description = "Token revoked"),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Requires a browser session", body = ErrorResponse),
+        (status = 404, description = "Token not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "auth"
+)]
+pub async fn revoke_api_token(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    let current_user = require_session_user(&state, &headers).await?;
+    let id = Uuid::parse_str(&id).map_err(|_| AppError::bad_request("Invalid id"))?;
+    let result = api_token::Entity::delete_many()
+        .filter(api_token::Column::Id.eq(id))
+        .filter(api_token::Column::UserId.eq(current_user.id))
+        .exec(&state.db)
+        .await?;
+    if result.rows_affected == 0 {
+        return Err(AppError::not_found("API token not found"));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Resolves a bearer token to its owner, rejecting unknown and expired tokens.
+pub(crate) async fn authenticate_api_token(
+    state: &AppState,
+    token: &str,
+) -> Result<(user::Model, api_token::Model), AppError> {
+    let now = Utc::now();
+    let record = api_token::Entity::find()
+        .filter(api_token::Column::TokenHash.eq(hash_token(token)))
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::unauthorized("Invalid API token"))?;
+    if record.expires_at <= now {
+        return Err(AppError::unauthorized("API token expired"));
+    }
+
+    let user = user::Entity::find_by_id(record.user_id)
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::unauthorized("Invalid API token"))?;
+
+    let stale = record
+        .last_used_at
+        .is_none_or(|used| now - used >= Duration::seconds(LAST_USED_RESOLUTION_SECS));
+    if stale {
+        api_token::Entity::update_many()
+            .col_expr(api_token::Column::LastUsedAt, Expr::value(now))
+            .filter(api_token::Column::Id.eq(record.id))
+            .exec(&state.db)
+            .await?;
+    }
+
+    Ok((user, record))
+}
+
+/// Whether `token` grants `required`, either directly or through the matching write scope.
+pub(crate) fn token_allows(token: &api_token::Model, required: ApiScope) -> bool {
+    let granted = parse_scopes(&token.scopes);
+    granted.contains(&required)
+        || required
+            .write_variant()
+            .is_some_and(|write| granted.contains(&write))
+}
+
+fn parse_scopes(value: &str) -> Vec<ApiScope> {
+    value
+        .split(',')
+        .filter_map(|scope| ApiScope::parse(scope.trim()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::workspace_member;
+    use crate::modules::auth::{require_access, require_session_user};
+    use crate::modules::test_support;
+    use axum::http::HeaderValue;
+    use sea_orm::{DatabaseBackend, DatabaseConnection, MockDatabase};
+
+    const TOKEN: &str = "ffk_test-token";
+
+    fn user_id() -> Uuid {
+        Uuid::from_u128(1)
+    }
+
+    fn workspace_id() -> Uuid {
+        Uuid::from_u128(2)
+    }
+
+    fn token(scopes: &str, expires_in: Duration) -> api_token::Model {
+        let now = Utc::now();
+        api_token::Model {
+            id: Uuid::from_u128(3),
+            user_id: user_id(),
+            name: "CI".to_string(),
+            token_prefix: TOKEN[..DISPLAY_PREFIX_LENGTH].to_string(),
+            token_hash: hash_token(TOKEN),
+            scopes: scopes.to_string(),
+            expires_at: now + expires_in,
+            // Recently used, so authenticating does not write `last_used_at`.
+            last_used_at: Some(now),
+            created_at: now - Duration::days(1),
+        }
+    }
+
+    fn membership(role: &str) -> workspace_member::Model {
+        workspace_member::Model {
+            id: Uuid::from_u128(4),
+            workspace_id: workspace_id(),
+            user_id: user_id(),
+            role: role.to_string(),
+            created_at: Utc::now(),
+        }
+    }
+
+    /// A database that resolves the bearer token and, if the scope check passes,
+    /// the caller's workspace membership.
+    fn db(token: api_token::Model, role: &str) -> DatabaseConnection {
+        MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([[token]])
+            .append_query_results([[test_support::user(user_id(), "owner@example.com")]])
+            .append_query_results([[membership(role)]])
+            .into_connection()
+    }
+
+    fn bearer() -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            axum::http::header::AUTHORIZATION,
+            HeaderValue::from_str(&format!("Bearer {TOKEN}")).unwrap(),
+        );
+        headers
+    }
+
+    async fn access(scopes: &str, role: &str, required: ApiScope) -> Result<Uuid, AppError> {
+        let state = AppState::for_tests(db(token(scopes, Duration::days(30)), role));
+        require_access(&state, &bearer(), required)
+            .await
+            .map(|access| access.workspace_id)
+    }
+
+    #[tokio::test]
+    async fn a_token_reaches_only_the_resources_it_is_scoped_to() {
+        assert_eq!(
+            access("invoices:read", "owner", ApiScope::InvoicesRead).await.ok(),
+            Some(workspace_id())
+        );
+
+        let error = access("invoices:read", "owner", ApiScope::ExpensesRead)
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(error.status(), StatusCode::FORBIDDEN);
+        assert_eq!(error.to_string(), "API token is missing the expenses:read scope");
+    }
+
+    #[tokio::test]
+    async fn read_scopes_do_not_allow_writes_but_write_scopes_allow_reads() {
+        let error = access("invoices:read", "owner", ApiScope::InvoicesWrite)
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(error.to_string(), "API token is missing the invoices:write scope");
+
+        assert!(access("invoices:write", "owner", ApiScope::InvoicesRead).await.is_ok());
+        assert!(access("expenses:read,invoices:write", "member", ApiScope::InvoicesWrite)
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn write_scopes_still_need_a_role_that_may_write() {
+        let error = access("invoices:write", "accountant", ApiScope::InvoicesWrite)
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(error.status(), StatusCode::FORBIDDEN);
+        assert_eq!(error.to_string(), "The accountant role is read-only in this workspace");
+
+        assert!(access("invoices:write", "accountant", ApiScope::InvoicesRead).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn expired_tokens_and_session_only_endpoints_are_rejected() {
+        let state = AppState::for_tests(db(token("invoices:read", -Duration::minutes(1)), "owner"));
+        let error = require_access(&state, &bearer(), ApiScope::InvoicesRead)
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(error.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(error.to_string(), "API token expired");
+
+        let state = AppState::for_tests(db(token("invoices:write", Duration::days(30)), "owner"));
+        let error = require_session_user(&state, &bearer()).await.err().unwrap();
+        assert_eq!(error.status(), StatusCode::FORBIDDEN);
+        assert_eq!(error.to_string(), "API tokens cannot be used for this endpoint");
+    }
+
+    #[test]
+    fn unknown_stored_scopes_are_ignored() {
+        let token = token("invoices:read, legacy:admin ,banking:write", Duration::days(1));
+        assert!(token_allows(&token, ApiScope::InvoicesRead));
+        assert!(token_allows(&token, ApiScope::BankingRead));
+        assert!(!token_allows(&token, ApiScope::CompaniesRead));
+        assert_eq!(
+            ApiTokenResponse::from(token).scopes,
+            [ApiScope::InvoicesRead, ApiScope::BankingWrite]
+        );
+    }
+}
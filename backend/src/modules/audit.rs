@@ -0,0 +1,50 @@
+use crate::entity::{access_grant, audit_event};
+use crate::modules::shared::{current_client_ip, AppError};
+use chrono::Utc;
+use sea_orm::{ActiveModelTrait, ConnectionTrait, Set};
+use uuid::Uuid;
+
+pub const LOGIN_FAILED: &str = "login_failed";
+pub const LOGIN_LOCKED: &str = "login_locked";
+pub const ACCOUNTANT_ACCESS: &str = "accountant_access";
+
+/// Appends a security-relevant event, tagged with the caller's IP address.
+pub async fn record_event<C: ConnectionTrait>(
+    db: &C,
+    user_id: Option<Uuid>,
+    event: &str,
+    detail: Option<String>,
+) -> Result<(), AppError> {
+    audit_event::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        user_id: Set(user_id),
+        event: Set(event.to_string()),
+        ip_address: Set(current_client_ip()),
+        detail: Set(detail),
+        access_grant_id: Set(None),
+        created_at: Set(Utc::now()),
+    }
+    .insert(db)
+    .await?;
+    Ok(())
+}
+
+/// Records a read made with an accountant access grant; `detail` names what was accessed.
+pub async fn record_grant_access<C: ConnectionTrait>(
+    db: &C,
+    grant: &access_grant::Model,
+    detail: String,
+) -> Result<(), AppError> {
+    audit_event::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        user_id: Set(None),
+        event: Set(ACCOUNTANT_ACCESS.to_string()),
+        ip_address: Set(current_client_ip()),
+        detail: Set(Some(detail)),
+        access_grant_id: Set(Some(grant.id)),
+        created_at: Set(Utc::now()),
+    }
+    .insert(db)
+    .await?;
+    Ok(())
+}
@@ -1,34 +1,72 @@
-use crate::entity::{session, user};
-use crate::modules::shared::AppState;
+use crate::entity::{api_token, session, user};
+use crate::modules::account::send_verification_email;
+use crate::modules::api_tokens::{authenticate_api_token, token_allows, ApiScope};
+use crate::modules::rate_limit::{
+    check_account_limit, clear_login_failures, login_lockout, record_login_failure,
+};
+use crate::modules::shared::{cookie_same_site, current_client_ip, AppError, AppState};
+use crate::modules::two_factor::start_login_challenge;
+use crate::modules::validation::{not_blank, ValidatedJson};
+use crate::modules::workspaces::{create_personal_workspace, resolve_workspace, WorkspaceAccess};
 use argon2::{
-    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    password_hash::{
+        rand_core::{OsRng, RngCore},
+        PasswordHash, PasswordHasher, PasswordVerifier, SaltString,
+    },
     Argon2,
 };
 use axum::{
-    extract::State,
-    http::{HeaderMap, StatusCode},
+    extract::{Request, State},
+    http::{
+        header::{SET_COOKIE, USER_AGENT},
+        HeaderMap, HeaderValue, StatusCode,
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
     Json,
 };
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
 use sea_orm::{
     ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set,
+    TransactionTrait,
 };
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::cell::RefCell;
+use std::sync::OnceLock;
 use utoipa::ToSchema;
 use uuid::Uuid;
+use validator::Validate;
 
 const SESSION_DURATION_DAYS: i64 = 7;
+/// Minimum time between two extensions of a session's expiry.
+const SESSION_RENEWAL_HOURS: i64 = 24;
+const LAST_SEEN_RESOLUTION_MINUTES: i64 = 5;
+const USER_AGENT_MAX_CHARS: usize = 512;
+
+static COOKIE_KEY: OnceLock<Vec<u8>> = OnceLock::new();
+static DUMMY_PASSWORD_HASH: OnceLock<String> = OnceLock::new();
+
+tokio::task_local! {
+    static RENEWED_SESSION_COOKIE: RefCell<Option<HeaderValue>>;
+}
 
-#[derive(Deserialize, ToSchema)]
+#[derive(Deserialize, ToSchema, Validate)]
 pub struct RegisterRequest {
+    #[validate(email(message = "must be a valid email address"))]
     pub email: String,
+    #[validate(length(min = 8, max = 128, message = "must be between 8 and 128 characters"))]
     pub password: String,
+    #[validate(length(max = 1000))]
     pub address: Option<String>,
 }
 
-#[derive(Deserialize, ToSchema)]
+#[derive(Deserialize, ToSchema, Validate)]
 pub struct LoginRequest {
+    #[validate(custom(function = not_blank))]
     pub email: String,
+    #[validate(custom(function = not_blank))]
     pub password: String,
 }
 
@@ -39,6 +77,22 @@ pub struct UserResponse {
     pub address: Option<String>,
     pub company_id: Option<Uuid>,
     pub created_at: DateTime<Utc>,
+    pub email_verified: bool,
+    pub two_factor_enabled: bool,
+}
+
+impl From<user::Model> for UserResponse {
+    fn from(user: user::Model) -> Self {
+        Self {
+            id: user.id,
+            email_verified: user.email_verified_at.is_some(),
+            two_factor_enabled: user.totp_enabled_at.is_some(),
+            email: user.email,
+            address: user.address,
+            company_id: user.company_id,
+            created_at: user.created_at,
+        }
+    }
 }
 
 #[derive(Serialize, ToSchema)]
@@ -46,8 +100,9 @@ pub struct SessionResponse {
     pub user: UserResponse,
 }
 
-#[derive(Deserialize, ToSchema)]
+#[derive(Deserialize, ToSchema, Validate)]
 pub struct UpdateProfileRequest {
+    #[validate(length(max = 1000))]
     pub address: Option<String>,
 }
 
@@ -57,33 +112,32 @@ pub struct UpdateProfileRequest {
     request_body = RegisterRequest,
     responses(
         (status = 200, description = "Registered and logged in", body = SessionResponse),
-        (status = 400, description = "Invalid input"),
-        (status = 409, description = "Email already exists"),
-        (status = 500, description = "Server error")
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 409, description = "Email already exists", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
     ),
     tag = "auth"
 )]
 pub async fn register(
     State(state): State<AppState>,
-    Json(payload): Json<RegisterRequest>,
-) -> Result<(HeaderMap, Json<SessionResponse>), (StatusCode, String)> {
+    request_headers: HeaderMap,
+    ValidatedJson(payload): ValidatedJson<RegisterRequest>,
+) -> Result<(HeaderMap, Json<SessionResponse>), AppError> {
     let email = payload.email.trim().to_lowercase();
-    if email.is_empty() || payload.password.trim().is_empty() {
-        return Err((StatusCode::BAD_REQUEST, "Email and password are required".to_string()));
-    }
 
     let existing = user::Entity::find()
         .filter(user::Column::Email.eq(email.clone()))
         .one(&state.db)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(AppError::internal)?;
 
     if existing.is_some() {
-        return Err((StatusCode::CONFLICT, "Email already exists".to_string()));
+        return Err(AppError::conflict("Email already exists"));
     }
 
     let password_hash = hash_password(&payload.password)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(AppError::internal)?;
 
     let user_active = user::ActiveModel {
         id: Set(Uuid::new_v4()),
@@ -92,29 +146,30 @@ pub async fn register(
         address: Set(payload.address),
         company_id: Set(None),
         created_at: Set(Utc::now()),
+        email_verified_at: Set(None),
+        totp_secret: Set(None),
+        totp_enabled_at: Set(None),
+        totp_last_step: Set(None),
+        deleted_at: Set(None),
     };
 
+    let txn = state.db.begin().await?;
     let user = user_active
-        .insert(&state.db)
+        .insert(&txn)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(AppError::internal)?;
+    create_personal_workspace(&txn, &user).await?;
+    txn.commit().await?;
 
-    let (_session, cookie) = create_session(&state.db, user.id).await?;
+    if let Err(error) = send_verification_email(&state, &user).await {
+        eprintln!("Verification email for {} failed: {error}", user.id);
+    }
+
+    let (_session, cookie) = create_session(&state.db, user.id, &request_headers).await?;
     let mut headers = HeaderMap::new();
     headers.insert(axum::http::header::SET_COOKIE, cookie);
 
-    Ok((
-        headers,
-        Json(SessionResponse {
-            user: UserResponse {
-                id: user.id,
-                email: user.email,
-                address: user.address,
-                company_id: user.company_id,
-                created_at: user.created_at,
-            },
-        }),
-    ))
+    Ok((headers, Json(SessionResponse { user: user.into() })))
 }
 
 #[utoipa::path(
@@ -123,42 +178,58 @@ pub async fn register(
     request_body = LoginRequest,
     responses(
         (status = 200, description = "Logged in", body = SessionResponse),
-        (status = 401, description = "Invalid credentials"),
-        (status = 500, description = "Server error")
+        (status = 202, description = "Password accepted; a second factor is required", body = TwoFactorChallengeResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 401, description = "Invalid credentials", body = ErrorResponse),
+        (status = 429, description = "Too many attempts; see Retry-After", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
     ),
     tag = "auth"
 )]
 pub async fn login(
     State(state): State<AppState>,
-    Json(payload): Json<LoginRequest>,
-) -> Result<(HeaderMap, Json<SessionResponse>), (StatusCode, String)> {
+    request_headers: HeaderMap,
+    ValidatedJson(payload): ValidatedJson<LoginRequest>,
+) -> Result<Response, AppError> {
     let email = payload.email.trim().to_lowercase();
     let user = user::Entity::find()
-        .filter(user::Column::Email.eq(email))
+        .filter(user::Column::Email.eq(email.clone()))
         .one(&state.db)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-        .ok_or_else(|| (StatusCode::UNAUTHORIZED, "Invalid credentials".to_string()))?;
+        .map_err(AppError::internal)?;
 
-    verify_password(&payload.password, &user.password_hash)
-        .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid credentials".to_string()))?;
+    let Some(user) = user else {
+        // Spend the same argon2 work as for a real account, so response times do not
+        // reveal which emails are registered.
+        let _ = verify_password(&payload.password, dummy_password_hash());
+        return Err(AppError::unauthorized("Invalid credentials"));
+    };
+    let lockout = login_lockout(&state.db, &email).await?;
+    if verify_password(&payload.password, &user.password_hash).is_err() {
+        // Attempts during a lockout are rejected without extending it.
+        if let Some(lockout) = lockout {
+            return Err(lockout);
+        }
+        record_login_failure(&state.db, &email, user.id).await?;
+        return Err(AppError::unauthorized("Invalid credentials"));
+    }
+
+    // The right password plus a second factor gets through a lockout, so guessing
+    // passwords cannot lock out accounts that have 2FA.
+    if user.totp_enabled_at.is_some() {
+        let challenge = start_login_challenge(&state.db, user.id).await?;
+        return Ok((StatusCode::ACCEPTED, Json(challenge)).into_response());
+    }
+    if let Some(lockout) = lockout {
+        return Err(lockout);
+    }
 
-    let (_session, cookie) = create_session(&state.db, user.id).await?;
+    clear_login_failures(&state.db, &email).await?;
+    let (_session, cookie) = create_session(&state.db, user.id, &request_headers).await?;
     let mut headers = HeaderMap::new();
     headers.insert(axum::http::header::SET_COOKIE, cookie);
 
-    Ok((
-        headers,
-        Json(SessionResponse {
-            user: UserResponse {
-                id: user.id,
-                email: user.email,
-                address: user.address,
-                company_id: user.company_id,
-                created_at: user.created_at,
-            },
-        }),
-    ))
+    Ok((headers, Json(SessionResponse { user: user.into() })).into_response())
 }
 
 #[utoipa::path(
@@ -166,35 +237,28 @@ pub async fn login(
     path = "/auth/logout",
     responses(
         (status = 200, description = "Logged out"),
-        (status = 401, description = "Not authenticated"),
-        (status = 500, description = "Server error")
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
     ),
     tag = "auth"
 )]
 pub async fn logout(
     State(state): State<AppState>,
     headers: HeaderMap,
-) -> Result<StatusCode, (StatusCode, String)> {
-    let session_id = extract_session_id(&headers)
-        .ok_or_else(|| (StatusCode::UNAUTHORIZED, "Not authenticated".to_string()))?;
+) -> Result<StatusCode, AppError> {
+    let token = extract_session_token(&headers)
+        .ok_or_else(|| AppError::unauthorized("Not authenticated"))?;
 
-    let session_uuid = Uuid::parse_str(&session_id)
-        .map_err(|_| (StatusCode::UNAUTHORIZED, "Not authenticated".to_string()))?;
-
-    let session = session::Entity::find_by_id(session_uuid)
-        .one(&state.db)
+    let result = session::Entity::delete_many()
+        .filter(session::Column::TokenHash.eq(hash_token(&token)))
+        .exec(&state.db)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(AppError::internal)?;
 
-    if session.is_none() {
-        return Err((StatusCode::UNAUTHORIZED, "Not authenticated".to_string()));
+    if result.rows_affected == 0 {
+        return Err(AppError::unauthorized("Not authenticated"));
     }
 
-    session::Entity::delete_by_id(session_uuid)
-        .exec(&state.db)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-
     Ok(StatusCode::OK)
 }
 
@@ -203,23 +267,17 @@ pub async fn logout(
     path = "/auth/me",
     responses(
         (status = 200, description = "Current user", body = UserResponse),
-        (status = 401, description = "Not authenticated"),
-        (status = 500, description = "Server error")
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
     ),
     tag = "auth"
 )]
 pub async fn me(
     State(state): State<AppState>,
     headers: HeaderMap,
-) -> Result<Json<UserResponse>, (StatusCode, String)> {
+) -> Result<Json<UserResponse>, AppError> {
     let user = require_user(&state, &headers).await?;
-    Ok(Json(UserResponse {
-        id: user.id,
-        email: user.email,
-        address: user.address,
-        company_id: user.company_id,
-        created_at: user.created_at,
-    }))
+    Ok(Json(user.into()))
 }
 
 #[utoipa::path(
@@ -228,17 +286,19 @@ pub async fn me(
     request_body = UpdateProfileRequest,
     responses(
         (status = 200, description = "Profile updated", body = UserResponse),
-        (status = 401, description = "Not authenticated"),
-        (status = 500, description = "Server error")
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Requires a browser session", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
     ),
     tag = "auth"
 )]
 pub async fn update_profile(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Json(payload): Json<UpdateProfileRequest>,
-) -> Result<Json<UserResponse>, (StatusCode, String)> {
-    let current_user = require_user(&state, &headers).await?;
+    ValidatedJson(payload): ValidatedJson<UpdateProfileRequest>,
+) -> Result<Json<UserResponse>, AppError> {
+    let current_user = require_session_user(&state, &headers).await?;
     let mut active: user::ActiveModel = current_user.into();
     if let Some(address) = payload.address {
         active.address = Set(Some(address));
@@ -247,91 +307,261 @@ pub async fn update_profile(
     let updated = active
         .update(&state.db)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(AppError::internal)?;
 
-    Ok(Json(UserResponse {
-        id: updated.id,
-        email: updated.email,
-        address: updated.address,
-        company_id: updated.company_id,
-        created_at: updated.created_at,
-    }))
+    Ok(Json(updated.into()))
 }
 
+/// Any authenticated caller: a session cookie or a personal API token of any scope.
 pub async fn require_user(
     state: &AppState,
     headers: &HeaderMap,
-) -> Result<user::Model, (StatusCode, String)> {
-    let session_id = extract_session_id(headers)
-        .ok_or_else(|| (StatusCode::UNAUTHORIZED, "Not authenticated".to_string()))?;
+) -> Result<user::Model, AppError> {
+    Ok(authenticate(state, headers).await?.0)
+}
+
+/// Like [`require_user`], but API tokens must carry `scope`. Resolves the workspace the
+/// request acts on; write scopes additionally require a role that may modify data.
+pub async fn require_access(
+    state: &AppState,
+    headers: &HeaderMap,
+    scope: ApiScope,
+) -> Result<WorkspaceAccess, AppError> {
+    let (user, credential) = authenticate(state, headers).await?;
+    if let Credential::ApiToken(api_token) = credential
+        && !token_allows(&api_token, scope)
+    {
+        return Err(AppError::forbidden(format!(
+            "API token is missing the {} scope",
+            scope
+        )));
+    }
+
+    let (workspace_id, role) = resolve_workspace(&state.db, user.id, headers).await?;
+    if scope.is_write() && !role.can_write() {
+        return Err(AppError::forbidden(format!(
+            "The {} role is read-only in this workspace",
+            role
+        )));
+    }
+    Ok(WorkspaceAccess { user, workspace_id })
+}
+
+/// Account and credential management is reserved for interactive sessions.
+pub async fn require_session_user(
+    state: &AppState,
+    headers: &HeaderMap,
+) -> Result<user::Model, AppError> {
+    Ok(require_session(state, headers).await?.0)
+}
+
+/// The signed-in user together with the browser session the request came from.
+pub(crate) async fn require_session(
+    state: &AppState,
+    headers: &HeaderMap,
+) -> Result<(user::Model, session::Model), AppError> {
+    match authenticate(state, headers).await? {
+        (user, Credential::Session(session)) => Ok((user, session)),
+        (_, Credential::ApiToken(_)) => Err(AppError::forbidden(
+            "API tokens cannot be used for this endpoint",
+        )),
+    }
+}
 
-    let session_uuid = Uuid::parse_str(&session_id)
-        .map_err(|_| (StatusCode::UNAUTHORIZED, "Not authenticated".to_string()))?;
+enum Credential {
+    Session(session::Model),
+    ApiToken(api_token::Model),
+}
 
-    let session = session::Entity::find_by_id(session_uuid)
+async fn authenticate(
+    state: &AppState,
+    headers: &HeaderMap,
+) -> Result<(user::Model, Credential), AppError> {
+    if let Some(token) = extract_bearer_token(headers) {
+        let (user, api_token) = authenticate_api_token(state, &token).await?;
+        check_account_limit(state, user.id).await?;
+        return Ok((user, Credential::ApiToken(api_token)));
+    }
+
+    let token = extract_session_token(headers)
+        .ok_or_else(|| AppError::unauthorized("Not authenticated"))?;
+
+    let session = session::Entity::find()
+        .filter(session::Column::TokenHash.eq(hash_token(&token)))
         .one(&state.db)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-        .ok_or_else(|| (StatusCode::UNAUTHORIZED, "Not authenticated".to_string()))?;
+        .map_err(AppError::internal)?
+        .ok_or_else(|| AppError::unauthorized("Not authenticated"))?;
 
     if session.expires_at < Utc::now() {
-        return Err((StatusCode::UNAUTHORIZED, "Session expired".to_string()));
+        return Err(AppError::unauthorized("Session expired"));
     }
 
     let user = user::Entity::find_by_id(session.user_id)
         .one(&state.db)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-        .ok_or_else(|| (StatusCode::UNAUTHORIZED, "Not authenticated".to_string()))?;
+        .map_err(AppError::internal)?
+        .ok_or_else(|| AppError::unauthorized("Not authenticated"))?;
+
+    check_account_limit(state, user.id).await?;
+    let session = touch_session(&state.db, session, &token).await?;
+    Ok((user, Credential::Session(session)))
+}
+
+/// Records activity on a session and slides its expiry forward, at most once per
+/// `SESSION_RENEWAL_HOURS`, so active users stay signed in.
+async fn touch_session(
+    db: &DatabaseConnection,
+    session: session::Model,
+    token: &str,
+) -> Result<session::Model, AppError> {
+    let now = Utc::now();
+    let lifetime = Duration::days(SESSION_DURATION_DAYS);
+    let renew = session.expires_at - now < lifetime - Duration::hours(SESSION_RENEWAL_HOURS);
+    let seen = now - session.last_seen_at >= Duration::minutes(LAST_SEEN_RESOLUTION_MINUTES);
+    if !renew && !seen {
+        return Ok(session);
+    }
 
-    Ok(user)
+    let mut active: session::ActiveModel = session.into();
+    active.last_seen_at = Set(now);
+    if let Some(ip) = current_client_ip() {
+        active.ip_address = Set(Some(ip));
+    }
+    if renew {
+        active.expires_at = Set(now + lifetime);
+    }
+    let session = active.update(db).await?;
+
+    if renew {
+        let cookie = session_cookie(&sign_session_token(token), lifetime.num_seconds())?;
+        // Outside of `session_cookie_renewal` (e.g. in background jobs) there is no response to update.
+        let _ = RENEWED_SESSION_COOKIE.try_with(|slot| *slot.borrow_mut() = Some(cookie));
+    }
+    Ok(session)
+}
+
+/// Sends the refreshed session cookie when the session was renewed while handling the request.
+pub async fn session_cookie_renewal(request: Request, next: Next) -> Response {
+    RENEWED_SESSION_COOKIE
+        .scope(RefCell::new(None), async move {
+            let mut response = next.run(request).await;
+            let cookie = RENEWED_SESSION_COOKIE.with(|slot| slot.borrow_mut().take());
+            if let Some(cookie) = cookie
+                && !response.headers().contains_key(SET_COOKIE)
+            {
+                response.headers_mut().insert(SET_COOKIE, cookie);
+            }
+            response
+        })
+        .await
 }
 
-async fn create_session(
+pub(crate) async fn create_session(
     db: &DatabaseConnection,
     user_id: Uuid,
-) -> Result<(session::Model, axum::http::HeaderValue), (StatusCode, String)> {
+    headers: &HeaderMap,
+) -> Result<(session::Model, HeaderValue), AppError> {
     let now = Utc::now();
-    let expires_at = now + chrono::Duration::days(SESSION_DURATION_DAYS);
+    let lifetime = Duration::days(SESSION_DURATION_DAYS);
+    let user_agent = headers
+        .get(USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.chars().take(USER_AGENT_MAX_CHARS).collect());
+    let token = generate_token();
     let session_active = session::ActiveModel {
         id: Set(Uuid::new_v4()),
         user_id: Set(user_id),
+        token_hash: Set(hash_token(&token)),
         created_at: Set(now),
-        expires_at: Set(expires_at),
+        expires_at: Set(now + lifetime),
+        user_agent: Set(user_agent),
+        ip_address: Set(current_client_ip()),
+        last_seen_at: Set(now),
     };
 
     let session = session_active
         .insert(db)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(AppError::internal)?;
+
+    let header = session_cookie(&sign_session_token(&token), lifetime.num_seconds())?;
+    Ok((session, header))
+}
 
-    let max_age = SESSION_DURATION_DAYS * 24 * 60 * 60;
+/// Builds the `Set-Cookie` value for the session cookie; `max_age = 0` clears it.
+pub(crate) fn session_cookie(value: &str, max_age: i64) -> Result<HeaderValue, AppError> {
     let secure = std::env::var("COOKIE_SECURE").unwrap_or_else(|_| "false".to_string());
     let secure_flag = if secure == "true" { "; Secure" } else { "" };
-    let same_site_env = std::env::var("COOKIE_SAMESITE")
-        .unwrap_or_else(|_| if secure == "true" { "None".to_string() } else { "Lax".to_string() });
 
     let cookie_value = format!(
         "session_id={}; Path=/; HttpOnly; SameSite={}; Max-Age={}{}",
-        session.id, same_site_env, max_age, secure_flag
+        value,
+        cookie_same_site(),
+        max_age,
+        secure_flag
     );
 
-    let header = axum::http::HeaderValue::from_str(&cookie_value)
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Invalid cookie".to_string()))?;
+    HeaderValue::from_str(&cookie_value).map_err(|_| AppError::internal("Invalid cookie"))
+}
 
-    Ok((session, header))
+pub(crate) fn extract_bearer_token(headers: &HeaderMap) -> Option<String> {
+    let value = headers.get(axum::http::header::AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = value.split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("bearer") || token.trim().is_empty() {
+        return None;
+    }
+    Some(token.trim().to_string())
+}
+
+pub(crate) fn has_session_cookie(headers: &HeaderMap) -> bool {
+    session_cookie_value(headers).is_some()
+}
+
+/// The session token from the cookie, provided its signature is valid.
+fn extract_session_token(headers: &HeaderMap) -> Option<String> {
+    let value = session_cookie_value(headers)?;
+    let (token, signature) = value.rsplit_once('.')?;
+    let signature = hex::decode(signature).ok()?;
+    cookie_mac(token).verify_slice(&signature).ok()?;
+    Some(token.to_string())
 }
 
-fn extract_session_id(headers: &HeaderMap) -> Option<String> {
+fn session_cookie_value(headers: &HeaderMap) -> Option<&str> {
     let cookie = headers.get(axum::http::header::COOKIE)?.to_str().ok()?;
     cookie
         .split(';')
         .map(|part| part.trim())
         .find_map(|part| part.strip_prefix("session_id="))
-        .map(|v| v.to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Cookie value for a session token: `<token>.<HMAC-SHA256 of the token>`.
+pub(crate) fn sign_session_token(token: &str) -> String {
+    format!("{}.{}", token, hex::encode(cookie_mac(token).finalize().into_bytes()))
 }
 
-fn hash_password(password: &str) -> Result<String, String> {
+fn cookie_mac(token: &str) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(cookie_key()).expect("HMAC accepts keys of any length");
+    mac.update(token.as_bytes());
+    mac
+}
+
+/// Key for signing session cookies and receipt download links, from `COOKIE_SECRET`.
+/// Without it a random key is used, which signs everyone out whenever the server restarts.
+pub(crate) fn cookie_key() -> &'static [u8] {
+    COOKIE_KEY.get_or_init(|| match std::env::var("COOKIE_SECRET") {
+        Ok(secret) if !secret.is_empty() => secret.into_bytes(),
+        _ => {
+            eprintln!("COOKIE_SECRET is not set; session cookies will not survive a restart");
+            let mut key = vec![0u8; 32];
+            OsRng.fill_bytes(&mut key);
+            key
+        }
+    })
+}
+
+pub(crate) fn hash_password(password: &str) -> Result<String, String> {
     let salt = SaltString::generate(&mut OsRng);
     let hash = Argon2::default()
         .hash_password(password.as_bytes(), &salt)
@@ -340,10 +570,30 @@ fn hash_password(password: &str) -> Result<String, String> {
     Ok(hash)
 }
 
-fn verify_password(password: &str, hash: &str) -> Result<(), String> {
+pub(crate) fn verify_password(password: &str, hash: &str) -> Result<(), String> {
     let parsed = PasswordHash::new(hash).map_err(|e| e.to_string())?;
     Argon2::default()
         .verify_password(password.as_bytes(), &parsed)
         .map_err(|e| e.to_string())?;
     Ok(())
 }
+
+/// A hash with the same parameters as stored passwords, checked against when a login
+/// names an unknown email.
+fn dummy_password_hash() -> &'static str {
+    DUMMY_PASSWORD_HASH.get_or_init(|| {
+        hash_password("freelance-forge-dummy-password").expect("hashing a fixed password")
+    })
+}
+
+/// Generates a high-entropy token for links and credentials (64 hex characters).
+pub(crate) fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// Tokens are stored as SHA-256 digests so a database leak does not expose them.
+pub(crate) fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
@@ -0,0 +1,1079 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use utoipa::ToSchema;
+use validator::{Validate, ValidationError};
+
+use crate::modules::validation::field_error;
+
+/// Statement file formats the importer understands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StatementFormat {
+    /// ISO 20022 bank to customer statement (camt.053)
+    Camt053,
+    /// SWIFT MT940
+    Mt940,
+    /// Open Financial Exchange, SGML (1.x) or XML (2.x)
+    Ofx,
+    /// Delimited text with a configurable column mapping
+    Csv,
+}
+
+impl StatementFormat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StatementFormat::Camt053 => "camt053",
+            StatementFormat::Mt940 => "mt940",
+            StatementFormat::Ofx => "ofx",
+            StatementFormat::Csv => "csv",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        [Self::Camt053, Self::Mt940, Self::Ofx, Self::Csv]
+            .into_iter()
+            .find(|format| format.as_str() == value.trim().to_lowercase())
+    }
+
+    /// Guesses the format from the file contents; anything unrecognised is treated as CSV.
+    pub fn detect(text: &str) -> Self {
+        let head: String = text.chars().take(4096).collect();
+        if head.contains("camt.053") || head.contains("BkToCstmrStmt") {
+            StatementFormat::Camt053
+        } else if head.contains("OFXHEADER") || head.contains("<OFX>") {
+            StatementFormat::Ofx
+        } else if head.contains(":20:") && text.contains(":61:") {
+            StatementFormat::Mt940
+        } else {
+            StatementFormat::Csv
+        }
+    }
+}
+
+/// Which CSV columns hold which values. Columns are matched by their header, ignoring case.
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema, Validate)]
+#[validate(schema(function = "validate_csv_mapping"))]
+pub struct CsvMapping {
+    /// Field delimiter, a single character (default `;`)
+    #[validate(length(equal = 1))]
+    pub delimiter: Option<String>,
+    /// Lines before the header row, e.g. account details some banks print first (default 0)
+    #[validate(range(max = 50))]
+    pub skip_lines: Option<usize>,
+    #[validate(length(min = 1, max = 100))]
+    pub booking_date: String,
+    #[validate(length(min = 1, max = 100))]
+    pub value_date: Option<String>,
+    /// chrono format of the date columns (default `%d.%m.%Y`)
+    #[validate(length(min = 2, max = 20))]
+    pub date_format: Option<String>,
+    /// Signed amount; alternatively give `debit` and `credit`
+    #[validate(length(min = 1, max = 100))]
+    pub amount: Option<String>,
+    /// Outgoing amounts, with or without a minus sign
+    #[validate(length(min = 1, max = 100))]
+    pub debit: Option<String>,
+    /// Incoming amounts
+    #[validate(length(min = 1, max = 100))]
+    pub credit: Option<String>,
+    /// `,` (default) or `.`
+    pub decimal_separator: Option<String>,
+    #[validate(length(min = 1, max = 100))]
+    pub currency: Option<String>,
+    #[validate(length(min = 1, max = 100))]
+    pub counterparty_name: Option<String>,
+    #[validate(length(min = 1, max = 100))]
+    pub counterparty_iban: Option<String>,
+    #[validate(length(min = 1, max = 100))]
+    pub remittance_info: Option<String>,
+    #[validate(length(min = 1, max = 100))]
+    pub reference: Option<String>,
+}
+
+fn validate_csv_mapping(mapping: &CsvMapping) -> Result<(), ValidationError> {
+    if mapping.amount.is_none() && mapping.debit.is_none() && mapping.credit.is_none() {
+        return Err(field_error(
+            "amount",
+            "required",
+            "Map either an amount column or debit and credit columns",
+        ));
+    }
+    if let Some(separator) = mapping.decimal_separator.as_deref()
+        && separator != ","
+        && separator != "."
+    {
+        return Err(field_error(
+            "decimal_separator",
+            "decimal_separator",
+            "must be , or .",
+        ));
+    }
+    Ok(())
+}
+
+/// The bookings of one statement file.
+#[derive(Debug, Default)]
+pub struct Statement {
+    /// Account the statement belongs to, when the file names one.
+    pub account_iban: Option<String>,
+    pub currency: Option<String>,
+    pub entries: Vec<StatementEntry>,
+}
+
+#[derive(Debug, Default)]
+pub struct StatementEntry {
+    pub booking_date: NaiveDate,
+    pub value_date: Option<NaiveDate>,
+    /// Positive for incoming, negative for outgoing payments.
+    pub amount: f64,
+    pub currency: Option<String>,
+    pub counterparty_name: Option<String>,
+    pub counterparty_iban: Option<String>,
+    pub remittance_info: Option<String>,
+    pub reference: Option<String>,
+}
+
+/// Decodes a statement file: UTF-8 (with or without BOM), otherwise Windows-1252, which
+/// German banks still use for CSV and MT940 exports.
+pub fn decode_statement(bytes: &[u8]) -> String {
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => encoding_rs::WINDOWS_1252.decode(bytes).0.into_owned(),
+    }
+}
+
+/// Parses a statement file. Errors describe what is wrong with the file.
+pub fn parse_statement(
+    format: StatementFormat,
+    text: &str,
+    mapping: Option<&CsvMapping>,
+) -> Result<Statement, String> {
+    let statement = match format {
+        StatementFormat::Camt053 => parse_camt053(text)?,
+        StatementFormat::Mt940 => parse_mt940(text)?,
+        StatementFormat::Ofx => parse_ofx(text)?,
+        StatementFormat::Csv => {
+            let mapping = mapping.ok_or_else(|| {
+                "CSV files need a column mapping, either on the bank account or with the upload"
+                    .to_string()
+            })?;
+            parse_csv(text, mapping)?
+        }
+    };
+    if statement.entries.is_empty() {
+        return Err("The file contains no bookings".to_string());
+    }
+    Ok(statement)
+}
+
+/// Fingerprints identifying each entry across imports. The bank's reference is used where
+/// there is one; otherwise date, amount, counterparty and purpose. Identical bookings on the
+/// same day are told apart by their position among each other, so a re-import of the same
+/// file maps them to the same fingerprints.
+pub fn fingerprints(entries: &[StatementEntry]) -> Vec<String> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    entries
+        .iter()
+        .map(|entry| {
+            let cents = (entry.amount * 100.0).round() as i64;
+            let key = match entry.reference.as_deref() {
+                Some(reference) => format!("ref|{}|{}|{}", entry.booking_date, cents, reference),
+                None => format!(
+                    "{}|{}|{}|{}|{}",
+                    entry.booking_date,
+                    cents,
+                    normalize(entry.counterparty_name.as_deref()),
+                    normalize(entry.counterparty_iban.as_deref()),
+                    normalize(entry.remittance_info.as_deref()),
+                ),
+            };
+            let occurrence = seen.entry(key.clone()).or_default();
+            *occurrence += 1;
+            hex::encode(Sha256::digest(format!("{key}|{occurrence}").as_bytes()))
+        })
+        .collect()
+}
+
+fn normalize(value: Option<&str>) -> String {
+    value
+        .unwrap_or_default()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Trims a value and drops it when empty.
+fn clean(value: impl AsRef<str>) -> Option<String> {
+    let value = value.as_ref().split_whitespace().collect::<Vec<_>>().join(" ");
+    (!value.is_empty()).then_some(value)
+}
+
+fn parse_camt053(text: &str) -> Result<Statement, String> {
+    use roxmltree::{Document, Node};
+
+    fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+        node.children()
+            .find(|child| child.is_element() && child.tag_name().name() == name)
+    }
+    fn path<'a, 'input>(node: Node<'a, 'input>, names: &[&str]) -> Option<Node<'a, 'input>> {
+        names.iter().try_fold(node, |node, name| child(node, name))
+    }
+    fn text_at(node: Node, names: &[&str]) -> Option<String> {
+        path(node, names).and_then(|node| node.text()).and_then(clean)
+    }
+    fn date_at(node: Node, names: &[&str]) -> Option<NaiveDate> {
+        let parent = path(node, names)?;
+        let value = text_at(parent, &["Dt"]).or_else(|| text_at(parent, &["DtTm"]))?;
+        NaiveDate::parse_from_str(value.get(..10)?, "%Y-%m-%d").ok()
+    }
+    /// Name of a party: `Nm` directly (camt.053.001.02) or under `Pty` (later versions).
+    fn party_name(node: Node, party: &str) -> Option<String> {
+        text_at(node, &["RltdPties", party, "Nm"])
+            .or_else(|| text_at(node, &["RltdPties", party, "Pty", "Nm"]))
+    }
+
+    let document = Document::parse(text).map_err(|error| format!("Invalid XML: {error}"))?;
+    let report = document
+        .descendants()
+        .find(|node| node.is_element() && node.tag_name().name() == "BkToCstmrStmt")
+        .ok_or("Not a camt.053 statement")?;
+
+    let mut statement = Statement::default();
+    for stmt in report
+        .children()
+        .filter(|node| node.is_element() && node.tag_name().name() == "Stmt")
+    {
+        statement.account_iban = statement
+            .account_iban
+            .or_else(|| text_at(stmt, &["Acct", "Id", "IBAN"]));
+        statement.currency = statement.currency.or_else(|| text_at(stmt, &["Acct", "Ccy"]));
+
+        for entry in stmt
+            .children()
+            .filter(|node| node.is_element() && node.tag_name().name() == "Ntry")
+        {
+            let status = text_at(entry, &["Sts"]).or_else(|| text_at(entry, &["Sts", "Cd"]));
+            if status.as_deref().is_some_and(|status| status != "BOOK") {
+                continue;
+            }
+            let amount_node = child(entry, "Amt").ok_or("Entry without Amt")?;
+            let amount: f64 = amount_node
+                .text()
+                .and_then(|value| value.trim().parse().ok())
+                .ok_or("Entry with an invalid amount")?;
+            let incoming = match text_at(entry, &["CdtDbtInd"]).as_deref() {
+                Some("CRDT") => true,
+                Some("DBIT") => false,
+                _ => return Err("Entry without CdtDbtInd".to_string()),
+            };
+            // Reversals book the opposite direction.
+            let reversal = text_at(entry, &["RvslInd"]).as_deref() == Some("true");
+            let sign = if incoming != reversal { 1.0 } else { -1.0 };
+            let booking_date = date_at(entry, &["BookgDt"])
+                .or_else(|| date_at(entry, &["ValDt"]))
+                .ok_or("Entry without booking date")?;
+            let value_date = date_at(entry, &["ValDt"]);
+            let currency = amount_node.attribute("Ccy").map(str::to_string);
+            let bank_reference = text_at(entry, &["AcctSvcrRef"]);
+            let entry_info = text_at(entry, &["AddtlNtryInf"]);
+
+            let details: Vec<Node> = entry
+                .children()
+                .filter(|node| node.is_element() && node.tag_name().name() == "NtryDtls")
+                .flat_map(|node| node.children())
+                .filter(|node| node.is_element() && node.tag_name().name() == "TxDtls")
+                .collect();
+            // Batch bookings are split when every part carries its own amount.
+            let split = details.len() > 1
+                && details.iter().all(|detail| {
+                    path(*detail, &["AmtDtls", "TxAmt", "Amt"]).is_some()
+                        || child(*detail, "Amt").is_some()
+                });
+
+            let parts: Vec<(f64, Option<Node>)> = if split {
+                details
+                    .iter()
+                    .map(|detail| {
+                        let amount = path(*detail, &["AmtDtls", "TxAmt", "Amt"])
+                            .or_else(|| child(*detail, "Amt"))
+                            .and_then(|node| node.text())
+                            .and_then(|value| value.trim().parse().ok())
+                            .unwrap_or(0.0);
+                        (amount, Some(*detail))
+                    })
+                    .collect()
+            } else {
+                vec![(amount, details.first().copied())]
+            };
+
+            for (index, (amount, detail)) in parts.into_iter().enumerate() {
+                let (party, party_account) = if incoming {
+                    ("Dbtr", "DbtrAcct")
+                } else {
+                    ("Cdtr", "CdtrAcct")
+                };
+                let remittance = detail.and_then(|detail| {
+                    let lines: Vec<String> = child(detail, "RmtInf")?
+                        .children()
+                        .filter(|node| node.is_element() && node.tag_name().name() == "Ustrd")
+                        .filter_map(|node| node.text().and_then(clean))
+                        .collect();
+                    clean(lines.join(" "))
+                });
+                let reference = detail
+                    .and_then(|detail| text_at(detail, &["Refs", "EndToEndId"]))
+                    .filter(|reference| reference != "NOTPROVIDED")
+                    .or_else(|| {
+                        bank_reference
+                            .as_ref()
+                            .map(|reference| match split {
+                                true => format!("{reference}/{index}"),
+                                false => reference.clone(),
+                            })
+                    });
+                statement.entries.push(StatementEntry {
+                    booking_date,
+                    value_date,
+                    amount: sign * amount,
+                    currency: currency.clone(),
+                    counterparty_name: detail.and_then(|detail| party_name(detail, party)),
+                    counterparty_iban: detail.and_then(|detail| {
+                        text_at(detail, &["RltdPties", party_account, "Id", "IBAN"])
+                    }),
+                    remittance_info: remittance.or_else(|| entry_info.clone()),
+                    reference,
+                });
+            }
+        }
+    }
+    Ok(statement)
+}
+
+fn parse_mt940(text: &str) -> Result<Statement, String> {
+    // Join continuation lines onto their field; fields start with `:tag:`.
+    let mut fields: Vec<(String, String)> = Vec::new();
+    for line in text.lines() {
+        let line = line.trim_end();
+        if line == "-" || line.is_empty() {
+            continue;
+        }
+        let tag = line
+            .strip_prefix(':')
+            .and_then(|rest| rest.split_once(':'))
+            .filter(|(tag, _)| tag.len() <= 3 && tag.starts_with(|c: char| c.is_ascii_digit()));
+        match tag {
+            Some((tag, value)) => fields.push((tag.to_string(), value.to_string())),
+            None => {
+                if let Some((_, value)) = fields.last_mut() {
+                    value.push('\n');
+                    value.push_str(line);
+                }
+            }
+        }
+    }
+
+    let mut statement = Statement::default();
+    for (tag, value) in &fields {
+        match tag.as_str() {
+            "25" => {
+                let account = value.rsplit('/').next().unwrap_or(value).trim();
+                if statement.account_iban.is_none()
+                    && account.len() > 14
+                    && account.starts_with(|c: char| c.is_ascii_alphabetic())
+                {
+                    statement.account_iban = Some(account.to_uppercase());
+                }
+            }
+            // C|D, YYMMDD, currency, amount
+            "60F" | "60M" if statement.currency.is_none() => {
+                statement.currency = value.get(7..10).map(str::to_string);
+            }
+            "61" => {
+                let entry = parse_mt940_line(value)?;
+                statement.entries.push(entry);
+            }
+            "86" => {
+                let Some(entry) = statement.entries.last_mut() else {
+                    continue;
+                };
+                apply_mt940_details(entry, value);
+            }
+            _ => {}
+        }
+    }
+    for entry in &mut statement.entries {
+        entry.currency = statement.currency.clone();
+    }
+    Ok(statement)
+}
+
+/// Parses a `:61:` statement line: value date, optional entry date, debit/credit mark,
+/// optional funds code, amount, transaction type, customer and bank reference.
+fn parse_mt940_line(value: &str) -> Result<StatementEntry, String> {
+    let invalid = || format!("Invalid :61: line {value:?}");
+    let first_line = value.lines().next().unwrap_or_default();
+    let value_date = first_line
+        .get(..6)
+        .and_then(|date| NaiveDate::parse_from_str(&format!("20{date}"), "%Y%m%d").ok())
+        .ok_or_else(invalid)?;
+    let mut rest = &first_line[6..];
+
+    let mut booking_date = value_date;
+    // Not indexed: a mis-encoded file may put a multi-byte character here.
+    if let Some(month_day) = rest
+        .get(..4)
+        .filter(|month_day| month_day.chars().all(|c| c.is_ascii_digit()))
+    {
+        // The entry date has no year; it may fall into the next or previous year.
+        booking_date = [0, 1, -1]
+            .into_iter()
+            .filter_map(|offset| {
+                NaiveDate::parse_from_str(
+                    &format!("{}{}", value_date.format("%Y").to_string().parse::<i32>().ok()? + offset, month_day),
+                    "%Y%m%d",
+                )
+                .ok()
+            })
+            .min_by_key(|date| (*date - value_date).num_days().abs())
+            .ok_or_else(invalid)?;
+        rest = &rest[4..];
+    }
+
+    let (sign, after_mark) = if let Some(rest) = rest.strip_prefix("RC") {
+        (-1.0, rest)
+    } else if let Some(rest) = rest.strip_prefix("RD") {
+        (1.0, rest)
+    } else if let Some(rest) = rest.strip_prefix('C') {
+        (1.0, rest)
+    } else if let Some(rest) = rest.strip_prefix('D') {
+        (-1.0, rest)
+    } else {
+        return Err(invalid());
+    };
+    // Optional funds code (third letter of the currency code).
+    let after_mark = match after_mark.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() => &after_mark[1..],
+        _ => after_mark,
+    };
+    let amount_length = after_mark
+        .find(|c: char| !c.is_ascii_digit() && c != ',')
+        .unwrap_or(after_mark.len());
+    let amount: f64 = after_mark[..amount_length]
+        .replace(',', ".")
+        .parse()
+        .map_err(|_| invalid())?;
+
+    // Transaction type (e.g. NTRF), then the customer reference up to `//`.
+    let rest = after_mark[amount_length..].get(4..).unwrap_or_default();
+    let (customer_reference, bank_reference) = match rest.split_once("//") {
+        Some((customer, bank)) => (customer, Some(bank)),
+        None => (rest, None),
+    };
+    let reference = clean(customer_reference)
+        .filter(|reference| reference != "NONREF")
+        .or_else(|| bank_reference.and_then(clean));
+
+    Ok(StatementEntry {
+        booking_date,
+        value_date: Some(value_date),
+        amount: sign * amount,
+        reference,
+        ..Default::default()
+    })
+}
+
+/// Reads a `:86:` field. German banks structure it with `?nn` subfields: 20–29 and 60–63
+/// purpose, 30 bank code, 31 account, 32–33 name. Anything else is kept as purpose.
+fn apply_mt940_details(entry: &mut StatementEntry, value: &str) {
+    let value = value.replace('\n', "");
+    let Some(separator) = value.chars().nth(3).filter(|c| !c.is_alphanumeric() && *c != ' ')
+    else {
+        entry.remittance_info = clean(&value);
+        return;
+    };
+
+    let mut purpose = Vec::new();
+    let mut name = Vec::new();
+    for part in value.split(separator).skip(1) {
+        let code_end = part.char_indices().nth(2).map_or(part.len(), |(index, _)| index);
+        let (code, text) = part.split_at(code_end);
+        match code {
+            "20" | "21" | "22" | "23" | "24" | "25" | "26" | "27" | "28" | "29" | "60" | "61"
+            | "62" | "63" => purpose.push(text.to_string()),
+            "31" => entry.counterparty_iban = clean(text),
+            "32" | "33" => name.push(text.to_string()),
+            _ => {}
+        }
+    }
+    entry.counterparty_name = clean(name.concat());
+    entry.remittance_info = clean(purpose.concat());
+}
+
+fn parse_ofx(text: &str) -> Result<Statement, String> {
+    /// Value of a leaf element: `<TAG>value` in SGML, `<TAG>value</TAG>` in XML.
+    fn field(block: &str, tag: &str) -> Option<String> {
+        let start = block.find(&format!("<{tag}>"))? + tag.len() + 2;
+        let rest = &block[start..];
+        let end = rest.find('<').unwrap_or(rest.len());
+        clean(decode_entities(&rest[..end]))
+    }
+    /// Contents of an aggregate such as `<BANKACCTTO>...</BANKACCTTO>`, which is closed in
+    /// both SGML and XML.
+    fn aggregate<'a>(block: &'a str, tag: &str) -> Option<&'a str> {
+        let start = block.find(&format!("<{tag}>"))? + tag.len() + 2;
+        let rest = &block[start..];
+        Some(&rest[..rest.find(&format!("</{tag}>"))?])
+    }
+    fn date(value: &str) -> Option<NaiveDate> {
+        NaiveDate::parse_from_str(value.get(..8)?, "%Y%m%d").ok()
+    }
+
+    let mut statement = Statement {
+        account_iban: field(text, "ACCTID").filter(|id| {
+            id.len() > 14 && id.starts_with(|c: char| c.is_ascii_alphabetic())
+        }),
+        currency: field(text, "CURDEF"),
+        entries: Vec::new(),
+    };
+
+    for block in text.split("<STMTTRN>").skip(1) {
+        let block = block.split("</STMTTRN>").next().unwrap_or(block);
+        let posted = field(block, "DTPOSTED").ok_or("Transaction without DTPOSTED")?;
+        let amount = field(block, "TRNAMT").ok_or("Transaction without TRNAMT")?;
+        let memo = field(block, "MEMO");
+        let name = field(block, "NAME").or_else(|| field(block, "PAYEE"));
+        statement.entries.push(StatementEntry {
+            booking_date: date(&posted).ok_or_else(|| format!("Invalid date {posted:?}"))?,
+            value_date: field(block, "DTAVAIL").as_deref().and_then(date),
+            amount: amount
+                .replace(',', ".")
+                .parse()
+                .map_err(|_| format!("Invalid amount {amount:?}"))?,
+            currency: aggregate(block, "CURRENCY")
+                .and_then(|currency| field(currency, "CURSYM"))
+                .or_else(|| statement.currency.clone()),
+            counterparty_name: name,
+            counterparty_iban: aggregate(block, "BANKACCTTO")
+                .and_then(|account| field(account, "ACCTID")),
+            remittance_info: memo,
+            reference: field(block, "FITID").or_else(|| field(block, "REFNUM")),
+        });
+    }
+    Ok(statement)
+}
+
+fn decode_entities(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn parse_csv(text: &str, mapping: &CsvMapping) -> Result<Statement, String> {
+    let delimiter = mapping
+        .delimiter
+        .as_deref()
+        .and_then(|delimiter| delimiter.bytes().next())
+        .unwrap_or(b';');
+    let date_format = mapping.date_format.as_deref().unwrap_or("%d.%m.%Y");
+    let decimal_comma = mapping.decimal_separator.as_deref() != Some(".");
+    let body: String = text
+        .lines()
+        .skip(mapping.skip_lines.unwrap_or(0))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_reader(body.as_bytes());
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(|error| format!("Could not read the header row: {error}"))?
+        .iter()
+        .map(|header| header.trim().to_lowercase())
+        .collect();
+    let column = |name: &Option<String>| -> Result<Option<usize>, String> {
+        let Some(name) = name else {
+            return Ok(None);
+        };
+        let wanted = name.trim().to_lowercase();
+        headers
+            .iter()
+            .position(|header| *header == wanted)
+            .map(Some)
+            .ok_or_else(|| format!("Column {name:?} not found in the header row"))
+    };
+    let booking_date = column(&Some(mapping.booking_date.clone()))?.unwrap_or_default();
+    let value_date = column(&mapping.value_date)?;
+    let amount = column(&mapping.amount)?;
+    let debit = column(&mapping.debit)?;
+    let credit = column(&mapping.credit)?;
+    let currency = column(&mapping.currency)?;
+    let counterparty_name = column(&mapping.counterparty_name)?;
+    let counterparty_iban = column(&mapping.counterparty_iban)?;
+    let remittance_info = column(&mapping.remittance_info)?;
+    let reference = column(&mapping.reference)?;
+
+    let mut statement = Statement::default();
+    for (index, record) in reader.records().enumerate() {
+        // Header and skipped lines come first; rows are numbered as in the file.
+        let line = index + mapping.skip_lines.unwrap_or(0) + 2;
+        let record = record.map_err(|error| format!("Line {line}: {error}"))?;
+        let get = |column: Option<usize>| column.and_then(|column| record.get(column)).and_then(clean);
+        if record.iter().all(|value| value.trim().is_empty()) {
+            continue;
+        }
+
+        let date_value = get(Some(booking_date)).ok_or(format!("Line {line}: missing date"))?;
+        let parse_date = |value: &str| NaiveDate::parse_from_str(value, date_format);
+        let booked = parse_date(&date_value)
+            .map_err(|_| format!("Line {line}: date {date_value:?} does not match {date_format}"))?;
+
+        let parse = |value: Option<String>| -> Result<Option<f64>, String> {
+            value
+                .map(|value| {
+                    parse_csv_amount(&value, decimal_comma)
+                        .ok_or_else(|| format!("Line {line}: invalid amount {value:?}"))
+                })
+                .transpose()
+        };
+        let signed = match amount {
+            Some(_) => parse(get(amount))?,
+            None => match (parse(get(debit))?, parse(get(credit))?) {
+                (Some(debit), _) if debit != 0.0 => Some(-debit.abs()),
+                (_, Some(credit)) => Some(credit.abs()),
+                (Some(debit), None) => Some(debit),
+                (None, None) => None,
+            },
+        };
+        let amount = signed.ok_or(format!("Line {line}: missing amount"))?;
+
+        statement.entries.push(StatementEntry {
+            booking_date: booked,
+            value_date: get(value_date).and_then(|value| parse_date(&value).ok()),
+            amount,
+            currency: get(currency).map(|currency| currency.to_uppercase()),
+            counterparty_name: get(counterparty_name),
+            counterparty_iban: get(counterparty_iban)
+                .map(|iban| iban.replace(' ', "").to_uppercase()),
+            remittance_info: get(remittance_info),
+            reference: get(reference),
+        });
+    }
+    Ok(statement)
+}
+
+/// Reads amounts such as `-1.234,56`, `1,234.56 €` or `12,00-`.
+fn parse_csv_amount(value: &str, decimal_comma: bool) -> Option<f64> {
+    let trimmed: String = value
+        .chars()
+        .filter(|c| c.is_ascii_digit() || matches!(c, ',' | '.' | '-' | '+'))
+        .collect();
+    let negative = trimmed.starts_with('-') || trimmed.ends_with('-');
+    let digits = trimmed.trim_matches(['-', '+']);
+    let normalized = if decimal_comma {
+        digits.replace('.', "").replace(',', ".")
+    } else {
+        digits.replace(',', "")
+    };
+    let amount: f64 = normalized.parse().ok()?;
+    Some(if negative { -amount } else { amount })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CAMT053: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.08">
+  <BkToCstmrStmt>
+    <Stmt>
+      <Acct><Id><IBAN>DE89370400440532013000</IBAN></Id><Ccy>EUR</Ccy></Acct>
+      <Ntry>
+        <Amt Ccy="EUR">1190.00</Amt>
+        <CdtDbtInd>CRDT</CdtDbtInd>
+        <Sts><Cd>BOOK</Cd></Sts>
+        <BookgDt><Dt>2026-03-02</Dt></BookgDt>
+        <ValDt><Dt>2026-03-03</Dt></ValDt>
+        <AcctSvcrRef>BANKREF1</AcctSvcrRef>
+        <NtryDtls><TxDtls>
+          <Refs><EndToEndId>RE-2026-0042</EndToEndId></Refs>
+          <RltdPties>
+            <Dbtr><Pty><Nm>Müller &amp; Söhne GmbH</Nm></Pty></Dbtr>
+            <DbtrAcct><Id><IBAN>DE02120300000000202051</IBAN></Id></DbtrAcct>
+          </RltdPties>
+          <RmtInf><Ustrd>Rechnung RE-2026-0042</Ustrd><Ustrd>Kunde 17</Ustrd></RmtInf>
+        </TxDtls></NtryDtls>
+      </Ntry>
+      <Ntry>
+        <Amt Ccy="EUR">300.00</Amt>
+        <CdtDbtInd>DBIT</CdtDbtInd>
+        <Sts><Cd>BOOK</Cd></Sts>
+        <BookgDt><DtTm>2026-03-04T09:30:00</DtTm></BookgDt>
+        <AcctSvcrRef>BATCH7</AcctSvcrRef>
+        <NtryDtls>
+          <TxDtls>
+            <Refs><EndToEndId>NOTPROVIDED</EndToEndId></Refs>
+            <AmtDtls><TxAmt><Amt Ccy="EUR">100.00</Amt></TxAmt></AmtDtls>
+            <RltdPties><Cdtr><Nm>Stadtwerke</Nm></Cdtr></RltdPties>
+          </TxDtls>
+          <TxDtls>
+            <Refs><EndToEndId>NOTPROVIDED</EndToEndId></Refs>
+            <AmtDtls><TxAmt><Amt Ccy="EUR">200.00</Amt></TxAmt></AmtDtls>
+            <RltdPties><Cdtr><Nm>Vermieter</Nm></Cdtr></RltdPties>
+          </TxDtls>
+        </NtryDtls>
+      </Ntry>
+      <Ntry>
+        <Amt Ccy="EUR">50.00</Amt>
+        <CdtDbtInd>CRDT</CdtDbtInd>
+        <RvslInd>true</RvslInd>
+        <Sts><Cd>BOOK</Cd></Sts>
+        <BookgDt><Dt>2026-03-05</Dt></BookgDt>
+        <AddtlNtryInf>Rücklastschrift</AddtlNtryInf>
+      </Ntry>
+      <Ntry>
+        <Amt Ccy="EUR">999.00</Amt>
+        <CdtDbtInd>CRDT</CdtDbtInd>
+        <Sts><Cd>PDNG</Cd></Sts>
+        <BookgDt><Dt>2026-03-06</Dt></BookgDt>
+      </Ntry>
+    </Stmt>
+  </BkToCstmrStmt>
+</Document>"#;
+
+    const MT940: &str = ":20:STARTUMS
+:25:DE89370400440532013000
+:28C:00001/001
+:60F:C251231EUR1000,00
+:61:2512311231C1190,00NTRFRE-2026-0042//BANKREF1
+:86:166?00GUTSCHRIFT?20Rechnung RE-2026-0042 ?21Kunde 17?30COBADEFFXXX
+?31DE02120300000000202051?32Müller & Söhne?33 GmbH
+:61:2601020101RD45,50NTRFNONREF//BANKREF2
+:86:Lastschrift Büromaterial
+:62F:C260102EUR2144,50
+-";
+
+    const OFX: &str = "OFXHEADER:100
+DATA:OFXSGML
+<OFX><BANKMSGSRSV1><STMTTRNRS><STMTRS>
+<CURDEF>EUR
+<BANKACCTFROM><BANKID>37040044<ACCTID>DE89370400440532013000</BANKACCTFROM>
+<BANKTRANLIST>
+<STMTTRN><TRNTYPE>CREDIT<DTPOSTED>20260302120000<TRNAMT>1190.00<FITID>FIT1
+<NAME>Müller &amp; Söhne<MEMO>Rechnung RE-2026-0042
+<BANKACCTTO><ACCTID>DE02120300000000202051</BANKACCTTO>
+</STMTTRN>
+<STMTTRN><TRNTYPE>DEBIT<DTPOSTED>20260304<DTAVAIL>20260305<TRNAMT>-45,50<FITID>FIT2
+<PAYEE>Bürobedarf<CURRENCY><CURSYM>USD</CURRENCY>
+</STMTTRN>
+</BANKTRANLIST></STMTRS></STMTTRNRS></BANKMSGSRSV1></OFX>";
+
+    const CSV: &str = "Kontoauszug Girokonto
+IBAN;DE89370400440532013000
+Buchungstag;Valuta;Empfänger;IBAN;Verwendungszweck;Soll;Haben;Währung
+02.03.2026;03.03.2026;Müller & Söhne GmbH;de02 1203 0000 0000 2020 51;Rechnung RE-2026-0042;;1.190,00;eur
+04.03.2026;04.03.2026;Stadtwerke;;Abschlag März;-1.045,50;;EUR
+;;;;;;;
+";
+
+    fn csv_mapping() -> CsvMapping {
+        CsvMapping {
+            delimiter: None,
+            skip_lines: Some(2),
+            booking_date: "Buchungstag".to_string(),
+            value_date: Some("Valuta".to_string()),
+            date_format: None,
+            amount: None,
+            debit: Some("Soll".to_string()),
+            credit: Some("Haben".to_string()),
+            decimal_separator: None,
+            currency: Some("Währung".to_string()),
+            counterparty_name: Some("Empfänger".to_string()),
+            counterparty_iban: Some("iban".to_string()),
+            remittance_info: Some("Verwendungszweck".to_string()),
+            reference: None,
+        }
+    }
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    fn amounts(statement: &Statement) -> Vec<f64> {
+        statement.entries.iter().map(|entry| entry.amount).collect()
+    }
+
+    fn entry(day: &str, amount: f64, name: &str) -> StatementEntry {
+        StatementEntry {
+            booking_date: date(day),
+            amount,
+            counterparty_name: Some(name.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn detects_formats_from_the_contents() {
+        assert_eq!(StatementFormat::detect(CAMT053), StatementFormat::Camt053);
+        assert_eq!(StatementFormat::detect(MT940), StatementFormat::Mt940);
+        assert_eq!(StatementFormat::detect(OFX), StatementFormat::Ofx);
+        assert_eq!(StatementFormat::detect(CSV), StatementFormat::Csv);
+    }
+
+    #[test]
+    fn parses_camt053_with_batches_and_reversals() {
+        let statement = parse_statement(StatementFormat::Camt053, CAMT053, None).unwrap();
+        assert_eq!(statement.account_iban.as_deref(), Some("DE89370400440532013000"));
+        assert_eq!(statement.currency.as_deref(), Some("EUR"));
+        // The pending entry is skipped and the batch is split into its two payments.
+        assert_eq!(amounts(&statement), vec![1190.0, -100.0, -200.0, -50.0]);
+
+        let invoice = &statement.entries[0];
+        assert_eq!(invoice.booking_date, date("2026-03-02"));
+        assert_eq!(invoice.value_date, Some(date("2026-03-03")));
+        assert_eq!(invoice.counterparty_name.as_deref(), Some("Müller & Söhne GmbH"));
+        assert_eq!(invoice.counterparty_iban.as_deref(), Some("DE02120300000000202051"));
+        assert_eq!(invoice.remittance_info.as_deref(), Some("Rechnung RE-2026-0042 Kunde 17"));
+        assert_eq!(invoice.reference.as_deref(), Some("RE-2026-0042"));
+
+        let batch = &statement.entries[1..3];
+        assert_eq!(batch[0].booking_date, date("2026-03-04"));
+        assert_eq!(batch[0].counterparty_name.as_deref(), Some("Stadtwerke"));
+        assert_eq!(batch[0].reference.as_deref(), Some("BATCH7/0"));
+        assert_eq!(batch[1].reference.as_deref(), Some("BATCH7/1"));
+
+        let reversal = &statement.entries[3];
+        assert_eq!(reversal.remittance_info.as_deref(), Some("Rücklastschrift"));
+        assert_eq!(reversal.reference, None);
+    }
+
+    #[test]
+    fn rejects_malformed_camt053() {
+        let cases = [
+            ("<Document><BkToCstmrStmt>", "Invalid XML"),
+            ("<Document><Other/></Document>", "Not a camt.053 statement"),
+            (
+                "<Document><BkToCstmrStmt><Stmt><Ntry><Amt>1.00</Amt><BookgDt><Dt>2026-03-02</Dt></BookgDt></Ntry></Stmt></BkToCstmrStmt></Document>",
+                "Entry without CdtDbtInd",
+            ),
+            (
+                "<Document><BkToCstmrStmt><Stmt><Ntry><Amt>1,00</Amt><CdtDbtInd>CRDT</CdtDbtInd></Ntry></Stmt></BkToCstmrStmt></Document>",
+                "Entry with an invalid amount",
+            ),
+            (
+                "<Document><BkToCstmrStmt><Stmt></Stmt></BkToCstmrStmt></Document>",
+                "The file contains no bookings",
+            ),
+        ];
+        for (text, expected) in cases {
+            let error = parse_statement(StatementFormat::Camt053, text, None).unwrap_err();
+            assert!(error.starts_with(expected), "{error}");
+        }
+    }
+
+    #[test]
+    fn parses_mt940_with_structured_details() {
+        let statement = parse_statement(StatementFormat::Mt940, MT940, None).unwrap();
+        assert_eq!(statement.account_iban.as_deref(), Some("DE89370400440532013000"));
+        assert_eq!(amounts(&statement), vec![1190.0, 45.5]);
+
+        let invoice = &statement.entries[0];
+        assert_eq!(invoice.value_date, Some(date("2025-12-31")));
+        assert_eq!(invoice.booking_date, date("2025-12-31"));
+        assert_eq!(invoice.currency.as_deref(), Some("EUR"));
+        assert_eq!(invoice.reference.as_deref(), Some("RE-2026-0042"));
+        assert_eq!(invoice.counterparty_name.as_deref(), Some("Müller & Söhne GmbH"));
+        assert_eq!(invoice.counterparty_iban.as_deref(), Some("DE02120300000000202051"));
+        assert_eq!(invoice.remittance_info.as_deref(), Some("Rechnung RE-2026-0042 Kunde 17"));
+
+        // Reversal of a debit; booked on the first of January for a value date a day later.
+        let refund = &statement.entries[1];
+        assert_eq!(refund.booking_date, date("2026-01-01"));
+        assert_eq!(refund.value_date, Some(date("2026-01-02")));
+        assert_eq!(refund.reference.as_deref(), Some("BANKREF2"));
+        assert_eq!(refund.remittance_info.as_deref(), Some("Lastschrift Büromaterial"));
+    }
+
+    #[test]
+    fn rejects_malformed_mt940_without_panicking() {
+        for line in [
+            ":61:26030ä0302C1,00NTRF",
+            ":61:260302030äC1,00NTRF",
+            ":61:2603020302X10,00NTRF",
+            ":61:260302C,xNTRF",
+        ] {
+            let text = format!(":20:START\n:25:DE89370400440532013000\n{line}\n-");
+            let error = parse_statement(StatementFormat::Mt940, &text, None).unwrap_err();
+            assert!(error.starts_with("Invalid :61: line"), "{line}: {error}");
+        }
+
+        // A multi-byte character right after the subfield separator.
+        let text = ":20:START\n:61:260302C10,00NTRFNONREF\n:86:166?2ä Miete?32Vermieter\n-";
+        let statement = parse_statement(StatementFormat::Mt940, text, None).unwrap();
+        assert_eq!(statement.entries[0].counterparty_name.as_deref(), Some("Vermieter"));
+        assert_eq!(statement.entries[0].remittance_info, None);
+    }
+
+    #[test]
+    fn parses_ofx() {
+        let statement = parse_statement(StatementFormat::Ofx, OFX, None).unwrap();
+        assert_eq!(statement.account_iban.as_deref(), Some("DE89370400440532013000"));
+        assert_eq!(amounts(&statement), vec![1190.0, -45.5]);
+
+        let invoice = &statement.entries[0];
+        assert_eq!(invoice.booking_date, date("2026-03-02"));
+        assert_eq!(invoice.currency.as_deref(), Some("EUR"));
+        assert_eq!(invoice.counterparty_name.as_deref(), Some("Müller & Söhne"));
+        assert_eq!(invoice.counterparty_iban.as_deref(), Some("DE02120300000000202051"));
+        assert_eq!(invoice.remittance_info.as_deref(), Some("Rechnung RE-2026-0042"));
+        assert_eq!(invoice.reference.as_deref(), Some("FIT1"));
+
+        let purchase = &statement.entries[1];
+        assert_eq!(purchase.value_date, Some(date("2026-03-05")));
+        assert_eq!(purchase.currency.as_deref(), Some("USD"));
+        assert_eq!(purchase.counterparty_name.as_deref(), Some("Bürobedarf"));
+    }
+
+    #[test]
+    fn rejects_malformed_ofx() {
+        let cases = [
+            ("<OFX><STMTTRN><TRNAMT>1.00</STMTTRN></OFX>", "Transaction without DTPOSTED"),
+            ("<OFX><STMTTRN><DTPOSTED>2026ä0302<TRNAMT>1.00</STMTTRN></OFX>", "Invalid date"),
+            ("<OFX><STMTTRN><DTPOSTED>20260302<TRNAMT>1.0.0</STMTTRN></OFX>", "Invalid amount"),
+        ];
+        for (text, expected) in cases {
+            let error = parse_statement(StatementFormat::Ofx, text, None).unwrap_err();
+            assert!(error.starts_with(expected), "{error}");
+        }
+    }
+
+    #[test]
+    fn parses_csv_with_debit_and_credit_columns() {
+        let statement = parse_statement(StatementFormat::Csv, CSV, Some(&csv_mapping())).unwrap();
+        assert_eq!(amounts(&statement), vec![1190.0, -1045.5]);
+
+        let invoice = &statement.entries[0];
+        assert_eq!(invoice.booking_date, date("2026-03-02"));
+        assert_eq!(invoice.value_date, Some(date("2026-03-03")));
+        assert_eq!(invoice.currency.as_deref(), Some("EUR"));
+        assert_eq!(invoice.counterparty_name.as_deref(), Some("Müller & Söhne GmbH"));
+        assert_eq!(invoice.counterparty_iban.as_deref(), Some("DE02120300000000202051"));
+        assert_eq!(statement.entries[1].counterparty_iban, None);
+    }
+
+    #[test]
+    fn decodes_windows_1252_exports() {
+        let bytes = encoding_rs::WINDOWS_1252.encode(CSV).0;
+        assert!(std::str::from_utf8(&bytes).is_err());
+        let text = decode_statement(&bytes);
+        let statement = parse_statement(StatementFormat::Csv, &text, Some(&csv_mapping())).unwrap();
+        assert_eq!(statement.entries[0].counterparty_name.as_deref(), Some("Müller & Söhne GmbH"));
+
+        assert_eq!(decode_statement(b"\xEF\xBB\xBFBuchungstag"), "Buchungstag");
+    }
+
+    #[test]
+    fn reports_the_csv_line_of_a_bad_row() {
+        let mapping = csv_mapping();
+        let bad_date = CSV.replace("04.03.2026;04.03.2026", "2026-03-04;04.03.2026");
+        let error = parse_statement(StatementFormat::Csv, &bad_date, Some(&mapping)).unwrap_err();
+        assert_eq!(error, "Line 5: date \"2026-03-04\" does not match %d.%m.%Y");
+
+        let bad_amount = CSV.replace("-1.045,50", "abc");
+        let error = parse_statement(StatementFormat::Csv, &bad_amount, Some(&mapping)).unwrap_err();
+        assert_eq!(error, "Line 5: invalid amount \"abc\"");
+
+        let mapping = CsvMapping {
+            reference: Some("Referenz".to_string()),
+            ..csv_mapping()
+        };
+        let error = parse_statement(StatementFormat::Csv, CSV, Some(&mapping)).unwrap_err();
+        assert_eq!(error, "Column \"Referenz\" not found in the header row");
+        assert!(parse_statement(StatementFormat::Csv, CSV, None).is_err());
+    }
+
+    #[test]
+    fn parses_csv_amount_formats() {
+        let cases = [
+            ("-1.234,56", true, Some(-1234.56)),
+            ("1.234,56 €", true, Some(1234.56)),
+            ("12,00-", true, Some(-12.0)),
+            ("+5", true, Some(5.0)),
+            ("1,234.56", false, Some(1234.56)),
+            ("-0.99 USD", false, Some(-0.99)),
+            ("", true, None),
+            ("n/a", true, None),
+        ];
+        for (value, decimal_comma, expected) in cases {
+            assert_eq!(parse_csv_amount(value, decimal_comma), expected, "{value}");
+        }
+    }
+
+    #[test]
+    fn fingerprints_are_stable_across_re_imports() {
+        for (format, text) in [
+            (StatementFormat::Camt053, CAMT053),
+            (StatementFormat::Mt940, MT940),
+            (StatementFormat::Ofx, OFX),
+            (StatementFormat::Csv, CSV),
+        ] {
+            let mapping = csv_mapping();
+            let first = parse_statement(format, text, Some(&mapping)).unwrap();
+            let second = parse_statement(format, text, Some(&mapping)).unwrap();
+            let fingerprints = fingerprints(&first.entries);
+            assert_eq!(fingerprints, super::fingerprints(&second.entries), "{format:?}");
+            let mut distinct = fingerprints.clone();
+            distinct.sort();
+            distinct.dedup();
+            assert_eq!(distinct.len(), fingerprints.len(), "{format:?}");
+        }
+    }
+
+    #[test]
+    fn overlapping_statements_share_the_fingerprints_of_common_bookings() {
+        let march = [
+            entry("2026-03-01", -4.5, "Bäckerei"),
+            entry("2026-03-02", -4.5, "Bäckerei"),
+            entry("2026-03-02", -4.5, "Bäckerei"),
+        ];
+        // A later export starts with the second of March and repeats both coffees.
+        let later = [
+            entry("2026-03-02", -4.5, "  BÄCKEREI "),
+            entry("2026-03-02", -4.5, "Bäckerei"),
+            entry("2026-03-02", -4.5, "Bäckerei"),
+            entry("2026-03-03", 100.0, "Kunde"),
+        ];
+        let known = fingerprints(&march);
+        let new: Vec<String> = fingerprints(&later)
+            .into_iter()
+            .filter(|fingerprint| !known.contains(fingerprint))
+            .collect();
+        // Only the third coffee of the day and the incoming payment are new.
+        assert_eq!(new.len(), 2);
+        assert_eq!(new[1], fingerprints(&later)[3]);
+    }
+
+    #[test]
+    fn bank_references_take_precedence_over_details() {
+        let with_reference = |name: &str| StatementEntry {
+            reference: Some("REF1".to_string()),
+            ..entry("2026-03-02", 10.0, name)
+        };
+        assert_eq!(
+            fingerprints(&[with_reference("Kunde")]),
+            fingerprints(&[with_reference("Kunde GmbH")]),
+        );
+        assert_ne!(
+            fingerprints(&[entry("2026-03-02", 10.0, "Kunde")]),
+            fingerprints(&[entry("2026-03-02", 10.01, "Kunde")]),
+        );
+    }
+}
@@ -0,0 +1,706 @@
+use crate::entity::{bank_account, bank_import, bank_match, bank_transaction};
+use crate::modules::api_tokens::ApiScope;
+use crate::modules::auth::require_access;
+use crate::modules::bank_statements::{
+    decode_statement, fingerprints, parse_statement, CsvMapping, StatementFormat,
+};
+use crate::modules::receipts::safe_filename;
+use crate::modules::reconciliation::{accepted_subquery, propose_matches};
+use crate::modules::shared::{
+    fetch_page, search_pattern, AppError, AppState, FieldError, Page, PageParams, SortOrder,
+};
+use crate::modules::validation::{currency_code, iban, not_blank, Path, Query, ValidatedJson};
+use crate::modules::workspaces::require_manager;
+use axum::{
+    extract::{multipart::MultipartError, Multipart, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use sea_orm::sea_query::{extension::postgres::PgExpr, Expr, Func};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, EntityTrait, PaginatorTrait,
+    QueryFilter, QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+use validator::Validate;
+
+const DEFAULT_MAX_STATEMENT_BYTES: usize = 10 * 1024 * 1024;
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct BankAccountCreateRequest {
+    #[validate(custom(function = not_blank), length(max = 100))]
+    pub name: String,
+    /// Imports are checked against it when the file names its account
+    #[validate(custom(function = crate::modules::validation::iban))]
+    pub iban: Option<String>,
+    #[validate(length(min = 8, max = 11))]
+    pub bic: Option<String>,
+    /// Default EUR
+    #[validate(custom(function = currency_code))]
+    pub currency: Option<String>,
+    /// Default column mapping for CSV imports
+    #[validate(nested)]
+    pub csv_mapping: Option<CsvMapping>,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct BankAccountUpdateRequest {
+    #[validate(custom(function = not_blank), length(max = 100))]
+    pub name: Option<String>,
+    /// An empty string removes the IBAN
+    pub iban: Option<String>,
+    /// An empty string removes the BIC
+    pub bic: Option<String>,
+    #[validate(custom(function = currency_code))]
+    pub currency: Option<String>,
+    #[validate(nested)]
+    pub csv_mapping: Option<CsvMapping>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BankAccountResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub iban: Option<String>,
+    pub bic: Option<String>,
+    pub currency: String,
+    pub csv_mapping: Option<CsvMapping>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<bank_account::Model> for BankAccountResponse {
+    fn from(account: bank_account::Model) -> Self {
+        Self {
+            id: account.id,
+            name: account.name,
+            iban: account.iban,
+            bic: account.bic,
+            currency: account.currency,
+            csv_mapping: account
+                .csv_mapping
+                .and_then(|mapping| serde_json::from_value(mapping).ok()),
+            created_at: account.created_at,
+        }
+    }
+}
+
+/// Multipart form accepted by `POST /bank-accounts/{id}/imports`. Only used for the API docs.
+#[derive(ToSchema)]
+#[allow(dead_code)]
+pub struct BankImportForm {
+    /// The statement file
+    #[schema(value_type = String, format = Binary)]
+    pub file: Vec<u8>,
+    /// Detected from the contents when omitted
+    pub format: Option<StatementFormat>,
+    /// JSON `CsvMapping` for this file; defaults to the account's mapping
+    pub mapping: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BankImportResponse {
+    pub id: Uuid,
+    pub bank_account_id: Uuid,
+    pub format: String,
+    pub filename: String,
+    /// New bookings added to the ledger
+    pub imported_count: i32,
+    /// Bookings skipped because an earlier import already contained them
+    pub duplicate_count: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<bank_import::Model> for BankImportResponse {
+    fn from(import: bank_import::Model) -> Self {
+        Self {
+            id: import.id,
+            bank_account_id: import.bank_account_id,
+            format: import.format,
+            filename: import.filename,
+            imported_count: import.imported_count,
+            duplicate_count: import.duplicate_count,
+            created_at: import.created_at,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BankTransactionResponse {
+    pub id: Uuid,
+    pub bank_account_id: Uuid,
+    pub import_id: Uuid,
+    pub booking_date: NaiveDate,
+    pub value_date: Option<NaiveDate>,
+    /// Positive for incoming, negative for outgoing payments
+    pub amount: f64,
+    pub currency: String,
+    pub counterparty_name: Option<String>,
+    pub counterparty_iban: Option<String>,
+    /// Purpose of payment
+    pub remittance_info: Option<String>,
+    pub reference: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<bank_transaction::Model> for BankTransactionResponse {
+    fn from(transaction: bank_transaction::Model) -> Self {
+        Self {
+            id: transaction.id,
+            bank_account_id: transaction.bank_account_id,
+            import_id: transaction.import_id,
+            booking_date: transaction.booking_date,
+            value_date: transaction.value_date,
+            amount: transaction.amount,
+            currency: transaction.currency,
+            counterparty_name: transaction.counterparty_name,
+            counterparty_iban: transaction.counterparty_iban,
+            remittance_info: transaction.remittance_info,
+            reference: transaction.reference,
+            created_at: transaction.created_at,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionDirection {
+    Incoming,
+    Outgoing,
+}
+
+#[derive(Clone, Copy, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionSort {
+    #[default]
+    BookingDate,
+    Amount,
+    Counterparty,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct BankTransactionListQuery {
+    pub bank_account_id: Option<Uuid>,
+    pub import_id: Option<Uuid>,
+    /// Only bookings on or after this day
+    pub date_from: Option<NaiveDate>,
+    /// Only bookings on or before this day
+    pub date_to: Option<NaiveDate>,
+    #[param(inline)]
+    pub direction: Option<TransactionDirection>,
+    /// Only transactions with (`true`) or without (`false`) an accepted match
+    pub reconciled: Option<bool>,
+    /// Minimum absolute amount (inclusive)
+    pub min_amount: Option<f64>,
+    /// Maximum absolute amount (inclusive)
+    pub max_amount: Option<f64>,
+    pub currency: Option<String>,
+    /// Searches counterparty, purpose of payment and reference
+    pub q: Option<String>,
+    /// Sort field (default booking_date)
+    #[param(inline)]
+    pub sort: Option<TransactionSort>,
+    /// Sort direction (default desc)
+    #[param(inline)]
+    pub order: Option<SortOrder>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/bank-accounts",
+    responses(
+        (status = 200, description = "Bank accounts of the workspace", body = [BankAccountResponse]),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "banking"
+)]
+pub async fn list_bank_accounts(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<BankAccountResponse>>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::BankingRead).await?;
+    let accounts = bank_account::Entity::find()
+        .filter(bank_account::Column::WorkspaceId.eq(access.workspace_id))
+        .order_by_asc(bank_account::Column::Name)
+        .all(&state.db)
+        .await?;
+    Ok(Json(accounts.into_iter().map(BankAccountResponse::from).collect()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/bank-accounts",
+    request_body = BankAccountCreateRequest,
+    responses(
+        (status = 200, description = "Bank account created", body = BankAccountResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 409, description = "An account with this IBAN already exists", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "banking"
+)]
+pub async fn create_bank_account(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidatedJson(payload): ValidatedJson<BankAccountCreateRequest>,
+) -> Result<Json<BankAccountResponse>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::BankingWrite).await?;
+    let iban = payload.iban.as_deref().and_then(normalize_account_id);
+    if let Some(iban) = iban.as_deref() {
+        ensure_unique_iban(&state.db, access.workspace_id, iban, None).await?;
+    }
+
+    let created = bank_account::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        workspace_id: Set(access.workspace_id),
+        name: Set(payload.name.trim().to_string()),
+        iban: Set(iban),
+        bic: Set(payload.bic.as_deref().and_then(normalize_account_id)),
+        currency: Set(payload.currency.unwrap_or_else(|| "EUR".to_string())),
+        csv_mapping: Set(payload.csv_mapping.map(mapping_json).transpose()?),
+        created_at: Set(Utc::now()),
+    }
+    .insert(&state.db)
+    .await?;
+
+    Ok(Json(BankAccountResponse::from(created)))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/bank-accounts/{id}",
+    request_body = BankAccountUpdateRequest,
+    responses(
+        (status = 200, description = "Bank account updated", body = BankAccountResponse),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 404, description = "Bank account not found", body = ErrorResponse),
+        (status = 409, description = "An account with this IBAN already exists", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "banking"
+)]
+pub async fn update_bank_account(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    ValidatedJson(payload): ValidatedJson<BankAccountUpdateRequest>,
+) -> Result<Json<BankAccountResponse>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::BankingWrite).await?;
+    let id = Uuid::parse_str(&id).map_err(|_| AppError::bad_request("Invalid id"))?;
+    let existing = find_account(&state.db, access.workspace_id, id).await?;
+
+    let mut active: bank_account::ActiveModel = existing.into();
+    if let Some(name) = payload.name {
+        active.name = Set(name.trim().to_string());
+    }
+    if let Some(value) = payload.iban {
+        let value = normalize_account_id(&value);
+        if let Some(value) = value.as_deref() {
+            iban(value).map_err(|err| {
+                AppError::Validation(vec![FieldError::new(
+                    "iban",
+                    "iban",
+                    err.message.map(|m| m.to_string()).unwrap_or_default(),
+                )])
+            })?;
+            ensure_unique_iban(&state.db, access.workspace_id, value, Some(id)).await?;
+        }
+        active.iban = Set(value);
+    }
+    if let Some(bic) = payload.bic {
+        active.bic = Set(normalize_account_id(&bic));
+    }
+    if let Some(currency) = payload.currency {
+        active.currency = Set(currency);
+    }
+    if let Some(mapping) = payload.csv_mapping {
+        active.csv_mapping = Set(Some(mapping_json(mapping)?));
+    }
+    let updated = active.update(&state.db).await?;
+
+    Ok(Json(BankAccountResponse::from(updated)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/bank-accounts/{id}",
+    responses(
+        (status = 204, description = "Bank account deleted together with its imported transactions"),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Requires the owner or admin role", body = ErrorResponse),
+        (status = 404, description = "Bank account not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "banking"
+)]
+pub async fn delete_bank_account(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    let access = require_access(&state, &headers, ApiScope::BankingWrite).await?;
+    let id = Uuid::parse_str(&id).map_err(|_| AppError::bad_request("Invalid id"))?;
+    let existing = find_account(&state.db, access.workspace_id, id).await?;
+    // Removes the whole ledger of the account, so only managers may do it.
+    require_manager(&state.db, access.workspace_id, access.user.id).await?;
+
+    bank_account::Entity::delete_by_id(existing.id)
+        .exec(&state.db)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/bank-accounts/{id}/imports",
+    request_body(content = BankImportForm, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "Statement imported", body = BankImportResponse),
+        (status = 400, description = "Malformed form, missing file or invalid mapping", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 404, description = "Bank account not found", body = ErrorResponse),
+        (status = 413, description = "File exceeds BANK_STATEMENT_MAX_BYTES", body = ErrorResponse),
+        (status = 422, description = "The file could not be read or belongs to another account", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "banking"
+)]
+pub async fn import_bank_statement(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    mut multipart: Multipart,
+) -> Result<Json<BankImportResponse>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::BankingWrite).await?;
+    let id = Uuid::parse_str(&id).map_err(|_| AppError::bad_request("Invalid id"))?;
+    let account = find_account(&state.db, access.workspace_id, id).await?;
+    let max_bytes = max_statement_bytes();
+
+    let mut file: Option<(String, Vec<u8>)> = None;
+    let mut format = None;
+    let mut mapping: Option<CsvMapping> = None;
+    while let Some(mut field) = multipart.next_field().await.map_err(multipart_error)? {
+        match field.name() {
+            Some("file") => {
+                let filename = safe_filename(field.file_name().unwrap_or("statement"));
+                let mut body = Vec::new();
+                while let Some(chunk) = field.chunk().await.map_err(multipart_error)? {
+                    if body.len() + chunk.len() > max_bytes {
+                        return Err(too_large(max_bytes));
+                    }
+                    body.extend_from_slice(&chunk);
+                }
+                file = Some((filename, body));
+            }
+            Some("format") => {
+                let value = field.text().await.map_err(multipart_error)?;
+                format = Some(StatementFormat::parse(&value).ok_or_else(|| {
+                    AppError::bad_request("format must be camt053, mt940, ofx or csv")
+                })?);
+            }
+            Some("mapping") => {
+                let value = field.text().await.map_err(multipart_error)?;
+                let parsed: CsvMapping = serde_json::from_str(&value)
+                    .map_err(|error| AppError::bad_request(format!("Invalid mapping: {error}")))?;
+                parsed.validate()?;
+                mapping = Some(parsed);
+            }
+            _ => {}
+        }
+    }
+    let (filename, body) = file.ok_or_else(|| AppError::bad_request("Missing file field"))?;
+    if body.is_empty() {
+        return Err(AppError::bad_request("The file is empty"));
+    }
+
+    let text = decode_statement(&body);
+    let format = format.unwrap_or_else(|| StatementFormat::detect(&text));
+    let mapping = mapping.or_else(|| {
+        account
+            .csv_mapping
+            .clone()
+            .and_then(|mapping| serde_json::from_value(mapping).ok())
+    });
+    let statement =
+        parse_statement(format, &text, mapping.as_ref()).map_err(invalid_statement)?;
+    if let (Some(expected), Some(found)) = (account.iban.as_deref(), statement.account_iban.as_deref())
+        && normalize_account_id(found).as_deref() != Some(expected)
+    {
+        return Err(invalid_statement(format!(
+            "The statement belongs to account {found}, not {expected}"
+        )));
+    }
+
+    let fingerprints = fingerprints(&statement.entries);
+    let txn = state.db.begin().await?;
+    let known: HashSet<String> = bank_transaction::Entity::find()
+        .select_only()
+        .column(bank_transaction::Column::Fingerprint)
+        .filter(bank_transaction::Column::BankAccountId.eq(account.id))
+        .filter(bank_transaction::Column::Fingerprint.is_in(fingerprints.clone()))
+        .into_tuple::<String>()
+        .all(&txn)
+        .await?
+        .into_iter()
+        .collect();
+
+    let import_id = Uuid::new_v4();
+    let now = Utc::now();
+    let currency = statement
+        .currency
+        .clone()
+        .unwrap_or_else(|| account.currency.clone());
+    let new_transactions: Vec<bank_transaction::ActiveModel> = statement
+        .entries
+        .into_iter()
+        .zip(fingerprints)
+        .filter(|(_, fingerprint)| !known.contains(fingerprint))
+        .map(|(entry, fingerprint)| bank_transaction::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            workspace_id: Set(access.workspace_id),
+            bank_account_id: Set(account.id),
+            import_id: Set(import_id),
+            booking_date: Set(entry.booking_date),
+            value_date: Set(entry.value_date),
+            amount: Set(entry.amount),
+            currency: Set(entry.currency.unwrap_or_else(|| currency.clone())),
+            counterparty_name: Set(entry.counterparty_name),
+            counterparty_iban: Set(entry.counterparty_iban),
+            remittance_info: Set(entry.remittance_info),
+            reference: Set(entry.reference),
+            fingerprint: Set(fingerprint),
+            created_at: Set(now),
+        })
+        .collect();
+    let imported_count = new_transactions.len() as i32;
+    let new_ids: Vec<Uuid> = new_transactions
+        .iter()
+        .filter_map(|transaction| transaction.id.clone().take())
+        .collect();
+
+    let import = bank_import::ActiveModel {
+        id: Set(import_id),
+        workspace_id: Set(access.workspace_id),
+        bank_account_id: Set(account.id),
+        user_id: Set(access.user.id),
+        format: Set(format.as_str().to_string()),
+        filename: Set(filename),
+        imported_count: Set(imported_count),
+        duplicate_count: Set(known.len() as i32),
+        created_at: Set(now),
+    }
+    .insert(&txn)
+    .await?;
+    if !new_transactions.is_empty() {
+        bank_transaction::Entity::insert_many(new_transactions)
+            .exec(&txn)
+            .await?;
+    }
+    txn.commit().await?;
+
+    // Proposals are a convenience; the import stands even if matching fails.
+    if let Err(error) = propose_matches(&state.db, access.workspace_id, Some(&new_ids)).await {
+        eprintln!("Reconciliation after import {} failed: {error}", import.id);
+    }
+
+    Ok(Json(BankImportResponse::from(import)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/bank-accounts/{id}/imports",
+    responses(
+        (status = 200, description = "Imports into the account, newest first", body = [BankImportResponse]),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 404, description = "Bank account not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "banking"
+)]
+pub async fn list_bank_imports(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<BankImportResponse>>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::BankingRead).await?;
+    let id = Uuid::parse_str(&id).map_err(|_| AppError::bad_request("Invalid id"))?;
+    let account = find_account(&state.db, access.workspace_id, id).await?;
+    let imports = bank_import::Entity::find()
+        .filter(bank_import::Column::BankAccountId.eq(account.id))
+        .order_by_desc(bank_import::Column::CreatedAt)
+        .all(&state.db)
+        .await?;
+    Ok(Json(imports.into_iter().map(BankImportResponse::from).collect()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/bank-transactions",
+    params(PageParams, BankTransactionListQuery),
+    responses(
+        (status = 200, description = "Bank transaction page", body = BankTransactionPage),
+        (status = 400, description = "Invalid query", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "banking"
+)]
+pub async fn list_bank_transactions(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(paging): Query<PageParams>,
+    Query(query): Query<BankTransactionListQuery>,
+) -> Result<Json<Page<BankTransactionResponse>>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::BankingRead).await?;
+    let mut select = bank_transaction::Entity::find()
+        .filter(bank_transaction::Column::WorkspaceId.eq(access.workspace_id));
+
+    if let Some(account_id) = query.bank_account_id {
+        select = select.filter(bank_transaction::Column::BankAccountId.eq(account_id));
+    }
+    if let Some(import_id) = query.import_id {
+        select = select.filter(bank_transaction::Column::ImportId.eq(import_id));
+    }
+    if let Some(date_from) = query.date_from {
+        select = select.filter(bank_transaction::Column::BookingDate.gte(date_from));
+    }
+    if let Some(date_to) = query.date_to {
+        select = select.filter(bank_transaction::Column::BookingDate.lte(date_to));
+    }
+    match query.direction {
+        Some(TransactionDirection::Incoming) => {
+            select = select.filter(bank_transaction::Column::Amount.gt(0.0));
+        }
+        Some(TransactionDirection::Outgoing) => {
+            select = select.filter(bank_transaction::Column::Amount.lt(0.0));
+        }
+        None => {}
+    }
+    match query.reconciled {
+        Some(true) => {
+            select = select.filter(
+                bank_transaction::Column::Id
+                    .in_subquery(accepted_subquery(bank_match::Column::BankTransactionId)),
+            );
+        }
+        Some(false) => {
+            select = select.filter(
+                bank_transaction::Column::Id
+                    .not_in_subquery(accepted_subquery(bank_match::Column::BankTransactionId)),
+            );
+        }
+        None => {}
+    }
+    let absolute = || Expr::expr(Func::abs(Expr::col(bank_transaction::Column::Amount)));
+    if let Some(min_amount) = query.min_amount {
+        select = select.filter(absolute().gte(min_amount));
+    }
+    if let Some(max_amount) = query.max_amount {
+        select = select.filter(absolute().lte(max_amount));
+    }
+    if let Some(currency) = query.currency.as_deref() {
+        select =
+            select.filter(bank_transaction::Column::Currency.eq(currency.trim().to_uppercase()));
+    }
+    if let Some(pattern) = query.q.as_deref().and_then(search_pattern) {
+        select = select.filter(
+            Condition::any()
+                .add(Expr::col(bank_transaction::Column::CounterpartyName).ilike(&pattern))
+                .add(Expr::col(bank_transaction::Column::RemittanceInfo).ilike(&pattern))
+                .add(Expr::col(bank_transaction::Column::Reference).ilike(&pattern)),
+        );
+    }
+
+    let order = query.order.unwrap_or(SortOrder::Desc);
+    let column = match query.sort.unwrap_or_default() {
+        TransactionSort::BookingDate => bank_transaction::Column::BookingDate,
+        TransactionSort::Amount => bank_transaction::Column::Amount,
+        TransactionSort::Counterparty => bank_transaction::Column::CounterpartyName,
+    };
+    select = select
+        .order_by(column, order.into())
+        .order_by(bank_transaction::Column::Id, order.into());
+
+    let page = fetch_page(&state.db, select, &paging).await?;
+    Ok(Json(page.map(BankTransactionResponse::from)))
+}
+
+/// Maximum statement file size, `BANK_STATEMENT_MAX_BYTES` (default 10 MiB).
+pub fn max_statement_bytes() -> usize {
+    std::env::var("BANK_STATEMENT_MAX_BYTES")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_MAX_STATEMENT_BYTES)
+}
+
+async fn find_account<C: ConnectionTrait>(
+    db: &C,
+    workspace_id: Uuid,
+    id: Uuid,
+) -> Result<bank_account::Model, AppError> {
+    bank_account::Entity::find_by_id(id)
+        .filter(bank_account::Column::WorkspaceId.eq(workspace_id))
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Bank account not found"))
+}
+
+async fn ensure_unique_iban<C: ConnectionTrait>(
+    db: &C,
+    workspace_id: Uuid,
+    iban: &str,
+    except: Option<Uuid>,
+) -> Result<(), AppError> {
+    let mut select = bank_account::Entity::find()
+        .filter(bank_account::Column::WorkspaceId.eq(workspace_id))
+        .filter(bank_account::Column::Iban.eq(iban));
+    if let Some(except) = except {
+        select = select.filter(bank_account::Column::Id.ne(except));
+    }
+    if select.count(db).await? > 0 {
+        return Err(AppError::conflict("An account with this IBAN already exists"));
+    }
+    Ok(())
+}
+
+/// IBANs and BICs are stored without spaces in upper case; blank means none.
+fn normalize_account_id(value: &str) -> Option<String> {
+    let value: String = value.chars().filter(|c| !c.is_whitespace()).collect();
+    (!value.is_empty()).then(|| value.to_uppercase())
+}
+
+fn mapping_json(mapping: CsvMapping) -> Result<serde_json::Value, AppError> {
+    serde_json::to_value(mapping).map_err(AppError::internal)
+}
+
+fn invalid_statement(message: impl Into<String>) -> AppError {
+    AppError::Validation(vec![FieldError::new("file", "invalid_statement", message)])
+}
+
+fn too_large(max_bytes: usize) -> AppError {
+    AppError::payload_too_large(format!("Statements may be at most {} bytes", max_bytes))
+}
+
+fn multipart_error(error: MultipartError) -> AppError {
+    if error.status() == StatusCode::PAYLOAD_TOO_LARGE {
+        too_large(max_statement_bytes())
+    } else {
+        AppError::bad_request(error.body_text())
+    }
+}
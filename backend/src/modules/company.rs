@@ -1,31 +1,67 @@
-use crate::entity::{company, user};
-use crate::modules::auth::require_user;
-use crate::modules::shared::AppState;
+use crate::entity::{company, company_contact, user};
+use crate::modules::api_tokens::ApiScope;
+use crate::modules::auth::require_access;
+use crate::modules::shared::{
+    fetch_page, search_pattern, AppError, AppState, Page, PageParams, SortOrder,
+};
+use crate::modules::validation::{blank_or_email, not_blank, Path, Query, ValidatedJson};
 use axum::{
     extract::State,
     http::HeaderMap,
     Json,
 };
 use chrono::{DateTime, Utc};
-use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+use sea_orm::sea_query::{extension::postgres::PgExpr, Expr};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, EntityTrait, QueryFilter,
+    QueryOrder, Set,
+};
 use serde::{Deserialize, Serialize};
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
 use uuid::Uuid;
 
-#[derive(Deserialize, ToSchema)]
+#[derive(Deserialize, ToSchema, Validate)]
 pub struct CompanyCreateRequest {
+    #[validate(custom(function = not_blank), length(max = 200))]
     pub name: String,
+    #[validate(custom(function = not_blank), length(max = 1000))]
     pub address: String,
+    #[validate(custom(function = not_blank), length(max = 100))]
     pub registration_number: String,
 }
 
-#[derive(Deserialize, ToSchema)]
+#[derive(Deserialize, ToSchema, Validate)]
 pub struct CompanyUpdateRequest {
+    #[validate(custom(function = not_blank), length(max = 200))]
     pub name: Option<String>,
+    #[validate(custom(function = not_blank), length(max = 1000))]
     pub address: Option<String>,
+    #[validate(custom(function = not_blank), length(max = 100))]
     pub registration_number: Option<String>,
 }
 
+#[derive(Clone, Copy, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CompanySort {
+    #[default]
+    Name,
+    CreatedAt,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CompanyListQuery {
+    /// Searches company name and registration number
+    pub q: Option<String>,
+    /// Sort field (default name)
+    #[param(inline)]
+    pub sort: Option<CompanySort>,
+    /// Sort direction (default asc)
+    #[param(inline)]
+    pub order: Option<SortOrder>,
+}
+
 #[derive(Serialize, ToSchema)]
 pub struct CompanyResponse {
     pub id: Uuid,
@@ -36,35 +72,84 @@ pub struct CompanyResponse {
     pub created_at: DateTime<Utc>,
 }
 
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct ContactCreateRequest {
+    #[validate(custom(function = not_blank), length(max = 200))]
+    pub name: String,
+    #[validate(length(max = 100))]
+    pub role: Option<String>,
+    #[validate(custom(function = blank_or_email))]
+    pub email: Option<String>,
+    #[validate(length(max = 50))]
+    pub phone: Option<String>,
+    pub is_billing: Option<bool>,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct ContactUpdateRequest {
+    #[validate(custom(function = not_blank), length(max = 200))]
+    pub name: Option<String>,
+    #[validate(length(max = 100))]
+    pub role: Option<String>,
+    #[validate(custom(function = blank_or_email))]
+    pub email: Option<String>,
+    #[validate(length(max = 50))]
+    pub phone: Option<String>,
+    pub is_billing: Option<bool>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ContactResponse {
+    pub id: Uuid,
+    pub company_id: Uuid,
+    pub name: String,
+    pub role: Option<String>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub is_billing: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<company_contact::Model> for ContactResponse {
+    fn from(contact: company_contact::Model) -> Self {
+        Self {
+            id: contact.id,
+            company_id: contact.company_id,
+            name: contact.name,
+            role: contact.role,
+            email: contact.email,
+            phone: contact.phone,
+            is_billing: contact.is_billing,
+            created_at: contact.created_at,
+        }
+    }
+}
+
 #[utoipa::path(
     post,
     path = "/company",
     request_body = CompanyCreateRequest,
     responses(
         (status = 200, description = "Company created", body = CompanyResponse),
-        (status = 400, description = "Invalid input"),
-        (status = 401, description = "Not authenticated"),
-        (status = 500, description = "Server error")
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
     ),
     tag = "company"
 )]
 pub async fn create_company(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Json(payload): Json<CompanyCreateRequest>,
-) -> Result<Json<CompanyResponse>, (axum::http::StatusCode, String)> {
-    if payload.name.trim().is_empty() || payload.address.trim().is_empty() {
-        return Err((axum::http::StatusCode::BAD_REQUEST, "Name and address are required".to_string()));
-    }
-    if payload.registration_number.trim().is_empty() {
-        return Err((axum::http::StatusCode::BAD_REQUEST, "Registration number is required".to_string()));
-    }
-
-    let current_user = require_user(&state, &headers).await?;
+    ValidatedJson(payload): ValidatedJson<CompanyCreateRequest>,
+) -> Result<Json<CompanyResponse>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::CompaniesWrite).await?;
 
     let active = company::ActiveModel {
         id: Set(Uuid::new_v4()),
-        user_id: Set(Some(current_user.id)),
+        user_id: Set(Some(access.user.id)),
+        workspace_id: Set(Some(access.workspace_id)),
         name: Set(payload.name),
         address: Set(payload.address),
         registration_number: Set(payload.registration_number),
@@ -74,14 +159,14 @@ pub async fn create_company(
     let created = active
         .insert(&state.db)
         .await
-        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(AppError::internal)?;
 
-    let mut user_active: user::ActiveModel = current_user.into();
+    let mut user_active: user::ActiveModel = access.user.into();
     user_active.company_id = Set(Some(created.id));
     user_active
         .update(&state.db)
         .await
-        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(AppError::internal)?;
 
     Ok(Json(CompanyResponse {
         id: created.id,
@@ -99,54 +184,48 @@ pub async fn create_company(
     request_body = CompanyUpdateRequest,
     responses(
         (status = 200, description = "Company updated", body = CompanyResponse),
-        (status = 400, description = "Invalid input"),
-        (status = 401, description = "Not authenticated"),
-        (status = 404, description = "Company not found"),
-        (status = 500, description = "Server error")
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 404, description = "Company not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
     ),
     tag = "company"
 )]
 pub async fn update_company(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Json(payload): Json<CompanyUpdateRequest>,
-) -> Result<Json<CompanyResponse>, (axum::http::StatusCode, String)> {
-    let current_user = require_user(&state, &headers).await?;
-    let company_id = current_user
+    ValidatedJson(payload): ValidatedJson<CompanyUpdateRequest>,
+) -> Result<Json<CompanyResponse>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::CompaniesWrite).await?;
+    let company_id = access
+        .user
         .company_id
-        .ok_or_else(|| (axum::http::StatusCode::NOT_FOUND, "Company not found".to_string()))?;
+        .ok_or_else(|| AppError::not_found("Company not found"))?;
 
     let existing = company::Entity::find_by_id(company_id)
-        .filter(company::Column::UserId.eq(current_user.id))
+        .filter(company::Column::WorkspaceId.eq(access.workspace_id))
         .one(&state.db)
         .await
-        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-        .ok_or_else(|| (axum::http::StatusCode::NOT_FOUND, "Company not found".to_string()))?;
+        .map_err(AppError::internal)?
+        .ok_or_else(|| AppError::not_found("Company not found"))?;
 
     let mut active: company::ActiveModel = existing.into();
     if let Some(name) = payload.name {
-        if name.trim().is_empty() {
-            return Err((axum::http::StatusCode::BAD_REQUEST, "Name is required".to_string()));
-        }
         active.name = Set(name);
     }
     if let Some(address) = payload.address {
-        if address.trim().is_empty() {
-            return Err((axum::http::StatusCode::BAD_REQUEST, "Address is required".to_string()));
-        }
         active.address = Set(address);
     }
     if let Some(registration_number) = payload.registration_number {
-        if registration_number.trim().is_empty() {
-            return Err((axum::http::StatusCode::BAD_REQUEST, "Registration number is required".to_string()));
-        }
         active.registration_number = Set(registration_number);
     }
 
     let updated = active
         .update(&state.db)
         .await
-        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(AppError::internal)?;
 
     Ok(Json(CompanyResponse {
         id: updated.id,
@@ -163,27 +242,29 @@ pub async fn update_company(
     path = "/company/me",
     responses(
         (status = 200, description = "Company", body = CompanyResponse),
-        (status = 401, description = "Not authenticated"),
-        (status = 404, description = "Company not found"),
-        (status = 500, description = "Server error")
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 404, description = "Company not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
     ),
     tag = "company"
 )]
 pub async fn get_my_company(
     State(state): State<AppState>,
     headers: HeaderMap,
-) -> Result<Json<CompanyResponse>, (axum::http::StatusCode, String)> {
-    let current_user = require_user(&state, &headers).await?;
-    let company_id = current_user
+) -> Result<Json<CompanyResponse>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::CompaniesRead).await?;
+    let company_id = access
+        .user
         .company_id
-        .ok_or_else(|| (axum::http::StatusCode::NOT_FOUND, "Company not found".to_string()))?;
+        .ok_or_else(|| AppError::not_found("Company not found"))?;
 
     let company = company::Entity::find_by_id(company_id)
-        .filter(company::Column::UserId.eq(current_user.id))
+        .filter(company::Column::WorkspaceId.eq(access.workspace_id))
         .one(&state.db)
         .await
-        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-        .ok_or_else(|| (axum::http::StatusCode::NOT_FOUND, "Company not found".to_string()))?;
+        .map_err(AppError::internal)?
+        .ok_or_else(|| AppError::not_found("Company not found"))?;
 
     Ok(Json(CompanyResponse {
         id: company.id,
@@ -198,35 +279,292 @@ pub async fn get_my_company(
 #[utoipa::path(
     get,
     path = "/company",
+    params(PageParams, CompanyListQuery),
     responses(
-        (status = 200, description = "Company list", body = [CompanyResponse]),
-        (status = 401, description = "Not authenticated"),
-        (status = 500, description = "Server error")
+        (status = 200, description = "Company page", body = CompanyPage),
+        (status = 400, description = "Invalid query", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
     ),
     tag = "company"
 )]
 pub async fn list_companies(
     State(state): State<AppState>,
     headers: HeaderMap,
-) -> Result<Json<Vec<CompanyResponse>>, (axum::http::StatusCode, String)> {
-    let current_user = require_user(&state, &headers).await?;
-    let companies = company::Entity::find()
-        .filter(company::Column::UserId.eq(current_user.id))
+    Query(paging): Query<PageParams>,
+    Query(query): Query<CompanyListQuery>,
+) -> Result<Json<Page<CompanyResponse>>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::CompaniesRead).await?;
+    let mut select =
+        company::Entity::find().filter(company::Column::WorkspaceId.eq(access.workspace_id));
+    if let Some(pattern) = query.q.as_deref().and_then(search_pattern) {
+        select = select.filter(
+            Condition::any()
+                .add(Expr::col(company::Column::Name).ilike(&pattern))
+                .add(Expr::col(company::Column::RegistrationNumber).ilike(&pattern)),
+        );
+    }
+
+    let order = query.order.unwrap_or_default();
+    let column = match query.sort.unwrap_or_default() {
+        CompanySort::Name => company::Column::Name,
+        CompanySort::CreatedAt => company::Column::CreatedAt,
+    };
+    select = select
+        .order_by(column, order.into())
+        .order_by(company::Column::Id, order.into());
+
+    let page = fetch_page(&state.db, select, &paging).await?;
+    Ok(Json(page.map(|item| CompanyResponse {
+        id: item.id,
+        user_id: item.user_id,
+        name: item.name,
+        address: item.address,
+        registration_number: item.registration_number,
+        created_at: item.created_at,
+    })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/company/{id}/contacts",
+    params(
+        ("id" = String, Path, description = "Company id (UUID)")
+    ),
+    responses(
+        (status = 200, description = "Contact list", body = [ContactResponse]),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 404, description = "Company not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "company"
+)]
+pub async fn list_contacts(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<ContactResponse>>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::CompaniesRead).await?;
+    let company_id = Uuid::parse_str(&id)
+        .map_err(|_| AppError::bad_request("Invalid id"))?;
+    let company = find_owned_company(&state.db, access.workspace_id, company_id).await?;
+
+    let contacts = company_contact::Entity::find()
+        .filter(company_contact::Column::CompanyId.eq(company.id))
+        .order_by_desc(company_contact::Column::IsBilling)
+        .order_by_asc(company_contact::Column::Name)
         .all(&state.db)
         .await
-        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-
-    let response = companies
-        .into_iter()
-        .map(|item| CompanyResponse {
-            id: item.id,
-            user_id: item.user_id,
-            name: item.name,
-            address: item.address,
-            registration_number: item.registration_number,
-            created_at: item.created_at,
-        })
-        .collect();
-
-    Ok(Json(response))
+        .map_err(AppError::internal)?;
+
+    Ok(Json(contacts.into_iter().map(ContactResponse::from).collect()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/company/{id}/contacts",
+    params(
+        ("id" = String, Path, description = "Company id (UUID)")
+    ),
+    request_body = ContactCreateRequest,
+    responses(
+        (status = 200, description = "Contact created", body = ContactResponse),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 404, description = "Company not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "company"
+)]
+pub async fn create_contact(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    ValidatedJson(payload): ValidatedJson<ContactCreateRequest>,
+) -> Result<Json<ContactResponse>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::CompaniesWrite).await?;
+    let company_id = Uuid::parse_str(&id)
+        .map_err(|_| AppError::bad_request("Invalid id"))?;
+    let company = find_owned_company(&state.db, access.workspace_id, company_id).await?;
+
+    let active = company_contact::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        company_id: Set(company.id),
+        name: Set(payload.name),
+        role: Set(normalize_optional(payload.role)),
+        email: Set(normalize_optional(payload.email).map(|email| email.to_lowercase())),
+        phone: Set(normalize_optional(payload.phone)),
+        is_billing: Set(payload.is_billing.unwrap_or(false)),
+        created_at: Set(Utc::now()),
+    };
+
+    let created = active
+        .insert(&state.db)
+        .await
+        .map_err(AppError::internal)?;
+
+    Ok(Json(created.into()))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/company/{id}/contacts/{contact_id}",
+    params(
+        ("id" = String, Path, description = "Company id (UUID)"),
+        ("contact_id" = String, Path, description = "Contact id (UUID)")
+    ),
+    request_body = ContactUpdateRequest,
+    responses(
+        (status = 200, description = "Contact updated", body = ContactResponse),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 404, description = "Contact not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "company"
+)]
+pub async fn update_contact(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((id, contact_id)): Path<(String, String)>,
+    ValidatedJson(payload): ValidatedJson<ContactUpdateRequest>,
+) -> Result<Json<ContactResponse>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::CompaniesWrite).await?;
+    let company_id = Uuid::parse_str(&id)
+        .map_err(|_| AppError::bad_request("Invalid id"))?;
+    let contact_id = Uuid::parse_str(&contact_id)
+        .map_err(|_| AppError::bad_request("Invalid id"))?;
+    let company = find_owned_company(&state.db, access.workspace_id, company_id).await?;
+    let existing = find_contact(&state.db, company.id, contact_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Contact not found"))?;
+
+    let mut active: company_contact::ActiveModel = existing.into();
+    if let Some(name) = payload.name {
+        active.name = Set(name);
+    }
+    if let Some(role) = payload.role {
+        active.role = Set(normalize_optional(Some(role)));
+    }
+    if let Some(email) = payload.email {
+        active.email = Set(normalize_optional(Some(email)).map(|email| email.to_lowercase()));
+    }
+    if let Some(phone) = payload.phone {
+        active.phone = Set(normalize_optional(Some(phone)));
+    }
+    if let Some(is_billing) = payload.is_billing {
+        active.is_billing = Set(is_billing);
+    }
+
+    let updated = active
+        .update(&state.db)
+        .await
+        .map_err(AppError::internal)?;
+
+    Ok(Json(updated.into()))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/company/{id}/contacts/{contact_id}",
+    params(
+        ("id" = String, Path, description = "Company id (UUID)"),
+        ("contact_id" = String, Path, description = "Contact id (UUID)")
+    ),
+    responses(
+        (status = 204, description = "Contact deleted"),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 404, description = "Contact not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "company"
+)]
+pub async fn delete_contact(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((id, contact_id)): Path<(String, String)>,
+) -> Result<axum::http::StatusCode, AppError> {
+    let access = require_access(&state, &headers, ApiScope::CompaniesWrite).await?;
+    let company_id = Uuid::parse_str(&id)
+        .map_err(|_| AppError::bad_request("Invalid id"))?;
+    let contact_id = Uuid::parse_str(&contact_id)
+        .map_err(|_| AppError::bad_request("Invalid id"))?;
+    let company = find_owned_company(&state.db, access.workspace_id, company_id).await?;
+    let existing = find_contact(&state.db, company.id, contact_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Contact not found"))?;
+
+    company_contact::Entity::delete_by_id(existing.id)
+        .exec(&state.db)
+        .await
+        .map_err(AppError::internal)?;
+
+    Ok(axum::http::StatusCode::NO_CONTENT)
+}
+
+async fn find_owned_company(
+    db: &DatabaseConnection,
+    workspace_id: Uuid,
+    company_id: Uuid,
+) -> Result<company::Model, AppError> {
+    company::Entity::find_by_id(company_id)
+        .filter(company::Column::WorkspaceId.eq(workspace_id))
+        .one(db)
+        .await
+        .map_err(AppError::internal)?
+        .ok_or_else(|| AppError::not_found("Company not found"))
+}
+
+pub(crate) async fn find_contact(
+    db: &DatabaseConnection,
+    company_id: Uuid,
+    contact_id: Uuid,
+) -> Result<Option<company_contact::Model>, AppError> {
+    company_contact::Entity::find_by_id(contact_id)
+        .filter(company_contact::Column::CompanyId.eq(company_id))
+        .one(db)
+        .await
+        .map_err(AppError::internal)
+}
+
+/// Recipients for an invoice: the chosen addressee if it has an email,
+/// otherwise every billing contact of the client company that has one.
+pub(crate) async fn invoice_recipients(
+    db: &DatabaseConnection,
+    company_id: Option<Uuid>,
+    contact_id: Option<Uuid>,
+) -> Result<Vec<company_contact::Model>, AppError> {
+    let Some(company_id) = company_id else {
+        return Ok(Vec::new());
+    };
+    if let Some(contact_id) = contact_id
+        && let Some(contact) = find_contact(db, company_id, contact_id).await?
+        && contact.email.is_some()
+    {
+        return Ok(vec![contact]);
+    }
+
+    company_contact::Entity::find()
+        .filter(company_contact::Column::CompanyId.eq(company_id))
+        .filter(company_contact::Column::IsBilling.eq(true))
+        .filter(company_contact::Column::Email.is_not_null())
+        .order_by_asc(company_contact::Column::Name)
+        .all(db)
+        .await
+        .map_err(AppError::internal)
+}
+
+fn normalize_optional(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
 }
@@ -0,0 +1,47 @@
+use crate::modules::auth::has_session_cookie;
+use crate::modules::shared::{cookie_same_site, frontend_origin, AppError};
+use axum::{
+    extract::Request,
+    http::{
+        header::{AUTHORIZATION, ORIGIN, REFERER},
+        HeaderMap, Method,
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+/// Rejects cross-site writes made with the session cookie. Only needed when the cookie is
+/// sent with `SameSite=None` (frontend and API on different sites); with `Lax`/`Strict`
+/// the browser already withholds it. State-changing requests must then carry an `Origin`
+/// (or `Referer`) matching the frontend origin. Bearer-token requests are not affected.
+pub async fn csrf_protection(request: Request, next: Next) -> Response {
+    let unsafe_method = matches!(
+        *request.method(),
+        Method::POST | Method::PUT | Method::PATCH | Method::DELETE
+    );
+    let headers = request.headers();
+    if unsafe_method
+        && cookie_same_site().eq_ignore_ascii_case("none")
+        && has_session_cookie(headers)
+        && !headers.contains_key(AUTHORIZATION)
+        && request_origin(headers).as_deref() != Some(frontend_origin().trim_end_matches('/'))
+    {
+        return AppError::forbidden("Cross-site request rejected").into_response();
+    }
+
+    next.run(request).await
+}
+
+/// `scheme://host[:port]` the request was made from, from `Origin` or else `Referer`.
+fn request_origin(headers: &HeaderMap) -> Option<String> {
+    if let Some(origin) = headers.get(ORIGIN).and_then(|value| value.to_str().ok())
+        && origin != "null"
+    {
+        return Some(origin.to_string());
+    }
+
+    let referer = headers.get(REFERER)?.to_str().ok()?;
+    let (scheme, rest) = referer.split_once("://")?;
+    let host = rest.split(['/', '?', '#']).next()?;
+    (!host.is_empty()).then(|| format!("{scheme}://{host}"))
+}
@@ -0,0 +1,725 @@
+use crate::entity::{dunning_level, invoice, invoice_reminder, workspace};
+use crate::modules::api_tokens::ApiScope;
+use crate::modules::auth::require_access;
+use crate::modules::company::invoice_recipients;
+use crate::modules::invoices::{format_money, html_to_pdf};
+use crate::modules::mail::{render_email, MailAttachment, OutgoingMail, REMINDER_EMAIL};
+use crate::modules::shared::{AppError, AppState};
+use crate::modules::validation::{non_negative, not_blank, Path, ValidatedJson};
+use axum::{
+    extract::State,
+    http::{HeaderMap, HeaderValue},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use handlebars::Handlebars;
+use sea_orm::sea_query::{Expr, OnConflict, Query};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set,
+    TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::time::Duration;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+/// Invoice statuses that are still awaiting payment and may be dunned.
+const DUNNABLE_STATUSES: &[&str] = &["sent"];
+
+/// A reminder email that failed is retried on later runs until this many attempts.
+const MAX_EMAIL_ATTEMPTS: i32 = 5;
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct DunningLevelInput {
+    #[validate(custom(function = not_blank), length(max = 200))]
+    pub name: String,
+    #[validate(range(min = 0, max = 3650))]
+    pub days_after_due: i32,
+    #[validate(custom(function = non_negative))]
+    pub fee: Option<f64>,
+    /// Annual interest rate in percent, charged from the due date.
+    #[validate(custom(function = non_negative))]
+    pub interest_rate: Option<f64>,
+    pub send_email: Option<bool>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DunningLevelResponse {
+    pub id: Uuid,
+    pub level: i32,
+    pub name: String,
+    pub days_after_due: i32,
+    pub fee: f64,
+    pub interest_rate: Option<f64>,
+    pub send_email: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ReminderResponse {
+    pub id: Uuid,
+    pub invoice_id: Uuid,
+    pub level: i32,
+    pub name: String,
+    pub days_overdue: i32,
+    pub fee: f64,
+    pub interest: f64,
+    pub total_due: f64,
+    /// `sent`, `failed` (retried on later runs while the invoice is overdue), `skipped`,
+    /// `logged` when the mail transport only prints messages, or `pending` while the
+    /// email is being delivered
+    pub email_status: String,
+    pub email_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DunningRunResponse {
+    pub reminders: Vec<ReminderResponse>,
+}
+
+impl From<dunning_level::Model> for DunningLevelResponse {
+    fn from(level: dunning_level::Model) -> Self {
+        Self {
+            id: level.id,
+            level: level.level,
+            name: level.name,
+            days_after_due: level.days_after_due,
+            fee: level.fee,
+            interest_rate: level.interest_rate,
+            send_email: level.send_email,
+        }
+    }
+}
+
+impl From<invoice_reminder::Model> for ReminderResponse {
+    fn from(reminder: invoice_reminder::Model) -> Self {
+        Self {
+            id: reminder.id,
+            invoice_id: reminder.invoice_id,
+            level: reminder.level,
+            name: reminder.name,
+            days_overdue: reminder.days_overdue,
+            fee: reminder.fee,
+            interest: reminder.interest,
+            total_due: reminder.total_due,
+            email_status: reminder.email_status,
+            email_error: reminder.email_error,
+            created_at: reminder.created_at,
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/dunning-levels",
+    responses(
+        (status = 200, description = "Dunning levels in escalation order", body = [DunningLevelResponse]),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "dunning"
+)]
+pub async fn list_dunning_levels(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<DunningLevelResponse>>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::InvoicesRead).await?;
+    let levels = load_levels(&state.db, access.workspace_id, access.user.id).await?;
+    Ok(Json(levels.into_iter().map(DunningLevelResponse::from).collect()))
+}
+
+#[utoipa::path(
+    put,
+    path = "/dunning-levels",
+    request_body = [DunningLevelInput],
+    responses(
+        (status = 200, description = "Dunning levels replaced", body = [DunningLevelResponse]),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "dunning"
+)]
+pub async fn replace_dunning_levels(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidatedJson(payload): ValidatedJson<Vec<DunningLevelInput>>,
+) -> Result<Json<Vec<DunningLevelResponse>>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::InvoicesWrite).await?;
+    if payload.is_empty() {
+        return Err(AppError::bad_request("At least one dunning level is required"));
+    }
+    let mut previous_days = -1;
+    for level in &payload {
+        if level.days_after_due <= previous_days {
+            return Err(AppError::bad_request(
+                "Levels must be ordered by increasing days after due",
+            ));
+        }
+        previous_days = level.days_after_due;
+    }
+
+    let txn = state
+        .db
+        .begin()
+        .await
+        .map_err(AppError::internal)?;
+
+    dunning_level::Entity::delete_many()
+        .filter(dunning_level::Column::WorkspaceId.eq(access.workspace_id))
+        .exec(&txn)
+        .await
+        .map_err(AppError::internal)?;
+
+    let mut response = Vec::with_capacity(payload.len());
+    for (index, level) in payload.into_iter().enumerate() {
+        let saved = dunning_level::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            user_id: Set(access.user.id),
+            workspace_id: Set(access.workspace_id),
+            level: Set(index as i32 + 1),
+            name: Set(level.name),
+            days_after_due: Set(level.days_after_due),
+            fee: Set(level.fee.unwrap_or(0.0)),
+            interest_rate: Set(level.interest_rate),
+            send_email: Set(level.send_email.unwrap_or(true)),
+            created_at: Set(Utc::now()),
+        }
+        .insert(&txn)
+        .await
+        .map_err(AppError::internal)?;
+        response.push(DunningLevelResponse::from(saved));
+    }
+
+    txn.commit()
+        .await
+        .map_err(AppError::internal)?;
+
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    post,
+    path = "/dunning/run",
+    responses(
+        (status = 200, description = "Reminders issued for the workspace's overdue invoices", body = DunningRunResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "dunning"
+)]
+pub async fn run_dunning_now(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<DunningRunResponse>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::InvoicesWrite).await?;
+    let reminders = run_dunning(&state, Some(access.workspace_id), Utc::now().date_naive()).await?;
+    Ok(Json(DunningRunResponse {
+        reminders: reminders.into_iter().map(ReminderResponse::from).collect(),
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/invoices/{id}/reminders/{reminder_id}/pdf",
+    params(
+        ("id" = String, Path, description = "Invoice id (UUID)"),
+        ("reminder_id" = String, Path, description = "Reminder id (UUID)")
+    ),
+    responses(
+        (status = 200, description = "Reminder PDF"),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 404, description = "Reminder not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "dunning"
+)]
+pub async fn get_reminder_pdf(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((id, reminder_id)): Path<(String, String)>,
+) -> Result<Response, AppError> {
+    let access = require_access(&state, &headers, ApiScope::InvoicesRead).await?;
+    let id = Uuid::parse_str(&id)
+        .map_err(|_| AppError::bad_request("Invalid id"))?;
+    let reminder_id = Uuid::parse_str(&reminder_id)
+        .map_err(|_| AppError::bad_request("Invalid id"))?;
+
+    let invoice = invoice::Entity::find()
+        .filter(invoice::Column::Id.eq(id))
+        .filter(invoice::Column::WorkspaceId.eq(access.workspace_id))
+        .one(&state.db)
+        .await
+        .map_err(AppError::internal)?
+        .ok_or_else(|| AppError::not_found("Reminder not found"))?;
+    let reminder = invoice_reminder::Entity::find_by_id(reminder_id)
+        .filter(invoice_reminder::Column::InvoiceId.eq(invoice.id))
+        .one(&state.db)
+        .await
+        .map_err(AppError::internal)?
+        .ok_or_else(|| AppError::not_found("Reminder not found"))?;
+
+    let pdf_bytes = build_reminder_pdf(&invoice, &reminder)
+        .map_err(AppError::internal)?;
+
+    let mut response_headers = HeaderMap::new();
+    response_headers.insert(
+        axum::http::header::CONTENT_TYPE,
+        HeaderValue::from_static("application/pdf"),
+    );
+    response_headers.insert(
+        axum::http::header::CONTENT_DISPOSITION,
+        HeaderValue::from_str(&format!(
+            "attachment; filename=\"reminder-{}-{}.pdf\"",
+            invoice.invoice_number, reminder.level
+        ))
+        .map_err(|_| AppError::internal("Invalid filename"))?,
+    );
+
+    Ok((response_headers, pdf_bytes).into_response())
+}
+
+/// Runs the dunning pass every `DUNNING_INTERVAL_MINUTES` (default 60)
+/// unless `DUNNING_ENABLED=false`.
+pub fn spawn_dunning_job(state: AppState) {
+    if std::env::var("DUNNING_ENABLED").map(|v| v == "false").unwrap_or(false) {
+        return;
+    }
+    let minutes = std::env::var("DUNNING_INTERVAL_MINUTES")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(60)
+        .max(1);
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(minutes * 60));
+        loop {
+            interval.tick().await;
+            match run_dunning(&state, None, Utc::now().date_naive()).await {
+                Ok(reminders) if !reminders.is_empty() => {
+                    println!("Dunning: issued {} reminder(s)", reminders.len());
+                }
+                Ok(_) => {}
+                Err(error) => eprintln!("Dunning run failed: {error}"),
+            }
+        }
+    });
+}
+
+/// Issues the next due reminder for every overdue invoice, optionally limited
+/// to one workspace. An invoice advances at most one level per run, and reminder
+/// emails that failed earlier are retried.
+pub async fn run_dunning(
+    state: &AppState,
+    workspace_id: Option<Uuid>,
+    today: NaiveDate,
+) -> Result<Vec<invoice_reminder::Model>, AppError> {
+    let mut query = invoice::Entity::find()
+        .filter(invoice::Column::Status.is_in(DUNNABLE_STATUSES.iter().copied()))
+        .filter(invoice::Column::DueDate.lt(today))
+        .filter(invoice::Column::UserId.is_not_null())
+        .filter(invoice::Column::WorkspaceId.is_not_null())
+        // Archived workspaces belong to deleted accounts and only keep their records.
+        .filter(
+            invoice::Column::WorkspaceId.not_in_subquery(
+                Query::select()
+                    .column(workspace::Column::Id)
+                    .from(workspace::Entity)
+                    .and_where(workspace::Column::ArchivedAt.is_not_null())
+                    .to_owned(),
+            ),
+        );
+    if let Some(workspace_id) = workspace_id {
+        query = query.filter(invoice::Column::WorkspaceId.eq(workspace_id));
+    }
+    let overdue = query
+        .all(&state.db)
+        .await
+        .map_err(AppError::internal)?;
+    if overdue.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let issued = invoice_reminder::Entity::find()
+        .filter(invoice_reminder::Column::InvoiceId.is_in(overdue.iter().map(|item| item.id)))
+        .all(&state.db)
+        .await
+        .map_err(AppError::internal)?;
+    let mut last_level: HashMap<Uuid, i32> = HashMap::new();
+    let mut failed = Vec::new();
+    for reminder in issued {
+        let entry = last_level.entry(reminder.invoice_id).or_insert(0);
+        *entry = (*entry).max(reminder.level);
+        if reminder.email_status == "failed" && reminder.email_attempts < MAX_EMAIL_ATTEMPTS {
+            failed.push(reminder);
+        }
+    }
+    for reminder in failed {
+        if let Some(invoice) = overdue.iter().find(|invoice| invoice.id == reminder.invoice_id) {
+            retry_reminder_email(state, invoice, reminder).await?;
+        }
+    }
+
+    let mut levels_by_workspace: HashMap<Uuid, Vec<dunning_level::Model>> = HashMap::new();
+    let mut created = Vec::new();
+    for invoice in overdue {
+        let (Some(owner_id), Some(workspace_id), Some(due_date)) =
+            (invoice.user_id, invoice.workspace_id, invoice.due_date)
+        else {
+            continue;
+        };
+        let levels = match levels_by_workspace.entry(workspace_id) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                entry.insert(load_levels(&state.db, workspace_id, owner_id).await?)
+            }
+        };
+        let days_overdue = (today - due_date).num_days() as i32;
+        let current = last_level.get(&invoice.id).copied().unwrap_or(0);
+        let Some(level) = next_level(levels, current, days_overdue).cloned() else {
+            continue;
+        };
+
+        if let Some(reminder) = issue_reminder(state, &invoice, &level, days_overdue).await? {
+            created.push(reminder);
+        }
+    }
+
+    Ok(created)
+}
+
+/// The level after `current` once it is due; later levels wait for later runs so an
+/// invoice that is already far overdue still starts with the first reminder.
+fn next_level(
+    levels: &[dunning_level::Model],
+    current: i32,
+    days_overdue: i32,
+) -> Option<&dunning_level::Model> {
+    levels
+        .iter()
+        .filter(|level| level.level > current)
+        .min_by_key(|level| level.level)
+        .filter(|level| days_overdue >= level.days_after_due)
+}
+
+/// Records the reminder and then emails it. `None` when another instance already issued
+/// this level, so every level is only sent once.
+async fn issue_reminder(
+    state: &AppState,
+    invoice: &invoice::Model,
+    level: &dunning_level::Model,
+    days_overdue: i32,
+) -> Result<Option<invoice_reminder::Model>, AppError> {
+    let interest = level
+        .interest_rate
+        .map(|rate| round_cents(invoice.total_amount * rate / 100.0 * days_overdue as f64 / 365.0))
+        .unwrap_or(0.0);
+    let fee = round_cents(level.fee);
+    let reminder = invoice_reminder::Model {
+        id: Uuid::new_v4(),
+        invoice_id: invoice.id,
+        level: level.level,
+        name: level.name.clone(),
+        days_overdue,
+        fee,
+        interest,
+        total_due: round_cents(invoice.total_amount + fee + interest),
+        email_status: if level.send_email { "pending" } else { "skipped" }.to_string(),
+        email_error: None,
+        email_attempts: 0,
+        created_at: Utc::now(),
+    };
+
+    let active: invoice_reminder::ActiveModel = reminder.clone().into();
+    let inserted = invoice_reminder::Entity::insert(active)
+        .on_conflict(
+            OnConflict::columns([
+                invoice_reminder::Column::InvoiceId,
+                invoice_reminder::Column::Level,
+            ])
+            .do_nothing()
+            .to_owned(),
+        )
+        .exec_without_returning(&state.db)
+        .await
+        .map_err(AppError::internal)?;
+    if inserted == 0 {
+        return Ok(None);
+    }
+
+    if !level.send_email {
+        return Ok(Some(reminder));
+    }
+    deliver_reminder(state, invoice, reminder).await.map(Some)
+}
+
+/// Sends a failed reminder email again, unless another instance has already picked it up.
+async fn retry_reminder_email(
+    state: &AppState,
+    invoice: &invoice::Model,
+    reminder: invoice_reminder::Model,
+) -> Result<(), AppError> {
+    let claimed = invoice_reminder::Entity::update_many()
+        .col_expr(invoice_reminder::Column::EmailStatus, Expr::value("pending"))
+        .filter(invoice_reminder::Column::Id.eq(reminder.id))
+        .filter(invoice_reminder::Column::EmailStatus.eq("failed"))
+        .exec(&state.db)
+        .await
+        .map_err(AppError::internal)?;
+    if claimed.rows_affected == 1 {
+        deliver_reminder(state, invoice, reminder).await?;
+    }
+    Ok(())
+}
+
+async fn deliver_reminder(
+    state: &AppState,
+    invoice: &invoice::Model,
+    reminder: invoice_reminder::Model,
+) -> Result<invoice_reminder::Model, AppError> {
+    let result = send_reminder_email(state, invoice, &reminder).await;
+    let attempts = reminder.email_attempts + 1;
+    let mut active: invoice_reminder::ActiveModel = reminder.into();
+    match result {
+        Ok(()) => {
+            let status = if state.mailer.delivers() { "sent" } else { "logged" };
+            active.email_status = Set(status.to_string());
+            active.email_error = Set(None);
+        }
+        Err(error) => {
+            active.email_status = Set("failed".to_string());
+            active.email_error = Set(Some(error));
+        }
+    }
+    active.email_attempts = Set(attempts);
+    active.update(&state.db).await.map_err(AppError::internal)
+}
+
+async fn send_reminder_email(
+    state: &AppState,
+    invoice: &invoice::Model,
+    reminder: &invoice_reminder::Model,
+) -> Result<(), String> {
+    let Some(workspace_id) = invoice.workspace_id else {
+        return Err("Invoice has no owner".to_string());
+    };
+    let recipients: Vec<String> = invoice_recipients(&state.db, invoice.company_id, invoice.contact_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter_map(|contact| contact.email)
+        .collect();
+    if recipients.is_empty() {
+        return Err("No recipients".to_string());
+    }
+
+    let pdf_bytes = build_reminder_pdf(invoice, reminder)?;
+    let ctx = reminder_context(invoice, reminder);
+    let email = render_email(&state.db, workspace_id, REMINDER_EMAIL, &ctx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    state
+        .mailer
+        .send(OutgoingMail {
+            to: recipients,
+            subject: email.subject,
+            html: email.html,
+            text: email.text,
+            attachments: vec![MailAttachment {
+                filename: format!("reminder-{}-{}.pdf", invoice.invoice_number, reminder.level),
+                content_type: "application/pdf".to_string(),
+                body: pdf_bytes,
+            }],
+        })
+        .await
+}
+
+/// Levels for a workspace in escalation order, seeding the defaults on first use
+/// (attributed to `user_id`).
+async fn load_levels(
+    db: &DatabaseConnection,
+    workspace_id: Uuid,
+    user_id: Uuid,
+) -> Result<Vec<dunning_level::Model>, AppError> {
+    let levels = dunning_level::Entity::find()
+        .filter(dunning_level::Column::WorkspaceId.eq(workspace_id))
+        .order_by_asc(dunning_level::Column::Level)
+        .all(db)
+        .await
+        .map_err(AppError::internal)?;
+    if !levels.is_empty() {
+        return Ok(levels);
+    }
+
+    let defaults = [
+        ("Payment reminder", 7, 0.0),
+        ("First dunning notice", 21, 5.0),
+        ("Second dunning notice", 35, 10.0),
+    ];
+    let mut seeded = Vec::with_capacity(defaults.len());
+    for (index, (name, days_after_due, fee)) in defaults.into_iter().enumerate() {
+        let saved = dunning_level::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            user_id: Set(user_id),
+            workspace_id: Set(workspace_id),
+            level: Set(index as i32 + 1),
+            name: Set(name.to_string()),
+            days_after_due: Set(days_after_due),
+            fee: Set(fee),
+            interest_rate: Set(None),
+            send_email: Set(true),
+            created_at: Set(Utc::now()),
+        }
+        .insert(db)
+        .await
+        .map_err(AppError::internal)?;
+        seeded.push(saved);
+    }
+    Ok(seeded)
+}
+
+fn reminder_context(
+    invoice: &invoice::Model,
+    reminder: &invoice_reminder::Model,
+) -> serde_json::Value {
+    let money = |value: f64| format!("{} {}", format_money(value, &invoice.currency), invoice.currency);
+    json!({
+        "reminder_name": reminder.name,
+        "reminder_level": reminder.level,
+        "reminder_date": reminder.created_at.date_naive().to_string(),
+        "invoice_number": invoice.invoice_number,
+        "invoice_date": invoice.date.to_string(),
+        "due_date": invoice.due_date.map(|date| date.to_string()),
+        "days_overdue": reminder.days_overdue,
+        "client_name": invoice.client_name,
+        "client_address": invoice.client_address,
+        "user_address": invoice.user_address,
+        "currency": invoice.currency,
+        "total_amount": money(invoice.total_amount),
+        "fee": money(reminder.fee),
+        "interest": money(reminder.interest),
+        "has_fee": reminder.fee > 0.0,
+        "has_interest": reminder.interest > 0.0,
+        "total": money(reminder.total_due),
+    })
+}
+
+fn build_reminder_pdf(
+    invoice: &invoice::Model,
+    reminder: &invoice_reminder::Model,
+) -> Result<Vec<u8>, String> {
+    let handlebars = Handlebars::new();
+    let html = handlebars
+        .render_template(REMINDER_HTML, &reminder_context(invoice, reminder))
+        .map_err(|e| e.to_string())?;
+    html_to_pdf(&html)
+}
+
+fn round_cents(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+const REMINDER_HTML: &str = r#"<!doctype html>
+<html>
+<head>
+  <meta charset="utf-8" />
+  <style>
+    body { font-family: "DejaVu Sans", Arial, sans-serif; color: #222; margin: 32px; }
+    h1 { margin: 0 0 8px; }
+    .section { margin-bottom: 18px; }
+    .muted { color: #666; font-size: 12px; }
+    table { width: 100%; border-collapse: collapse; margin-top: 12px; }
+    td { border-bottom: 1px solid #ddd; padding: 6px 4px; }
+    .right { text-align: right; }
+    .total td { font-weight: bold; }
+  </style>
+</head>
+<body>
+  <div class="section">
+    <div class="muted">{{user_address}}</div>
+  </div>
+  <div class="section">
+    <div>{{client_name}}</div>
+    <div class="muted">{{client_address}}</div>
+  </div>
+  <div class="section">
+    <h1>{{reminder_name}}</h1>
+    <div class="muted">Date: {{reminder_date}}</div>
+  </div>
+  <p>
+    Our invoice {{invoice_number}} dated {{invoice_date}} was due on {{due_date}} and is now
+    {{days_overdue}} days overdue. Please transfer the outstanding amount without further delay.
+  </p>
+  <table>
+    <tr><td>Invoice {{invoice_number}}</td><td class="right">{{total_amount}}</td></tr>
+    {{#if has_fee}}<tr><td>Dunning fee</td><td class="right">{{fee}}</td></tr>{{/if}}
+    {{#if has_interest}}<tr><td>Default interest</td><td class="right">{{interest}}</td></tr>{{/if}}
+    <tr class="total"><td>Amount due</td><td class="right">{{total}}</td></tr>
+  </table>
+  <p class="muted">If you have already paid, please disregard this notice.</p>
+</body>
+</html>"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level(level: i32, days_after_due: i32) -> dunning_level::Model {
+        dunning_level::Model {
+            id: Uuid::new_v4(),
+            user_id: Uuid::nil(),
+            workspace_id: Uuid::nil(),
+            level,
+            name: format!("Level {level}"),
+            days_after_due,
+            fee: 0.0,
+            interest_rate: None,
+            send_email: true,
+            created_at: Utc::now(),
+        }
+    }
+
+    fn levels() -> Vec<dunning_level::Model> {
+        vec![level(1, 7), level(2, 21), level(3, 35)]
+    }
+
+    #[test]
+    fn far_overdue_invoice_starts_with_the_first_level() {
+        let levels = levels();
+        assert_eq!(next_level(&levels, 0, 60).map(|l| l.level), Some(1));
+    }
+
+    #[test]
+    fn advances_one_level_per_run() {
+        let levels = levels();
+        assert_eq!(next_level(&levels, 1, 60).map(|l| l.level), Some(2));
+        assert_eq!(next_level(&levels, 2, 60).map(|l| l.level), Some(3));
+        assert_eq!(next_level(&levels, 3, 60), None);
+    }
+
+    #[test]
+    fn waits_until_the_next_level_is_due() {
+        let levels = levels();
+        assert_eq!(next_level(&levels, 0, 6), None);
+        assert_eq!(next_level(&levels, 0, 7).map(|l| l.level), Some(1));
+        assert_eq!(next_level(&levels, 1, 20), None);
+    }
+
+    #[test]
+    fn skips_gaps_in_level_numbers() {
+        let levels = vec![level(1, 7), level(3, 21)];
+        assert_eq!(next_level(&levels, 1, 30).map(|l| l.level), Some(3));
+    }
+}
@@ -0,0 +1,467 @@
+use crate::entity::{expense, expense_category};
+use crate::modules::api_tokens::ApiScope;
+use crate::modules::auth::require_access;
+use crate::modules::shared::{AppError, AppState, FieldError};
+use crate::modules::validation::{ledger_account, not_blank, Path, ValidatedJson};
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use sea_orm::sea_query::{Expr, Func};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, PaginatorTrait,
+    QueryFilter, QueryOrder, Set, SqlErr, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+/// Categories every new workspace starts with: name, SKR03 account, SKR04 account and
+/// deductible percentage. Keep in sync with the seed in the expense categories migration.
+const DEFAULT_CATEGORIES: &[(&str, &str, &str, i32)] = &[
+    ("Office", "4930", "6815", 100),
+    ("Software", "4964", "6837", 100),
+    ("Hardware", "0480", "0670", 100),
+    ("Phone & internet", "4920", "6805", 100),
+    ("Travel", "4670", "6670", 100),
+    ("Business meals", "4650", "6640", 70),
+    ("Gifts", "4630", "6610", 100),
+    ("Marketing", "4600", "6600", 100),
+    ("Services", "3100", "5900", 100),
+    ("Training", "4945", "6821", 100),
+    ("Books & media", "4940", "6820", 100),
+    ("Legal & tax advice", "4950", "6825", 100),
+    ("Insurance", "4360", "6400", 100),
+    ("Rent", "4210", "6310", 100),
+    ("Bank fees", "4970", "6855", 100),
+    ("Other", "4900", "6300", 100),
+];
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct ExpenseCategoryCreateRequest {
+    #[validate(custom(function = not_blank), length(max = 100))]
+    pub name: String,
+    #[validate(custom(function = ledger_account))]
+    pub skr03_account: Option<String>,
+    #[validate(custom(function = ledger_account))]
+    pub skr04_account: Option<String>,
+    /// Share of the amount that is tax deductible (default 100)
+    #[validate(range(min = 0, max = 100))]
+    pub deductible_percent: Option<i32>,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct ExpenseCategoryUpdateRequest {
+    #[validate(custom(function = not_blank), length(max = 100))]
+    pub name: Option<String>,
+    /// An empty string removes the account
+    pub skr03_account: Option<String>,
+    /// An empty string removes the account
+    pub skr04_account: Option<String>,
+    #[validate(range(min = 0, max = 100))]
+    pub deductible_percent: Option<i32>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ExpenseCategoryResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub skr03_account: Option<String>,
+    pub skr04_account: Option<String>,
+    pub deductible_percent: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<expense_category::Model> for ExpenseCategoryResponse {
+    fn from(category: expense_category::Model) -> Self {
+        Self {
+            id: category.id,
+            name: category.name,
+            skr03_account: category.skr03_account,
+            skr04_account: category.skr04_account,
+            deductible_percent: category.deductible_percent,
+            created_at: category.created_at,
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/expense-categories",
+    responses(
+        (status = 200, description = "Expense categories of the workspace", body = [ExpenseCategoryResponse]),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "expenses"
+)]
+pub async fn list_expense_categories(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<ExpenseCategoryResponse>>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::ExpensesRead).await?;
+    let categories = expense_category::Entity::find()
+        .filter(expense_category::Column::WorkspaceId.eq(access.workspace_id))
+        .order_by_asc(expense_category::Column::Name)
+        .all(&state.db)
+        .await?;
+    Ok(Json(categories.into_iter().map(ExpenseCategoryResponse::from).collect()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/expense-categories",
+    request_body = ExpenseCategoryCreateRequest,
+    responses(
+        (status = 201, description = "Expense category created", body = ExpenseCategoryResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 409, description = "A category with this name already exists", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "expenses"
+)]
+pub async fn create_expense_category(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidatedJson(payload): ValidatedJson<ExpenseCategoryCreateRequest>,
+) -> Result<(StatusCode, Json<ExpenseCategoryResponse>), AppError> {
+    let access = require_access(&state, &headers, ApiScope::ExpensesWrite).await?;
+    let name = payload.name.trim().to_string();
+    if find_by_name(&state.db, access.workspace_id, &name).await?.is_some() {
+        return Err(AppError::conflict("A category with this name already exists"));
+    }
+
+    let created = expense_category::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        workspace_id: Set(access.workspace_id),
+        name: Set(name),
+        skr03_account: Set(payload.skr03_account),
+        skr04_account: Set(payload.skr04_account),
+        deductible_percent: Set(payload.deductible_percent.unwrap_or(100)),
+        created_at: Set(Utc::now()),
+    }
+    .insert(&state.db)
+    .await
+    .map_err(name_conflict)?;
+
+    Ok((StatusCode::CREATED, Json(ExpenseCategoryResponse::from(created))))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/expense-categories/{id}",
+    request_body = ExpenseCategoryUpdateRequest,
+    responses(
+        (status = 200, description = "Expense category updated", body = ExpenseCategoryResponse),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 404, description = "Expense category not found", body = ErrorResponse),
+        (status = 409, description = "A category with this name already exists", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "expenses"
+)]
+pub async fn update_expense_category(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    ValidatedJson(payload): ValidatedJson<ExpenseCategoryUpdateRequest>,
+) -> Result<Json<ExpenseCategoryResponse>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::ExpensesWrite).await?;
+    let id = Uuid::parse_str(&id).map_err(|_| AppError::bad_request("Invalid id"))?;
+    let existing = find_in_workspace(&state.db, access.workspace_id, id).await?;
+
+    let skr03_account = optional_account("skr03_account", payload.skr03_account)?;
+    let skr04_account = optional_account("skr04_account", payload.skr04_account)?;
+    let renamed = match payload.name.map(|name| name.trim().to_string()) {
+        Some(name) if name != existing.name => {
+            if let Some(other) = find_by_name(&state.db, access.workspace_id, &name).await?
+                && other.id != existing.id
+            {
+                return Err(AppError::conflict("A category with this name already exists"));
+            }
+            Some(name)
+        }
+        _ => None,
+    };
+
+    let txn = state.db.begin().await?;
+    let mut active: expense_category::ActiveModel = existing.into();
+    if let Some(name) = renamed.clone() {
+        active.name = Set(name);
+    }
+    if let Some(account) = skr03_account {
+        active.skr03_account = Set(account);
+    }
+    if let Some(account) = skr04_account {
+        active.skr04_account = Set(account);
+    }
+    if let Some(percent) = payload.deductible_percent {
+        active.deductible_percent = Set(percent);
+    }
+    let updated = active.update(&txn).await.map_err(name_conflict)?;
+
+    // Expenses keep the category name for filtering and sorting, so carry renames over.
+    if let Some(name) = renamed {
+        expense::Entity::update_many()
+            .col_expr(expense::Column::Category, Expr::value(name))
+            .filter(expense::Column::CategoryId.eq(updated.id))
+            .exec(&txn)
+            .await?;
+    }
+    txn.commit().await?;
+
+    Ok(Json(ExpenseCategoryResponse::from(updated)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/expense-categories/{id}",
+    responses(
+        (status = 204, description = "Expense category deleted"),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 404, description = "Expense category not found", body = ErrorResponse),
+        (status = 409, description = "Category is still used by expenses", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "expenses"
+)]
+pub async fn delete_expense_category(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    let access = require_access(&state, &headers, ApiScope::ExpensesWrite).await?;
+    let id = Uuid::parse_str(&id).map_err(|_| AppError::bad_request("Invalid id"))?;
+    let existing = find_in_workspace(&state.db, access.workspace_id, id).await?;
+
+    let in_use = expense::Entity::find()
+        .filter(expense::Column::CategoryId.eq(existing.id))
+        .count(&state.db)
+        .await?;
+    if in_use > 0 {
+        return Err(AppError::conflict(format!(
+            "Category is used by {in_use} expense(s); move them to another category first"
+        )));
+    }
+
+    expense_category::Entity::delete_by_id(existing.id)
+        .exec(&state.db)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Gives a new workspace the default categories.
+pub(crate) async fn seed_default_categories<C: ConnectionTrait>(
+    db: &C,
+    workspace_id: Uuid,
+) -> Result<(), AppError> {
+    let now = Utc::now();
+    let categories = DEFAULT_CATEGORIES.iter().map(|(name, skr03, skr04, percent)| {
+        expense_category::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            workspace_id: Set(workspace_id),
+            name: Set(name.to_string()),
+            skr03_account: Set(Some(skr03.to_string())),
+            skr04_account: Set(Some(skr04.to_string())),
+            deductible_percent: Set(*percent),
+            created_at: Set(now),
+        }
+    });
+    expense_category::Entity::insert_many(categories).exec(db).await?;
+    Ok(())
+}
+
+/// Looks up the category an expense refers to, by id or else by name ignoring case. A blank
+/// name means no category; anything that does not exist in the workspace is a validation error.
+pub(crate) async fn resolve_category<C: ConnectionTrait>(
+    db: &C,
+    workspace_id: Uuid,
+    category_id: Option<Uuid>,
+    name: Option<&str>,
+) -> Result<Option<expense_category::Model>, AppError> {
+    if let Some(category_id) = category_id {
+        return expense_category::Entity::find_by_id(category_id)
+            .filter(expense_category::Column::WorkspaceId.eq(workspace_id))
+            .one(db)
+            .await?
+            .map(Some)
+            .ok_or_else(|| {
+                AppError::Validation(vec![FieldError::new(
+                    "category_id",
+                    "unknown_category",
+                    "Unknown expense category",
+                )])
+            });
+    }
+
+    let Some(name) = name.map(str::trim).filter(|name| !name.is_empty()) else {
+        return Ok(None);
+    };
+    find_by_name(db, workspace_id, name)
+        .await?
+        .map(Some)
+        .ok_or_else(|| {
+            AppError::Validation(vec![FieldError::new(
+                "category",
+                "unknown_category",
+                format!("Unknown expense category \"{name}\""),
+            )])
+        })
+}
+
+async fn find_by_name<C: ConnectionTrait>(
+    db: &C,
+    workspace_id: Uuid,
+    name: &str,
+) -> Result<Option<expense_category::Model>, AppError> {
+    Ok(expense_category::Entity::find()
+        .filter(expense_category::Column::WorkspaceId.eq(workspace_id))
+        .filter(
+            Expr::expr(Func::lower(Expr::col(expense_category::Column::Name)))
+                .eq(name.to_lowercase()),
+        )
+        .one(db)
+        .await?)
+}
+
+async fn find_in_workspace<C: ConnectionTrait>(
+    db: &C,
+    workspace_id: Uuid,
+    id: Uuid,
+) -> Result<expense_category::Model, AppError> {
+    expense_category::Entity::find_by_id(id)
+        .filter(expense_category::Column::WorkspaceId.eq(workspace_id))
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Expense category not found"))
+}
+
+/// Two requests creating or renaming to the same name at once both pass the lookup; the
+/// unique index decides.
+fn name_conflict(error: DbErr) -> AppError {
+    match error.sql_err() {
+        Some(SqlErr::UniqueConstraintViolation(_)) => {
+            AppError::conflict("A category with this name already exists")
+        }
+        _ => AppError::from(error),
+    }
+}
+
+/// `None` leaves the account unchanged, an empty string clears it.
+fn optional_account(
+    field: &str,
+    value: Option<String>,
+) -> Result<Option<Option<String>>, AppError> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    let value = value.trim().to_string();
+    if value.is_empty() {
+        return Ok(Some(None));
+    }
+    ledger_account(&value).map_err(|err| {
+        AppError::Validation(vec![FieldError::new(
+            field,
+            "ledger_account",
+            err.message.map(|m| m.to_string()).unwrap_or_default(),
+        )])
+    })?;
+    Ok(Some(Some(value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::workspace_member;
+    use crate::modules::test_support::{self, session_headers};
+    use sea_orm::MockDatabase;
+
+    fn workspace_id() -> Uuid {
+        Uuid::from_u128(10)
+    }
+
+    fn category(name: &str) -> expense_category::Model {
+        expense_category::Model {
+            id: Uuid::from_u128(20),
+            workspace_id: workspace_id(),
+            name: name.to_string(),
+            skr03_account: Some("4964".to_string()),
+            skr04_account: None,
+            deductible_percent: 100,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// A signed-in owner of the workspace, as resolved by `require_access`.
+    fn signed_in_owner() -> MockDatabase {
+        let user_id = Uuid::from_u128(1);
+        test_support::signed_in(
+            test_support::session(Uuid::from_u128(2), user_id),
+            test_support::user(user_id, "owner@example.com"),
+        )
+        .append_query_results([[workspace_member::Model {
+            id: Uuid::from_u128(3),
+            workspace_id: workspace_id(),
+            user_id,
+            role: "owner".to_string(),
+            created_at: Utc::now(),
+        }]])
+    }
+
+    fn request(name: &str) -> ExpenseCategoryCreateRequest {
+        ExpenseCategoryCreateRequest {
+            name: name.to_string(),
+            skr03_account: Some("4964".to_string()),
+            skr04_account: None,
+            deductible_percent: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn creating_a_category_answers_created() {
+        let db = signed_in_owner()
+            .append_query_results([Vec::<expense_category::Model>::new()])
+            .append_query_results([[category("Subscriptions")]])
+            .into_connection();
+        let (status, Json(created)) = create_expense_category(
+            State(AppState::for_tests(db)),
+            session_headers(),
+            ValidatedJson(request(" Subscriptions ")),
+        )
+        .await
+        .ok()
+        .unwrap();
+
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(created.name, "Subscriptions");
+    }
+
+    #[tokio::test]
+    async fn an_existing_name_in_another_case_is_a_conflict() {
+        let db = signed_in_owner()
+            .append_query_results([[category("Software")]])
+            .into_connection();
+        let error = create_expense_category(
+            State(AppState::for_tests(db)),
+            session_headers(),
+            ValidatedJson(request("software")),
+        )
+        .await
+        .err()
+        .unwrap();
+
+        assert_eq!(error.status(), StatusCode::CONFLICT);
+        assert_eq!(error.to_string(), "A category with this name already exists");
+    }
+}
@@ -1,104 +1,312 @@
-use crate::entity::expense;
-use crate::modules::auth::require_user;
-use crate::modules::shared::AppState;
+use crate::entity::{expense, receipt};
+use crate::modules::api_tokens::ApiScope;
+use crate::modules::auth::require_access;
+use crate::modules::expense_categories::resolve_category;
+use crate::modules::receipts::{
+    delete_receipt_if_unused, delete_stored_receipts, receipt_extension, receipt_response,
+    require_receipt, safe_filename, storage_key,
+};
+use crate::modules::storage::ReceiptStorage;
+use crate::modules::travel::{MileageDetails, PerDiemDetails};
+use crate::modules::shared::{
+    fetch_page, search_pattern, AppError, AppState, FieldError, Page, PageParams, SortOrder,
+};
+use crate::modules::validation::{currency_code, not_blank, positive, Path, Query, ValidatedJson};
 use axum::{
-    extract::{Path, State},
+    extract::State,
     http::{HeaderMap, StatusCode},
     Json,
 };
-use chrono::NaiveDate;
-use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+use chrono::{DateTime, NaiveDate, Utc};
+use sea_orm::sea_query::{extension::postgres::PgExpr, Expr, Func};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, EntityTrait, QueryFilter,
+    QueryOrder, Set,
+};
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
 use uuid::Uuid;
 
-use aws_sdk_s3::config::{Credentials, Region};
-use aws_sdk_s3::presigning::PresigningConfig;
-use aws_sdk_s3::Client;
-
-#[derive(Deserialize, ToSchema)]
+#[derive(Deserialize, Serialize, ToSchema, Validate)]
 pub struct ExpenseCreateRequest {
+    #[validate(custom(function = not_blank), length(max = 200))]
     pub vendor: String,
+    #[validate(length(max = 1000))]
     pub description: String,
-    pub amount: f64,
+    /// Gross amount; give any two of `amount`, `net_amount` and `vat_rate`, or just `amount`
+    /// when VAT is not tracked
+    #[validate(custom(function = positive))]
+    pub amount: Option<f64>,
+    #[validate(custom(function = positive))]
+    pub net_amount: Option<f64>,
+    /// VAT rate in percent, e.g. 19
+    #[validate(range(min = 0.0, max = 100.0))]
+    pub vat_rate: Option<f64>,
+    /// Defaults to whether VAT is tracked for the expense
+    pub input_tax_deductible: Option<bool>,
+    /// Foreign vendor without VAT (e.g. SaaS from outside Germany): gross equals net and
+    /// `vat_rate` is the rate to self-assess
+    #[serde(default)]
+    pub reverse_charge: bool,
+    #[validate(custom(function = currency_code))]
     pub currency: String,
     pub date: NaiveDate,
+    /// Name of an expense category of the workspace, matched ignoring case
+    #[validate(length(max = 100))]
     pub category: Option<String>,
+    /// Takes precedence over `category`
+    pub category_id: Option<Uuid>,
+    /// A receipt uploaded with `POST /receipts` or confirmed with `POST /receipts/{id}/confirm`
+    pub receipt_id: Option<Uuid>,
+    /// Link to a receipt stored elsewhere
+    #[validate(url)]
     pub receipt_url: Option<String>,
 }
 
-#[derive(Deserialize, ToSchema)]
+#[derive(Deserialize, ToSchema, Validate)]
 pub struct ExpenseUpdateRequest {
+    #[validate(custom(function = not_blank), length(max = 200))]
     pub vendor: Option<String>,
+    #[validate(length(max = 1000))]
     pub description: Option<String>,
+    /// Changing one of `amount`, `net_amount` and `vat_rate` recomputes the others, keeping
+    /// the rate where possible
+    #[validate(custom(function = positive))]
     pub amount: Option<f64>,
+    #[validate(custom(function = positive))]
+    pub net_amount: Option<f64>,
+    #[validate(range(min = 0.0, max = 100.0))]
+    pub vat_rate: Option<f64>,
+    pub input_tax_deductible: Option<bool>,
+    pub reverse_charge: Option<bool>,
+    #[validate(custom(function = currency_code))]
     pub currency: Option<String>,
     pub date: Option<NaiveDate>,
+    /// Name of an expense category of the workspace; an empty string removes the category
+    #[validate(length(max = 100))]
     pub category: Option<String>,
+    /// Takes precedence over `category`
+    pub category_id: Option<Uuid>,
+    /// A receipt uploaded with `POST /receipts`; delete the receipt to detach it
+    pub receipt_id: Option<Uuid>,
+    #[validate(url)]
     pub receipt_url: Option<String>,
 }
 
+/// How an expense's amount comes about: from a receipt, or computed from a workspace rate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExpenseKind {
+    Receipt,
+    Mileage,
+    PerDiem,
+}
+
+impl ExpenseKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ExpenseKind::Receipt => "receipt",
+            ExpenseKind::Mileage => "mileage",
+            ExpenseKind::PerDiem => "per_diem",
+        }
+    }
+
+    pub(crate) fn from_stored(value: &str) -> Self {
+        match value {
+            "mileage" => ExpenseKind::Mileage,
+            "per_diem" => ExpenseKind::PerDiem,
+            _ => ExpenseKind::Receipt,
+        }
+    }
+}
+
 #[derive(Serialize, ToSchema)]
 pub struct ExpenseResponse {
     pub id: Uuid,
+    pub kind: ExpenseKind,
     pub vendor: String,
     pub description: String,
+    /// Gross amount
     pub amount: f64,
+    pub net_amount: Option<f64>,
+    pub vat_rate: Option<f64>,
+    /// Input tax (for reverse charge: the self-assessed tax)
+    pub vat_amount: Option<f64>,
+    pub input_tax_deductible: bool,
+    pub reverse_charge: bool,
     pub currency: String,
     pub date: NaiveDate,
     pub category: Option<String>,
+    pub category_id: Option<Uuid>,
+    /// Fetch `GET /receipts/{id}` for a download link
+    pub receipt_id: Option<Uuid>,
     pub receipt_url: Option<String>,
+    /// Trip details of a mileage expense
+    pub mileage: Option<MileageDetails>,
+    /// Trip details of a per diem expense
+    pub per_diem: Option<PerDiemDetails>,
+}
+
+impl From<expense::Model> for ExpenseResponse {
+    fn from(expense: expense::Model) -> Self {
+        let kind = ExpenseKind::from_stored(&expense.kind);
+        let mileage = (kind == ExpenseKind::Mileage)
+            .then(|| MileageDetails::from_expense(&expense))
+            .flatten();
+        let per_diem = (kind == ExpenseKind::PerDiem)
+            .then(|| PerDiemDetails::from_expense(&expense))
+            .flatten();
+        Self {
+            id: expense.id,
+            kind,
+            vendor: expense.vendor,
+            description: expense.description,
+            amount: expense.amount,
+            net_amount: expense.net_amount,
+            vat_rate: expense.vat_rate,
+            vat_amount: expense.vat_amount,
+            input_tax_deductible: expense.input_tax_deductible,
+            reverse_charge: expense.reverse_charge,
+            currency: expense.currency,
+            date: expense.date,
+            category: expense.category,
+            category_id: expense.category_id,
+            receipt_id: expense.receipt_id,
+            receipt_url: expense.receipt_url,
+            mileage,
+            per_diem,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExpenseSort {
+    #[default]
+    Date,
+    Amount,
+    Vendor,
+    Category,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExpenseListQuery {
+    /// Only expenses dated on or after this day
+    pub date_from: Option<NaiveDate>,
+    /// Only expenses dated on or before this day
+    pub date_to: Option<NaiveDate>,
+    /// Category name, matched ignoring case
+    pub category: Option<String>,
+    pub category_id: Option<Uuid>,
+    #[param(inline)]
+    pub kind: Option<ExpenseKind>,
+    pub currency: Option<String>,
+    /// Minimum amount (inclusive)
+    pub min_amount: Option<f64>,
+    /// Maximum amount (inclusive)
+    pub max_amount: Option<f64>,
+    /// Searches vendor and description
+    pub q: Option<String>,
+    /// Sort field (default date)
+    #[param(inline)]
+    pub sort: Option<ExpenseSort>,
+    /// Sort direction (default desc)
+    #[param(inline)]
+    pub order: Option<SortOrder>,
 }
 
-#[derive(Deserialize, ToSchema)]
+#[derive(Deserialize, ToSchema, Validate)]
 pub struct ReceiptUploadRequest {
+    #[validate(custom(function = not_blank), length(max = 255))]
     pub filename: String,
+    #[validate(custom(function = not_blank), length(max = 100))]
     pub content_type: String,
 }
 
 #[derive(Serialize, ToSchema)]
 pub struct ReceiptUploadResponse {
+    /// The pending receipt; confirm it with `POST /receipts/{id}/confirm` after the upload
+    pub receipt_id: Uuid,
     pub upload_url: String,
-    pub receipt_url: String,
+    /// Signed link to the receipt once uploaded, valid until `download_url_expires_at`
+    pub download_url: String,
+    pub download_url_expires_at: DateTime<Utc>,
 }
 
 #[utoipa::path(
     get,
     path = "/expenses",
+    params(PageParams, ExpenseListQuery),
     responses(
-        (status = 200, description = "Expense list", body = [ExpenseResponse]),
-        (status = 401, description = "Not authenticated"),
-        (status = 500, description = "Server error")
+        (status = 200, description = "Expense page", body = ExpensePage),
+        (status = 400, description = "Invalid query", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
     ),
     tag = "expenses"
 )]
 pub async fn list_expenses(
     State(state): State<AppState>,
     headers: HeaderMap,
-) -> Result<Json<Vec<ExpenseResponse>>, (StatusCode, String)> {
-    let current_user = require_user(&state, &headers).await?;
-    let expenses = expense::Entity::find()
-        .filter(expense::Column::UserId.eq(current_user.id))
-        .all(&state.db)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Query(paging): Query<PageParams>,
+    Query(query): Query<ExpenseListQuery>,
+) -> Result<Json<Page<ExpenseResponse>>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::ExpensesRead).await?;
+    let mut select =
+        expense::Entity::find().filter(expense::Column::WorkspaceId.eq(access.workspace_id));
 
-    Ok(Json(
-        expenses
-            .into_iter()
-            .map(|item| ExpenseResponse {
-                id: item.id,
-                vendor: item.vendor,
-                description: item.description,
-                amount: item.amount,
-                currency: item.currency,
-                date: item.date,
-                category: item.category,
-                receipt_url: item.receipt_url,
-            })
-            .collect(),
-    ))
+    if let Some(date_from) = query.date_from {
+        select = select.filter(expense::Column::Date.gte(date_from));
+    }
+    if let Some(date_to) = query.date_to {
+        select = select.filter(expense::Column::Date.lte(date_to));
+    }
+    if let Some(category) = query.category.as_deref() {
+        select = select.filter(
+            Expr::expr(Func::lower(Expr::col(expense::Column::Category)))
+                .eq(category.trim().to_lowercase()),
+        );
+    }
+    if let Some(category_id) = query.category_id {
+        select = select.filter(expense::Column::CategoryId.eq(category_id));
+    }
+    if let Some(kind) = query.kind {
+        select = select.filter(expense::Column::Kind.eq(kind.as_str()));
+    }
+    if let Some(currency) = query.currency.as_deref() {
+        select = select.filter(expense::Column::Currency.eq(currency.trim().to_uppercase()));
+    }
+    if let Some(min_amount) = query.min_amount {
+        select = select.filter(expense::Column::Amount.gte(min_amount));
+    }
+    if let Some(max_amount) = query.max_amount {
+        select = select.filter(expense::Column::Amount.lte(max_amount));
+    }
+    if let Some(pattern) = query.q.as_deref().and_then(search_pattern) {
+        select = select.filter(
+            Condition::any()
+                .add(Expr::col(expense::Column::Vendor).ilike(&pattern))
+                .add(Expr::col(expense::Column::Description).ilike(&pattern)),
+        );
+    }
+
+    let order = query.order.unwrap_or(SortOrder::Desc);
+    let column = match query.sort.unwrap_or_default() {
+        ExpenseSort::Date => expense::Column::Date,
+        ExpenseSort::Amount => expense::Column::Amount,
+        ExpenseSort::Vendor => expense::Column::Vendor,
+        ExpenseSort::Category => expense::Column::Category,
+    };
+    select = select
+        .order_by(column, order.into())
+        .order_by(expense::Column::Id, order.into());
+
+    let page = fetch_page(&state.db, select, &paging).await?;
+    Ok(Json(page.map(ExpenseResponse::from)))
 }
 
 #[utoipa::path(
@@ -107,53 +315,83 @@ pub async fn list_expenses(
     request_body = ExpenseCreateRequest,
     responses(
         (status = 200, description = "Expense created", body = ExpenseResponse),
-        (status = 400, description = "Invalid input"),
-        (status = 401, description = "Not authenticated"),
-        (status = 500, description = "Server error")
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
     ),
     tag = "expenses"
 )]
 pub async fn create_expense(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Json(payload): Json<ExpenseCreateRequest>,
-) -> Result<Json<ExpenseResponse>, (StatusCode, String)> {
-    let current_user = require_user(&state, &headers).await?;
-    if payload.vendor.trim().is_empty() {
-        return Err((StatusCode::BAD_REQUEST, "Vendor is required".to_string()));
-    }
-    if payload.amount <= 0.0 {
-        return Err((StatusCode::BAD_REQUEST, "Amount must be positive".to_string()));
+    ValidatedJson(payload): ValidatedJson<ExpenseCreateRequest>,
+) -> Result<Json<ExpenseResponse>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::ExpensesWrite).await?;
+    let category = resolve_category(
+        &state.db,
+        access.workspace_id,
+        payload.category_id,
+        payload.category.as_deref(),
+    )
+    .await?;
+    let vat = vat_breakdown(
+        payload.amount,
+        payload.net_amount,
+        payload.vat_rate,
+        payload.reverse_charge,
+    )?;
+    let receipt_id = match payload.receipt_id {
+        Some(receipt_id) => Some(receipt_id),
+        None => uploaded_receipt_id(&state.db, access.workspace_id, payload.receipt_url.as_deref())
+            .await?,
+    };
+    if let Some(receipt_id) = receipt_id {
+        require_receipt(&state.db, access.workspace_id, receipt_id).await?;
     }
 
     let active = expense::ActiveModel {
         id: Set(Uuid::new_v4()),
-        user_id: Set(current_user.id),
+        user_id: Set(access.user.id),
+        workspace_id: Set(access.workspace_id),
         vendor: Set(payload.vendor),
         description: Set(payload.description),
-        amount: Set(payload.amount),
+        amount: Set(vat.gross),
+        net_amount: Set(vat.net),
+        vat_rate: Set(vat.rate),
+        vat_amount: Set(vat.vat),
+        input_tax_deductible: Set(payload.input_tax_deductible.unwrap_or(vat.vat.is_some())),
+        reverse_charge: Set(payload.reverse_charge),
         currency: Set(payload.currency),
         date: Set(payload.date),
-        category: Set(payload.category),
+        category: Set(category.as_ref().map(|category| category.name.clone())),
+        category_id: Set(category.map(|category| category.id)),
+        receipt_id: Set(receipt_id),
         receipt_url: Set(payload.receipt_url),
+        kind: Set(ExpenseKind::Receipt.as_str().to_string()),
+        trip_from: Set(None),
+        trip_to: Set(None),
+        distance_km: Set(None),
+        vehicle: Set(None),
+        rate_per_km: Set(None),
+        country: Set(None),
+        departure_at: Set(None),
+        return_at: Set(None),
+        full_days: Set(None),
+        partial_days: Set(None),
+        breakfasts_provided: Set(None),
+        lunches_provided: Set(None),
+        dinners_provided: Set(None),
         created_at: Set(chrono::Utc::now()),
     };
 
     let saved = active
         .insert(&state.db)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-
-    Ok(Json(ExpenseResponse {
-        id: saved.id,
-        vendor: saved.vendor,
-        description: saved.description,
-        amount: saved.amount,
-        currency: saved.currency,
-        date: saved.date,
-        category: saved.category,
-        receipt_url: saved.receipt_url,
-    }))
+        .map_err(AppError::internal)?;
+
+    Ok(Json(ExpenseResponse::from(saved)))
 }
 
 #[utoipa::path(
@@ -162,10 +400,12 @@ pub async fn create_expense(
     request_body = ExpenseUpdateRequest,
     responses(
         (status = 200, description = "Expense updated", body = ExpenseResponse),
-        (status = 400, description = "Invalid id"),
-        (status = 401, description = "Not authenticated"),
-        (status = 404, description = "Expense not found"),
-        (status = 500, description = "Server error")
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 404, description = "Expense not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
     ),
     tag = "expenses"
 )]
@@ -173,17 +413,42 @@ pub async fn update_expense(
     State(state): State<AppState>,
     headers: HeaderMap,
     Path(id): Path<String>,
-    Json(payload): Json<ExpenseUpdateRequest>,
-) -> Result<Json<ExpenseResponse>, (StatusCode, String)> {
-    let current_user = require_user(&state, &headers).await?;
+    ValidatedJson(payload): ValidatedJson<ExpenseUpdateRequest>,
+) -> Result<Json<ExpenseResponse>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::ExpensesWrite).await?;
     let id = Uuid::parse_str(&id)
-        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid id".to_string()))?;
+        .map_err(|_| AppError::bad_request("Invalid id"))?;
     let existing = expense::Entity::find_by_id(id)
-        .filter(expense::Column::UserId.eq(current_user.id))
+        .filter(expense::Column::WorkspaceId.eq(access.workspace_id))
         .one(&state.db)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-        .ok_or_else(|| (StatusCode::NOT_FOUND, "Expense not found".to_string()))?;
+        .map_err(AppError::internal)?
+        .ok_or_else(|| AppError::not_found("Expense not found"))?;
+
+    // Mileage and per diem amounts follow from the trip; change those through their own
+    // endpoints so the amount is recomputed.
+    if existing.kind != ExpenseKind::Receipt.as_str() {
+        let computed = [
+            ("amount", payload.amount.is_some()),
+            ("net_amount", payload.net_amount.is_some()),
+            ("vat_rate", payload.vat_rate.is_some()),
+            ("reverse_charge", payload.reverse_charge.is_some()),
+            ("currency", payload.currency.is_some()),
+            ("date", payload.date.is_some()),
+        ];
+        let errors: Vec<FieldError> = computed
+            .into_iter()
+            .filter(|(_, given)| *given)
+            .map(|(field, _)| {
+                FieldError::new(field, "computed", "is computed from the trip of this expense")
+            })
+            .collect();
+        if !errors.is_empty() {
+            return Err(AppError::Validation(errors));
+        }
+    }
+
+    let vat = updated_vat(&existing, &payload)?;
 
     let mut active: expense::ActiveModel = existing.into();
     if let Some(vendor) = payload.vendor {
@@ -192,8 +457,15 @@ pub async fn update_expense(
     if let Some(description) = payload.description {
         active.description = Set(description);
     }
-    if let Some(amount) = payload.amount {
-        active.amount = Set(amount);
+    if let Some((vat, reverse_charge)) = vat {
+        active.amount = Set(vat.gross);
+        active.net_amount = Set(vat.net);
+        active.vat_rate = Set(vat.rate);
+        active.vat_amount = Set(vat.vat);
+        active.reverse_charge = Set(reverse_charge);
+    }
+    if let Some(input_tax_deductible) = payload.input_tax_deductible {
+        active.input_tax_deductible = Set(input_tax_deductible);
     }
     if let Some(currency) = payload.currency {
         active.currency = Set(currency);
@@ -201,8 +473,25 @@ pub async fn update_expense(
     if let Some(date) = payload.date {
         active.date = Set(date);
     }
-    if let Some(category) = payload.category {
-        active.category = Set(Some(category));
+    if payload.category_id.is_some() || payload.category.is_some() {
+        let category = resolve_category(
+            &state.db,
+            access.workspace_id,
+            payload.category_id,
+            payload.category.as_deref(),
+        )
+        .await?;
+        active.category = Set(category.as_ref().map(|category| category.name.clone()));
+        active.category_id = Set(category.map(|category| category.id));
+    }
+    let receipt_id = match payload.receipt_id {
+        Some(receipt_id) => Some(receipt_id),
+        None => uploaded_receipt_id(&state.db, access.workspace_id, payload.receipt_url.as_deref())
+            .await?,
+    };
+    if let Some(receipt_id) = receipt_id {
+        require_receipt(&state.db, access.workspace_id, receipt_id).await?;
+        active.receipt_id = Set(Some(receipt_id));
     }
     if let Some(receipt_url) = payload.receipt_url {
         active.receipt_url = Set(Some(receipt_url));
@@ -211,28 +500,20 @@ pub async fn update_expense(
     let updated = active
         .update(&state.db)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-
-    Ok(Json(ExpenseResponse {
-        id: updated.id,
-        vendor: updated.vendor,
-        description: updated.description,
-        amount: updated.amount,
-        currency: updated.currency,
-        date: updated.date,
-        category: updated.category,
-        receipt_url: updated.receipt_url,
-    }))
+        .map_err(AppError::internal)?;
+
+    Ok(Json(ExpenseResponse::from(updated)))
 }
 
 #[utoipa::path(
     delete,
     path = "/expenses/{id}",
     responses(
-        (status = 204, description = "Expense deleted"),
-        (status = 401, description = "Not authenticated"),
-        (status = 404, description = "Expense not found"),
-        (status = 500, description = "Server error")
+        (status = 204, description = "Expense deleted along with a receipt no other expense uses"),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 404, description = "Expense not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
     ),
     tag = "expenses"
 )]
@@ -240,21 +521,43 @@ pub async fn delete_expense(
     State(state): State<AppState>,
     headers: HeaderMap,
     Path(id): Path<String>,
-) -> Result<StatusCode, (StatusCode, String)> {
-    let current_user = require_user(&state, &headers).await?;
+) -> Result<StatusCode, AppError> {
+    let access = require_access(&state, &headers, ApiScope::ExpensesWrite).await?;
     let id = Uuid::parse_str(&id)
-        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid id".to_string()))?;
+        .map_err(|_| AppError::bad_request("Invalid id"))?;
     let existing = expense::Entity::find_by_id(id)
-        .filter(expense::Column::UserId.eq(current_user.id))
+        .filter(expense::Column::WorkspaceId.eq(access.workspace_id))
         .one(&state.db)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-        .ok_or_else(|| (StatusCode::NOT_FOUND, "Expense not found".to_string()))?;
+        .map_err(AppError::internal)?
+        .ok_or_else(|| AppError::not_found("Expense not found"))?;
 
     expense::Entity::delete_by_id(existing.id)
         .exec(&state.db)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(AppError::internal)?;
+
+    if let Some(receipt_id) = existing.receipt_id {
+        delete_receipt_if_unused(&state.db, state.receipts.as_ref(), receipt_id).await?;
+    }
+    // Direct uploads from before receipts were tracked are only known by their URL.
+    if let Some(receipt_url) = existing.receipt_url.as_deref()
+        && let Some(key) = legacy_storage_key(receipt_url)
+    {
+        let in_use = expense::Entity::find()
+            .filter(expense::Column::ReceiptUrl.eq(receipt_url))
+            .one(&state.db)
+            .await?
+            .is_some();
+        let tracked = receipt::Entity::find()
+            .filter(receipt::Column::StorageKey.eq(key.as_str()))
+            .one(&state.db)
+            .await?
+            .is_some();
+        if !in_use && !tracked {
+            delete_stored_receipts(state.receipts.as_ref(), [key]).await;
+        }
+    }
 
     Ok(StatusCode::NO_CONTENT)
 }
@@ -264,83 +567,383 @@ pub async fn delete_expense(
     path = "/expenses/receipt-url",
     request_body = ReceiptUploadRequest,
     responses(
-        (status = 200, description = "Presigned upload URL", body = ReceiptUploadResponse),
-        (status = 400, description = "Invalid input"),
-        (status = 401, description = "Not authenticated"),
-        (status = 500, description = "Server error")
+        (status = 200, description = "Pending receipt with a presigned upload URL", body = ReceiptUploadResponse),
+        (status = 400, description = "Receipt storage does not support direct uploads", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 415, description = "Not a PDF, PNG, JPEG or WebP content type", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
     ),
     tag = "expenses"
 )]
 pub async fn create_receipt_upload_url(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Json(payload): Json<ReceiptUploadRequest>,
-) -> Result<Json<ReceiptUploadResponse>, (StatusCode, String)> {
-    let current_user = require_user(&state, &headers).await?;
-    if payload.filename.trim().is_empty() {
-        return Err((StatusCode::BAD_REQUEST, "Filename is required".to_string()));
-    }
-    if payload.content_type.trim().is_empty() {
-        return Err((StatusCode::BAD_REQUEST, "Content type is required".to_string()));
-    }
-
-    let bucket = std::env::var("R2_BUCKET")
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "R2_BUCKET missing".to_string()))?;
-    let public_base = std::env::var("R2_PUBLIC_BASE_URL")
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "R2_PUBLIC_BASE_URL missing".to_string()))?;
-
-    let extension = payload
-        .filename
-        .rsplit('.')
-        .next()
-        .unwrap_or("bin")
-        .to_lowercase();
-    let key = format!(
-        "receipts/{}/{}.{}",
-        current_user.id,
-        Uuid::new_v4(),
-        extension
-    );
-
-    let client = build_s3_client().await?;
-    let presigned = client
-        .put_object()
-        .bucket(&bucket)
-        .key(&key)
-        .content_type(payload.content_type)
-        .presigned(PresigningConfig::expires_in(Duration::from_secs(600)).map_err(|e| {
-            (StatusCode::INTERNAL_SERVER_ERROR, format!("Invalid expiry: {}", e))
-        })?)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    ValidatedJson(payload): ValidatedJson<ReceiptUploadRequest>,
+) -> Result<Json<ReceiptUploadResponse>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::ExpensesWrite).await?;
+
+    let content_type = payload.content_type.trim().to_lowercase();
+    let extension = receipt_extension(&content_type).ok_or_else(|| {
+        AppError::unsupported_media_type("Receipts must be PDF, PNG, JPEG or WebP files")
+    })?;
+    let id = Uuid::new_v4();
+    let key = storage_key(access.workspace_id, id, extension);
+
+    let upload_url = state
+        .receipts
+        .presigned_put(&key, &content_type, Duration::from_secs(600))
+        .await?
+        .ok_or_else(|| {
+            AppError::bad_request("Direct uploads need S3 storage; use POST /receipts instead")
+        })?;
+
+    // Tracked as pending until confirmed; the sweeper removes uploads that never are.
+    let pending = receipt::ActiveModel {
+        id: Set(id),
+        workspace_id: Set(access.workspace_id),
+        user_id: Set(access.user.id),
+        storage_key: Set(key),
+        filename: Set(safe_filename(&payload.filename)),
+        content_type: Set(content_type),
+        size_bytes: Set(None),
+        sha256: Set(None),
+        confirmed_at: Set(None),
+        created_at: Set(Utc::now()),
+    }
+    .insert(&state.db)
+    .await?;
+    let receipt = receipt_response(state.receipts.as_ref(), pending).await?;
 
     Ok(Json(ReceiptUploadResponse {
-        upload_url: presigned.uri().to_string(),
-        receipt_url: format!("{}/{}", public_base.trim_end_matches('/'), key),
+        receipt_id: id,
+        upload_url,
+        download_url: receipt.download_url,
+        download_url_expires_at: receipt.download_url_expires_at,
     }))
 }
 
-async fn build_s3_client() -> Result<Client, (StatusCode, String)> {
-    let endpoint = std::env::var("R2_ENDPOINT")
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "R2_ENDPOINT missing".to_string()))?;
-    let access_key = std::env::var("R2_ACCESS_KEY_ID")
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "R2_ACCESS_KEY_ID missing".to_string()))?;
-    let secret_key = std::env::var("R2_SECRET_ACCESS_KEY").map_err(|_| {
-        (StatusCode::INTERNAL_SERVER_ERROR, "R2_SECRET_ACCESS_KEY missing".to_string())
-    })?;
-    let region = std::env::var("R2_REGION").unwrap_or_else(|_| "auto".to_string());
-
-    let config = aws_sdk_s3::config::Builder::new()
-        .credentials_provider(Credentials::new(
-            access_key,
-            secret_key,
-            None,
-            None,
-            "r2",
-        ))
-        .region(Region::new(region))
-        .endpoint_url(endpoint)
-        .build();
-
-    Ok(Client::from_conf(config))
+/// The storage key behind a legacy public `receipt_url` from before direct uploads returned
+/// signed links, or `None` for URLs outside `R2_PUBLIC_BASE_URL` or when it is unset.
+fn legacy_storage_key(receipt_url: &str) -> Option<String> {
+    let public_base = std::env::var("R2_PUBLIC_BASE_URL").ok()?;
+    receipt_url
+        .strip_prefix(public_base.trim_end_matches('/'))
+        .and_then(|rest| rest.strip_prefix('/'))
+        .map(str::to_string)
+}
+
+/// The tracked receipt behind a legacy public `receipt_url`, so expenses
+/// that only send the URL still keep their upload from being swept.
+async fn uploaded_receipt_id<C: ConnectionTrait>(
+    db: &C,
+    workspace_id: Uuid,
+    receipt_url: Option<&str>,
+) -> Result<Option<Uuid>, AppError> {
+    let Some(key) = receipt_url.and_then(legacy_storage_key) else {
+        return Ok(None);
+    };
+    Ok(receipt::Entity::find()
+        .filter(receipt::Column::WorkspaceId.eq(workspace_id))
+        .filter(receipt::Column::StorageKey.eq(key))
+        .one(db)
+        .await?
+        .map(|receipt| receipt.id))
+}
+
+/// Downloads the receipt behind a legacy public `receipt_url`, returning its storage
+/// key and contents. URLs outside `R2_PUBLIC_BASE_URL` are not ours and yield `None`.
+pub(crate) async fn fetch_receipt(
+    storage: &dyn ReceiptStorage,
+    receipt_url: &str,
+) -> Result<Option<(String, Vec<u8>)>, AppError> {
+    let Some(key) = legacy_storage_key(receipt_url) else {
+        return Ok(None);
+    };
+    Ok(storage.get(&key).await?.map(|body| (key, body)))
+}
+
+/// Gross, net, rate and VAT amount of an expense with the missing values filled in.
+#[derive(Debug, PartialEq)]
+struct VatBreakdown {
+    gross: f64,
+    net: Option<f64>,
+    rate: Option<f64>,
+    vat: Option<f64>,
+}
+
+/// VAT figures after an update, or `None` when the update leaves them alone. A single changed
+/// value is combined with the stored rate, or else the stored gross.
+fn updated_vat(
+    existing: &expense::Model,
+    payload: &ExpenseUpdateRequest,
+) -> Result<Option<(VatBreakdown, bool)>, AppError> {
+    let vat_changed = payload.amount.is_some()
+        || payload.net_amount.is_some()
+        || payload.vat_rate.is_some()
+        || payload.reverse_charge.is_some();
+    if !vat_changed {
+        return Ok(None);
+    }
+    let mut gross = payload.amount;
+    let net = payload.net_amount;
+    let mut rate = payload.vat_rate;
+    if [gross, net, rate].iter().flatten().count() < 2 {
+        rate = rate.or(existing.vat_rate);
+    }
+    if [gross, net, rate].iter().flatten().count() < 2 {
+        gross = gross.or(Some(existing.amount));
+    }
+    let reverse_charge = payload.reverse_charge.unwrap_or(existing.reverse_charge);
+    Ok(Some((vat_breakdown(gross, net, rate, reverse_charge)?, reverse_charge)))
+}
+
+/// Completes the VAT figures from any two of gross, net and rate. Gross alone means VAT is
+/// not tracked. Under reverse charge the vendor invoices the net amount and the recipient
+/// self-assesses VAT at `rate`, so gross equals net.
+fn vat_breakdown(
+    gross: Option<f64>,
+    net: Option<f64>,
+    rate: Option<f64>,
+    reverse_charge: bool,
+) -> Result<VatBreakdown, AppError> {
+    let invalid = |field: &str, code: &str, message: &str| {
+        AppError::Validation(vec![FieldError::new(field, code, message)])
+    };
+
+    if reverse_charge {
+        let amount = match (gross, net) {
+            (Some(gross), Some(net)) if (gross - net).abs() > 0.005 => {
+                return Err(invalid(
+                    "net_amount",
+                    "vat_mismatch",
+                    "Reverse charge expenses carry no VAT, so net must equal the amount",
+                ));
+            }
+            (Some(amount), _) | (None, Some(amount)) => amount,
+            (None, None) => return Err(invalid("amount", "required", "Amount is required")),
+        };
+        let rate = rate.ok_or_else(|| {
+            invalid("vat_rate", "required", "Reverse charge expenses need the VAT rate")
+        })?;
+        return Ok(VatBreakdown {
+            gross: amount,
+            net: Some(amount),
+            rate: Some(rate),
+            vat: Some(round_cents(amount * rate / 100.0)),
+        });
+    }
+
+    let (gross, net, rate) = match (gross, net, rate) {
+        (Some(gross), Some(net), Some(rate)) => {
+            // Compare whole cents so a one-cent rounding difference is not lost to float error.
+            if ((net * (1.0 + rate / 100.0) - gross) * 100.0).round().abs() > 1.0 {
+                return Err(invalid(
+                    "amount",
+                    "vat_mismatch",
+                    "Amount does not match net amount plus VAT",
+                ));
+            }
+            (gross, net, rate)
+        }
+        (Some(gross), Some(net), None) => {
+            if net > gross {
+                return Err(invalid(
+                    "net_amount",
+                    "vat_mismatch",
+                    "Net amount cannot exceed the amount",
+                ));
+            }
+            (gross, net, round_cents((gross - net) / net * 100.0))
+        }
+        (Some(gross), None, Some(rate)) => {
+            (gross, round_cents(gross / (1.0 + rate / 100.0)), rate)
+        }
+        (None, Some(net), Some(rate)) => (round_cents(net * (1.0 + rate / 100.0)), net, rate),
+        (Some(gross), None, None) => {
+            return Ok(VatBreakdown { gross, net: None, rate: None, vat: None });
+        }
+        _ => {
+            return Err(invalid(
+                "amount",
+                "required",
+                "Give the amount, or two of amount, net amount and VAT rate",
+            ));
+        }
+    };
+    Ok(VatBreakdown {
+        gross,
+        net: Some(net),
+        rate: Some(rate),
+        vat: Some(round_cents(gross - net)),
+    })
+}
+
+fn round_cents(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn breakdown(gross: f64, net: Option<f64>, rate: Option<f64>, vat: Option<f64>) -> VatBreakdown {
+        VatBreakdown { gross, net, rate, vat }
+    }
+
+    fn error_field(result: Result<VatBreakdown, AppError>) -> (String, String) {
+        match result {
+            Err(AppError::Validation(errors)) => {
+                (errors[0].field.to_string(), errors[0].code.to_string())
+            }
+            Err(error) => panic!("unexpected error {error}"),
+            Ok(vat) => panic!("expected a validation error, got {vat:?}"),
+        }
+    }
+
+    #[test]
+    fn completes_any_two_of_gross_net_and_rate() {
+        let cases = [
+            ((Some(119.0), None, Some(19.0)), breakdown(119.0, Some(100.0), Some(19.0), Some(19.0))),
+            ((None, Some(100.0), Some(7.0)), breakdown(107.0, Some(100.0), Some(7.0), Some(7.0))),
+            ((Some(119.0), Some(100.0), None), breakdown(119.0, Some(100.0), Some(19.0), Some(19.0))),
+            ((Some(119.0), Some(100.0), Some(19.0)), breakdown(119.0, Some(100.0), Some(19.0), Some(19.0))),
+            ((Some(10.0), None, Some(19.0)), breakdown(10.0, Some(8.4), Some(19.0), Some(1.6))),
+            ((Some(50.0), None, Some(0.0)), breakdown(50.0, Some(50.0), Some(0.0), Some(0.0))),
+            ((Some(50.0), None, None), breakdown(50.0, None, None, None)),
+        ];
+        for ((gross, net, rate), expected) in cases {
+            let vat = vat_breakdown(gross, net, rate, false).ok().unwrap();
+            assert_eq!(vat, expected, "gross {gross:?}, net {net:?}, rate {rate:?}");
+        }
+    }
+
+    #[test]
+    fn tolerates_a_cent_of_rounding_between_all_three() {
+        assert!(vat_breakdown(Some(119.01), Some(100.0), Some(19.0), false).is_ok());
+        assert!(vat_breakdown(Some(118.99), Some(100.0), Some(19.0), false).is_ok());
+        assert_eq!(
+            error_field(vat_breakdown(Some(119.05), Some(100.0), Some(19.0), false)),
+            ("amount".to_string(), "vat_mismatch".to_string()),
+        );
+    }
+
+    #[test]
+    fn rejects_inconsistent_or_missing_amounts() {
+        assert_eq!(
+            error_field(vat_breakdown(Some(100.0), Some(119.0), None, false)),
+            ("net_amount".to_string(), "vat_mismatch".to_string()),
+        );
+        for (gross, net, rate) in [(None, None, None), (None, Some(100.0), None), (None, None, Some(19.0))] {
+            assert_eq!(
+                error_field(vat_breakdown(gross, net, rate, false)),
+                ("amount".to_string(), "required".to_string()),
+            );
+        }
+    }
+
+    #[test]
+    fn reverse_charge_keeps_gross_equal_to_net() {
+        let expected = breakdown(100.0, Some(100.0), Some(19.0), Some(19.0));
+        for (gross, net) in [(Some(100.0), None), (None, Some(100.0)), (Some(100.0), Some(100.0))] {
+            assert_eq!(vat_breakdown(gross, net, Some(19.0), true).ok().unwrap(), expected);
+        }
+        assert_eq!(
+            error_field(vat_breakdown(Some(100.0), Some(84.03), Some(19.0), true)),
+            ("net_amount".to_string(), "vat_mismatch".to_string()),
+        );
+        assert_eq!(
+            error_field(vat_breakdown(Some(100.0), None, None, true)),
+            ("vat_rate".to_string(), "required".to_string()),
+        );
+        assert_eq!(
+            error_field(vat_breakdown(None, None, Some(19.0), true)),
+            ("amount".to_string(), "required".to_string()),
+        );
+    }
+
+    fn stored(amount: f64, net: Option<f64>, rate: Option<f64>) -> expense::Model {
+        expense::Model {
+            id: Uuid::new_v4(),
+            user_id: Uuid::nil(),
+            workspace_id: Uuid::nil(),
+            vendor: "Office Supply GmbH".to_string(),
+            description: String::new(),
+            amount,
+            net_amount: net,
+            vat_rate: rate,
+            vat_amount: net.map(|net| round_cents(amount - net)),
+            input_tax_deductible: true,
+            reverse_charge: false,
+            currency: "EUR".to_string(),
+            date: NaiveDate::from_ymd_opt(2026, 3, 1).unwrap(),
+            category: None,
+            category_id: None,
+            receipt_id: None,
+            receipt_url: None,
+            kind: ExpenseKind::Receipt.as_str().to_string(),
+            trip_from: None,
+            trip_to: None,
+            distance_km: None,
+            vehicle: None,
+            rate_per_km: None,
+            country: None,
+            departure_at: None,
+            return_at: None,
+            full_days: None,
+            partial_days: None,
+            breakfasts_provided: None,
+            lunches_provided: None,
+            dinners_provided: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    fn update(existing: &expense::Model, payload: serde_json::Value) -> Option<(VatBreakdown, bool)> {
+        let payload: ExpenseUpdateRequest = serde_json::from_value(payload).unwrap();
+        updated_vat(existing, &payload).ok().unwrap()
+    }
+
+    #[test]
+    fn single_field_updates_keep_the_stored_rate() {
+        let existing = stored(119.0, Some(100.0), Some(19.0));
+        let cases = [
+            (json!({ "amount": 238.0 }), breakdown(238.0, Some(200.0), Some(19.0), Some(38.0))),
+            (json!({ "net_amount": 50.0 }), breakdown(59.5, Some(50.0), Some(19.0), Some(9.5))),
+            // A new rate keeps the gross that was paid.
+            (json!({ "vat_rate": 7.0 }), breakdown(119.0, Some(111.21), Some(7.0), Some(7.79))),
+            (
+                json!({ "amount": 107.0, "vat_rate": 7.0 }),
+                breakdown(107.0, Some(100.0), Some(7.0), Some(7.0)),
+            ),
+        ];
+        for (payload, expected) in cases {
+            let (vat, reverse_charge) = update(&existing, payload.clone()).unwrap();
+            assert_eq!(vat, expected, "{payload}");
+            assert!(!reverse_charge);
+        }
+    }
+
+    #[test]
+    fn updates_without_a_stored_rate_stay_untracked() {
+        let existing = stored(50.0, None, None);
+        let (vat, _) = update(&existing, json!({ "amount": 60.0 })).unwrap();
+        assert_eq!(vat, breakdown(60.0, None, None, None));
+    }
+
+    #[test]
+    fn switching_to_reverse_charge_recomputes_from_the_stored_amount() {
+        let existing = stored(119.0, Some(100.0), Some(19.0));
+        let (vat, reverse_charge) = update(&existing, json!({ "reverse_charge": true })).unwrap();
+        assert_eq!(vat, breakdown(119.0, Some(119.0), Some(19.0), Some(22.61)));
+        assert!(reverse_charge);
+    }
+
+    #[test]
+    fn other_updates_leave_vat_alone() {
+        let existing = stored(119.0, Some(100.0), Some(19.0));
+        assert!(update(&existing, json!({ "vendor": "Paper AG", "date": "2026-03-02" })).is_none());
+    }
 }
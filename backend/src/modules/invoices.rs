@@ -1,40 +1,97 @@
-use crate::entity::{company, invoice, invoice_line_item, invoice_template};
-use crate::modules::auth::require_user;
-use crate::modules::shared::AppState;
+use crate::entity::{
+    company, company_contact, invoice, invoice_delivery, invoice_line_item, invoice_reminder,
+    invoice_template, user,
+};
+use crate::modules::api_tokens::ApiScope;
+use crate::modules::auth::require_access;
+use crate::modules::company::{find_contact, invoice_recipients, ContactResponse};
+use crate::modules::dunning::ReminderResponse;
+use crate::modules::mail::{render_email, MailAttachment, OutgoingMail, INVOICE_EMAIL};
+use crate::modules::validation::{
+    currency_code, email_list, field_error, non_negative, not_blank, positive, Path, Query,
+    ValidatedJson,
+};
+use crate::modules::shared::{
+    fetch_page, search_pattern, AppError, AppState, Page, PageParams, SortOrder,
+};
 use axum::{
-    extract::{Path, State},
+    extract::State,
     http::{HeaderMap, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
-use chrono::NaiveDate;
+use chrono::{DateTime, NaiveDate, Utc};
+use sea_orm::sea_query::{extension::postgres::PgExpr, Expr};
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QuerySelect, Set,
-    TransactionTrait,
+    ActiveModelTrait, ColumnTrait, Condition, EntityTrait, PaginatorTrait, QueryFilter,
+    QueryOrder, QuerySelect, QueryTrait, Select, Set, TransactionTrait,
 };
 use serde::{Deserialize, Serialize};
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
+use validator::{Validate, ValidationError};
 use uuid::Uuid;
+use std::collections::HashMap;
 use std::io::Write;
 use std::process::{Command, Stdio};
 use handlebars::{Context, Handlebars, Helper, HelperResult, Output, RenderContext};
 use serde_json::json;
 
-#[derive(Deserialize, ToSchema)]
+const DEFAULT_PAYMENT_TERM_DAYS: i64 = 14;
+const INVOICE_STATUSES: &[&str] = &["draft", "sent", "paid", "cancelled"];
+
+fn invoice_status(status: &str) -> Result<(), ValidationError> {
+    if !INVOICE_STATUSES.contains(&status) {
+        return Err(ValidationError::new("invalid_status")
+            .with_message("must be one of draft, sent, paid or cancelled".into()));
+    }
+    Ok(())
+}
+
+fn validate_new_invoice_dates(invoice: &NewInvoice) -> Result<(), ValidationError> {
+    if invoice.due_date.is_some_and(|due_date| due_date < invoice.date) {
+        return Err(field_error(
+            "due_date",
+            "before_invoice_date",
+            "must not be before the invoice date",
+        ));
+    }
+    Ok(())
+}
+
+fn override_or(value: Option<String>, fallback: &str) -> String {
+    value
+        .filter(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| fallback.to_string())
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+#[validate(schema(function = validate_new_invoice_dates, skip_on_field_errors = false))]
 pub struct NewInvoice {
     pub company_id: Uuid,
     pub template_id: Option<Uuid>,
-    pub client_name: String,
-    pub client_address: String,
+    pub contact_id: Option<Uuid>,
+    /// Defaults to the company's name when absent or blank.
+    #[validate(length(max = 200))]
+    pub client_name: Option<String>,
+    /// Defaults to the company's address when absent or blank.
+    #[validate(length(max = 1000))]
+    pub client_address: Option<String>,
+    #[validate(custom(function = currency_code))]
     pub currency: String,
     pub date: NaiveDate,
+    /// Defaults to 14 days after `date`.
+    pub due_date: Option<NaiveDate>,
+    #[validate(length(min = 1, message = "at least one line item is required"), nested)]
     pub items: Vec<LineItemInput>,
 }
 
-#[derive(Deserialize, ToSchema)]
+#[derive(Serialize, Deserialize, ToSchema, Validate)]
 pub struct LineItemInput {
+    #[validate(custom(function = not_blank), length(max = 1000))]
     pub description: String,
+    #[validate(custom(function = positive))]
     pub quantity: f64,
+    #[validate(custom(function = non_negative))]
     pub unit_price: f64,
     pub use_quantity: Option<bool>,
 }
@@ -56,6 +113,7 @@ pub struct InvoiceResponse {
     pub company_id: Option<Uuid>,
     pub user_id: Option<Uuid>,
     pub template_id: Option<Uuid>,
+    pub contact_id: Option<Uuid>,
     pub client_name: String,
     pub client_address: String,
     pub description: String,
@@ -64,25 +122,169 @@ pub struct InvoiceResponse {
     pub user_address: String,
     pub total_amount: f64,
     pub date: NaiveDate,
-    pub items: Vec<LineItemResponse>,
+    pub due_date: Option<NaiveDate>,
+    pub status: String,
+    pub sent_at: Option<DateTime<Utc>>,
+    /// Left out of list responses requested with `include_items=false`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub items: Option<Vec<LineItemResponse>>,
+    /// Only populated by `GET /invoices/{id}`.
+    pub reminders: Option<Vec<ReminderResponse>>,
 }
 
-#[derive(Deserialize, ToSchema)]
+impl InvoiceResponse {
+    /// Response for list views, which leave out reminders.
+    pub(crate) fn with_items(invoice: invoice::Model, items: Option<Vec<LineItemResponse>>) -> Self {
+        Self {
+            id: invoice.id,
+            invoice_number: invoice.invoice_number,
+            company_id: invoice.company_id,
+            user_id: invoice.user_id,
+            template_id: invoice.template_id,
+            contact_id: invoice.contact_id,
+            client_name: invoice.client_name,
+            client_address: invoice.client_address,
+            description: invoice.description,
+            amount: invoice.amount,
+            currency: invoice.currency,
+            user_address: invoice.user_address,
+            total_amount: invoice.total_amount,
+            date: invoice.date,
+            due_date: invoice.due_date,
+            status: invoice.status,
+            sent_at: invoice.sent_at,
+            items,
+            reminders: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum InvoiceSort {
+    #[default]
+    Date,
+    DueDate,
+    InvoiceNumber,
+    ClientName,
+    TotalAmount,
+    Status,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct InvoiceListQuery {
+    /// Only invoices dated on or after this day
+    pub date_from: Option<NaiveDate>,
+    /// Only invoices dated on or before this day
+    pub date_to: Option<NaiveDate>,
+    /// Only invoices for this client company
+    pub company_id: Option<Uuid>,
+    /// draft, sent, paid or cancelled
+    pub status: Option<String>,
+    pub currency: Option<String>,
+    /// Minimum total amount (inclusive)
+    pub min_amount: Option<f64>,
+    /// Maximum total amount (inclusive)
+    pub max_amount: Option<f64>,
+    /// Searches invoice number, client name and line item descriptions
+    pub q: Option<String>,
+    /// Set to false to return invoices without their `items` (default true)
+    pub include_items: Option<bool>,
+    /// Sort field (default date)
+    #[param(inline)]
+    pub sort: Option<InvoiceSort>,
+    /// Sort direction (default desc)
+    #[param(inline)]
+    pub order: Option<SortOrder>,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
 pub struct UpdateInvoiceRequest {
     pub company_id: Option<Uuid>,
     pub template_id: Option<Uuid>,
+    pub contact_id: Option<Uuid>,
+    #[validate(length(max = 200))]
     pub client_name: Option<String>,
+    #[validate(length(max = 1000))]
     pub client_address: Option<String>,
+    #[validate(length(max = 1000))]
     pub description: Option<String>,
+    #[validate(custom(function = non_negative))]
     pub amount: Option<f64>,
+    #[validate(custom(function = currency_code))]
     pub currency: Option<String>,
     pub date: Option<NaiveDate>,
+    pub due_date: Option<NaiveDate>,
+    /// One of `draft`, `sent`, `paid` or `cancelled`.
+    #[validate(custom(function = invoice_status))]
+    pub status: Option<String>,
+    #[validate(length(min = 1, message = "at least one line item is required"), nested)]
     pub items: Option<Vec<LineItemInput>>,
 }
 
-#[derive(Deserialize, ToSchema)]
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct SendInvoiceRequest {
+    /// Overrides the default recipients (addressee or billing contacts).
+    #[validate(custom(function = email_list))]
+    pub to: Option<Vec<String>>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DeliveryResponse {
+    pub id: Uuid,
+    pub recipients: Vec<String>,
+    pub subject: String,
+    /// `sent`, `failed`, or `logged` when the mail transport only prints messages.
+    pub status: String,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<invoice_delivery::Model> for DeliveryResponse {
+    fn from(delivery: invoice_delivery::Model) -> Self {
+        Self {
+            id: delivery.id,
+            recipients: delivery
+                .recipients
+                .split(',')
+                .filter(|r| !r.is_empty())
+                .map(|r| r.to_string())
+                .collect(),
+            subject: delivery.subject,
+            status: delivery.status,
+            error: delivery.error,
+            created_at: delivery.created_at,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TemplateSort {
+    #[default]
+    Name,
+    CreatedAt,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TemplateListQuery {
+    /// Searches template names
+    pub q: Option<String>,
+    /// Sort field (default name)
+    #[param(inline)]
+    pub sort: Option<TemplateSort>,
+    /// Sort direction (default asc)
+    #[param(inline)]
+    pub order: Option<SortOrder>,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
 pub struct TemplateCreateRequest {
+    #[validate(custom(function = not_blank), length(max = 200))]
     pub name: String,
+    #[validate(custom(function = not_blank))]
     pub html: String,
 }
 
@@ -99,30 +301,36 @@ pub struct TemplateResponse {
     request_body = NewInvoice,
     responses(
         (status = 200, description = "Invoice created", body = InvoiceResponse),
-        (status = 401, description = "Not authenticated"),
-        (status = 500, description = "Server error")
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
     ),
     tag = "invoices"
 )]
 pub async fn create_invoice(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Json(payload): Json<NewInvoice>,
-) -> Result<Json<InvoiceResponse>, (axum::http::StatusCode, String)> {
-    let user = require_user(&state, &headers).await?;
-    let user_address = user
+    ValidatedJson(payload): ValidatedJson<NewInvoice>,
+) -> Result<Json<InvoiceResponse>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::InvoicesWrite).await?;
+    let user_address = access
+        .user
         .address
-        .ok_or_else(|| (axum::http::StatusCode::BAD_REQUEST, "User address is required".to_string()))?;
-    if payload.items.is_empty() {
-        return Err((StatusCode::BAD_REQUEST, "At least one line item is required".to_string()));
-    }
+        .ok_or_else(|| AppError::bad_request("User address is required"))?;
 
     let company = company::Entity::find_by_id(payload.company_id)
-        .filter(company::Column::UserId.eq(user.id))
+        .filter(company::Column::WorkspaceId.eq(access.workspace_id))
         .one(&state.db)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-        .ok_or_else(|| (StatusCode::BAD_REQUEST, "Invalid company".to_string()))?;
+        .map_err(AppError::internal)?
+        .ok_or_else(|| AppError::bad_request("Invalid company"))?;
+
+    if let Some(contact_id) = payload.contact_id {
+        find_contact(&state.db, company.id, contact_id)
+            .await?
+            .ok_or_else(|| AppError::bad_request("Invalid contact"))?;
+    }
 
     let total_amount = payload
         .items
@@ -138,7 +346,7 @@ pub async fn create_invoice(
 
     let description = payload
         .items
-        .get(0)
+        .first()
         .map(|item| item.description.clone())
         .unwrap_or_else(|| "Line items".to_string());
 
@@ -146,31 +354,40 @@ pub async fn create_invoice(
         .db
         .begin()
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(AppError::internal)?;
 
-    let template_id = resolve_template_id(&state.db, user.id, payload.template_id).await?;
+    let template_id = resolve_template_id(&state.db, access.workspace_id, payload.template_id).await?;
 
-    let invoice_number = next_invoice_number(&state.db, user.id).await?;
+    let invoice_number = next_invoice_number(&state.db, access.workspace_id).await?;
     let active = invoice::ActiveModel {
         id: Set(Uuid::new_v4()),
         invoice_number: Set(invoice_number),
-        user_id: Set(Some(user.id)),
+        user_id: Set(Some(access.user.id)),
+        workspace_id: Set(Some(access.workspace_id)),
         company_id: Set(Some(company.id)),
         template_id: Set(template_id),
-        client_name: Set(company.name.clone()),
-        client_address: Set(company.address.clone()),
+        contact_id: Set(payload.contact_id),
+        client_name: Set(override_or(payload.client_name, &company.name)),
+        client_address: Set(override_or(payload.client_address, &company.address)),
         description: Set(description),
         amount: Set(total_amount),
         currency: Set(payload.currency),
         user_address: Set(user_address.clone()),
         total_amount: Set(total_amount),
         date: Set(payload.date),
+        due_date: Set(Some(
+            payload
+                .due_date
+                .unwrap_or(payload.date + chrono::Duration::days(DEFAULT_PAYMENT_TERM_DAYS)),
+        )),
+        status: Set("draft".to_string()),
+        sent_at: Set(None),
     };
 
     let created = active
         .insert(&txn)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(AppError::internal)?;
 
     let mut items_response = Vec::with_capacity(payload.items.len());
     for item in payload.items {
@@ -192,7 +409,7 @@ pub async fn create_invoice(
         let saved = active_item
             .insert(&txn)
             .await
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+            .map_err(AppError::internal)?;
         items_response.push(LineItemResponse {
             id: saved.id,
             description: saved.description,
@@ -205,7 +422,7 @@ pub async fn create_invoice(
 
     txn.commit()
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(AppError::internal)?;
 
     Ok(Json(InvoiceResponse {
         id: created.id,
@@ -213,6 +430,7 @@ pub async fn create_invoice(
         company_id: created.company_id,
         user_id: created.user_id,
         template_id: created.template_id,
+        contact_id: created.contact_id,
         client_name: created.client_name,
         client_address: created.client_address,
         description: created.description,
@@ -221,53 +439,110 @@ pub async fn create_invoice(
         user_address: created.user_address,
         total_amount: created.total_amount,
         date: created.date,
-        items: items_response,
+        due_date: created.due_date,
+        status: created.status,
+        sent_at: created.sent_at,
+        items: Some(items_response),
+        reminders: None,
     }))
 }
 
 #[utoipa::path(
     get,
     path = "/invoices",
+    params(PageParams, InvoiceListQuery),
     responses(
-        (status = 200, description = "Invoice list", body = [InvoiceResponse]),
-        (status = 401, description = "Not authenticated"),
-        (status = 500, description = "Server error")
+        (status = 200, description = "Invoice page", body = InvoicePage),
+        (status = 400, description = "Invalid query", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
     ),
     tag = "invoices"
 )]
 pub async fn list_invoices(
     State(state): State<AppState>,
     headers: HeaderMap,
-) -> Result<Json<Vec<InvoiceResponse>>, (StatusCode, String)> {
-    let current_user = require_user(&state, &headers).await?;
-    let invoices = invoice::Entity::find()
-        .filter(invoice::Column::UserId.eq(current_user.id))
-        .all(&state.db)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Query(paging): Query<PageParams>,
+    Query(query): Query<InvoiceListQuery>,
+) -> Result<Json<Page<InvoiceResponse>>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::InvoicesRead).await?;
+    let mut select =
+        invoice::Entity::find().filter(invoice::Column::WorkspaceId.eq(access.workspace_id));
+
+    if let Some(date_from) = query.date_from {
+        select = select.filter(invoice::Column::Date.gte(date_from));
+    }
+    if let Some(date_to) = query.date_to {
+        select = select.filter(invoice::Column::Date.lte(date_to));
+    }
+    if let Some(company_id) = query.company_id {
+        select = select.filter(invoice::Column::CompanyId.eq(company_id));
+    }
+    if let Some(status) = query.status.as_deref() {
+        if !INVOICE_STATUSES.contains(&status) {
+            return Err(AppError::bad_request(format!("Unknown status {}", status)));
+        }
+        select = select.filter(invoice::Column::Status.eq(status));
+    }
+    if let Some(currency) = query.currency.as_deref() {
+        select = select.filter(invoice::Column::Currency.eq(currency.trim().to_uppercase()));
+    }
+    if let Some(min_amount) = query.min_amount {
+        select = select.filter(invoice::Column::TotalAmount.gte(min_amount));
+    }
+    if let Some(max_amount) = query.max_amount {
+        select = select.filter(invoice::Column::TotalAmount.lte(max_amount));
+    }
+    if let Some(pattern) = query.q.as_deref().and_then(search_pattern) {
+        let matching_items = invoice_line_item::Entity::find()
+            .select_only()
+            .column(invoice_line_item::Column::InvoiceId)
+            .filter(Expr::col(invoice_line_item::Column::Description).ilike(&pattern))
+            .into_query();
+        select = select.filter(
+            Condition::any()
+                .add(Expr::col(invoice::Column::InvoiceNumber).ilike(&pattern))
+                .add(Expr::col(invoice::Column::ClientName).ilike(&pattern))
+                .add(invoice::Column::Id.in_subquery(matching_items)),
+        );
+    }
 
-    let mut response = Vec::with_capacity(invoices.len());
-    for item in invoices {
-        let items = load_items(&state.db, item.id).await?;
-        response.push(InvoiceResponse {
-            id: item.id,
-            invoice_number: item.invoice_number,
-            company_id: item.company_id,
-            user_id: item.user_id,
-            template_id: item.template_id,
-            client_name: item.client_name,
-            client_address: item.client_address,
-            description: item.description,
-            amount: item.amount,
-            currency: item.currency,
-            user_address: item.user_address,
-            total_amount: item.total_amount,
-            date: item.date,
-            items,
-        });
+    let order = query.order.unwrap_or(SortOrder::Desc);
+    let column = match query.sort.unwrap_or_default() {
+        InvoiceSort::Date => invoice::Column::Date,
+        InvoiceSort::DueDate => invoice::Column::DueDate,
+        InvoiceSort::InvoiceNumber => invoice::Column::InvoiceNumber,
+        InvoiceSort::ClientName => invoice::Column::ClientName,
+        InvoiceSort::TotalAmount => invoice::Column::TotalAmount,
+        InvoiceSort::Status => invoice::Column::Status,
+    };
+    select = select
+        .order_by(column, order.into())
+        .order_by(invoice::Column::Id, order.into());
+
+    let page = invoice_page(&state.db, select, &paging, query.include_items.unwrap_or(true)).await?;
+    Ok(Json(page))
+}
+
+/// Fetches one page of invoices and, when requested, their line items in one extra query.
+async fn invoice_page(
+    db: &sea_orm::DatabaseConnection,
+    select: Select<invoice::Entity>,
+    paging: &PageParams,
+    include_items: bool,
+) -> Result<Page<InvoiceResponse>, AppError> {
+    let page = fetch_page(db, select, paging).await?;
+    if !include_items {
+        return Ok(page.map(|item| InvoiceResponse::with_items(item, None)));
     }
 
-    Ok(Json(response))
+    let ids: Vec<Uuid> = page.items.iter().map(|item| item.id).collect();
+    let mut items_by_invoice = load_items_for_invoices(db, &ids).await?;
+    Ok(page.map(|item| {
+        let items = items_by_invoice.remove(&item.id).unwrap_or_default();
+        InvoiceResponse::with_items(item, Some(items))
+    }))
 }
 
 #[utoipa::path(
@@ -278,10 +553,11 @@ pub async fn list_invoices(
     ),
     responses(
         (status = 200, description = "Invoice found", body = InvoiceResponse),
-        (status = 400, description = "Invalid id"),
-        (status = 401, description = "Not authenticated"),
-        (status = 404, description = "Invoice not found"),
-        (status = 500, description = "Server error")
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 404, description = "Invoice not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
     ),
     tag = "invoices"
 )]
@@ -289,26 +565,33 @@ pub async fn get_invoice(
     State(state): State<AppState>,
     headers: HeaderMap,
     Path(id): Path<String>,
-) -> Result<Json<InvoiceResponse>, (axum::http::StatusCode, String)> {
-    let current_user = require_user(&state, &headers).await?;
+) -> Result<Json<InvoiceResponse>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::InvoicesRead).await?;
     let id = Uuid::parse_str(&id)
-        .map_err(|_| (axum::http::StatusCode::BAD_REQUEST, "Invalid id".to_string()))?;
+        .map_err(|_| AppError::bad_request("Invalid id"))?;
 
     let invoice = invoice::Entity::find()
         .filter(invoice::Column::Id.eq(id))
-        .filter(invoice::Column::UserId.eq(current_user.id))
+        .filter(invoice::Column::WorkspaceId.eq(access.workspace_id))
         .one(&state.db)
         .await
-        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-        .ok_or_else(|| (axum::http::StatusCode::NOT_FOUND, "Invoice not found".to_string()))?;
+        .map_err(AppError::internal)?
+        .ok_or_else(|| AppError::not_found("Invoice not found"))?;
 
     let items = load_items(&state.db, invoice.id).await?;
+    let reminders = invoice_reminder::Entity::find()
+        .filter(invoice_reminder::Column::InvoiceId.eq(invoice.id))
+        .order_by_asc(invoice_reminder::Column::CreatedAt)
+        .all(&state.db)
+        .await
+        .map_err(AppError::internal)?;
     Ok(Json(InvoiceResponse {
         id: invoice.id,
         invoice_number: invoice.invoice_number,
         company_id: invoice.company_id,
         user_id: invoice.user_id,
         template_id: invoice.template_id,
+        contact_id: invoice.contact_id,
         client_name: invoice.client_name,
         client_address: invoice.client_address,
         description: invoice.description,
@@ -317,7 +600,11 @@ pub async fn get_invoice(
         user_address: invoice.user_address,
         total_amount: invoice.total_amount,
         date: invoice.date,
-        items,
+        due_date: invoice.due_date,
+        status: invoice.status,
+        sent_at: invoice.sent_at,
+        items: Some(items),
+        reminders: Some(reminders.into_iter().map(ReminderResponse::from).collect()),
     }))
 }
 
@@ -330,10 +617,12 @@ pub async fn get_invoice(
     request_body = UpdateInvoiceRequest,
     responses(
         (status = 200, description = "Invoice updated", body = InvoiceResponse),
-        (status = 400, description = "Invalid id"),
-        (status = 401, description = "Not authenticated"),
-        (status = 404, description = "Invoice not found"),
-        (status = 500, description = "Server error")
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 404, description = "Invoice not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
     ),
     tag = "invoices"
 )]
@@ -341,20 +630,21 @@ pub async fn update_invoice(
     State(state): State<AppState>,
     headers: HeaderMap,
     Path(id): Path<String>,
-    Json(payload): Json<UpdateInvoiceRequest>,
-) -> Result<Json<InvoiceResponse>, (StatusCode, String)> {
-    let current_user = require_user(&state, &headers).await?;
+    ValidatedJson(payload): ValidatedJson<UpdateInvoiceRequest>,
+) -> Result<Json<InvoiceResponse>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::InvoicesWrite).await?;
     let id = Uuid::parse_str(&id)
-        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid id".to_string()))?;
+        .map_err(|_| AppError::bad_request("Invalid id"))?;
 
     let existing = invoice::Entity::find()
         .filter(invoice::Column::Id.eq(id))
-        .filter(invoice::Column::UserId.eq(current_user.id))
+        .filter(invoice::Column::WorkspaceId.eq(access.workspace_id))
         .one(&state.db)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-        .ok_or_else(|| (StatusCode::NOT_FOUND, "Invoice not found".to_string()))?;
+        .map_err(AppError::internal)?
+        .ok_or_else(|| AppError::not_found("Invoice not found"))?;
 
+    let existing_company_id = existing.company_id;
     let mut active: invoice::ActiveModel = existing.into();
     if let Some(client_name) = payload.client_name {
         active.client_name = Set(client_name);
@@ -364,17 +654,30 @@ pub async fn update_invoice(
     }
     if let Some(company_id) = payload.company_id {
         let company = company::Entity::find_by_id(company_id)
-            .filter(company::Column::UserId.eq(current_user.id))
+            .filter(company::Column::WorkspaceId.eq(access.workspace_id))
             .one(&state.db)
             .await
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-            .ok_or_else(|| (StatusCode::BAD_REQUEST, "Invalid company".to_string()))?;
+            .map_err(AppError::internal)?
+            .ok_or_else(|| AppError::bad_request("Invalid company"))?;
+        if existing_company_id != Some(company.id) && payload.contact_id.is_none() {
+            active.contact_id = Set(None);
+        }
         active.company_id = Set(Some(company.id));
         active.client_name = Set(company.name);
         active.client_address = Set(company.address);
     }
+    if let Some(contact_id) = payload.contact_id {
+        let company_id = payload
+            .company_id
+            .or(existing_company_id)
+            .ok_or_else(|| AppError::bad_request("Invalid contact"))?;
+        find_contact(&state.db, company_id, contact_id)
+            .await?
+            .ok_or_else(|| AppError::bad_request("Invalid contact"))?;
+        active.contact_id = Set(Some(contact_id));
+    }
     if let Some(template_id) = payload.template_id {
-        let resolved = resolve_template_id(&state.db, current_user.id, Some(template_id)).await?;
+        let resolved = resolve_template_id(&state.db, access.workspace_id, Some(template_id)).await?;
         active.template_id = Set(resolved);
     }
     if let Some(description) = payload.description {
@@ -390,10 +693,13 @@ pub async fn update_invoice(
     if let Some(date) = payload.date {
         active.date = Set(date);
     }
+    if let Some(due_date) = payload.due_date {
+        active.due_date = Set(Some(due_date));
+    }
+    if let Some(status) = payload.status {
+        active.status = Set(status);
+    }
     if let Some(items) = payload.items {
-        if items.is_empty() {
-            return Err((StatusCode::BAD_REQUEST, "At least one line item is required".to_string()));
-        }
         let total_amount = items
             .iter()
             .map(|item| {
@@ -406,7 +712,7 @@ pub async fn update_invoice(
             .sum::<f64>();
         active.amount = Set(total_amount);
         active.total_amount = Set(total_amount);
-        if let Some(first) = items.get(0) {
+        if let Some(first) = items.first() {
             active.description = Set(first.description.clone());
         }
 
@@ -414,18 +720,18 @@ pub async fn update_invoice(
             .db
             .begin()
             .await
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+            .map_err(AppError::internal)?;
 
         let updated = active
             .update(&txn)
             .await
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+            .map_err(AppError::internal)?;
 
         invoice_line_item::Entity::delete_many()
             .filter(invoice_line_item::Column::InvoiceId.eq(updated.id))
             .exec(&txn)
             .await
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+            .map_err(AppError::internal)?;
 
         let mut items_response = Vec::with_capacity(items.len());
         for item in items {
@@ -447,7 +753,7 @@ pub async fn update_invoice(
             let saved = active_item
                 .insert(&txn)
                 .await
-                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+                .map_err(AppError::internal)?;
             items_response.push(LineItemResponse {
                 id: saved.id,
                 description: saved.description,
@@ -460,7 +766,7 @@ pub async fn update_invoice(
 
         txn.commit()
             .await
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+            .map_err(AppError::internal)?;
 
         return Ok(Json(InvoiceResponse {
             id: updated.id,
@@ -468,6 +774,7 @@ pub async fn update_invoice(
             company_id: updated.company_id,
             user_id: updated.user_id,
             template_id: updated.template_id,
+            contact_id: updated.contact_id,
             client_name: updated.client_name,
             client_address: updated.client_address,
             description: updated.description,
@@ -476,14 +783,18 @@ pub async fn update_invoice(
             user_address: updated.user_address,
             total_amount: updated.total_amount,
             date: updated.date,
-            items: items_response,
+            due_date: updated.due_date,
+            status: updated.status,
+            sent_at: updated.sent_at,
+            items: Some(items_response),
+            reminders: None,
         }));
     }
 
     let updated = active
         .update(&state.db)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(AppError::internal)?;
 
     let items = load_items(&state.db, updated.id).await?;
 
@@ -493,6 +804,7 @@ pub async fn update_invoice(
         company_id: updated.company_id,
         user_id: updated.user_id,
         template_id: updated.template_id,
+        contact_id: updated.contact_id,
         client_name: updated.client_name,
         client_address: updated.client_address,
         description: updated.description,
@@ -501,7 +813,11 @@ pub async fn update_invoice(
         user_address: updated.user_address,
         total_amount: updated.total_amount,
         date: updated.date,
-        items,
+        due_date: updated.due_date,
+        status: updated.status,
+        sent_at: updated.sent_at,
+        items: Some(items),
+        reminders: None,
     }))
 }
 
@@ -513,10 +829,11 @@ pub async fn update_invoice(
     ),
     responses(
         (status = 200, description = "Invoice PDF"),
-        (status = 400, description = "Invalid id"),
-        (status = 401, description = "Not authenticated"),
-        (status = 404, description = "Invoice not found"),
-        (status = 500, description = "Server error")
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 404, description = "Invoice not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
     ),
     tag = "invoices"
 )]
@@ -524,23 +841,28 @@ pub async fn get_invoice_pdf(
     State(state): State<AppState>,
     headers: HeaderMap,
     Path(id): Path<String>,
-) -> Result<Response, (StatusCode, String)> {
-    let current_user = require_user(&state, &headers).await?;
+) -> Result<Response, AppError> {
+    let access = require_access(&state, &headers, ApiScope::InvoicesRead).await?;
     let id = Uuid::parse_str(&id)
-        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid id".to_string()))?;
+        .map_err(|_| AppError::bad_request("Invalid id"))?;
 
     let invoice = invoice::Entity::find()
         .filter(invoice::Column::Id.eq(id))
-        .filter(invoice::Column::UserId.eq(current_user.id))
+        .filter(invoice::Column::WorkspaceId.eq(access.workspace_id))
         .one(&state.db)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-        .ok_or_else(|| (StatusCode::NOT_FOUND, "Invoice not found".to_string()))?;
+        .map_err(AppError::internal)?
+        .ok_or_else(|| AppError::not_found("Invoice not found"))?;
 
-    let items = load_items(&state.db, invoice.id).await?;
-    let template = load_template(&state.db, invoice.user_id, invoice.template_id).await?;
-    let pdf_bytes = build_invoice_pdf(&invoice, &items, &template)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    invoice_pdf_response(&state.db, &invoice).await
+}
+
+/// Renders an invoice with its template and returns it as a PDF download.
+pub(crate) async fn invoice_pdf_response(
+    db: &sea_orm::DatabaseConnection,
+    invoice: &invoice::Model,
+) -> Result<Response, AppError> {
+    let pdf_bytes = render_invoice_pdf(db, invoice).await?;
 
     let mut response_headers = HeaderMap::new();
     response_headers.insert(
@@ -550,43 +872,279 @@ pub async fn get_invoice_pdf(
     response_headers.insert(
         axum::http::header::CONTENT_DISPOSITION,
         HeaderValue::from_str(&format!("attachment; filename=\"invoice-{}.pdf\"", invoice.id))
-            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Invalid filename".to_string()))?,
+            .map_err(|_| AppError::internal("Invalid filename"))?,
     );
 
     Ok((response_headers, pdf_bytes).into_response())
 }
 
+/// Renders an invoice to PDF with its template, line items and billing contact.
+pub(crate) async fn render_invoice_pdf(
+    db: &sea_orm::DatabaseConnection,
+    invoice: &invoice::Model,
+) -> Result<Vec<u8>, AppError> {
+    let items = load_items(db, invoice.id).await?;
+    let template = load_template(db, invoice.workspace_id, invoice.template_id).await?;
+    let contact = load_contact(db, invoice).await?;
+    build_invoice_pdf(invoice, &items, contact.as_ref(), &template).map_err(AppError::internal)
+}
+
 #[utoipa::path(
     get,
-    path = "/invoice-templates",
+    path = "/invoices/{id}/recipients",
+    params(
+        ("id" = String, Path, description = "Invoice id (UUID)")
+    ),
     responses(
-        (status = 200, description = "Template list", body = [TemplateResponse]),
-        (status = 401, description = "Not authenticated"),
-        (status = 500, description = "Server error")
+        (status = 200, description = "Contacts the invoice is sent to", body = [ContactResponse]),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 404, description = "Invoice not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
     ),
     tag = "invoices"
 )]
-pub async fn list_templates(
+pub async fn get_invoice_recipients(
     State(state): State<AppState>,
     headers: HeaderMap,
-) -> Result<Json<Vec<TemplateResponse>>, (StatusCode, String)> {
-    let current_user = require_user(&state, &headers).await?;
-    let templates = invoice_template::Entity::find()
-        .filter(invoice_template::Column::UserId.eq(current_user.id))
-        .all(&state.db)
+    Path(id): Path<String>,
+) -> Result<Json<Vec<ContactResponse>>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::InvoicesRead).await?;
+    let id = Uuid::parse_str(&id)
+        .map_err(|_| AppError::bad_request("Invalid id"))?;
+
+    let invoice = invoice::Entity::find()
+        .filter(invoice::Column::Id.eq(id))
+        .filter(invoice::Column::WorkspaceId.eq(access.workspace_id))
+        .one(&state.db)
+        .await
+        .map_err(AppError::internal)?
+        .ok_or_else(|| AppError::not_found("Invoice not found"))?;
+
+    let recipients = invoice_recipients(&state.db, invoice.company_id, invoice.contact_id).await?;
+    Ok(Json(recipients.into_iter().map(ContactResponse::from).collect()))
+}
+
+/// Only a mail that left the process marks the invoice sent.
+fn delivery_status(result: &Result<(), String>, delivers: bool) -> &'static str {
+    match result {
+        Err(_) => "failed",
+        Ok(()) if delivers => "sent",
+        Ok(()) => "logged",
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/invoices/{id}/send",
+    params(
+        ("id" = String, Path, description = "Invoice id (UUID)")
+    ),
+    request_body = SendInvoiceRequest,
+    responses(
+        (status = 200, description = "Invoice sent, or only logged by the mail transport", body = DeliveryResponse),
+        (status = 400, description = "Invalid input or no recipients", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 404, description = "Invoice not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse),
+        (status = 502, description = "Mail delivery failed", body = ErrorResponse)
+    ),
+    tag = "invoices"
+)]
+pub async fn send_invoice(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    ValidatedJson(payload): ValidatedJson<SendInvoiceRequest>,
+) -> Result<Json<DeliveryResponse>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::InvoicesWrite).await?;
+    let id = Uuid::parse_str(&id)
+        .map_err(|_| AppError::bad_request("Invalid id"))?;
+
+    let invoice = invoice::Entity::find()
+        .filter(invoice::Column::Id.eq(id))
+        .filter(invoice::Column::WorkspaceId.eq(access.workspace_id))
+        .one(&state.db)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(AppError::internal)?
+        .ok_or_else(|| AppError::not_found("Invoice not found"))?;
 
-    Ok(Json(
-        templates
+    let recipients = match payload.to {
+        Some(to) => to
             .into_iter()
-            .map(|item| TemplateResponse {
-                id: item.id,
-                name: item.name,
-                html: item.html,
-            })
+            .map(|address| address.trim().to_lowercase())
+            .filter(|address| !address.is_empty())
+            .collect::<Vec<_>>(),
+        None => invoice_recipients(&state.db, invoice.company_id, invoice.contact_id)
+            .await?
+            .into_iter()
+            .filter_map(|contact| contact.email)
             .collect(),
-    ))
+    };
+    if recipients.is_empty() {
+        return Err(AppError::bad_request("No recipients: add a billing contact with an email"));
+    }
+    if let Some(invalid) = recipients.iter().find(|address| !address.contains('@')) {
+        return Err(AppError::bad_request(format!("Invalid recipient: {invalid}")));
+    }
+
+    let items = load_items(&state.db, invoice.id).await?;
+    let template = load_template(&state.db, invoice.workspace_id, invoice.template_id).await?;
+    let contact = load_contact(&state.db, &invoice).await?;
+    let pdf_bytes = build_invoice_pdf(&invoice, &items, contact.as_ref(), &template)
+        .map_err(AppError::internal)?;
+
+    let ctx = json!({
+        "invoice_number": invoice.invoice_number,
+        "invoice_date": invoice.date.to_string(),
+        "client_name": invoice.client_name,
+        "contact": contact.as_ref().map(|contact| json!({
+            "name": contact.name,
+            "role": contact.role,
+            "email": contact.email,
+        })),
+        "currency": invoice.currency,
+        "total_amount": invoice.total_amount,
+        "total": format!("{} {}", format_money(invoice.total_amount, &invoice.currency), invoice.currency),
+        "sender_name": sender_name(&state.db, &access.user).await?,
+    });
+    let email = render_email(&state.db, access.workspace_id, INVOICE_EMAIL, &ctx).await?;
+
+    let result = state
+        .mailer
+        .send(OutgoingMail {
+            to: recipients.clone(),
+            subject: email.subject.clone(),
+            html: email.html,
+            text: email.text,
+            attachments: vec![MailAttachment {
+                filename: format!("invoice-{}.pdf", invoice.invoice_number),
+                content_type: "application/pdf".to_string(),
+                body: pdf_bytes,
+            }],
+        })
+        .await;
+
+    let delivery = invoice_delivery::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        invoice_id: Set(invoice.id),
+        recipients: Set(recipients.join(",")),
+        subject: Set(email.subject),
+        status: Set(delivery_status(&result, state.mailer.delivers()).to_string()),
+        error: Set(result.as_ref().err().cloned()),
+        created_at: Set(Utc::now()),
+    }
+    .insert(&state.db)
+    .await
+    .map_err(AppError::internal)?;
+
+    if let Err(error) = result {
+        return Err(AppError::bad_gateway(format!("Mail delivery failed: {error}")));
+    }
+    if delivery.status != "sent" {
+        return Ok(Json(delivery.into()));
+    }
+
+    let mut active: invoice::ActiveModel = invoice.into();
+    if active.status.as_ref() == "draft" {
+        active.status = Set("sent".to_string());
+    }
+    active.sent_at = Set(Some(delivery.created_at));
+    active
+        .update(&state.db)
+        .await
+        .map_err(AppError::internal)?;
+
+    Ok(Json(delivery.into()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/invoices/{id}/deliveries",
+    params(
+        ("id" = String, Path, description = "Invoice id (UUID)")
+    ),
+    responses(
+        (status = 200, description = "Delivery attempts, newest first", body = [DeliveryResponse]),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 404, description = "Invoice not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "invoices"
+)]
+pub async fn list_invoice_deliveries(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<DeliveryResponse>>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::InvoicesRead).await?;
+    let id = Uuid::parse_str(&id)
+        .map_err(|_| AppError::bad_request("Invalid id"))?;
+
+    let invoice = invoice::Entity::find()
+        .filter(invoice::Column::Id.eq(id))
+        .filter(invoice::Column::WorkspaceId.eq(access.workspace_id))
+        .one(&state.db)
+        .await
+        .map_err(AppError::internal)?
+        .ok_or_else(|| AppError::not_found("Invoice not found"))?;
+
+    let deliveries = invoice_delivery::Entity::find()
+        .filter(invoice_delivery::Column::InvoiceId.eq(invoice.id))
+        .order_by_desc(invoice_delivery::Column::CreatedAt)
+        .all(&state.db)
+        .await
+        .map_err(AppError::internal)?;
+
+    Ok(Json(deliveries.into_iter().map(DeliveryResponse::from).collect()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/invoice-templates",
+    params(PageParams, TemplateListQuery),
+    responses(
+        (status = 200, description = "Template page", body = TemplatePage),
+        (status = 400, description = "Invalid query", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "invoices"
+)]
+pub async fn list_templates(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(paging): Query<PageParams>,
+    Query(query): Query<TemplateListQuery>,
+) -> Result<Json<Page<TemplateResponse>>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::TemplatesRead).await?;
+    let mut select = invoice_template::Entity::find()
+        .filter(invoice_template::Column::WorkspaceId.eq(access.workspace_id));
+    if let Some(pattern) = query.q.as_deref().and_then(search_pattern) {
+        select = select.filter(Expr::col(invoice_template::Column::Name).ilike(&pattern));
+    }
+
+    let order = query.order.unwrap_or_default();
+    let column = match query.sort.unwrap_or_default() {
+        TemplateSort::Name => invoice_template::Column::Name,
+        TemplateSort::CreatedAt => invoice_template::Column::CreatedAt,
+    };
+    select = select
+        .order_by(column, order.into())
+        .order_by(invoice_template::Column::Id, order.into());
+
+    let page = fetch_page(&state.db, select, &paging).await?;
+    Ok(Json(page.map(|item| TemplateResponse {
+        id: item.id,
+        name: item.name,
+        html: item.html,
+    })))
 }
 
 #[utoipa::path(
@@ -595,25 +1153,24 @@ pub async fn list_templates(
     request_body = TemplateCreateRequest,
     responses(
         (status = 200, description = "Template created", body = TemplateResponse),
-        (status = 400, description = "Invalid input"),
-        (status = 401, description = "Not authenticated"),
-        (status = 500, description = "Server error")
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
     ),
     tag = "invoices"
 )]
 pub async fn create_template(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Json(payload): Json<TemplateCreateRequest>,
-) -> Result<Json<TemplateResponse>, (StatusCode, String)> {
-    let current_user = require_user(&state, &headers).await?;
-    if payload.name.trim().is_empty() {
-        return Err((StatusCode::BAD_REQUEST, "Name is required".to_string()));
-    }
-
+    ValidatedJson(payload): ValidatedJson<TemplateCreateRequest>,
+) -> Result<Json<TemplateResponse>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::TemplatesWrite).await?;
     let active = invoice_template::ActiveModel {
         id: Set(Uuid::new_v4()),
-        user_id: Set(current_user.id),
+        user_id: Set(access.user.id),
+        workspace_id: Set(access.workspace_id),
         name: Set(payload.name),
         html: Set(payload.html),
         created_at: Set(chrono::Utc::now()),
@@ -622,7 +1179,7 @@ pub async fn create_template(
     let created = active
         .insert(&state.db)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(AppError::internal)?;
 
     Ok(Json(TemplateResponse {
         id: created.id,
@@ -637,10 +1194,12 @@ pub async fn create_template(
     request_body = TemplateCreateRequest,
     responses(
         (status = 200, description = "Template updated", body = TemplateResponse),
-        (status = 400, description = "Invalid input"),
-        (status = 401, description = "Not authenticated"),
-        (status = 404, description = "Template not found"),
-        (status = 500, description = "Server error")
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 404, description = "Template not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
     ),
     tag = "invoices"
 )]
@@ -648,21 +1207,17 @@ pub async fn update_template(
     State(state): State<AppState>,
     headers: HeaderMap,
     Path(id): Path<String>,
-    Json(payload): Json<TemplateCreateRequest>,
-) -> Result<Json<TemplateResponse>, (StatusCode, String)> {
-    let current_user = require_user(&state, &headers).await?;
+    ValidatedJson(payload): ValidatedJson<TemplateCreateRequest>,
+) -> Result<Json<TemplateResponse>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::TemplatesWrite).await?;
     let id = Uuid::parse_str(&id)
-        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid id".to_string()))?;
+        .map_err(|_| AppError::bad_request("Invalid id"))?;
     let existing = invoice_template::Entity::find_by_id(id)
-        .filter(invoice_template::Column::UserId.eq(current_user.id))
+        .filter(invoice_template::Column::WorkspaceId.eq(access.workspace_id))
         .one(&state.db)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-        .ok_or_else(|| (StatusCode::NOT_FOUND, "Template not found".to_string()))?;
-
-    if payload.name.trim().is_empty() {
-        return Err((StatusCode::BAD_REQUEST, "Name is required".to_string()));
-    }
+        .map_err(AppError::internal)?
+        .ok_or_else(|| AppError::not_found("Template not found"))?;
 
     let mut active: invoice_template::ActiveModel = existing.into();
     active.name = Set(payload.name);
@@ -671,7 +1226,7 @@ pub async fn update_template(
     let updated = active
         .update(&state.db)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(AppError::internal)?;
 
     Ok(Json(TemplateResponse {
         id: updated.id,
@@ -685,9 +1240,10 @@ pub async fn update_template(
     path = "/invoice-templates/{id}",
     responses(
         (status = 204, description = "Template deleted"),
-        (status = 401, description = "Not authenticated"),
-        (status = 404, description = "Template not found"),
-        (status = 500, description = "Server error")
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 404, description = "Template not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
     ),
     tag = "invoices"
 )]
@@ -695,21 +1251,21 @@ pub async fn delete_template(
     State(state): State<AppState>,
     headers: HeaderMap,
     Path(id): Path<String>,
-) -> Result<StatusCode, (StatusCode, String)> {
-    let current_user = require_user(&state, &headers).await?;
+) -> Result<StatusCode, AppError> {
+    let access = require_access(&state, &headers, ApiScope::TemplatesWrite).await?;
     let id = Uuid::parse_str(&id)
-        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid id".to_string()))?;
+        .map_err(|_| AppError::bad_request("Invalid id"))?;
     let existing = invoice_template::Entity::find_by_id(id)
-        .filter(invoice_template::Column::UserId.eq(current_user.id))
+        .filter(invoice_template::Column::WorkspaceId.eq(access.workspace_id))
         .one(&state.db)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-        .ok_or_else(|| (StatusCode::NOT_FOUND, "Template not found".to_string()))?;
+        .map_err(AppError::internal)?
+        .ok_or_else(|| AppError::not_found("Template not found"))?;
 
     invoice_template::Entity::delete_by_id(existing.id)
         .exec(&state.db)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(AppError::internal)?;
 
     Ok(StatusCode::NO_CONTENT)
 }
@@ -717,6 +1273,7 @@ pub async fn delete_template(
 fn build_invoice_pdf(
     invoice: &invoice::Model,
     items: &[LineItemResponse],
+    contact: Option<&company_contact::Model>,
     template: &InvoiceTemplateData,
 ) -> Result<Vec<u8>, String> {
     let mut handlebars = Handlebars::new();
@@ -752,6 +1309,12 @@ fn build_invoice_pdf(
         "invoice_date": invoice.date.to_string(),
         "client_name": invoice.client_name,
         "client_address": invoice.client_address,
+        "contact": contact.map(|contact| json!({
+            "name": contact.name,
+            "role": contact.role,
+            "email": contact.email,
+            "phone": contact.phone,
+        })),
         "user_address": invoice.user_address,
         "currency": invoice.currency,
         "total_amount": invoice.total_amount,
@@ -845,6 +1408,7 @@ fn build_invoice_pdf(
     <h2>Bill To</h2>
     <div>{}</div>
     <div class="muted">{}</div>
+    {{{{#if contact}}}}<div class="muted">Attn: {{{{contact.name}}}}</div>{{{{/if}}}}
   </div>
 
   <table>
@@ -887,13 +1451,17 @@ fn build_invoice_pdf(
         );
         handlebars
             .render_template(&html_template, &ctx)
-            .unwrap_or_else(|_| html_template)
+            .unwrap_or(html_template)
     };
 
     let html = html
         .replace("{{currency}}", &invoice.currency)
         .replace("{{currency_symbol}}", symbol);
 
+    html_to_pdf(&html)
+}
+
+pub(crate) fn html_to_pdf(html: &str) -> Result<Vec<u8>, String> {
     let mut child = Command::new("wkhtmltopdf")
         .args(["-q", "--encoding", "utf-8", "-", "-"])
         .stdin(Stdio::piped())
@@ -918,7 +1486,7 @@ fn build_invoice_pdf(
     Ok(output.stdout)
 }
 
-fn format_money(value: f64, currency: &str) -> String {
+pub(crate) fn format_money(value: f64, currency: &str) -> String {
     let (thousands, decimal) = match currency {
         "EUR" => ('.', ','),
         "USD" | "GBP" => (',', '.'),
@@ -951,18 +1519,18 @@ struct InvoiceTemplateData {
 
 async fn resolve_template_id(
     db: &sea_orm::DatabaseConnection,
-    user_id: Uuid,
+    workspace_id: Uuid,
     template_id: Option<Uuid>,
-) -> Result<Option<Uuid>, (StatusCode, String)> {
+) -> Result<Option<Uuid>, AppError> {
     if let Some(id) = template_id {
         let exists = invoice_template::Entity::find_by_id(id)
-            .filter(invoice_template::Column::UserId.eq(user_id))
+            .filter(invoice_template::Column::WorkspaceId.eq(workspace_id))
             .one(db)
             .await
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+            .map_err(AppError::internal)?
             .is_some();
         if !exists {
-            return Err((StatusCode::BAD_REQUEST, "Invalid template".to_string()));
+            return Err(AppError::bad_request("Invalid template"));
         }
         return Ok(Some(id));
     }
@@ -971,25 +1539,24 @@ async fn resolve_template_id(
 
 async fn load_template(
     db: &sea_orm::DatabaseConnection,
-    user_id: Option<Uuid>,
+    workspace_id: Option<Uuid>,
     template_id: Option<Uuid>,
-) -> Result<InvoiceTemplateData, (StatusCode, String)> {
+) -> Result<InvoiceTemplateData, AppError> {
     let default_note = "Rechnungsbetrag ohne Umsatzsteuer gemäß § 19 Abs. 1 UStG. (Invoice amount without sales tax according to § 19 paragraph 1 UStG)".to_string();
-    let Some(user_id) = user_id else {
+    let Some(workspace_id) = workspace_id else {
         return Ok(default_template(default_note));
     };
-    if let Some(id) = template_id {
-        if let Some(template) = invoice_template::Entity::find_by_id(id)
-            .filter(invoice_template::Column::UserId.eq(user_id))
+    if let Some(id) = template_id
+        && let Some(template) = invoice_template::Entity::find_by_id(id)
+            .filter(invoice_template::Column::WorkspaceId.eq(workspace_id))
             .one(db)
             .await
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-        {
-            return Ok(InvoiceTemplateData {
-                html: template.html,
-                is_custom: true,
-            });
-        }
+            .map_err(AppError::internal)?
+    {
+        return Ok(InvoiceTemplateData {
+            html: template.html,
+            is_custom: true,
+        });
     }
     Ok(default_template(default_note))
 }
@@ -1010,6 +1577,7 @@ fn default_template(note: String) -> InvoiceTemplateData {
   <h2>Bill To</h2>
   <div>{{{{client_name}}}}</div>
   <div class="muted">{{{{client_address}}}}</div>
+  {{{{#if contact}}}}<div class="muted">Attn: {{{{contact.name}}}}</div>{{{{/if}}}}
 </div>
 
 <table>
@@ -1048,15 +1616,39 @@ fn default_template(note: String) -> InvoiceTemplateData {
     }
 }
 
+async fn sender_name(
+    db: &sea_orm::DatabaseConnection,
+    user: &user::Model,
+) -> Result<String, AppError> {
+    let Some(company_id) = user.company_id else {
+        return Ok(user.email.clone());
+    };
+    let company = company::Entity::find_by_id(company_id)
+        .one(db)
+        .await
+        .map_err(AppError::internal)?;
+    Ok(company.map(|company| company.name).unwrap_or_else(|| user.email.clone()))
+}
+
+async fn load_contact(
+    db: &sea_orm::DatabaseConnection,
+    invoice: &invoice::Model,
+) -> Result<Option<company_contact::Model>, AppError> {
+    match (invoice.company_id, invoice.contact_id) {
+        (Some(company_id), Some(contact_id)) => find_contact(db, company_id, contact_id).await,
+        _ => Ok(None),
+    }
+}
+
 async fn load_items(
     db: &sea_orm::DatabaseConnection,
     invoice_id: Uuid,
-) -> Result<Vec<LineItemResponse>, (StatusCode, String)> {
+) -> Result<Vec<LineItemResponse>, AppError> {
     let items = invoice_line_item::Entity::find()
         .filter(invoice_line_item::Column::InvoiceId.eq(invoice_id))
         .all(db)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(AppError::internal)?;
 
     Ok(items
         .into_iter()
@@ -1071,14 +1663,145 @@ async fn load_items(
         .collect())
 }
 
+/// Loads the line items of several invoices in a single query, grouped by invoice.
+pub(crate) async fn load_items_for_invoices(
+    db: &sea_orm::DatabaseConnection,
+    invoice_ids: &[Uuid],
+) -> Result<HashMap<Uuid, Vec<LineItemResponse>>, AppError> {
+    let mut grouped: HashMap<Uuid, Vec<LineItemResponse>> = HashMap::new();
+    if invoice_ids.is_empty() {
+        return Ok(grouped);
+    }
+
+    let items = invoice_line_item::Entity::find()
+        .filter(invoice_line_item::Column::InvoiceId.is_in(invoice_ids.iter().copied()))
+        .all(db)
+        .await
+        .map_err(AppError::internal)?;
+
+    for item in items {
+        grouped.entry(item.invoice_id).or_default().push(LineItemResponse {
+            id: item.id,
+            description: item.description,
+            quantity: item.quantity,
+            unit_price: item.unit_price,
+            line_total: item.line_total,
+            use_quantity: item.use_quantity,
+        });
+    }
+
+    Ok(grouped)
+}
+
 async fn next_invoice_number(
     db: &sea_orm::DatabaseConnection,
-    user_id: Uuid,
-) -> Result<String, (StatusCode, String)> {
+    workspace_id: Uuid,
+) -> Result<String, AppError> {
     let count = invoice::Entity::find()
-        .filter(invoice::Column::UserId.eq(user_id))
+        .filter(invoice::Column::WorkspaceId.eq(workspace_id))
         .count(db)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(AppError::internal)?;
     Ok(format!("IN-{:05}", count + 1))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{DatabaseBackend, MockDatabase, Value};
+    use std::collections::BTreeMap;
+
+    fn stored_invoice(index: usize) -> invoice::Model {
+        invoice::Model {
+            id: Uuid::new_v4(),
+            invoice_number: format!("IN-{:05}", index + 1),
+            user_id: None,
+            workspace_id: None,
+            company_id: None,
+            template_id: None,
+            contact_id: None,
+            client_name: "Client".to_string(),
+            client_address: "Street 1".to_string(),
+            description: "Work".to_string(),
+            amount: 100.0,
+            currency: "EUR".to_string(),
+            user_address: "Home 1".to_string(),
+            total_amount: 100.0,
+            date: NaiveDate::from_ymd_opt(2026, 1, 15).unwrap(),
+            due_date: None,
+            status: "draft".to_string(),
+            sent_at: None,
+        }
+    }
+
+    fn stored_item(invoice_id: Uuid) -> invoice_line_item::Model {
+        invoice_line_item::Model {
+            id: Uuid::new_v4(),
+            invoice_id,
+            description: "Consulting".to_string(),
+            quantity: 2.0,
+            unit_price: 50.0,
+            line_total: 100.0,
+            use_quantity: true,
+        }
+    }
+
+    async fn run_page(count: usize, include_items: bool) -> (Page<InvoiceResponse>, usize) {
+        let invoices: Vec<invoice::Model> = (0..count).map(stored_invoice).collect();
+        let items: Vec<invoice_line_item::Model> = invoices
+            .iter()
+            .flat_map(|invoice| [stored_item(invoice.id), stored_item(invoice.id)])
+            .collect();
+        let total = BTreeMap::from([("num_items", Value::BigInt(Some(count as i64)))]);
+        let mut mock = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([[total]])
+            .append_query_results([invoices]);
+        if include_items {
+            mock = mock.append_query_results([items]);
+        }
+        let db = mock.into_connection();
+
+        let paging = PageParams {
+            page: Some(1),
+            per_page: Some(100),
+        };
+        let page = invoice_page(&db, invoice::Entity::find(), &paging, include_items)
+            .await
+            .unwrap();
+        (page, db.into_transaction_log().len())
+    }
+
+    #[tokio::test]
+    async fn invoice_page_query_count_does_not_grow_with_invoices() {
+        let (single, single_queries) = run_page(1, true).await;
+        let (many, many_queries) = run_page(50, true).await;
+
+        assert_eq!(single_queries, 3);
+        assert_eq!(many_queries, single_queries);
+        assert_eq!(single.items[0].items.as_ref().map(Vec::len), Some(2));
+        assert!(many.items.iter().all(|invoice| invoice.items.as_ref().map(Vec::len) == Some(2)));
+    }
+
+    #[tokio::test]
+    async fn invoice_page_without_items_skips_the_item_query_and_field() {
+        let (page, queries) = run_page(10, false).await;
+
+        assert_eq!(queries, 2);
+        let json = serde_json::to_value(&page.items[0]).unwrap();
+        assert!(json.get("items").is_none());
+    }
+
+    #[test]
+    fn client_overrides_fall_back_to_the_company_when_absent_or_blank() {
+        assert_eq!(override_or(None, "Company GmbH"), "Company GmbH");
+        assert_eq!(override_or(Some("  ".to_string()), "Company GmbH"), "Company GmbH");
+        assert_eq!(override_or(Some("Branch Office".to_string()), "Company GmbH"), "Branch Office");
+    }
+
+    #[test]
+    fn only_delivered_mail_counts_as_sent() {
+        assert_eq!(delivery_status(&Ok(()), true), "sent");
+        assert_eq!(delivery_status(&Ok(()), false), "logged");
+        assert_eq!(delivery_status(&Err("refused".to_string()), true), "failed");
+    }
+}
@@ -0,0 +1,585 @@
+use crate::entity::email_template;
+use crate::modules::api_tokens::ApiScope;
+use crate::modules::auth::require_access;
+use crate::modules::shared::{AppError, AppState};
+use crate::modules::validation::{not_blank, Path, ValidatedJson};
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use chrono::Utc;
+use handlebars::Handlebars;
+use lettre::message::{header::ContentType, Attachment, Mailbox, MultiPart};
+use lettre::transport::smtp::authentication::Credentials;
+#[cfg(test)]
+use lettre::transport::stub::AsyncStubTransport;
+use lettre::{AsyncFileTransport, AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+pub const INVOICE_EMAIL: &str = "invoice";
+pub const REMINDER_EMAIL: &str = "reminder";
+
+const EMAIL_KINDS: &[&str] = &[INVOICE_EMAIL, REMINDER_EMAIL];
+
+/// Account emails (verification, password reset, workspace invitations) use built-in
+/// templates and are not customisable per workspace.
+pub const VERIFY_EMAIL: &str = "verify_email";
+pub const PASSWORD_RESET_EMAIL: &str = "password_reset";
+pub const WORKSPACE_INVITATION_EMAIL: &str = "workspace_invitation";
+pub const ACCOUNTANT_ACCESS_EMAIL: &str = "accountant_access";
+
+/// Outbound mail, configured from `MAIL_TRANSPORT`:
+/// `smtp` (default when `SMTP_HOST` is set), `file` (writes `.eml` files to
+/// `MAIL_FILE_DIR`) or `log` (prints messages to stdout). Without either variable
+/// the server refuses to start. Tests use an in-process `memory` transport, which
+/// the environment cannot select. Only `smtp` and `file` count as delivered, see
+/// [`Mailer::delivers`].
+#[derive(Clone)]
+pub struct Mailer {
+    transport: Arc<MailTransport>,
+    from: Mailbox,
+}
+
+enum MailTransport {
+    Smtp(AsyncSmtpTransport<Tokio1Executor>),
+    File(AsyncFileTransport<Tokio1Executor>),
+    #[cfg(test)]
+    Memory(AsyncStubTransport),
+    Log,
+}
+
+pub struct MailAttachment {
+    pub filename: String,
+    pub content_type: String,
+    pub body: Vec<u8>,
+}
+
+pub struct OutgoingMail {
+    pub to: Vec<String>,
+    pub subject: String,
+    pub html: String,
+    pub text: String,
+    pub attachments: Vec<MailAttachment>,
+}
+
+pub struct RenderedEmail {
+    pub subject: String,
+    pub html: String,
+    pub text: String,
+}
+
+impl Mailer {
+    pub fn from_env() -> anyhow::Result<Self> {
+        let from = std::env::var("MAIL_FROM")
+            .unwrap_or_else(|_| "Freelance Forge <no-reply@localhost>".to_string())
+            .parse::<Mailbox>()?;
+        let name = transport_name(
+            std::env::var("MAIL_TRANSPORT").ok(),
+            std::env::var("SMTP_HOST").is_ok(),
+        )?;
+        let transport = match name.as_str() {
+            "smtp" => MailTransport::Smtp(build_smtp_transport()?),
+            "file" => {
+                let dir =
+                    std::env::var("MAIL_FILE_DIR").unwrap_or_else(|_| "mail-outbox".to_string());
+                std::fs::create_dir_all(&dir)?;
+                MailTransport::File(AsyncFileTransport::<Tokio1Executor>::new(dir))
+            }
+            "memory" => anyhow::bail!("MAIL_TRANSPORT=memory is only available in tests"),
+            "log" => MailTransport::Log,
+            other => anyhow::bail!("Unknown MAIL_TRANSPORT: {other}"),
+        };
+
+        Ok(Self {
+            transport: Arc::new(transport),
+            from,
+        })
+    }
+
+    /// Whether sent messages leave the process. `log` only prints them, so
+    /// callers must not record them as delivered.
+    pub fn delivers(&self) -> bool {
+        match self.transport.as_ref() {
+            MailTransport::Smtp(_) | MailTransport::File(_) => true,
+            #[cfg(test)]
+            MailTransport::Memory(_) => false,
+            MailTransport::Log => false,
+        }
+    }
+
+    /// A mailer that keeps messages in process, for tests.
+    #[cfg(test)]
+    pub(crate) fn memory() -> Self {
+        Self {
+            transport: Arc::new(MailTransport::Memory(AsyncStubTransport::new_ok())),
+            from: "Freelance Forge <no-reply@localhost>".parse().unwrap(),
+        }
+    }
+
+    /// Raw messages sent through a memory mailer.
+    #[cfg(test)]
+    pub(crate) async fn sent(&self) -> Vec<String> {
+        match self.transport.as_ref() {
+            MailTransport::Memory(transport) => transport
+                .messages()
+                .await
+                .into_iter()
+                .map(|(_, message)| message)
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    pub async fn send(&self, mail: OutgoingMail) -> Result<(), String> {
+        if let MailTransport::Log = self.transport.as_ref() {
+            println!(
+                "Mail to {}: {}\n{}{}",
+                mail.to.join(", "),
+                mail.subject,
+                mail.text,
+                mail.attachments
+                    .iter()
+                    .map(|attachment| format!("\n[attachment: {}]", attachment.filename))
+                    .collect::<String>()
+            );
+            return Ok(());
+        }
+
+        let mut builder = Message::builder()
+            .from(self.from.clone())
+            .subject(mail.subject);
+        for to in &mail.to {
+            let mailbox = to
+                .parse::<Mailbox>()
+                .map_err(|e| format!("Invalid recipient {to}: {e}"))?;
+            builder = builder.to(mailbox);
+        }
+
+        let mut body =
+            MultiPart::mixed().multipart(MultiPart::alternative_plain_html(mail.text, mail.html));
+        for attachment in mail.attachments {
+            let content_type = ContentType::parse(&attachment.content_type)
+                .map_err(|e| format!("Invalid attachment type: {e}"))?;
+            body = body.singlepart(
+                Attachment::new(attachment.filename).body(attachment.body, content_type),
+            );
+        }
+        let message = builder.multipart(body).map_err(|e| e.to_string())?;
+
+        match self.transport.as_ref() {
+            MailTransport::Smtp(transport) => transport
+                .send(message)
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+            MailTransport::File(transport) => transport
+                .send(message)
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+            #[cfg(test)]
+            MailTransport::Memory(transport) => {
+                transport.send(message).await.map_err(|e| e.to_string())
+            }
+            MailTransport::Log => Ok(()),
+        }
+    }
+}
+
+/// `MAIL_TRANSPORT`, or `smtp` when only `SMTP_HOST` is set.
+fn transport_name(configured: Option<String>, smtp_host_set: bool) -> anyhow::Result<String> {
+    match configured {
+        Some(name) => Ok(name),
+        None if smtp_host_set => Ok("smtp".to_string()),
+        None => anyhow::bail!(
+            "No mail transport configured: set SMTP_HOST, or MAIL_TRANSPORT=file|log \
+             for development"
+        ),
+    }
+}
+
+fn build_smtp_transport() -> anyhow::Result<AsyncSmtpTransport<Tokio1Executor>> {
+    let host = std::env::var("SMTP_HOST").map_err(|_| anyhow::anyhow!("SMTP_HOST missing"))?;
+    let security = std::env::var("SMTP_SECURITY").unwrap_or_else(|_| "starttls".to_string());
+    let mut builder = match security.as_str() {
+        "tls" => AsyncSmtpTransport::<Tokio1Executor>::relay(&host)?,
+        "starttls" => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&host)?,
+        "none" => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&host),
+        other => anyhow::bail!("Unknown SMTP_SECURITY: {other}"),
+    };
+    if let Ok(port) = std::env::var("SMTP_PORT") {
+        builder = builder.port(port.parse()?);
+    }
+    if let (Ok(username), Ok(password)) = (
+        std::env::var("SMTP_USERNAME"),
+        std::env::var("SMTP_PASSWORD"),
+    ) {
+        builder = builder.credentials(Credentials::new(username, password));
+    }
+    Ok(builder.build())
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct EmailTemplateRequest {
+    #[validate(custom(function = not_blank), length(max = 300))]
+    pub subject: String,
+    pub html: String,
+    pub text: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct EmailTemplateResponse {
+    pub kind: String,
+    pub subject: String,
+    pub html: String,
+    pub text: String,
+    pub is_default: bool,
+}
+
+#[utoipa::path(
+    get,
+    path = "/email-templates",
+    responses(
+        (status = 200, description = "Email templates, falling back to defaults", body = [EmailTemplateResponse]),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "mail"
+)]
+pub async fn list_email_templates(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<EmailTemplateResponse>>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::TemplatesRead).await?;
+    let stored = email_template::Entity::find()
+        .filter(email_template::Column::WorkspaceId.eq(access.workspace_id))
+        .all(&state.db)
+        .await
+        .map_err(AppError::internal)?;
+
+    let response = EMAIL_KINDS
+        .iter()
+        .map(|kind| match stored.iter().find(|item| item.kind == *kind) {
+            Some(item) => EmailTemplateResponse {
+                kind: item.kind.clone(),
+                subject: item.subject.clone(),
+                html: item.html.clone(),
+                text: item.text.clone(),
+                is_default: false,
+            },
+            None => {
+                let (subject, html, text) = default_email_template(kind);
+                EmailTemplateResponse {
+                    kind: kind.to_string(),
+                    subject: subject.to_string(),
+                    html: html.to_string(),
+                    text: text.to_string(),
+                    is_default: true,
+                }
+            }
+        })
+        .collect();
+
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    put,
+    path = "/email-templates/{kind}",
+    params(
+        ("kind" = String, Path, description = "Template kind, e.g. `invoice`")
+    ),
+    request_body = EmailTemplateRequest,
+    responses(
+        (status = 200, description = "Email template saved", body = EmailTemplateResponse),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 404, description = "Unknown template kind", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "mail"
+)]
+pub async fn save_email_template(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(kind): Path<String>,
+    ValidatedJson(payload): ValidatedJson<EmailTemplateRequest>,
+) -> Result<Json<EmailTemplateResponse>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::TemplatesWrite).await?;
+    if !EMAIL_KINDS.contains(&kind.as_str()) {
+        return Err(AppError::not_found("Unknown template kind"));
+    }
+    let mut handlebars = Handlebars::new();
+    for (name, source) in [
+        ("subject", &payload.subject),
+        ("html", &payload.html),
+        ("text", &payload.text),
+    ] {
+        handlebars
+            .register_template_string(name, source)
+            .map_err(|e| {
+                AppError::bad_request(format!("Invalid {name} template: {e}"))
+            })?;
+    }
+
+    let existing = email_template::Entity::find()
+        .filter(email_template::Column::WorkspaceId.eq(access.workspace_id))
+        .filter(email_template::Column::Kind.eq(kind.clone()))
+        .one(&state.db)
+        .await
+        .map_err(AppError::internal)?;
+
+    let saved = match existing {
+        Some(existing) => {
+            let mut active: email_template::ActiveModel = existing.into();
+            active.subject = Set(payload.subject);
+            active.html = Set(payload.html);
+            active.text = Set(payload.text);
+            active.update(&state.db).await
+        }
+        None => {
+            email_template::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                user_id: Set(access.user.id),
+                workspace_id: Set(access.workspace_id),
+                kind: Set(kind),
+                subject: Set(payload.subject),
+                html: Set(payload.html),
+                text: Set(payload.text),
+                created_at: Set(Utc::now()),
+            }
+            .insert(&state.db)
+            .await
+        }
+    }
+    .map_err(AppError::internal)?;
+
+    Ok(Json(EmailTemplateResponse {
+        kind: saved.kind,
+        subject: saved.subject,
+        html: saved.html,
+        text: saved.text,
+        is_default: false,
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/email-templates/{kind}",
+    params(
+        ("kind" = String, Path, description = "Template kind, e.g. `invoice`")
+    ),
+    responses(
+        (status = 204, description = "Email template reset to default"),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 404, description = "Unknown template kind", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "mail"
+)]
+pub async fn reset_email_template(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(kind): Path<String>,
+) -> Result<StatusCode, AppError> {
+    let access = require_access(&state, &headers, ApiScope::TemplatesWrite).await?;
+    if !EMAIL_KINDS.contains(&kind.as_str()) {
+        return Err(AppError::not_found("Unknown template kind"));
+    }
+
+    email_template::Entity::delete_many()
+        .filter(email_template::Column::WorkspaceId.eq(access.workspace_id))
+        .filter(email_template::Column::Kind.eq(kind))
+        .exec(&state.db)
+        .await
+        .map_err(AppError::internal)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Renders the workspace's template for `kind` (or the built-in default) against `ctx`.
+/// The HTML body is escaped; subject and text are rendered verbatim.
+pub(crate) async fn render_email(
+    db: &DatabaseConnection,
+    workspace_id: Uuid,
+    kind: &str,
+    ctx: &serde_json::Value,
+) -> Result<RenderedEmail, AppError> {
+    let stored = email_template::Entity::find()
+        .filter(email_template::Column::WorkspaceId.eq(workspace_id))
+        .filter(email_template::Column::Kind.eq(kind))
+        .one(db)
+        .await
+        .map_err(AppError::internal)?;
+    let (subject, html, text) = match &stored {
+        Some(item) => (
+            item.subject.as_str(),
+            item.html.as_str(),
+            item.text.as_str(),
+        ),
+        None => default_email_template(kind),
+    };
+    render_parts(subject, html, text, ctx)
+}
+
+/// Renders one of the built-in account emails (`VERIFY_EMAIL`, `PASSWORD_RESET_EMAIL`,
+/// `WORKSPACE_INVITATION_EMAIL`, `ACCOUNTANT_ACCESS_EMAIL`).
+pub(crate) fn render_account_email(
+    kind: &str,
+    ctx: &serde_json::Value,
+) -> Result<RenderedEmail, AppError> {
+    let (subject, html, text) = match kind {
+        PASSWORD_RESET_EMAIL => (
+            "Reset your Freelance Forge password",
+            r#"<p>Hello,</p>
+<p>someone asked to reset the password for {{email}}. Use the link below within {{valid_hours}} hour(s) to choose a new one:</p>
+<p><a href="{{link}}">Reset password</a></p>
+<p>If this wasn't you, ignore this email; your password stays unchanged.</p>"#,
+            "Hello,\n\nsomeone asked to reset the password for {{email}}. Use the link below within {{valid_hours}} hour(s) to choose a new one:\n\n{{link}}\n\nIf this wasn't you, ignore this email; your password stays unchanged.\n",
+        ),
+        WORKSPACE_INVITATION_EMAIL => (
+            "{{inviter}} invited you to {{workspace}} on Freelance Forge",
+            r#"<p>Hello,</p>
+<p>{{inviter}} invited you to join the workspace <strong>{{workspace}}</strong> as {{role}}. Sign in or create an account with {{email}}, then accept the invitation within {{valid_days}} day(s):</p>
+<p><a href="{{link}}">Accept invitation</a></p>"#,
+            "Hello,\n\n{{inviter}} invited you to join the workspace {{workspace}} as {{role}}. Sign in or create an account with {{email}}, then accept the invitation within {{valid_days}} day(s):\n\n{{link}}\n",
+        ),
+        ACCOUNTANT_ACCESS_EMAIL => (
+            "Read-only access to {{workspace}} on Freelance Forge",
+            r#"<p>Hello,</p>
+<p>{{inviter}} gave you read-only access to the invoices, expenses and receipts of <strong>{{workspace}}</strong> dated {{period_start}} to {{period_end}}. The link below works until {{expires_at}}; every access is logged.</p>
+<p><a href="{{link}}">Open documents</a></p>"#,
+            "Hello,\n\n{{inviter}} gave you read-only access to the invoices, expenses and receipts of {{workspace}} dated {{period_start}} to {{period_end}}. The link below works until {{expires_at}}; every access is logged.\n\n{{link}}\n",
+        ),
+        _ => (
+            "Confirm your email address",
+            r#"<p>Hello,</p>
+<p>please confirm that {{email}} belongs to your Freelance Forge account by opening the link below within {{valid_hours}} hour(s):</p>
+<p><a href="{{link}}">Confirm email address</a></p>"#,
+            "Hello,\n\nplease confirm that {{email}} belongs to your Freelance Forge account by opening the link below within {{valid_hours}} hour(s):\n\n{{link}}\n",
+        ),
+    };
+    render_parts(subject, html, text, ctx)
+}
+
+fn render_parts(
+    subject: &str,
+    html: &str,
+    text: &str,
+    ctx: &serde_json::Value,
+) -> Result<RenderedEmail, AppError> {
+    let html_renderer = Handlebars::new();
+    let mut plain_renderer = Handlebars::new();
+    plain_renderer.register_escape_fn(handlebars::no_escape);
+    let render_error = |e: handlebars::RenderError| {
+        AppError::bad_request(format!("Email template failed to render: {e}"))
+    };
+
+    Ok(RenderedEmail {
+        subject: plain_renderer
+            .render_template(subject, ctx)
+            .map_err(render_error)?
+            .trim()
+            .to_string(),
+        html: html_renderer
+            .render_template(html, ctx)
+            .map_err(render_error)?,
+        text: plain_renderer
+            .render_template(text, ctx)
+            .map_err(render_error)?,
+    })
+}
+
+fn default_email_template(kind: &str) -> (&'static str, &'static str, &'static str) {
+    match kind {
+        REMINDER_EMAIL => (
+            "{{reminder_name}}: invoice {{invoice_number}}",
+            r#"<p>Hello {{client_name}},</p>
+<p>our invoice <strong>{{invoice_number}}</strong> dated {{invoice_date}} was due on {{due_date}} and is {{days_overdue}} days overdue.</p>
+<p>Please transfer the outstanding amount of {{total}}. The attached notice lists the details.</p>
+<p>Kind regards</p>"#,
+            "Hello {{client_name}},\n\nour invoice {{invoice_number}} dated {{invoice_date}} was due on {{due_date}} and is {{days_overdue}} days overdue.\n\nPlease transfer the outstanding amount of {{total}}. The attached notice lists the details.\n\nKind regards\n",
+        ),
+        _ => (
+            "Invoice {{invoice_number}} from {{sender_name}}",
+            r#"<p>Hello {{#if contact}}{{contact.name}}{{else}}{{client_name}}{{/if}},</p>
+<p>please find attached invoice <strong>{{invoice_number}}</strong> dated {{invoice_date}} for {{total}}.</p>
+<p>Kind regards<br/>{{sender_name}}</p>"#,
+            "Hello {{#if contact}}{{contact.name}}{{else}}{{client_name}}{{/if}},\n\nplease find attached invoice {{invoice_number}} dated {{invoice_date}} for {{total}}.\n\nKind regards\n{{sender_name}}\n",
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transport_requires_explicit_configuration() {
+        assert!(transport_name(None, false).is_err());
+        assert_eq!(transport_name(None, true).unwrap(), "smtp");
+        assert_eq!(transport_name(Some("log".into()), false).unwrap(), "log");
+        assert_eq!(transport_name(Some("file".into()), true).unwrap(), "file");
+    }
+
+    #[tokio::test]
+    async fn memory_transport_keeps_sent_messages() {
+        let mailer = Mailer::memory();
+        mailer
+            .send(OutgoingMail {
+                to: vec!["client@example.com".to_string()],
+                subject: "Invoice INV-0001".to_string(),
+                html: "<p>Please find attached</p>".to_string(),
+                text: "Please find attached".to_string(),
+                attachments: vec![MailAttachment {
+                    filename: "INV-0001.pdf".to_string(),
+                    content_type: "application/pdf".to_string(),
+                    body: b"%PDF-1.4".to_vec(),
+                }],
+            })
+            .await
+            .unwrap();
+
+        let sent = mailer.sent().await;
+        assert_eq!(sent.len(), 1);
+        assert!(sent[0].contains("To: client@example.com"));
+        assert!(sent[0].contains("Subject: Invoice INV-0001"));
+        assert!(sent[0].contains("INV-0001.pdf"));
+    }
+
+    #[test]
+    fn only_smtp_and_file_count_as_delivered() {
+        let log = Mailer {
+            transport: Arc::new(MailTransport::Log),
+            from: "Freelance Forge <no-reply@localhost>".parse().unwrap(),
+        };
+        assert!(!log.delivers());
+        assert!(!Mailer::memory().delivers());
+    }
+
+    #[tokio::test]
+    async fn invalid_recipient_is_an_error() {
+        let mailer = Mailer::memory();
+        let result = mailer
+            .send(OutgoingMail {
+                to: vec!["not an address".to_string()],
+                subject: "Hi".to_string(),
+                html: String::new(),
+                text: String::new(),
+                attachments: Vec::new(),
+            })
+            .await;
+        assert!(result.is_err());
+        assert!(mailer.sent().await.is_empty());
+    }
+}
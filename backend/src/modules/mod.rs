@@ -1,6 +1,31 @@
+pub mod access_grants;
+pub mod account;
+pub mod api_tokens;
 pub mod auth;
 pub mod ai;
+pub mod audit;
+pub mod bank_statements;
+pub mod banking;
 pub mod company;
+pub mod csrf;
+pub mod dunning;
+pub mod expense_categories;
 pub mod expenses;
 pub mod invoices;
+pub mod mail;
+pub mod ocr;
+pub mod oidc;
+pub mod privacy;
+pub mod rate_limit;
+pub mod reconciliation;
+pub mod receipt_extraction;
+pub mod receipts;
+pub mod sessions;
 pub mod shared;
+pub mod storage;
+#[cfg(test)]
+pub(crate) mod test_support;
+pub mod travel;
+pub mod two_factor;
+pub mod validation;
+pub mod workspaces;
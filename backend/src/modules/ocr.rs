@@ -0,0 +1,179 @@
+use crate::modules::ai::OpenAiOcr;
+use crate::modules::shared::AppError;
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use uuid::Uuid;
+
+/// Turns a receipt file (PDF, PNG, JPEG or WebP) into plain text.
+#[async_trait]
+pub trait OcrEngine: Send + Sync {
+    /// Short engine name for logs and responses.
+    fn name(&self) -> &'static str;
+
+    async fn recognize(&self, content_type: &str, body: &[u8]) -> Result<String, AppError>;
+}
+
+/// Picks the engine from `OCR_ENGINE` (`tesseract` or `openai`). Without it, OpenAI is used
+/// when `OPENAI_API_KEY` is set and the local Tesseract binary otherwise.
+pub fn ocr_from_env() -> anyhow::Result<Arc<dyn OcrEngine>> {
+    let default_engine = if std::env::var("OPENAI_API_KEY").is_ok() {
+        "openai"
+    } else {
+        "tesseract"
+    };
+    match std::env::var("OCR_ENGINE")
+        .unwrap_or_else(|_| default_engine.to_string())
+        .as_str()
+    {
+        "openai" => Ok(Arc::new(OpenAiOcr::from_env()?)),
+        "tesseract" => Ok(Arc::new(TesseractOcr::from_env())),
+        other => anyhow::bail!("Unknown OCR_ENGINE {other:?}, expected tesseract or openai"),
+    }
+}
+
+/// Runs the `tesseract` command line tool. PDFs with a text layer are read with
+/// `pdftotext`; scanned PDFs are rendered page by page with `pdftoppm` first.
+pub struct TesseractOcr {
+    tesseract: String,
+    pdftotext: String,
+    pdftoppm: String,
+    languages: String,
+    max_pages: u32,
+    timeout: Duration,
+}
+
+impl TesseractOcr {
+    /// Reads `TESSERACT_BIN`, `TESSERACT_LANG` (default `deu+eng`), `PDFTOTEXT_BIN`,
+    /// `PDFTOPPM_BIN`, `OCR_MAX_PDF_PAGES` (default 3) and `OCR_TIMEOUT_SECONDS` (default 60).
+    pub fn from_env() -> Self {
+        let var =
+            |name: &str, default: &str| std::env::var(name).unwrap_or_else(|_| default.to_string());
+        Self {
+            tesseract: var("TESSERACT_BIN", "tesseract"),
+            pdftotext: var("PDFTOTEXT_BIN", "pdftotext"),
+            pdftoppm: var("PDFTOPPM_BIN", "pdftoppm"),
+            languages: var("TESSERACT_LANG", "deu+eng"),
+            max_pages: std::env::var("OCR_MAX_PDF_PAGES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(3)
+                .max(1),
+            timeout: Duration::from_secs(
+                std::env::var("OCR_TIMEOUT_SECONDS")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(60),
+            ),
+        }
+    }
+
+    async fn recognize_pdf(&self, dir: &Path, body: &[u8]) -> Result<String, AppError> {
+        let input = dir.join("receipt.pdf");
+        tokio::fs::write(&input, body)
+            .await
+            .map_err(AppError::internal)?;
+
+        let last_page = self.max_pages.to_string();
+        let mut pdftotext = Command::new(&self.pdftotext);
+        pdftotext
+            .args(["-layout", "-l", &last_page])
+            .arg(&input)
+            .arg("-");
+        let text = self.run(pdftotext, None).await?;
+        // A handful of characters is a stamp or page number, not a text layer.
+        if text.chars().filter(|c| c.is_alphanumeric()).count() >= 20 {
+            return Ok(text);
+        }
+
+        let mut pdftoppm = Command::new(&self.pdftoppm);
+        pdftoppm
+            .args(["-r", "300", "-png", "-l", &last_page])
+            .arg(&input)
+            .arg(dir.join("page"));
+        self.run(pdftoppm, None).await?;
+
+        let mut pages = Vec::new();
+        let mut entries = tokio::fs::read_dir(dir).await.map_err(AppError::internal)?;
+        while let Some(entry) = entries.next_entry().await.map_err(AppError::internal)? {
+            let path = entry.path();
+            if path.extension().is_some_and(|extension| extension == "png") {
+                pages.push(path);
+            }
+        }
+        pages.sort();
+
+        let mut text = String::new();
+        for page in pages {
+            let mut tesseract = Command::new(&self.tesseract);
+            tesseract.arg(&page).args(["stdout", "-l", &self.languages]);
+            text.push_str(&self.run(tesseract, None).await?);
+            text.push('\n');
+        }
+        Ok(text)
+    }
+
+    /// Runs the command, feeding it `stdin`, and returns its standard output.
+    async fn run(&self, mut command: Command, stdin: Option<&[u8]>) -> Result<String, AppError> {
+        command
+            .stdin(if stdin.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        let program = command
+            .as_std()
+            .get_program()
+            .to_string_lossy()
+            .into_owned();
+        let mut child = command
+            .spawn()
+            .map_err(|error| AppError::internal(format!("Could not start {program}: {error}")))?;
+        if let (Some(input), Some(mut pipe)) = (stdin, child.stdin.take()) {
+            pipe.write_all(input).await.map_err(AppError::internal)?;
+        }
+        let output = tokio::time::timeout(self.timeout, child.wait_with_output())
+            .await
+            .map_err(|_| AppError::internal(format!("{program} timed out")))?
+            .map_err(AppError::internal)?;
+        if !output.status.success() {
+            return Err(AppError::internal(format!(
+                "{program} failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+#[async_trait]
+impl OcrEngine for TesseractOcr {
+    fn name(&self) -> &'static str {
+        "tesseract"
+    }
+
+    async fn recognize(&self, content_type: &str, body: &[u8]) -> Result<String, AppError> {
+        if content_type != "application/pdf" {
+            let mut tesseract = Command::new(&self.tesseract);
+            tesseract.args(["stdin", "stdout", "-l", &self.languages]);
+            return self.run(tesseract, Some(body)).await;
+        }
+
+        let dir: PathBuf = std::env::temp_dir().join(format!("receipt-ocr-{}", Uuid::new_v4()));
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .map_err(AppError::internal)?;
+        let result = self.recognize_pdf(&dir, body).await;
+        if let Err(error) = tokio::fs::remove_dir_all(&dir).await {
+            eprintln!("Could not remove {}: {error}", dir.display());
+        }
+        result
+    }
+}
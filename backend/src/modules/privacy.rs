@@ -0,0 +1,782 @@
+use crate::entity::{
+    api_token, audit_event, auth_token, bank_account, bank_transaction, company, company_contact,
+    dunning_level, email_template, expense, invoice, invoice_payment, invoice_template,
+    login_throttle, receipt, recovery_code, user, user_identity, workspace, workspace_invitation,
+    workspace_member,
+};
+use crate::modules::account::end_all_sessions;
+use crate::modules::auth::{require_session_user, session_cookie, verify_password, UserResponse};
+use crate::modules::banking::{BankAccountResponse, BankTransactionResponse};
+use crate::modules::company::{CompanyResponse, ContactResponse};
+use crate::modules::dunning::DunningLevelResponse;
+use crate::modules::expenses::{fetch_receipt, ExpenseResponse};
+use crate::modules::invoices::{
+    load_items_for_invoices, render_invoice_pdf, InvoiceResponse, TemplateResponse,
+};
+use crate::modules::receipts::delete_stored_receipts;
+use crate::modules::reconciliation::InvoicePaymentResponse;
+use crate::modules::shared::{AppError, AppState};
+use crate::modules::storage::ReceiptStorage;
+use crate::modules::two_factor::verify_second_factor;
+use crate::modules::validation::{not_blank, ValidatedJson};
+use crate::modules::workspaces::WorkspaceRole;
+use axum::{
+    extract::State,
+    http::{header::SET_COOKIE, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use sea_orm::sea_query::{Expr, Query};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait,
+    PaginatorTrait, QueryFilter, QueryOrder, Set, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::{Cursor, Write};
+use std::time::Duration;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+use zip::write::SimpleFileOptions;
+
+/// Invoices and the receipts behind expenses must be kept for ten full calendar years
+/// (§ 147 AO, § 14b UStG).
+const RETENTION_YEARS: i32 = 10;
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct DeleteAccountRequest {
+    #[validate(custom(function = not_blank))]
+    pub password: String,
+    /// Authenticator or recovery code; required when 2FA is enabled
+    #[validate(length(max = 32))]
+    pub code: Option<String>,
+}
+
+/// Layout of `data.json` in the export archive.
+#[derive(Serialize)]
+struct AccountExport {
+    exported_at: DateTime<Utc>,
+    user: UserResponse,
+    memberships: Vec<MembershipExport>,
+    /// Full contents of the workspaces the user owns
+    workspaces: Vec<WorkspaceExport>,
+    /// Invoice PDFs and receipts (archive path, or URL for linked receipts) that could not
+    /// be included
+    missing_files: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct MembershipExport {
+    workspace_id: Uuid,
+    workspace: String,
+    role: String,
+    joined_at: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+struct WorkspaceExport {
+    id: Uuid,
+    name: String,
+    companies: Vec<CompanyExport>,
+    invoices: Vec<InvoiceResponse>,
+    invoice_payments: Vec<InvoicePaymentResponse>,
+    expenses: Vec<ExpenseResponse>,
+    receipts: Vec<ReceiptExport>,
+    invoice_templates: Vec<TemplateResponse>,
+    email_templates: Vec<EmailTemplateExport>,
+    dunning_levels: Vec<DunningLevelResponse>,
+    bank_accounts: Vec<BankAccountResponse>,
+    bank_transactions: Vec<BankTransactionResponse>,
+}
+
+#[derive(Serialize)]
+struct CompanyExport {
+    #[serde(flatten)]
+    company: CompanyResponse,
+    contacts: Vec<ContactResponse>,
+}
+
+#[derive(Serialize)]
+struct ReceiptExport {
+    id: Uuid,
+    filename: String,
+    content_type: String,
+    size_bytes: i64,
+    sha256: String,
+    created_at: DateTime<Utc>,
+    /// Location of the file in the archive
+    path: String,
+}
+
+#[derive(Serialize)]
+struct EmailTemplateExport {
+    kind: String,
+    subject: String,
+    html: String,
+    text: String,
+}
+
+#[utoipa::path(
+    get,
+    path = "/auth/account/export",
+    responses(
+        (status = 200, description = "ZIP archive with data.json, invoice PDFs and receipt files", content_type = "application/zip"),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Requires a browser session", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "auth"
+)]
+pub async fn export_account(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let current_user = require_session_user(&state, &headers).await?;
+    let memberships = workspace_member::Entity::find()
+        .filter(workspace_member::Column::UserId.eq(current_user.id))
+        .order_by_asc(workspace_member::Column::CreatedAt)
+        .all(&state.db)
+        .await?;
+    let workspace_ids: Vec<Uuid> = memberships.iter().map(|m| m.workspace_id).collect();
+    let workspaces: HashMap<Uuid, workspace::Model> = workspace::Entity::find()
+        .filter(workspace::Column::Id.is_in(workspace_ids))
+        .all(&state.db)
+        .await?
+        .into_iter()
+        .map(|workspace| (workspace.id, workspace))
+        .collect();
+
+    let mut files = Vec::new();
+    let mut missing_files = Vec::new();
+    let mut membership_exports = Vec::new();
+    let mut workspace_exports = Vec::new();
+    for membership in memberships {
+        let Some(workspace) = workspaces.get(&membership.workspace_id) else {
+            continue;
+        };
+        if membership.role == WorkspaceRole::Owner.as_str() {
+            let export = export_workspace(
+                &state.db,
+                state.receipts.as_ref(),
+                workspace,
+                &mut files,
+                &mut missing_files,
+            )
+            .await?;
+            workspace_exports.push(export);
+        }
+        membership_exports.push(MembershipExport {
+            workspace_id: workspace.id,
+            workspace: workspace.name.clone(),
+            role: membership.role,
+            joined_at: membership.created_at,
+        });
+    }
+
+    let now = Utc::now();
+    let data = AccountExport {
+        exported_at: now,
+        user: current_user.into(),
+        memberships: membership_exports,
+        workspaces: workspace_exports,
+        missing_files,
+    };
+    let json = serde_json::to_vec_pretty(&data).map_err(AppError::internal)?;
+    files.insert(0, ("data.json".to_string(), json));
+    let archive = build_zip(files).map_err(AppError::internal)?;
+
+    let mut response_headers = HeaderMap::new();
+    response_headers.insert(
+        axum::http::header::CONTENT_TYPE,
+        HeaderValue::from_static("application/zip"),
+    );
+    response_headers.insert(
+        axum::http::header::CONTENT_DISPOSITION,
+        HeaderValue::from_str(&format!(
+            "attachment; filename=\"freelance-forge-export-{}.zip\"",
+            now.date_naive()
+        ))
+        .map_err(|_| AppError::internal("Invalid filename"))?,
+    );
+    Ok((response_headers, archive).into_response())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/auth/account",
+    request_body = DeleteAccountRequest,
+    responses(
+        (status = 204, description = "Account deleted and signed out"),
+        (status = 400, description = "Wrong password or authentication code", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Requires a browser session", body = ErrorResponse),
+        (status = 409, description = "A shared workspace would be left without an owner", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "auth"
+)]
+pub async fn delete_account(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidatedJson(payload): ValidatedJson<DeleteAccountRequest>,
+) -> Result<(HeaderMap, StatusCode), AppError> {
+    let current_user = require_session_user(&state, &headers).await?;
+    verify_password(&payload.password, &current_user.password_hash)
+        .map_err(|_| AppError::bad_request("Current password is wrong"))?;
+    if current_user.totp_enabled_at.is_some() {
+        let code = payload.code.as_deref().unwrap_or_default();
+        if !verify_second_factor(&state.db, &current_user, code, Utc::now()).await? {
+            return Err(AppError::bad_request("Invalid authentication code"));
+        }
+    }
+
+    // Check every workspace before changing anything, so a refusal leaves the account intact.
+    let memberships = workspace_member::Entity::find()
+        .filter(workspace_member::Column::UserId.eq(current_user.id))
+        .all(&state.db)
+        .await?;
+    let mut sole_member_of = Vec::new();
+    for membership in &memberships {
+        let others = workspace_member::Entity::find()
+            .filter(workspace_member::Column::WorkspaceId.eq(membership.workspace_id))
+            .filter(workspace_member::Column::UserId.ne(current_user.id))
+            .all(&state.db)
+            .await?;
+        if others.is_empty() {
+            sole_member_of.push(membership.workspace_id);
+            continue;
+        }
+        let owner = WorkspaceRole::Owner.as_str();
+        if membership.role == owner && !others.iter().any(|other| other.role == owner) {
+            let name = workspace::Entity::find_by_id(membership.workspace_id)
+                .one(&state.db)
+                .await?
+                .map(|workspace| workspace.name)
+                .unwrap_or_default();
+            return Err(AppError::conflict(format!(
+                "Make another member owner of {} before deleting your account",
+                name
+            )));
+        }
+    }
+
+    let today = Utc::now().date_naive();
+    let retain_until = NaiveDate::from_ymd_opt(today.year() + RETENTION_YEARS + 1, 1, 1)
+        .ok_or_else(|| AppError::internal("Retention date out of range"))?;
+    let user_id = current_user.id;
+    let email = current_user.email.clone();
+
+    let txn = state.db.begin().await?;
+    let mut removed_receipts = Vec::new();
+    for workspace_id in sole_member_of {
+        removed_receipts.extend(close_workspace(&txn, workspace_id, retain_until).await?);
+    }
+    workspace_member::Entity::delete_many()
+        .filter(workspace_member::Column::UserId.eq(user_id))
+        .exec(&txn)
+        .await?;
+    workspace_invitation::Entity::delete_many()
+        .filter(workspace_invitation::Column::Email.eq(email.clone()))
+        .exec(&txn)
+        .await?;
+    end_all_sessions(&txn, user_id).await?;
+    api_token::Entity::delete_many()
+        .filter(api_token::Column::UserId.eq(user_id))
+        .exec(&txn)
+        .await?;
+    auth_token::Entity::delete_many()
+        .filter(auth_token::Column::UserId.eq(user_id))
+        .exec(&txn)
+        .await?;
+    recovery_code::Entity::delete_many()
+        .filter(recovery_code::Column::UserId.eq(user_id))
+        .exec(&txn)
+        .await?;
+    user_identity::Entity::delete_many()
+        .filter(user_identity::Column::UserId.eq(user_id))
+        .exec(&txn)
+        .await?;
+    audit_event::Entity::delete_many()
+        .filter(audit_event::Column::UserId.eq(user_id))
+        .exec(&txn)
+        .await?;
+    login_throttle::Entity::delete_by_id(email).exec(&txn).await?;
+
+    // The row stays because retained invoices and expenses still reference it.
+    let mut active: user::ActiveModel = current_user.into();
+    active.email = Set(format!("deleted-{}@deleted.invalid", user_id));
+    active.password_hash = Set(String::new());
+    active.address = Set(None);
+    active.company_id = Set(None);
+    active.email_verified_at = Set(None);
+    active.totp_secret = Set(None);
+    active.totp_enabled_at = Set(None);
+    active.totp_last_step = Set(None);
+    active.deleted_at = Set(Some(Utc::now()));
+    active.update(&txn).await?;
+    txn.commit().await?;
+    delete_stored_receipts(state.receipts.as_ref(), removed_receipts).await;
+
+    let mut response_headers = HeaderMap::new();
+    response_headers.insert(SET_COOKIE, session_cookie("", 0)?);
+    Ok((response_headers, StatusCode::NO_CONTENT))
+}
+
+/// Deletes archived workspaces whose retention period has ended, once a day by default
+/// (`RETENTION_PURGE_INTERVAL_HOURS`).
+pub fn spawn_retention_purge_job(state: AppState) {
+    let hours = std::env::var("RETENTION_PURGE_INTERVAL_HOURS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(24)
+        .max(1);
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(hours * 3600));
+        loop {
+            interval.tick().await;
+            let today = Utc::now().date_naive();
+            match purge_expired_workspaces(&state.db, state.receipts.as_ref(), today).await {
+                Ok(0) => {}
+                Ok(count) => println!("Retention: purged {count} archived workspace(s)"),
+                Err(error) => eprintln!("Retention purge failed: {error}"),
+            }
+        }
+    });
+}
+
+pub async fn purge_expired_workspaces(
+    db: &DatabaseConnection,
+    storage: &dyn ReceiptStorage,
+    today: NaiveDate,
+) -> Result<u64, AppError> {
+    let expired = workspace::Entity::find()
+        .filter(workspace::Column::ArchivedAt.is_not_null())
+        .filter(workspace::Column::RetainUntil.lt(today))
+        .all(db)
+        .await?;
+    if expired.is_empty() {
+        return Ok(0);
+    }
+    let ids: Vec<Uuid> = expired.iter().map(|workspace| workspace.id).collect();
+    let receipt_keys: Vec<String> = receipt::Entity::find()
+        .filter(receipt::Column::WorkspaceId.is_in(ids.clone()))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|receipt| receipt.storage_key)
+        .collect();
+    let result = workspace::Entity::delete_many()
+        .filter(workspace::Column::Id.is_in(ids))
+        .exec(db)
+        .await?;
+    delete_stored_receipts(storage, receipt_keys).await;
+    Ok(result.rows_affected)
+}
+
+/// Removes a workspace whose last member is leaving. Without issued invoices, expenses or bank
+/// transactions it is deleted outright; otherwise everything that is not a fiscal record goes
+/// and the rest is archived, read only, until `retain_until`. Returns the storage keys of
+/// removed receipts, to be deleted once the transaction has committed.
+async fn close_workspace<C: ConnectionTrait>(
+    db: &C,
+    workspace_id: Uuid,
+    retain_until: NaiveDate,
+) -> Result<Vec<String>, AppError> {
+    let issued = invoice::Entity::find()
+        .filter(invoice::Column::WorkspaceId.eq(workspace_id))
+        .filter(invoice::Column::Status.ne("draft"))
+        .count(db)
+        .await?;
+    let expenses = expense::Entity::find()
+        .filter(expense::Column::WorkspaceId.eq(workspace_id))
+        .count(db)
+        .await?;
+    let bank_transactions = bank_transaction::Entity::find()
+        .filter(bank_transaction::Column::WorkspaceId.eq(workspace_id))
+        .count(db)
+        .await?;
+    if issued == 0 && expenses == 0 && bank_transactions == 0 {
+        let receipt_keys = receipt::Entity::find()
+            .filter(receipt::Column::WorkspaceId.eq(workspace_id))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|receipt| receipt.storage_key)
+            .collect();
+        workspace::Entity::delete_by_id(workspace_id).exec(db).await?;
+        return Ok(receipt_keys);
+    }
+
+    invoice::Entity::delete_many()
+        .filter(invoice::Column::WorkspaceId.eq(workspace_id))
+        .filter(invoice::Column::Status.eq("draft"))
+        .exec(db)
+        .await?;
+    company_contact::Entity::delete_many()
+        .filter(
+            company_contact::Column::CompanyId.in_subquery(
+                Query::select()
+                    .column(company::Column::Id)
+                    .from(company::Entity)
+                    .and_where(company::Column::WorkspaceId.eq(workspace_id))
+                    .to_owned(),
+            ),
+        )
+        .exec(db)
+        .await?;
+    email_template::Entity::delete_many()
+        .filter(email_template::Column::WorkspaceId.eq(workspace_id))
+        .exec(db)
+        .await?;
+    dunning_level::Entity::delete_many()
+        .filter(dunning_level::Column::WorkspaceId.eq(workspace_id))
+        .exec(db)
+        .await?;
+    workspace_invitation::Entity::delete_many()
+        .filter(workspace_invitation::Column::WorkspaceId.eq(workspace_id))
+        .exec(db)
+        .await?;
+    // Receipts no expense refers to are not fiscal records.
+    let unattached = receipt::Entity::find()
+        .filter(receipt::Column::WorkspaceId.eq(workspace_id))
+        .filter(
+            receipt::Column::Id.not_in_subquery(
+                Query::select()
+                    .column(expense::Column::ReceiptId)
+                    .from(expense::Entity)
+                    .and_where(expense::Column::ReceiptId.is_not_null())
+                    .to_owned(),
+            ),
+        )
+        .all(db)
+        .await?;
+    receipt::Entity::delete_many()
+        .filter(receipt::Column::Id.is_in(unattached.iter().map(|receipt| receipt.id)))
+        .exec(db)
+        .await?;
+    workspace::Entity::update_many()
+        .col_expr(workspace::Column::ArchivedAt, Expr::value(Utc::now()))
+        .col_expr(workspace::Column::RetainUntil, Expr::value(retain_until))
+        .filter(workspace::Column::Id.eq(workspace_id))
+        .exec(db)
+        .await?;
+    Ok(unattached.into_iter().map(|receipt| receipt.storage_key).collect())
+}
+
+/// Collects one workspace's records, adding invoice PDFs and receipts to `files`.
+async fn export_workspace(
+    db: &DatabaseConnection,
+    storage: &dyn ReceiptStorage,
+    workspace: &workspace::Model,
+    files: &mut Vec<(String, Vec<u8>)>,
+    missing_files: &mut Vec<String>,
+) -> Result<WorkspaceExport, AppError> {
+    let companies = company::Entity::find()
+        .filter(company::Column::WorkspaceId.eq(workspace.id))
+        .order_by_asc(company::Column::CreatedAt)
+        .all(db)
+        .await?;
+    let company_ids: Vec<Uuid> = companies.iter().map(|company| company.id).collect();
+    let mut contacts_by_company: HashMap<Uuid, Vec<ContactResponse>> = HashMap::new();
+    for contact in company_contact::Entity::find()
+        .filter(company_contact::Column::CompanyId.is_in(company_ids))
+        .order_by_asc(company_contact::Column::CreatedAt)
+        .all(db)
+        .await?
+    {
+        contacts_by_company
+            .entry(contact.company_id)
+            .or_default()
+            .push(contact.into());
+    }
+
+    let invoices = invoice::Entity::find()
+        .filter(invoice::Column::WorkspaceId.eq(workspace.id))
+        .order_by_asc(invoice::Column::Date)
+        .order_by_asc(invoice::Column::InvoiceNumber)
+        .all(db)
+        .await?;
+    let invoice_ids: Vec<Uuid> = invoices.iter().map(|invoice| invoice.id).collect();
+    let mut items_by_invoice = load_items_for_invoices(db, &invoice_ids).await?;
+    for invoice in &invoices {
+        let path = invoice_pdf_path(workspace.id, invoice);
+        match render_invoice_pdf(db, invoice).await {
+            Ok(pdf) => files.push((path, pdf)),
+            Err(error) => {
+                eprintln!("Export: could not render {path}: {error}");
+                missing_files.push(path);
+            }
+        }
+    }
+
+    let expenses = expense::Entity::find()
+        .filter(expense::Column::WorkspaceId.eq(workspace.id))
+        .order_by_asc(expense::Column::Date)
+        .all(db)
+        .await?;
+    // Several expenses can share a receipt, and a legacy key can name a stored receipt;
+    // each file goes into the archive once.
+    let mut receipt_paths = HashSet::new();
+    for receipt_url in expenses.iter().filter_map(|expense| expense.receipt_url.as_deref()) {
+        match fetch_receipt(storage, receipt_url).await {
+            Ok(Some((key, body))) => {
+                let path = format!("receipts/{key}");
+                if receipt_paths.insert(path.clone()) {
+                    files.push((path, body));
+                }
+            }
+            Ok(None) => {}
+            Err(error) => {
+                eprintln!("Export: could not fetch {receipt_url}: {error}");
+                missing_files.push(receipt_url.to_string());
+            }
+        }
+    }
+    let receipts = receipt::Entity::find()
+        .filter(receipt::Column::WorkspaceId.eq(workspace.id))
+        .filter(receipt::Column::ConfirmedAt.is_not_null())
+        .order_by_asc(receipt::Column::CreatedAt)
+        .all(db)
+        .await?;
+    let mut receipt_exports = Vec::with_capacity(receipts.len());
+    for receipt in receipts {
+        let path = format!("receipts/{}", receipt.storage_key);
+        match storage.get(&receipt.storage_key).await {
+            Ok(Some(_)) if !receipt_paths.insert(path.clone()) => {}
+            Ok(Some(body)) => files.push((path.clone(), body)),
+            Ok(None) => missing_files.push(path.clone()),
+            Err(error) => {
+                eprintln!("Export: could not fetch {path}: {error}");
+                missing_files.push(path.clone());
+            }
+        }
+        receipt_exports.push(ReceiptExport {
+            id: receipt.id,
+            filename: receipt.filename,
+            content_type: receipt.content_type,
+            size_bytes: receipt.size_bytes.unwrap_or_default(),
+            sha256: receipt.sha256.unwrap_or_default(),
+            created_at: receipt.created_at,
+            path,
+        });
+    }
+
+    let invoice_templates = invoice_template::Entity::find()
+        .filter(invoice_template::Column::WorkspaceId.eq(workspace.id))
+        .order_by_asc(invoice_template::Column::CreatedAt)
+        .all(db)
+        .await?;
+    let email_templates = email_template::Entity::find()
+        .filter(email_template::Column::WorkspaceId.eq(workspace.id))
+        .order_by_asc(email_template::Column::Kind)
+        .all(db)
+        .await?;
+    let dunning_levels = dunning_level::Entity::find()
+        .filter(dunning_level::Column::WorkspaceId.eq(workspace.id))
+        .order_by_asc(dunning_level::Column::Level)
+        .all(db)
+        .await?;
+    let invoice_payments = invoice_payment::Entity::find()
+        .filter(invoice_payment::Column::WorkspaceId.eq(workspace.id))
+        .order_by_asc(invoice_payment::Column::PaidOn)
+        .all(db)
+        .await?;
+    let bank_accounts = bank_account::Entity::find()
+        .filter(bank_account::Column::WorkspaceId.eq(workspace.id))
+        .order_by_asc(bank_account::Column::Name)
+        .all(db)
+        .await?;
+    let bank_transactions = bank_transaction::Entity::find()
+        .filter(bank_transaction::Column::WorkspaceId.eq(workspace.id))
+        .order_by_asc(bank_transaction::Column::BookingDate)
+        .all(db)
+        .await?;
+
+    Ok(WorkspaceExport {
+        id: workspace.id,
+        name: workspace.name.clone(),
+        companies: companies
+            .into_iter()
+            .map(|company| CompanyExport {
+                contacts: contacts_by_company.remove(&company.id).unwrap_or_default(),
+                company: CompanyResponse {
+                    id: company.id,
+                    user_id: company.user_id,
+                    name: company.name,
+                    address: company.address,
+                    registration_number: company.registration_number,
+                    created_at: company.created_at,
+                },
+            })
+            .collect(),
+        invoices: invoices
+            .into_iter()
+            .map(|invoice| {
+                let items = items_by_invoice.remove(&invoice.id).unwrap_or_default();
+                InvoiceResponse::with_items(invoice, Some(items))
+            })
+            .collect(),
+        invoice_payments: invoice_payments
+            .into_iter()
+            .map(InvoicePaymentResponse::from)
+            .collect(),
+        expenses: expenses.into_iter().map(ExpenseResponse::from).collect(),
+        receipts: receipt_exports,
+        invoice_templates: invoice_templates
+            .into_iter()
+            .map(|template| TemplateResponse {
+                id: template.id,
+                name: template.name,
+                html: template.html,
+            })
+            .collect(),
+        email_templates: email_templates
+            .into_iter()
+            .map(|template| EmailTemplateExport {
+                kind: template.kind,
+                subject: template.subject,
+                html: template.html,
+                text: template.text,
+            })
+            .collect(),
+        dunning_levels: dunning_levels.into_iter().map(DunningLevelResponse::from).collect(),
+        bank_accounts: bank_accounts.into_iter().map(BankAccountResponse::from).collect(),
+        bank_transactions: bank_transactions
+            .into_iter()
+            .map(BankTransactionResponse::from)
+            .collect(),
+    })
+}
+
+fn build_zip(files: Vec<(String, Vec<u8>)>) -> zip::result::ZipResult<Vec<u8>> {
+    let mut archive = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let options =
+        SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    for (path, body) in files {
+        archive.start_file(path, options)?;
+        archive.write_all(&body)?;
+    }
+    Ok(archive.finish()?.into_inner())
+}
+
+/// Invoice numbers are not unique, so the id keeps two invoices from sharing a path.
+fn invoice_pdf_path(workspace_id: Uuid, invoice: &invoice::Model) -> String {
+    format!(
+        "invoices/{}/{}-{}.pdf",
+        workspace_id,
+        safe_file_name(&invoice.invoice_number),
+        invoice.id
+    )
+}
+
+/// Keeps invoice numbers like `2026/001` from creating nested folders in the archive.
+fn safe_file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::test_support::{self, session_headers};
+    use std::io::Read;
+
+    fn invoice(id: u128, number: &str) -> invoice::Model {
+        invoice::Model {
+            id: Uuid::from_u128(id),
+            invoice_number: number.to_string(),
+            user_id: None,
+            workspace_id: Some(Uuid::from_u128(10)),
+            company_id: None,
+            template_id: None,
+            contact_id: None,
+            client_name: "Client GmbH".to_string(),
+            client_address: String::new(),
+            description: String::new(),
+            amount: 100.0,
+            currency: "EUR".to_string(),
+            user_address: String::new(),
+            total_amount: 100.0,
+            date: NaiveDate::from_ymd_opt(2026, 3, 1).unwrap(),
+            due_date: None,
+            status: "sent".to_string(),
+            sent_at: None,
+        }
+    }
+
+    fn archive_entries(archive: Vec<u8>) -> Vec<String> {
+        let archive = zip::ZipArchive::new(Cursor::new(archive)).unwrap();
+        archive.file_names().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn invoices_sharing_a_number_get_their_own_pdf() {
+        let workspace_id = Uuid::from_u128(10);
+        let first = invoice_pdf_path(workspace_id, &invoice(1, "2026/001"));
+        let second = invoice_pdf_path(workspace_id, &invoice(2, "2026/001"));
+
+        assert_eq!(
+            first,
+            format!("invoices/{workspace_id}/2026_001-{}.pdf", Uuid::from_u128(1))
+        );
+        assert_ne!(first, second);
+
+        let files = vec![(first.clone(), b"%PDF-1".to_vec()), (second.clone(), b"%PDF-2".to_vec())];
+        let archive = build_zip(files).ok().unwrap();
+        let mut entries = archive_entries(archive);
+        entries.sort();
+        assert_eq!(entries, [first, second]);
+    }
+
+    #[test]
+    fn duplicate_archive_paths_fail_the_export() {
+        let path = "receipts/a.pdf".to_string();
+        assert!(build_zip(vec![(path.clone(), Vec::new()), (path, Vec::new())]).is_err());
+    }
+
+    #[tokio::test]
+    async fn members_export_their_memberships_but_not_the_workspace_contents() {
+        let user_id = Uuid::from_u128(1);
+        let workspace = workspace::Model {
+            id: Uuid::from_u128(10),
+            name: "Studio".to_string(),
+            created_at: Utc::now(),
+            archived_at: None,
+            retain_until: None,
+        };
+        let db = test_support::signed_in(
+            test_support::session(Uuid::from_u128(2), user_id),
+            test_support::user(user_id, "member@example.com"),
+        )
+        .append_query_results([[workspace_member::Model {
+            id: Uuid::from_u128(3),
+            workspace_id: workspace.id,
+            user_id,
+            role: WorkspaceRole::Member.as_str().to_string(),
+            created_at: Utc::now(),
+        }]])
+        .append_query_results([[workspace.clone()]])
+        .into_connection();
+
+        let response = export_account(State(AppState::for_tests(db)), session_headers())
+            .await
+            .ok()
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(archive_entries(body.to_vec()), ["data.json"]);
+
+        let mut archive = zip::ZipArchive::new(Cursor::new(body.to_vec())).unwrap();
+        let mut json = String::new();
+        archive.by_name("data.json").unwrap().read_to_string(&mut json).unwrap();
+        let data: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(data["user"]["email"], "member@example.com");
+        assert_eq!(data["memberships"][0]["workspace"], "Studio");
+        assert_eq!(data["memberships"][0]["role"], "member");
+        assert_eq!(data["workspaces"], serde_json::json!([]));
+    }
+}
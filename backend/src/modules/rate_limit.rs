@@ -0,0 +1,483 @@
+use crate::entity::login_throttle;
+use crate::modules::audit::{record_event, LOGIN_FAILED, LOGIN_LOCKED};
+use crate::modules::shared::{clone_db, current_client_ip, AppError, AppState};
+use axum::{
+    extract::{Request, State},
+    http::Method,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, DbBackend, EntityTrait,
+    QueryFilter, Statement,
+};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Failed logins for one email before it is locked out.
+const LOCKOUT_THRESHOLD: i32 = 5;
+const LOCKOUT_BASE_SECS: i64 = 60;
+const LOCKOUT_MAX_SECS: i64 = 60 * 60;
+/// Failures older than this no longer count towards a lockout.
+const FAILURE_MEMORY_HOURS: i64 = 24;
+const CLEANUP_INTERVAL_SECS: u64 = 10 * 60;
+
+tokio::task_local! {
+    static ROUTE_GROUP: RouteGroup;
+}
+
+/// Routes that share a limit, configured with `RATE_LIMIT_<GROUP>=<requests>/<seconds>`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RouteGroup {
+    /// Login, registration and other unauthenticated credential endpoints
+    Auth,
+    /// Endpoints that call paid AI providers, including receipt OCR
+    Ai,
+    /// Everything else
+    Api,
+}
+
+impl RouteGroup {
+    fn of(method: &Method, path: &str) -> Option<Self> {
+        const AUTH_PATHS: [&str; 6] = [
+            "/auth/login",
+            "/auth/login/2fa",
+            "/auth/register",
+            "/auth/password-reset/request",
+            "/auth/password-reset",
+            "/auth/verify-email",
+        ];
+        if path == "/" || path.starts_with("/docs") || path.starts_with("/api-doc") {
+            return None;
+        }
+        if method == Method::POST && AUTH_PATHS.contains(&path) {
+            return Some(Self::Auth);
+        }
+        if path.starts_with("/ai/")
+            || method == Method::POST
+                && path.starts_with("/receipts/")
+                && path.ends_with("/extract")
+        {
+            return Some(Self::Ai);
+        }
+        Some(Self::Api)
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Auth => "auth",
+            Self::Ai => "ai",
+            Self::Api => "api",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Limit {
+    requests: u32,
+    window: Duration,
+}
+
+impl Limit {
+    /// Reads `<requests>/<seconds>` from `var`, falling back to the given default.
+    fn from_env(var: &str, requests: u32, seconds: u64) -> anyhow::Result<Self> {
+        let Ok(value) = std::env::var(var) else {
+            return Ok(Self {
+                requests,
+                window: Duration::from_secs(seconds),
+            });
+        };
+        let (requests, seconds) = value
+            .split_once('/')
+            .and_then(|(requests, seconds)| {
+                let requests = requests.trim().parse::<u32>().ok()?;
+                let seconds = seconds.trim().parse::<u64>().ok()?;
+                Some((requests, seconds))
+            })
+            .filter(|(requests, seconds)| *requests > 0 && *seconds > 0)
+            .ok_or_else(|| {
+                anyhow::anyhow!("{var} must look like <requests>/<seconds>, got {value}")
+            })?;
+        Ok(Self {
+            requests,
+            window: Duration::from_secs(seconds),
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimiter {
+    store: Option<Arc<RateLimitStore>>,
+    auth: Limit,
+    ai: Limit,
+    api: Limit,
+}
+
+enum RateLimitStore {
+    /// Per-process counters; fine for a single instance.
+    Memory(Mutex<HashMap<String, MemoryBucket>>),
+    /// Shared counters in the `rate_limit_bucket` table for multi-instance deployments.
+    Postgres(DatabaseConnection),
+}
+
+struct MemoryBucket {
+    window_start: Instant,
+    count: u32,
+}
+
+impl RateLimiter {
+    pub fn from_env(db: &DatabaseConnection) -> anyhow::Result<Self> {
+        let enabled = std::env::var("RATE_LIMIT_ENABLED")
+            .map(|v| v != "false")
+            .unwrap_or(true);
+        let store = match std::env::var("RATE_LIMIT_STORE")
+            .unwrap_or_else(|_| "memory".to_string())
+            .as_str()
+        {
+            _ if !enabled => None,
+            "memory" => Some(RateLimitStore::Memory(Mutex::new(HashMap::new()))),
+            "postgres" => Some(RateLimitStore::Postgres(clone_db(db))),
+            other => anyhow::bail!("Unknown RATE_LIMIT_STORE: {other}"),
+        };
+
+        Ok(Self {
+            store: store.map(Arc::new),
+            auth: Limit::from_env("RATE_LIMIT_AUTH", 10, 60)?,
+            ai: Limit::from_env("RATE_LIMIT_AI", 20, 60)?,
+            api: Limit::from_env("RATE_LIMIT_API", 300, 60)?,
+        })
+    }
+
+    /// A limiter that never throttles, for tests.
+    #[cfg(test)]
+    pub(crate) fn disabled() -> Self {
+        let limit = Limit {
+            requests: 1,
+            window: Duration::from_secs(1),
+        };
+        Self {
+            store: None,
+            auth: limit,
+            ai: limit,
+            api: limit,
+        }
+    }
+
+    fn limit(&self, group: RouteGroup) -> Limit {
+        match group {
+            RouteGroup::Auth => self.auth,
+            RouteGroup::Ai => self.ai,
+            RouteGroup::Api => self.api,
+        }
+    }
+
+    /// Counts a request against `key`. Returns the seconds until the window resets
+    /// once the limit is exceeded.
+    async fn hit(&self, key: &str, limit: Limit) -> Result<Option<u64>, AppError> {
+        let Some(store) = &self.store else {
+            return Ok(None);
+        };
+        match store.as_ref() {
+            RateLimitStore::Memory(buckets) => {
+                let now = Instant::now();
+                let mut buckets = buckets.lock().unwrap_or_else(|e| e.into_inner());
+                let bucket = buckets.entry(key.to_string()).or_insert(MemoryBucket {
+                    window_start: now,
+                    count: 0,
+                });
+                if now.duration_since(bucket.window_start) >= limit.window {
+                    bucket.window_start = now;
+                    bucket.count = 0;
+                }
+                bucket.count += 1;
+                if bucket.count <= limit.requests {
+                    return Ok(None);
+                }
+                let elapsed = now.duration_since(bucket.window_start);
+                Ok(Some(limit.window.saturating_sub(elapsed).as_secs().max(1)))
+            }
+            RateLimitStore::Postgres(db) => {
+                let now = Utc::now();
+                let window =
+                    chrono::Duration::from_std(limit.window).map_err(AppError::internal)?;
+                let row = db
+                    .query_one(Statement::from_sql_and_values(
+                        DbBackend::Postgres,
+                        r#"INSERT INTO rate_limit_bucket (key, window_start, count)
+                           VALUES ($1, $2, 1)
+                           ON CONFLICT (key) DO UPDATE SET
+                               count = CASE WHEN rate_limit_bucket.window_start <= $3
+                                            THEN 1 ELSE rate_limit_bucket.count + 1 END,
+                               window_start = CASE WHEN rate_limit_bucket.window_start <= $3
+                                                   THEN $2 ELSE rate_limit_bucket.window_start END
+                           RETURNING count, window_start"#,
+                        [key.into(), now.into(), (now - window).into()],
+                    ))
+                    .await?
+                    .ok_or_else(|| AppError::internal("rate limit upsert returned no row"))?;
+                let count: i32 = row.try_get("", "count")?;
+                let window_start: DateTime<Utc> = row.try_get("", "window_start")?;
+                if count <= limit.requests as i32 {
+                    return Ok(None);
+                }
+                let remaining = (window_start + window - now).num_seconds();
+                Ok(Some(remaining.max(1) as u64))
+            }
+        }
+    }
+
+    /// Drops counters whose window has long passed.
+    async fn purge(&self) -> Result<u64, AppError> {
+        let Some(store) = &self.store else {
+            return Ok(0);
+        };
+        let longest = [self.auth, self.ai, self.api]
+            .into_iter()
+            .map(|limit| limit.window)
+            .max()
+            .unwrap_or_default();
+        match store.as_ref() {
+            RateLimitStore::Memory(buckets) => {
+                let mut buckets = buckets.lock().unwrap_or_else(|e| e.into_inner());
+                let before = buckets.len();
+                buckets.retain(|_, bucket| bucket.window_start.elapsed() < longest);
+                Ok((before - buckets.len()) as u64)
+            }
+            RateLimitStore::Postgres(db) => {
+                let cutoff =
+                    Utc::now() - chrono::Duration::from_std(longest).map_err(AppError::internal)?;
+                let result = db
+                    .execute(Statement::from_sql_and_values(
+                        DbBackend::Postgres,
+                        "DELETE FROM rate_limit_bucket WHERE window_start < $1",
+                        [cutoff.into()],
+                    ))
+                    .await?;
+                Ok(result.rows_affected())
+            }
+        }
+    }
+}
+
+/// Applies the per-IP limit of the request's route group and remembers the group so
+/// [`check_account_limit`] can apply the per-account limit once the caller is known.
+pub async fn rate_limit(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let Some(group) = RouteGroup::of(request.method(), request.uri().path()) else {
+        return next.run(request).await;
+    };
+
+    if let Some(ip) = current_client_ip() {
+        let key = format!("{}:ip:{}", group.name(), ip);
+        match state.rate_limiter.hit(&key, state.rate_limiter.limit(group)).await {
+            Ok(Some(retry_after)) => {
+                return AppError::too_many_requests("Too many requests", retry_after)
+                    .into_response();
+            }
+            Ok(None) => {}
+            // A broken limiter store must not take the whole API down.
+            Err(error) => eprintln!("Rate limiter unavailable: {error}"),
+        }
+    }
+
+    ROUTE_GROUP.scope(group, next.run(request)).await
+}
+
+/// Per-account limit for the current route group, called after authentication.
+pub(crate) async fn check_account_limit(state: &AppState, user_id: Uuid) -> Result<(), AppError> {
+    let Ok(group) = ROUTE_GROUP.try_with(|group| *group) else {
+        return Ok(());
+    };
+    let key = format!("{}:user:{}", group.name(), user_id);
+    match state.rate_limiter.hit(&key, state.rate_limiter.limit(group)).await {
+        Ok(Some(retry_after)) => Err(AppError::too_many_requests(
+            "Too many requests",
+            retry_after,
+        )),
+        Ok(None) => Ok(()),
+        Err(error) => {
+            eprintln!("Rate limiter unavailable: {error}");
+            Ok(())
+        }
+    }
+}
+
+/// Removes stale rate limit counters and login throttles every few minutes.
+pub fn spawn_rate_limit_cleanup(limiter: RateLimiter, db: DatabaseConnection) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(CLEANUP_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            if let Err(error) = limiter.purge().await {
+                eprintln!("Rate limit cleanup failed: {error}");
+            }
+            if let Err(error) = purge_login_throttles(&db, Utc::now()).await {
+                eprintln!("Login throttle cleanup failed: {error}");
+            }
+        }
+    });
+}
+
+/// Drops throttles whose failures no longer count and whose lockout has ended.
+async fn purge_login_throttles<C: ConnectionTrait>(
+    db: &C,
+    now: DateTime<Utc>,
+) -> Result<u64, AppError> {
+    let result = login_throttle::Entity::delete_many()
+        .filter(
+            login_throttle::Column::LastFailedAt
+                .lt(now - chrono::Duration::hours(FAILURE_MEMORY_HOURS)),
+        )
+        .filter(
+            Condition::any()
+                .add(login_throttle::Column::LockedUntil.is_null())
+                .add(login_throttle::Column::LockedUntil.lte(now)),
+        )
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+/// Returns the error to answer with while `email` is locked out, if it is.
+pub(crate) async fn login_lockout<C: ConnectionTrait>(
+    db: &C,
+    email: &str,
+) -> Result<Option<AppError>, AppError> {
+    let now = Utc::now();
+    let locked_until = login_throttle::Entity::find()
+        .filter(login_throttle::Column::Email.eq(email))
+        .one(db)
+        .await?
+        .and_then(|throttle| throttle.locked_until)
+        .filter(|locked_until| *locked_until > now);
+    Ok(locked_until.map(|locked_until| {
+        AppError::too_many_requests(
+            "Too many failed login attempts. Try again later.",
+            (locked_until - now).num_seconds().max(1) as u64,
+        )
+    }))
+}
+
+/// Counts a failed login for an existing account and locks it once `LOCKOUT_THRESHOLD`
+/// is reached. Every further failure doubles the lockout, up to `LOCKOUT_MAX_SECS`.
+/// Unknown emails are not tracked, so they cannot fill the table.
+pub(crate) async fn record_login_failure<C: ConnectionTrait>(
+    db: &C,
+    email: &str,
+    user_id: Uuid,
+) -> Result<(), AppError> {
+    let now = Utc::now();
+    let row = db
+        .query_one(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            r#"INSERT INTO login_throttle (email, failed_count, last_failed_at, locked_until)
+               VALUES ($1, 1, $2, NULL)
+               ON CONFLICT (email) DO UPDATE SET
+                   failed_count = CASE WHEN login_throttle.last_failed_at < $3
+                                       THEN 1 ELSE login_throttle.failed_count + 1 END,
+                   last_failed_at = EXCLUDED.last_failed_at
+               RETURNING failed_count"#,
+            [
+                email.into(),
+                now.into(),
+                (now - chrono::Duration::hours(FAILURE_MEMORY_HOURS)).into(),
+            ],
+        ))
+        .await?
+        .ok_or_else(|| AppError::internal("login throttle upsert returned no row"))?;
+    let failed_count: i32 = row.try_get("", "failed_count")?;
+
+    record_event(
+        db,
+        Some(user_id),
+        LOGIN_FAILED,
+        Some(format!("email={email} attempt={failed_count}")),
+    )
+    .await?;
+
+    if failed_count >= LOCKOUT_THRESHOLD {
+        let doublings = (failed_count - LOCKOUT_THRESHOLD).min(16) as u32;
+        let seconds = (LOCKOUT_BASE_SECS << doublings).min(LOCKOUT_MAX_SECS);
+        let locked_until = now + chrono::Duration::seconds(seconds);
+        login_throttle::Entity::update_many()
+            .col_expr(login_throttle::Column::LockedUntil, Expr::value(locked_until))
+            .filter(login_throttle::Column::Email.eq(email))
+            .exec(db)
+            .await?;
+        record_event(
+            db,
+            Some(user_id),
+            LOGIN_LOCKED,
+            Some(format!("email={email} seconds={seconds}")),
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Forgets past failures after a successful login.
+pub(crate) async fn clear_login_failures<C: ConnectionTrait>(
+    db: &C,
+    email: &str,
+) -> Result<(), AppError> {
+    login_throttle::Entity::delete_many()
+        .filter(login_throttle::Column::Email.eq(email))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{MockDatabase, MockExecResult, Transaction};
+
+    #[tokio::test]
+    async fn purge_keeps_recent_failures_and_running_lockouts() {
+        let db = MockDatabase::new(DbBackend::Postgres)
+            .append_exec_results([MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 3,
+            }])
+            .into_connection();
+        let now = Utc::now();
+
+        assert_eq!(purge_login_throttles(&db, now).await.unwrap(), 3);
+        assert_eq!(
+            db.into_transaction_log(),
+            vec![Transaction::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"DELETE FROM "login_throttle" WHERE "login_throttle"."last_failed_at" < $1 AND ("login_throttle"."locked_until" IS NULL OR "login_throttle"."locked_until" <= $2)"#,
+                [(now - chrono::Duration::hours(FAILURE_MEMORY_HOURS)).into(), now.into()],
+            )]
+        );
+    }
+
+    #[test]
+    fn groups_routes_by_cost() {
+        let cases = [
+            (Method::POST, "/auth/login", Some(RouteGroup::Auth)),
+            (Method::GET, "/auth/login", Some(RouteGroup::Api)),
+            (Method::POST, "/ai/chat", Some(RouteGroup::Ai)),
+            (
+                Method::POST,
+                "/receipts/6f1c2a4e-8b1d-4d35-9a0e-3c5b7e9f1a2b/extract",
+                Some(RouteGroup::Ai),
+            ),
+            (
+                Method::GET,
+                "/receipts/6f1c2a4e-8b1d-4d35-9a0e-3c5b7e9f1a2b",
+                Some(RouteGroup::Api),
+            ),
+            (Method::POST, "/receipts", Some(RouteGroup::Api)),
+            (Method::GET, "/docs/index.html", None),
+        ];
+        for (method, path, expected) in cases {
+            assert_eq!(RouteGroup::of(&method, path), expected, "{method} {path}");
+        }
+    }
+}
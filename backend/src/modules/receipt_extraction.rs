@@ -0,0 +1,678 @@
+use crate::modules::api_tokens::ApiScope;
+use crate::modules::auth::require_access;
+use crate::modules::expenses::ExpenseCreateRequest;
+use crate::modules::receipts::find_receipt;
+use crate::modules::shared::{AppError, AppState};
+use crate::modules::validation::Path;
+use axum::{
+    Json,
+    extract::State,
+    http::HeaderMap,
+};
+use chrono::{Datelike, NaiveDate, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Total, net and VAT amounts that add up within a cent are trusted more.
+const CONSISTENT_CONFIDENCE: f64 = 0.95;
+
+#[derive(Serialize, ToSchema)]
+pub struct ReceiptExtractionResponse {
+    pub receipt_id: Uuid,
+    /// OCR engine that read the receipt: `tesseract` or `openai`
+    pub engine: String,
+    /// Recognised text
+    pub text: String,
+    pub fields: ExtractedFields,
+    /// Expense prefilled from the fields; review it and send it to `POST /expenses`
+    pub draft: ExpenseCreateRequest,
+}
+
+/// Values found on the receipt. Each carries a confidence between 0 and 1; fields that
+/// could not be found are omitted.
+#[derive(Default, Serialize, ToSchema)]
+pub struct ExtractedFields {
+    pub vendor: Option<ExtractedText>,
+    pub date: Option<ExtractedDate>,
+    /// Gross total
+    pub amount: Option<ExtractedAmount>,
+    pub net_amount: Option<ExtractedAmount>,
+    pub vat_amount: Option<ExtractedAmount>,
+    /// VAT rate in percent; omitted when the receipt mixes rates
+    pub vat_rate: Option<ExtractedAmount>,
+    pub currency: Option<ExtractedText>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ExtractedText {
+    pub value: String,
+    pub confidence: f64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ExtractedDate {
+    pub value: NaiveDate,
+    pub confidence: f64,
+}
+
+#[derive(Clone, Copy, Serialize, ToSchema)]
+pub struct ExtractedAmount {
+    pub value: f64,
+    pub confidence: f64,
+}
+
+#[utoipa::path(
+    post,
+    path = "/receipts/{id}/extract",
+    responses(
+        (status = 200, description = "Recognised text, extracted fields and a draft expense", body = ReceiptExtractionResponse),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 404, description = "Receipt not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse),
+        (status = 502, description = "OCR provider failed", body = ErrorResponse)
+    ),
+    tag = "expenses"
+)]
+pub async fn extract_receipt(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ReceiptExtractionResponse>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::ExpensesWrite).await?;
+    let id = Uuid::parse_str(&id).map_err(|_| AppError::bad_request("Invalid id"))?;
+    let receipt = find_receipt(&state.db, access.workspace_id, id).await?;
+    let body = state
+        .receipts
+        .get(&receipt.storage_key)
+        .await?
+        .ok_or_else(|| {
+            AppError::internal(format!("Receipt file {} missing", receipt.storage_key))
+        })?;
+
+    let text = state.ocr.recognize(&receipt.content_type, &body).await?;
+    let fields = extract_fields(&text, Utc::now().date_naive());
+    let draft = draft_expense(&fields, receipt.id, receipt.created_at.date_naive());
+
+    Ok(Json(ReceiptExtractionResponse {
+        receipt_id: receipt.id,
+        engine: state.ocr.name().to_string(),
+        text,
+        fields,
+        draft,
+    }))
+}
+
+/// An expense from the extracted fields. Missing vendor and currency stay empty and EUR,
+/// a missing date falls back to the upload date. Of the VAT figures only the total and the
+/// rate (or else the net amount) are passed on, so the expense endpoint derives the rest.
+pub(crate) fn draft_expense(
+    fields: &ExtractedFields,
+    receipt_id: Uuid,
+    uploaded_on: NaiveDate,
+) -> ExpenseCreateRequest {
+    let amount = fields.amount.map(|amount| amount.value);
+    let vat_rate = fields.vat_rate.map(|rate| rate.value);
+    let net_amount = fields
+        .net_amount
+        .map(|net| net.value)
+        .filter(|net| amount.is_none_or(|gross| *net < gross))
+        .filter(|_| amount.is_none() || vat_rate.is_none());
+
+    ExpenseCreateRequest {
+        vendor: fields
+            .vendor
+            .as_ref()
+            .map(|vendor| vendor.value.clone())
+            .unwrap_or_default(),
+        description: String::new(),
+        amount,
+        net_amount,
+        vat_rate,
+        input_tax_deductible: None,
+        reverse_charge: false,
+        currency: fields
+            .currency
+            .as_ref()
+            .map(|currency| currency.value.clone())
+            .unwrap_or_else(|| "EUR".to_string()),
+        date: fields
+            .date
+            .as_ref()
+            .map(|date| date.value)
+            .unwrap_or(uploaded_on),
+        category: None,
+        category_id: None,
+        receipt_id: Some(receipt_id),
+        receipt_url: None,
+    }
+}
+
+/// Reads vendor, date, totals, VAT and currency from OCR text of German or English
+/// receipts. Dates after `today` are ignored.
+pub(crate) fn extract_fields(text: &str, today: NaiveDate) -> ExtractedFields {
+    let lines: Vec<String> = text
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect();
+
+    let mut fields = ExtractedFields {
+        vendor: find_vendor(&lines),
+        date: find_date(&lines, today),
+        currency: find_currency(&lines),
+        ..Default::default()
+    };
+    find_amounts(&lines, &mut fields);
+    fields
+}
+
+fn find_vendor(lines: &[String]) -> Option<ExtractedText> {
+    const SKIP: &[&str] = &[
+        "rechnung",
+        "quittung",
+        "beleg",
+        "kassenbon",
+        "bon",
+        "receipt",
+        "invoice",
+        "tel",
+        "telefon",
+        "fax",
+        "www",
+        "http",
+        "https",
+        "str",
+        "strasse",
+        "straße",
+        "ust-id",
+        "steuernummer",
+        "willkommen",
+        "welcome",
+    ];
+    const LEGAL_FORMS: &[&str] = &[
+        "gmbh", "ag", "ug", "kg", "ohg", "ek", "e.k", "ltd", "inc", "llc",
+    ];
+
+    lines.iter().take(6).find_map(|line| {
+        let words = words(line);
+        let letters = line.chars().filter(|c| c.is_alphabetic()).count();
+        let digits = line.chars().filter(|c| c.is_ascii_digit()).count();
+        if letters < 3 || digits > letters || words.iter().any(|word| SKIP.contains(&word.as_str()))
+        {
+            return None;
+        }
+        let confidence = if words
+            .iter()
+            .any(|word| LEGAL_FORMS.contains(&word.as_str()))
+        {
+            0.8
+        } else {
+            0.6
+        };
+        Some(ExtractedText {
+            value: line.chars().take(200).collect(),
+            confidence,
+        })
+    })
+}
+
+fn find_date(lines: &[String], today: NaiveDate) -> Option<ExtractedDate> {
+    let mut labelled = None;
+    let mut found = Vec::new();
+    for line in lines {
+        let words = words(line);
+        for token in line.split_whitespace() {
+            let Some(date) = parse_date(token) else {
+                continue;
+            };
+            if date > today {
+                continue;
+            }
+            if labelled.is_none()
+                && words
+                    .iter()
+                    .any(|word| ["datum", "date", "rechnungsdatum"].contains(&word.as_str()))
+            {
+                labelled = Some(date);
+            }
+            found.push(date);
+        }
+    }
+
+    if let Some(date) = labelled {
+        return Some(ExtractedDate {
+            value: date,
+            confidence: 0.9,
+        });
+    }
+    let first = *found.first()?;
+    let confidence = if found.iter().all(|date| *date == first) {
+        0.8
+    } else {
+        0.6
+    };
+    Some(ExtractedDate {
+        value: first,
+        confidence,
+    })
+}
+
+fn parse_date(token: &str) -> Option<NaiveDate> {
+    const FORMATS: &[&str] = &[
+        "%d.%m.%Y", "%d.%m.%y", "%Y-%m-%d", "%d/%m/%Y", "%d/%m/%y", "%d-%m-%Y",
+    ];
+    let token = token.trim_matches(|c: char| !c.is_ascii_digit());
+    FORMATS.iter().find_map(|format| {
+        NaiveDate::parse_from_str(token, format)
+            .ok()
+            .filter(|date| date.year() >= 2000)
+    })
+}
+
+fn find_currency(lines: &[String]) -> Option<ExtractedText> {
+    const CURRENCIES: &[(&str, &str, &str)] = &[
+        ("EUR", "€", "eur"),
+        ("USD", "$", "usd"),
+        ("GBP", "£", "gbp"),
+        ("CHF", "chf", "chf"),
+    ];
+    let mut counts = [0usize; 4];
+    for line in lines {
+        let lower = line.to_lowercase();
+        let words = words(line);
+        for (index, (_, symbol, code)) in CURRENCIES.iter().enumerate() {
+            counts[index] += lower.matches(symbol).count();
+            if symbol != code {
+                counts[index] += words.iter().filter(|word| word == code).count();
+            }
+        }
+    }
+    let (index, count) = counts.iter().enumerate().max_by_key(|(_, count)| **count)?;
+    if *count == 0 {
+        return None;
+    }
+    let others = counts.iter().filter(|other| **other > 0).count() > 1;
+    Some(ExtractedText {
+        value: CURRENCIES[index].0.to_string(),
+        confidence: if others { 0.6 } else { 0.9 },
+    })
+}
+
+fn find_amounts(lines: &[String], fields: &mut ExtractedFields) {
+    const TOTAL: &[&str] = &[
+        "gesamt",
+        "gesamtbetrag",
+        "summe",
+        "total",
+        "endbetrag",
+        "zahlbetrag",
+        "betrag",
+        "brutto",
+        "bruttobetrag",
+        "due",
+        "zahlen",
+    ];
+    const NET: &[&str] = &["netto", "nettobetrag", "net", "subtotal", "zwischensumme"];
+    const VAT: &[&str] = &[
+        "mwst",
+        "ust",
+        "umsatzsteuer",
+        "mehrwertsteuer",
+        "vat",
+        "tax",
+        "steuer",
+    ];
+
+    let mut totals = Vec::new();
+    let mut nets = Vec::new();
+    let mut vat_amounts = Vec::new();
+    let mut rates = Vec::new();
+    let mut largest: Option<f64> = None;
+
+    for (index, line) in lines.iter().enumerate() {
+        let words = words(line);
+        let has = |keywords: &[&str]| words.iter().any(|word| keywords.contains(&word.as_str()));
+        let is_total = has(TOTAL);
+        let amounts = parse_amounts(line);
+        largest = amounts.iter().copied().fold(largest, |max, amount| {
+            Some(max.map_or(amount, |max: f64| max.max(amount)))
+        });
+
+        if has(VAT) {
+            let rate = parse_percent(line);
+            if let Some(rate) = rate {
+                rates.push(rate);
+            }
+            match amounts.as_slice() {
+                // Tax summary rows: net, VAT and gross.
+                [.., net, vat, gross] if cents_equal(net + vat, *gross) => {
+                    nets.push(*net);
+                    vat_amounts.push(*vat);
+                    totals.push(*gross);
+                }
+                [net, vat] if rate.is_some_and(|rate| cents_equal(net * rate / 100.0, *vat)) => {
+                    nets.push(*net);
+                    vat_amounts.push(*vat);
+                }
+                // "Total incl. VAT 11,90"
+                [gross] if is_total => totals.push(*gross),
+                [.., vat] => vat_amounts.push(*vat),
+                [] => {}
+            }
+        } else if has(NET) {
+            if let Some(net) = amounts.last() {
+                nets.push(*net);
+            }
+        } else if is_total {
+            // The amount is often printed on the line below the label.
+            let amount = amounts.last().copied().or_else(|| {
+                lines
+                    .get(index + 1)
+                    .and_then(|next| parse_amounts(next).last().copied())
+            });
+            if let Some(amount) = amount {
+                totals.push(amount);
+            }
+        }
+    }
+
+    fields.amount = match totals.iter().copied().reduce(f64::max) {
+        Some(total) => {
+            let agreed = totals.iter().all(|other| cents_equal(*other, total));
+            Some(ExtractedAmount {
+                value: total,
+                confidence: if agreed { 0.9 } else { 0.75 },
+            })
+        }
+        None => largest.map(|value| ExtractedAmount {
+            value,
+            confidence: 0.4,
+        }),
+    };
+
+    let mut distinct_rates: Vec<f64> = Vec::new();
+    for rate in rates {
+        if !distinct_rates.iter().any(|other| cents_equal(*other, rate)) {
+            distinct_rates.push(rate);
+        }
+    }
+    if let [rate] = distinct_rates.as_slice() {
+        fields.vat_rate = Some(ExtractedAmount {
+            value: *rate,
+            confidence: 0.85,
+        });
+    }
+
+    let mut distinct_vat: Vec<f64> = Vec::new();
+    for vat in vat_amounts {
+        if !distinct_vat.iter().any(|other| cents_equal(*other, vat)) {
+            distinct_vat.push(vat);
+        }
+    }
+    fields.vat_amount = match distinct_vat.as_slice() {
+        [] => None,
+        [vat] => Some(ExtractedAmount {
+            value: *vat,
+            confidence: 0.8,
+        }),
+        // One row per rate on mixed-rate receipts.
+        many if distinct_rates.len() > 1 => Some(ExtractedAmount {
+            value: round_cents(many.iter().sum()),
+            confidence: 0.6,
+        }),
+        [first, ..] => Some(ExtractedAmount {
+            value: *first,
+            confidence: 0.5,
+        }),
+    };
+
+    fields.net_amount = nets
+        .iter()
+        .copied()
+        .reduce(f64::max)
+        .map(|net| ExtractedAmount {
+            value: net,
+            confidence: 0.75,
+        });
+    if distinct_rates.len() > 1 && nets.len() > 1 {
+        fields.net_amount = Some(ExtractedAmount {
+            value: round_cents(nets.iter().sum()),
+            confidence: 0.6,
+        });
+    }
+
+    if let (Some(gross), Some(net), Some(vat)) = (
+        &mut fields.amount,
+        &mut fields.net_amount,
+        &mut fields.vat_amount,
+    ) && cents_equal(net.value + vat.value, gross.value)
+    {
+        for field in [gross, net, vat] {
+            field.confidence = field.confidence.max(CONSISTENT_CONFIDENCE);
+        }
+    }
+}
+
+/// Money amounts on a line: two decimals with `,` or `.`, optional thousands separators.
+/// Percentages, dates and negative amounts (discounts, change) are skipped.
+fn parse_amounts(line: &str) -> Vec<f64> {
+    line.replace(" %", "%")
+        .split_whitespace()
+        .filter(|token| !token.contains('%') && !token.starts_with('-'))
+        .filter_map(|token| {
+            let token = token.trim_matches(|c: char| !c.is_ascii_digit());
+            if token.is_empty() || parse_date(token).is_some() {
+                return None;
+            }
+            parse_amount(token)
+        })
+        .collect()
+}
+
+fn parse_amount(token: &str) -> Option<f64> {
+    let split = token.rfind(['.', ','])?;
+    let (integer, decimals) = (&token[..split], &token[split + 1..]);
+    if decimals.len() != 2 || !decimals.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let groups: Vec<&str> = integer.split(['.', ',']).collect();
+    let valid = groups.iter().enumerate().all(|(index, group)| {
+        let length_ok = if index == 0 {
+            (1..=3).contains(&group.len()) || groups.len() == 1 && !group.is_empty()
+        } else {
+            group.len() == 3
+        };
+        length_ok && group.chars().all(|c| c.is_ascii_digit())
+    });
+    if !valid {
+        return None;
+    }
+    format!("{}.{}", groups.concat(), decimals).parse().ok()
+}
+
+/// The first percentage on a line that looks like a VAT rate.
+fn parse_percent(line: &str) -> Option<f64> {
+    let line = line.replace(" %", "%");
+    line.split_whitespace().find_map(|token| {
+        let (number, _) = token.split_once('%')?;
+        let number = number.trim_start_matches(|c: char| !c.is_ascii_digit());
+        let rate: f64 = number.replace(',', ".").parse().ok()?;
+        (rate > 0.0 && rate <= 30.0).then_some(rate)
+    })
+}
+
+/// Lowercase words of a line, split at anything but letters, digits and dots inside words.
+fn words(line: &str) -> Vec<String> {
+    line.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric() && c != '-' && c != '.')
+        .map(|word| word.trim_matches(|c: char| c == '.' || c == '-'))
+        .filter(|word| !word.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn cents_equal(a: f64, b: f64) -> bool {
+    (a - b).abs() < 0.015
+}
+
+fn round_cents(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SUPERMARKET: &str = "
+        REWE Markt GmbH
+        Hauptstr. 12
+        10115 Berlin
+        Milch 1,19 B
+        Brot 2,49 B
+        Wein 8,99 A
+        SUMME EUR 12,67
+        Geg. BAR EUR 20,00
+        Rückgeld EUR -7,33
+        Steuer % Netto Steuer Brutto
+        MwSt A 19 % 7,55 1,44 8,99
+        MwSt B 7 % 3,44 0,24 3,68
+        Gesamtbetrag 10,99 1,68 12,67
+        Datum: 14.03.2026 12:31
+    ";
+
+    const RESTAURANT: &str = "
+        Trattoria Da Luigi
+        Bewirtungsbeleg
+        Gutschein gültig bis 31.12.2026
+        02.03.2026 19:45
+        Pizza Margherita 9,50
+        Pasta Carbonara 12,90
+        Zu zahlen
+        22,40 EUR
+        Netto 18,82
+        MwSt 19% 3,58
+    ";
+
+    const OFFICE_SUPPLIES: &str = "
+        Acme Office Supplies Ltd
+        12 High Street, London
+        Invoice
+        Date: 2026-02-03
+        Standing desk 1,249.00
+        Chair 83.50
+        Subtotal 1,332.50
+        VAT 20% 266.50
+        Total
+        £1,599.00
+        Thank you for your order
+    ";
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 3, 20).unwrap()
+    }
+
+    fn value(amount: &Option<ExtractedAmount>) -> Option<f64> {
+        amount.map(|amount| amount.value)
+    }
+
+    fn confidence(amount: &Option<ExtractedAmount>) -> f64 {
+        amount.map(|amount| amount.confidence).unwrap_or_default()
+    }
+
+    fn date(day: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(day, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn reads_a_german_receipt_with_mixed_vat_rates() {
+        let fields = extract_fields(SUPERMARKET, today());
+        assert_eq!(fields.vendor.as_ref().map(|v| v.value.as_str()), Some("REWE Markt GmbH"));
+        assert_eq!(fields.date.as_ref().map(|d| d.value), Some(date("2026-03-14")));
+        assert_eq!(fields.currency.as_ref().map(|c| c.value.as_str()), Some("EUR"));
+        assert_eq!(value(&fields.amount), Some(12.67));
+        // Net and VAT add up over both rates; there is no single rate to report.
+        assert_eq!(value(&fields.net_amount), Some(10.99));
+        assert_eq!(value(&fields.vat_amount), Some(1.68));
+        assert_eq!(value(&fields.vat_rate), None);
+        assert_eq!(confidence(&fields.amount), CONSISTENT_CONFIDENCE);
+
+        let draft = draft_expense(&fields, Uuid::nil(), today());
+        assert_eq!((draft.amount, draft.net_amount, draft.vat_rate), (Some(12.67), Some(10.99), None));
+    }
+
+    #[test]
+    fn reads_a_german_receipt_with_the_total_below_its_label() {
+        let fields = extract_fields(RESTAURANT, today());
+        assert_eq!(fields.vendor.as_ref().map(|v| v.value.as_str()), Some("Trattoria Da Luigi"));
+        // The voucher's expiry lies in the future and is not the receipt date.
+        assert_eq!(fields.date.as_ref().map(|d| d.value), Some(date("2026-03-02")));
+        assert_eq!(value(&fields.amount), Some(22.40));
+        assert_eq!(value(&fields.net_amount), Some(18.82));
+        assert_eq!(value(&fields.vat_amount), Some(3.58));
+        assert_eq!(value(&fields.vat_rate), Some(19.0));
+        assert_eq!(confidence(&fields.net_amount), CONSISTENT_CONFIDENCE);
+
+        let draft = draft_expense(&fields, Uuid::nil(), today());
+        assert_eq!((draft.amount, draft.net_amount, draft.vat_rate), (Some(22.40), None, Some(19.0)));
+        assert_eq!(draft.date, date("2026-03-02"));
+    }
+
+    #[test]
+    fn reads_an_english_invoice_with_thousands_separators() {
+        let fields = extract_fields(OFFICE_SUPPLIES, today());
+        assert_eq!(
+            fields.vendor.as_ref().map(|v| v.value.as_str()),
+            Some("Acme Office Supplies Ltd"),
+        );
+        assert_eq!(fields.date.as_ref().map(|d| (d.value, d.confidence)), Some((date("2026-02-03"), 0.9)));
+        assert_eq!(fields.currency.as_ref().map(|c| c.value.as_str()), Some("GBP"));
+        assert_eq!(value(&fields.amount), Some(1599.0));
+        assert_eq!(value(&fields.net_amount), Some(1332.5));
+        assert_eq!(value(&fields.vat_amount), Some(266.5));
+        assert_eq!(value(&fields.vat_rate), Some(20.0));
+    }
+
+    #[test]
+    fn falls_back_to_the_largest_amount_without_a_total() {
+        let fields = extract_fields("Kiosk am Markt\nZeitung 2,80\nKaffee 3,20\n", today());
+        assert_eq!(value(&fields.amount), Some(3.20));
+        assert_eq!(confidence(&fields.amount), 0.4);
+        assert_eq!(value(&fields.vat_rate), None);
+        assert!(fields.date.is_none());
+
+        let draft = draft_expense(&fields, Uuid::nil(), today());
+        assert_eq!((draft.currency.as_str(), draft.date), ("EUR", today()));
+    }
+
+    #[test]
+    fn parses_german_and_english_number_formats() {
+        let cases = [
+            ("12,99", Some(12.99)),
+            ("12.99", Some(12.99)),
+            ("0,50", Some(0.5)),
+            ("1.234,56", Some(1234.56)),
+            ("1,234.56", Some(1234.56)),
+            ("1.234.567,89", Some(1234567.89)),
+            ("1234,56", Some(1234.56)),
+            ("1.2345,67", None),
+            ("12,9", None),
+            ("1.234", None),
+            (",50", None),
+            ("12,ab", None),
+        ];
+        for (token, expected) in cases {
+            assert_eq!(parse_amount(token), expected, "{token}");
+        }
+    }
+
+    #[test]
+    fn skips_dates_percentages_and_negative_amounts() {
+        assert_eq!(parse_amounts("14.03.2026 Kaffee 3,20 € 19 % -0,50"), vec![3.20]);
+        assert_eq!(parse_amounts("Total: EUR 1.299,00*"), vec![1299.0]);
+        assert_eq!(parse_percent("MwSt. 7,0 % 0,21"), Some(7.0));
+        assert_eq!(parse_percent("Rabatt 50% 1,00"), None);
+    }
+}
@@ -0,0 +1,869 @@
+use crate::entity::{expense, receipt};
+use crate::modules::account::api_url;
+use crate::modules::api_tokens::ApiScope;
+use crate::modules::auth::{cookie_key, require_access};
+use crate::modules::shared::{AppError, AppState, FieldError};
+use crate::modules::storage::ReceiptStorage;
+use crate::modules::validation::{Path, Query, ValidatedJson};
+use crate::modules::workspaces::require_manager;
+use axum::{
+    extract::{multipart::MultipartError, Multipart, State},
+    http::{
+        header::{CACHE_CONTROL, CONTENT_DISPOSITION, CONTENT_TYPE, X_CONTENT_TYPE_OPTIONS},
+        HeaderMap, HeaderValue, StatusCode,
+    },
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
+use sea_orm::sea_query::{Expr, Query as SqlQuery};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, EntityTrait,
+    PaginatorTrait, QueryFilter, QueryOrder, Set,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+use validator::Validate;
+
+const DEFAULT_MAX_BYTES: usize = 10 * 1024 * 1024;
+const DEFAULT_URL_TTL_SECONDS: i64 = 300;
+
+/// Multipart form accepted by `POST /receipts`. Only used for the API docs.
+#[derive(ToSchema)]
+#[allow(dead_code)]
+pub struct ReceiptUploadForm {
+    /// PDF, PNG, JPEG or WebP; the type is detected from the contents
+    #[schema(value_type = String, format = Binary)]
+    pub file: Vec<u8>,
+    /// Attach the receipt to this expense right away
+    pub expense_id: Option<Uuid>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ReceiptResponse {
+    pub id: Uuid,
+    pub filename: String,
+    pub content_type: String,
+    pub size_bytes: i64,
+    pub sha256: String,
+    pub created_at: DateTime<Utc>,
+    /// Short-lived link to the file; request the receipt again for a fresh one
+    pub download_url: String,
+    pub download_url_expires_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct ReceiptSweepRequest {
+    /// Only report what would be removed (default true)
+    pub dry_run: Option<bool>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ReceiptSweepReport {
+    pub dry_run: bool,
+    pub removed: Vec<SweptReceipt>,
+    /// Size of the confirmed receipts among them
+    pub total_bytes: i64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SweptReceipt {
+    pub id: Uuid,
+    pub workspace_id: Uuid,
+    pub filename: String,
+    pub size_bytes: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    /// `pending` (direct upload never confirmed) or `unattached` (not used by any expense)
+    pub reason: String,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DownloadQuery {
+    /// Unix timestamp after which the link stops working
+    pub expires: i64,
+    pub signature: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/receipts",
+    request_body(content = ReceiptUploadForm, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "Receipt stored", body = ReceiptResponse),
+        (status = 400, description = "Malformed form or missing file", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 404, description = "Expense not found", body = ErrorResponse),
+        (status = 413, description = "File exceeds RECEIPT_MAX_BYTES", body = ErrorResponse),
+        (status = 415, description = "Not a PDF, PNG, JPEG or WebP file", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "expenses"
+)]
+pub async fn upload_receipt(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    mut multipart: Multipart,
+) -> Result<Json<ReceiptResponse>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::ExpensesWrite).await?;
+    let max_bytes = max_receipt_bytes();
+
+    let mut file: Option<(String, Vec<u8>)> = None;
+    let mut expense_id = None;
+    while let Some(mut field) = multipart.next_field().await.map_err(multipart_error)? {
+        match field.name() {
+            Some("file") => {
+                let filename = safe_filename(field.file_name().unwrap_or("receipt"));
+                let mut body = Vec::new();
+                while let Some(chunk) = field.chunk().await.map_err(multipart_error)? {
+                    check_size((body.len() + chunk.len()) as u64, max_bytes)?;
+                    body.extend_from_slice(&chunk);
+                }
+                file = Some((filename, body));
+            }
+            Some("expense_id") => {
+                let value = field.text().await.map_err(multipart_error)?;
+                expense_id = Some(
+                    Uuid::parse_str(value.trim())
+                        .map_err(|_| AppError::bad_request("Invalid expense_id"))?,
+                );
+            }
+            _ => {}
+        }
+    }
+    let (filename, body) =
+        file.ok_or_else(|| AppError::bad_request("Missing file field"))?;
+    if body.is_empty() {
+        return Err(AppError::bad_request("The file is empty"));
+    }
+    let (content_type, extension) = sniff_content_type(&body).ok_or_else(|| {
+        AppError::unsupported_media_type("Receipts must be PDF, PNG, JPEG or WebP files")
+    })?;
+
+    let expense = match expense_id {
+        Some(expense_id) => Some(
+            expense::Entity::find_by_id(expense_id)
+                .filter(expense::Column::WorkspaceId.eq(access.workspace_id))
+                .one(&state.db)
+                .await?
+                .ok_or_else(|| AppError::not_found("Expense not found"))?,
+        ),
+        None => None,
+    };
+
+    let id = Uuid::new_v4();
+    let storage_key = storage_key(access.workspace_id, id, extension);
+    let size_bytes = body.len() as i64;
+    let sha256 = hex::encode(Sha256::digest(&body));
+    state.receipts.put(&storage_key, content_type, body).await?;
+
+    let created = receipt::ActiveModel {
+        id: Set(id),
+        workspace_id: Set(access.workspace_id),
+        user_id: Set(access.user.id),
+        storage_key: Set(storage_key),
+        filename: Set(filename),
+        content_type: Set(content_type.to_string()),
+        size_bytes: Set(Some(size_bytes)),
+        sha256: Set(Some(sha256)),
+        confirmed_at: Set(Some(Utc::now())),
+        created_at: Set(Utc::now()),
+    }
+    .insert(&state.db)
+    .await?;
+    if let Some(expense) = expense {
+        let mut active: expense::ActiveModel = expense.into();
+        active.receipt_id = Set(Some(created.id));
+        active.update(&state.db).await?;
+    }
+
+    Ok(Json(receipt_response(state.receipts.as_ref(), created).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/receipts/{id}",
+    responses(
+        (status = 200, description = "Receipt with a fresh download link", body = ReceiptResponse),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 404, description = "Receipt not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "expenses"
+)]
+pub async fn get_receipt(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ReceiptResponse>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::ExpensesRead).await?;
+    let id = Uuid::parse_str(&id).map_err(|_| AppError::bad_request("Invalid id"))?;
+    let receipt = find_receipt(&state.db, access.workspace_id, id).await?;
+    Ok(Json(receipt_response(state.receipts.as_ref(), receipt).await?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/receipts/{id}/confirm",
+    responses(
+        (status = 200, description = "Direct upload checked and confirmed", body = ReceiptResponse),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 404, description = "Receipt not found", body = ErrorResponse),
+        (status = 409, description = "The file has not been uploaded yet", body = ErrorResponse),
+        (status = 413, description = "File exceeds RECEIPT_MAX_BYTES; the upload is discarded", body = ErrorResponse),
+        (status = 415, description = "File does not match the announced type; the upload is discarded", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "expenses"
+)]
+pub async fn confirm_receipt(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ReceiptResponse>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::ExpensesWrite).await?;
+    let id = Uuid::parse_str(&id).map_err(|_| AppError::bad_request("Invalid id"))?;
+    let upload = find_upload(&state.db, access.workspace_id, id).await?;
+    if upload.confirmed_at.is_some() {
+        return Ok(Json(receipt_response(state.receipts.as_ref(), upload).await?));
+    }
+
+    let not_uploaded = || AppError::conflict("The file has not been uploaded yet");
+    let size = state
+        .receipts
+        .size(&upload.storage_key)
+        .await?
+        .ok_or_else(not_uploaded)?;
+    let max_bytes = max_receipt_bytes();
+    let checked = if let Err(error) = check_size(size, max_bytes) {
+        Err(error)
+    } else {
+        let body = state
+            .receipts
+            .get(&upload.storage_key)
+            .await?
+            .ok_or_else(not_uploaded)?;
+        match sniff_content_type(&body) {
+            Some((content_type, _)) if content_type == upload.content_type => Ok(body),
+            _ => Err(AppError::unsupported_media_type(format!(
+                "The uploaded file is not a valid {} file",
+                upload.content_type
+            ))),
+        }
+    };
+    let body = match checked {
+        Ok(body) => body,
+        Err(error) => {
+            receipt::Entity::delete_by_id(upload.id)
+                .exec(&state.db)
+                .await?;
+            delete_stored_receipts(state.receipts.as_ref(), [upload.storage_key]).await;
+            return Err(error);
+        }
+    };
+
+    let mut active: receipt::ActiveModel = upload.into();
+    active.size_bytes = Set(Some(body.len() as i64));
+    active.sha256 = Set(Some(hex::encode(Sha256::digest(&body))));
+    active.confirmed_at = Set(Some(Utc::now()));
+    let confirmed = active.update(&state.db).await?;
+    Ok(Json(receipt_response(state.receipts.as_ref(), confirmed).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/receipts/{id}/download",
+    params(DownloadQuery),
+    responses(
+        (status = 200, description = "Receipt file", content_type = "application/octet-stream"),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 401, description = "Invalid or expired link", body = ErrorResponse),
+        (status = 404, description = "Receipt not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "expenses"
+)]
+pub async fn download_receipt(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<DownloadQuery>,
+) -> Result<Response, AppError> {
+    let id = Uuid::parse_str(&id).map_err(|_| AppError::bad_request("Invalid id"))?;
+    verify_download_link(id, query.expires, &query.signature, Utc::now().timestamp())?;
+
+    let receipt = receipt::Entity::find_by_id(id)
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Receipt not found"))?;
+    let body = state
+        .receipts
+        .get(&receipt.storage_key)
+        .await?
+        .ok_or_else(|| AppError::not_found("Receipt file not found"))?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_str(&receipt.content_type).map_err(AppError::internal)?,
+    );
+    headers.insert(
+        CONTENT_DISPOSITION,
+        HeaderValue::from_str(&content_disposition(&receipt.filename))
+            .map_err(AppError::internal)?,
+    );
+    headers.insert(X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+    headers.insert(CACHE_CONTROL, HeaderValue::from_static("private, no-store"));
+    Ok((headers, body).into_response())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/receipts/{id}",
+    responses(
+        (status = 204, description = "Receipt or pending upload deleted and detached from its expenses"),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 404, description = "Receipt not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "expenses"
+)]
+pub async fn delete_receipt(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    let access = require_access(&state, &headers, ApiScope::ExpensesWrite).await?;
+    let id = Uuid::parse_str(&id).map_err(|_| AppError::bad_request("Invalid id"))?;
+    let receipt = find_upload(&state.db, access.workspace_id, id).await?;
+
+    expense::Entity::update_many()
+        .col_expr(expense::Column::ReceiptId, Expr::value(Option::<Uuid>::None))
+        .filter(expense::Column::ReceiptId.eq(receipt.id))
+        .exec(&state.db)
+        .await?;
+    receipt::Entity::delete_by_id(receipt.id)
+        .exec(&state.db)
+        .await?;
+    delete_stored_receipts(state.receipts.as_ref(), [receipt.storage_key]).await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/receipts/sweep",
+    request_body = ReceiptSweepRequest,
+    responses(
+        (status = 200, description = "Orphaned receipts of the workspace that were (or would be) removed", body = ReceiptSweepReport),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Requires the owner or admin role", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "expenses"
+)]
+pub async fn sweep_receipts(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidatedJson(payload): ValidatedJson<ReceiptSweepRequest>,
+) -> Result<Json<ReceiptSweepReport>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::ExpensesWrite).await?;
+    require_manager(&state.db, access.workspace_id, access.user.id).await?;
+    let report = sweep_orphaned_receipts(
+        &state.db,
+        state.receipts.as_ref(),
+        Some(access.workspace_id),
+        Utc::now(),
+        payload.dry_run.unwrap_or(true),
+    )
+    .await?;
+    Ok(Json(report))
+}
+
+/// Removes orphaned receipts every `RECEIPT_SWEEP_INTERVAL_HOURS` (default 6). With
+/// `RECEIPT_SWEEP_DRY_RUN=true` it only logs what it would remove.
+pub fn spawn_receipt_sweep_job(state: AppState) {
+    let hours = std::env::var("RECEIPT_SWEEP_INTERVAL_HOURS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(6)
+        .max(1);
+    let dry_run = std::env::var("RECEIPT_SWEEP_DRY_RUN")
+        .map(|value| value == "true" || value == "1")
+        .unwrap_or(false);
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(hours * 3600));
+        loop {
+            interval.tick().await;
+            let result = sweep_orphaned_receipts(
+                &state.db,
+                state.receipts.as_ref(),
+                None,
+                Utc::now(),
+                dry_run,
+            )
+            .await;
+            match result {
+                Ok(report) if report.removed.is_empty() => {}
+                Ok(report) => {
+                    let verb = if dry_run { "would remove" } else { "removed" };
+                    println!(
+                        "Receipt sweep {verb} {} receipt(s), {} bytes",
+                        report.removed.len(),
+                        report.total_bytes
+                    );
+                    if dry_run {
+                        for receipt in &report.removed {
+                            println!(
+                                "  {} {} ({}, workspace {}, uploaded {})",
+                                receipt.reason,
+                                receipt.id,
+                                receipt.filename,
+                                receipt.workspace_id,
+                                receipt.created_at
+                            );
+                        }
+                    }
+                }
+                Err(error) => eprintln!("Receipt sweep failed: {error}"),
+            }
+        }
+    });
+}
+
+/// Finds receipts nobody will use: direct uploads never confirmed within
+/// `RECEIPT_PENDING_TTL_HOURS` (default 24), and confirmed receipts that no expense has
+/// referred to for `RECEIPT_UNATTACHED_TTL_DAYS` (default 30). Unless `dry_run` is set they
+/// are deleted along with their files.
+pub async fn sweep_orphaned_receipts(
+    db: &DatabaseConnection,
+    storage: &dyn ReceiptStorage,
+    workspace_id: Option<Uuid>,
+    now: DateTime<Utc>,
+    dry_run: bool,
+) -> Result<ReceiptSweepReport, AppError> {
+    let pending_hours = std::env::var("RECEIPT_PENDING_TTL_HOURS")
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(24)
+        .max(1);
+    let unattached_days = std::env::var("RECEIPT_UNATTACHED_TTL_DAYS")
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(30)
+        .max(1);
+
+    let referenced = SqlQuery::select()
+        .column(expense::Column::ReceiptId)
+        .from(expense::Entity)
+        .and_where(expense::Column::ReceiptId.is_not_null())
+        .to_owned();
+    let mut select = receipt::Entity::find().filter(
+        Condition::any()
+            .add(
+                Condition::all()
+                    .add(receipt::Column::ConfirmedAt.is_null())
+                    .add(receipt::Column::CreatedAt.lt(now - Duration::hours(pending_hours))),
+            )
+            .add(
+                Condition::all()
+                    .add(receipt::Column::ConfirmedAt.is_not_null())
+                    .add(receipt::Column::CreatedAt.lt(now - Duration::days(unattached_days)))
+                    .add(receipt::Column::Id.not_in_subquery(referenced)),
+            ),
+    );
+    if let Some(workspace_id) = workspace_id {
+        select = select.filter(receipt::Column::WorkspaceId.eq(workspace_id));
+    }
+    let orphans = select
+        .order_by_asc(receipt::Column::CreatedAt)
+        .all(db)
+        .await?;
+
+    let report = ReceiptSweepReport {
+        dry_run,
+        total_bytes: orphans.iter().filter_map(|receipt| receipt.size_bytes).sum(),
+        removed: orphans
+            .iter()
+            .map(|receipt| SweptReceipt {
+                id: receipt.id,
+                workspace_id: receipt.workspace_id,
+                filename: receipt.filename.clone(),
+                size_bytes: receipt.size_bytes,
+                created_at: receipt.created_at,
+                reason: if receipt.confirmed_at.is_some() {
+                    "unattached"
+                } else {
+                    "pending"
+                }
+                .to_string(),
+            })
+            .collect(),
+    };
+    if !dry_run && !orphans.is_empty() {
+        receipt::Entity::delete_many()
+            .filter(receipt::Column::Id.is_in(orphans.iter().map(|receipt| receipt.id)))
+            .exec(db)
+            .await?;
+        delete_stored_receipts(storage, orphans.into_iter().map(|receipt| receipt.storage_key))
+            .await;
+    }
+    Ok(report)
+}
+
+/// A confirmed receipt of the workspace; pending uploads are not found.
+pub(crate) async fn find_receipt<C: ConnectionTrait>(
+    db: &C,
+    workspace_id: Uuid,
+    id: Uuid,
+) -> Result<receipt::Model, AppError> {
+    receipt::Entity::find_by_id(id)
+        .filter(receipt::Column::WorkspaceId.eq(workspace_id))
+        .filter(receipt::Column::ConfirmedAt.is_not_null())
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Receipt not found"))
+}
+
+/// A receipt of the workspace, confirmed or not.
+async fn find_upload<C: ConnectionTrait>(
+    db: &C,
+    workspace_id: Uuid,
+    id: Uuid,
+) -> Result<receipt::Model, AppError> {
+    receipt::Entity::find_by_id(id)
+        .filter(receipt::Column::WorkspaceId.eq(workspace_id))
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Receipt not found"))
+}
+
+/// Deletes a receipt and its file once no expense refers to it any more.
+pub(crate) async fn delete_receipt_if_unused(
+    db: &DatabaseConnection,
+    storage: &dyn ReceiptStorage,
+    id: Uuid,
+) -> Result<(), AppError> {
+    let in_use = expense::Entity::find()
+        .filter(expense::Column::ReceiptId.eq(id))
+        .count(db)
+        .await?;
+    if in_use > 0 {
+        return Ok(());
+    }
+    let Some(receipt) = receipt::Entity::find_by_id(id).one(db).await? else {
+        return Ok(());
+    };
+    receipt::Entity::delete_by_id(receipt.id).exec(db).await?;
+    delete_stored_receipts(storage, [receipt.storage_key]).await;
+    Ok(())
+}
+
+/// Checks that a receipt an expense should point at belongs to the workspace and is confirmed.
+pub(crate) async fn require_receipt(
+    db: &DatabaseConnection,
+    workspace_id: Uuid,
+    id: Uuid,
+) -> Result<receipt::Model, AppError> {
+    let upload = match find_upload(db, workspace_id, id).await {
+        Err(AppError::NotFound(_)) => {
+            return Err(AppError::Validation(vec![FieldError::new(
+                "receipt_id",
+                "unknown_receipt",
+                "Unknown receipt",
+            )]));
+        }
+        result => result?,
+    };
+    if upload.confirmed_at.is_none() {
+        return Err(AppError::Validation(vec![FieldError::new(
+            "receipt_id",
+            "receipt_not_confirmed",
+            "Confirm the upload with POST /receipts/{id}/confirm first",
+        )]));
+    }
+    Ok(upload)
+}
+
+/// Builds the response with a download link valid for `RECEIPT_URL_TTL_SECONDS` (default
+/// five minutes): presigned by the storage backend where possible, otherwise an API link
+/// signed with `COOKIE_SECRET`.
+pub(crate) async fn receipt_response(
+    storage: &dyn ReceiptStorage,
+    receipt: receipt::Model,
+) -> Result<ReceiptResponse, AppError> {
+    let ttl = std::env::var("RECEIPT_URL_TTL_SECONDS")
+        .ok()
+        .and_then(|value| value.parse::<i64>().ok())
+        .unwrap_or(DEFAULT_URL_TTL_SECONDS)
+        .clamp(10, 7 * 24 * 3600);
+    let expires_at = Utc::now() + Duration::seconds(ttl);
+
+    let presigned = storage
+        .presigned_get(
+            &receipt.storage_key,
+            &receipt.content_type,
+            &content_disposition(&receipt.filename),
+            std::time::Duration::from_secs(ttl as u64),
+        )
+        .await?;
+    let download_url = match presigned {
+        Some(url) => url,
+        None => {
+            let expires = expires_at.timestamp();
+            format!(
+                "{}/receipts/{}/download?expires={}&signature={}",
+                api_url(),
+                receipt.id,
+                expires,
+                download_signature(receipt.id, expires)
+            )
+        }
+    };
+
+    Ok(ReceiptResponse {
+        id: receipt.id,
+        filename: receipt.filename,
+        content_type: receipt.content_type,
+        size_bytes: receipt.size_bytes.unwrap_or_default(),
+        sha256: receipt.sha256.unwrap_or_default(),
+        created_at: receipt.created_at,
+        download_url,
+        download_url_expires_at: expires_at,
+    })
+}
+
+/// Removes files from storage after their records are gone. Failures are only logged; the
+/// objects are unreachable either way.
+pub(crate) async fn delete_stored_receipts(
+    storage: &dyn ReceiptStorage,
+    keys: impl IntoIterator<Item = String>,
+) {
+    for key in keys {
+        if let Err(error) = storage.delete(&key).await {
+            eprintln!("Could not delete receipt {key} from {} storage: {error}", storage.name());
+        }
+    }
+}
+
+/// Upload size limit from `RECEIPT_MAX_BYTES`, 10 MiB by default.
+pub fn max_receipt_bytes() -> usize {
+    std::env::var("RECEIPT_MAX_BYTES")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_MAX_BYTES)
+}
+
+/// Accepted receipt types with the extension used for their storage keys.
+const RECEIPT_TYPES: &[(&str, &str)] = &[
+    ("application/pdf", "pdf"),
+    ("image/png", "png"),
+    ("image/jpeg", "jpg"),
+    ("image/webp", "webp"),
+];
+
+/// Detects the file type from its leading bytes and returns the MIME type and file
+/// extension. Anything other than PDF, PNG, JPEG or WebP is rejected.
+pub(crate) fn sniff_content_type(body: &[u8]) -> Option<(&'static str, &'static str)> {
+    let content_type = if body.starts_with(b"%PDF-") {
+        "application/pdf"
+    } else if body.starts_with(b"\x89PNG\r\n\x1a\n") {
+        "image/png"
+    } else if body.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        "image/jpeg"
+    } else if body.len() >= 12 && body.starts_with(b"RIFF") && &body[8..12] == b"WEBP" {
+        "image/webp"
+    } else {
+        return None;
+    };
+    RECEIPT_TYPES.iter().copied().find(|(known, _)| *known == content_type)
+}
+
+/// Storage key extension for an accepted receipt MIME type.
+pub(crate) fn receipt_extension(content_type: &str) -> Option<&'static str> {
+    RECEIPT_TYPES
+        .iter()
+        .find(|(known, _)| *known == content_type)
+        .map(|(_, extension)| *extension)
+}
+
+/// Where receipt `id` of a workspace is stored, for both proxied and direct uploads.
+pub(crate) fn storage_key(workspace_id: Uuid, id: Uuid, extension: &str) -> String {
+    format!("{}/{}.{}", workspace_id, id, extension)
+}
+
+fn download_mac(id: Uuid, expires: i64) -> Hmac<Sha256> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(cookie_key()).expect("HMAC accepts keys of any length");
+    mac.update(format!("receipt-download:{id}:{expires}").as_bytes());
+    mac
+}
+
+fn download_signature(id: Uuid, expires: i64) -> String {
+    hex::encode(download_mac(id, expires).finalize().into_bytes())
+}
+
+/// Checks a download link issued by `receipt_response` for the receipt `id` at time `now`.
+fn verify_download_link(id: Uuid, expires: i64, signature: &str, now: i64) -> Result<(), AppError> {
+    let signature =
+        hex::decode(signature).map_err(|_| AppError::unauthorized("Invalid download link"))?;
+    if download_mac(id, expires).verify_slice(&signature).is_err() {
+        return Err(AppError::unauthorized("Invalid download link"));
+    }
+    if expires < now {
+        return Err(AppError::unauthorized("Download link expired"));
+    }
+    Ok(())
+}
+
+/// Keeps the base name of the client's file name, without control characters or quotes.
+pub(crate) fn safe_filename(name: &str) -> String {
+    let base = name.rsplit(['/', '\\']).next().unwrap_or_default();
+    let cleaned: String = base
+        .chars()
+        .filter(|c| !c.is_control() && *c != '"')
+        .take(200)
+        .collect();
+    let cleaned = cleaned.trim();
+    if cleaned.is_empty() {
+        "receipt".to_string()
+    } else {
+        cleaned.to_string()
+    }
+}
+
+fn content_disposition(filename: &str) -> String {
+    let ascii: String = filename
+        .chars()
+        .map(|c| if c.is_ascii_graphic() || c == ' ' { c } else { '_' })
+        .collect();
+    format!(
+        "inline; filename=\"{}\"; filename*=UTF-8''{}",
+        ascii,
+        urlencoding::encode(filename)
+    )
+}
+
+fn check_size(size: u64, max_bytes: usize) -> Result<(), AppError> {
+    if size > max_bytes as u64 {
+        return Err(too_large(max_bytes));
+    }
+    Ok(())
+}
+
+fn too_large(max_bytes: usize) -> AppError {
+    AppError::payload_too_large(format!("Receipts may be at most {} bytes", max_bytes))
+}
+
+fn multipart_error(error: MultipartError) -> AppError {
+    if error.status() == StatusCode::PAYLOAD_TOO_LARGE {
+        too_large(max_receipt_bytes())
+    } else {
+        AppError::bad_request(error.body_text())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::storage::LocalStorage;
+
+    #[test]
+    fn sniffs_receipt_types_from_the_leading_bytes() {
+        let cases: [(&[u8], _); 9] = [
+            (b"%PDF-1.7\n%\xe2\xe3", Some(("application/pdf", "pdf"))),
+            (b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR", Some(("image/png", "png"))),
+            (b"\xff\xd8\xff\xe0\0\x10JFIF", Some(("image/jpeg", "jpg"))),
+            (b"RIFF\x24\0\0\0WEBPVP8 ", Some(("image/webp", "webp"))),
+            // A WAV file is RIFF too.
+            (b"RIFF\x24\0\0\0WAVEfmt ", None),
+            (b"RIFF\x24\0\0\0WEB", None),
+            (b"<html><body>%PDF-</body></html>", None),
+            (b"GIF89a", None),
+            (b"", None),
+        ];
+        for (body, expected) in cases {
+            assert_eq!(sniff_content_type(body), expected, "{body:?}");
+        }
+    }
+
+    #[test]
+    fn enforces_the_size_limit() {
+        assert!(check_size(1024, 1024).is_ok());
+        assert!(check_size(0, 1024).is_ok());
+        let error = check_size(1025, 1024).err().unwrap();
+        assert_eq!(error.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[test]
+    fn keys_receipts_by_workspace_and_id() {
+        let workspace_id = Uuid::new_v4();
+        let id = Uuid::new_v4();
+        assert_eq!(
+            storage_key(workspace_id, id, "pdf"),
+            format!("{workspace_id}/{id}.pdf")
+        );
+    }
+
+    #[test]
+    fn download_links_expire_and_cannot_be_altered() {
+        let id = Uuid::new_v4();
+        let now = Utc::now().timestamp();
+        let signature = download_signature(id, now + 300);
+
+        assert!(verify_download_link(id, now + 300, &signature, now).is_ok());
+        assert!(verify_download_link(id, now + 300, &signature, now + 300).is_ok());
+
+        let rejected = |expires, signature: &str, receipt| {
+            verify_download_link(receipt, expires, signature, now + 301).err().unwrap().to_string()
+        };
+        assert_eq!(rejected(now + 300, &signature, id), "Download link expired");
+        // Extending the expiry or reusing the signature for another receipt breaks it.
+        assert_eq!(rejected(now + 900, &signature, id), "Invalid download link");
+        assert_eq!(rejected(now + 300, &signature, Uuid::new_v4()), "Invalid download link");
+        assert_eq!(rejected(now + 300, "not-hex", id), "Invalid download link");
+    }
+
+    #[tokio::test]
+    async fn local_receipts_get_a_signed_api_link() {
+        let receipt = receipt::Model {
+            id: Uuid::new_v4(),
+            workspace_id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            storage_key: "workspace/receipt.pdf".to_string(),
+            filename: "Rechnung März.pdf".to_string(),
+            content_type: "application/pdf".to_string(),
+            size_bytes: Some(8),
+            sha256: None,
+            confirmed_at: Some(Utc::now()),
+            created_at: Utc::now(),
+        };
+        let id = receipt.id;
+        let response = receipt_response(&LocalStorage::new("/srv/receipts"), receipt)
+            .await
+            .ok()
+            .unwrap();
+
+        let (path, query) = response.download_url.split_once('?').unwrap();
+        assert!(path.ends_with(&format!("/receipts/{id}/download")));
+        let params: std::collections::HashMap<_, _> = query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .collect();
+        let expires: i64 = params["expires"].parse().unwrap();
+        assert_eq!(expires, response.download_url_expires_at.timestamp());
+        let now = Utc::now().timestamp();
+        assert!(verify_download_link(id, expires, params["signature"], now).is_ok());
+        assert!(verify_download_link(id, expires, params["signature"], expires + 1).is_err());
+    }
+}
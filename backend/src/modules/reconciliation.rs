@@ -0,0 +1,970 @@
+use crate::entity::{bank_match, bank_transaction, expense, invoice, invoice_payment};
+use crate::modules::api_tokens::ApiScope;
+use crate::modules::auth::require_access;
+use crate::modules::banking::BankTransactionResponse;
+use crate::modules::shared::{fetch_page, AppError, AppState, Page, PageParams};
+use crate::modules::validation::{Path, Query};
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    Json,
+};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use sea_orm::sea_query::{Query as SqlQuery, SelectStatement};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    QuerySelect, Set, SqlErr, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+/// Proposals below this confidence are not stored.
+const MIN_CONFIDENCE: f64 = 0.4;
+/// Candidates kept per transaction, best first.
+const MAX_PROPOSALS: usize = 3;
+/// Outgoing transactions are compared with expenses dated at most this many days apart.
+const EXPENSE_DATE_WINDOW_DAYS: i64 = 30;
+/// Differences below half a cent are rounding.
+const AMOUNT_TOLERANCE: f64 = 0.005;
+/// Amounts within this share are near matches, e.g. after bank fees or a cash discount.
+const NEAR_AMOUNT_SHARE: f64 = 0.02;
+/// Legal forms and filler words that say nothing about who a party is.
+const NAME_STOPWORDS: &[&str] = &[
+    "gmbh", "mbh", "ug", "ag", "kg", "ohg", "gbr", "ek", "co", "haftungsbeschraenkt", "ltd",
+    "inc", "llc", "sa", "sarl", "bv", "plc", "und", "and", "the", "der", "die", "das",
+];
+
+/// A possible counterpart of a transaction before it is stored as a proposal.
+struct Candidate {
+    score: f64,
+    reasons: Vec<String>,
+    invoice_id: Option<Uuid>,
+    expense_id: Option<Uuid>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchStatus {
+    Proposed,
+    Accepted,
+    Rejected,
+}
+
+impl MatchStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MatchStatus::Proposed => "proposed",
+            MatchStatus::Accepted => "accepted",
+            MatchStatus::Rejected => "rejected",
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BankMatchResponse {
+    pub id: Uuid,
+    pub status: MatchStatus,
+    /// Between 0 and 1
+    pub confidence: f64,
+    /// What the match is based on
+    pub reasons: Vec<String>,
+    pub transaction: BankTransactionResponse,
+    /// The invoice an incoming transaction pays
+    pub invoice: Option<MatchedInvoice>,
+    /// The expense an outgoing transaction paid
+    pub expense: Option<MatchedExpense>,
+    pub decided_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct MatchedInvoice {
+    pub id: Uuid,
+    pub invoice_number: String,
+    pub client_name: String,
+    pub total_amount: f64,
+    /// Total minus the payments recorded so far
+    pub open_amount: f64,
+    pub currency: String,
+    pub date: NaiveDate,
+    pub due_date: Option<NaiveDate>,
+    pub status: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct MatchedExpense {
+    pub id: Uuid,
+    pub vendor: String,
+    pub description: String,
+    pub amount: f64,
+    pub currency: String,
+    pub date: NaiveDate,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct InvoicePaymentResponse {
+    pub id: Uuid,
+    pub invoice_id: Uuid,
+    pub bank_transaction_id: Option<Uuid>,
+    pub amount: f64,
+    pub currency: String,
+    pub paid_on: NaiveDate,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<invoice_payment::Model> for InvoicePaymentResponse {
+    fn from(payment: invoice_payment::Model) -> Self {
+        Self {
+            id: payment.id,
+            invoice_id: payment.invoice_id,
+            bank_transaction_id: payment.bank_transaction_id,
+            amount: payment.amount,
+            currency: payment.currency,
+            paid_on: payment.paid_on,
+            created_at: payment.created_at,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ReconciliationRunResponse {
+    /// Unreconciled transactions that were compared
+    pub transactions: usize,
+    /// Proposals now waiting for a decision
+    pub proposed: usize,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct BankMatchListQuery {
+    /// Default `proposed`
+    #[param(inline)]
+    pub status: Option<MatchStatus>,
+    pub bank_transaction_id: Option<Uuid>,
+    pub invoice_id: Option<Uuid>,
+    pub expense_id: Option<Uuid>,
+    /// Only matches at least this confident
+    pub min_confidence: Option<f64>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/bank-matches/run",
+    responses(
+        (status = 200, description = "Proposals refreshed for all unreconciled transactions", body = ReconciliationRunResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "banking"
+)]
+pub async fn run_reconciliation(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<ReconciliationRunResponse>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::BankingWrite).await?;
+    let txn = state.db.begin().await?;
+    let report = propose_matches(&txn, access.workspace_id, None).await?;
+    txn.commit().await?;
+    Ok(Json(report))
+}
+
+#[utoipa::path(
+    get,
+    path = "/bank-matches",
+    params(PageParams, BankMatchListQuery),
+    responses(
+        (status = 200, description = "Matches, most confident first", body = BankMatchPage),
+        (status = 400, description = "Invalid query", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "banking"
+)]
+pub async fn list_bank_matches(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(paging): Query<PageParams>,
+    Query(query): Query<BankMatchListQuery>,
+) -> Result<Json<Page<BankMatchResponse>>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::BankingRead).await?;
+    let status = query.status.unwrap_or(MatchStatus::Proposed);
+    let mut select = bank_match::Entity::find()
+        .filter(bank_match::Column::WorkspaceId.eq(access.workspace_id))
+        .filter(bank_match::Column::Status.eq(status.as_str()));
+    if let Some(transaction_id) = query.bank_transaction_id {
+        select = select.filter(bank_match::Column::BankTransactionId.eq(transaction_id));
+    }
+    if let Some(invoice_id) = query.invoice_id {
+        select = select.filter(bank_match::Column::InvoiceId.eq(invoice_id));
+    }
+    if let Some(expense_id) = query.expense_id {
+        select = select.filter(bank_match::Column::ExpenseId.eq(expense_id));
+    }
+    if let Some(min_confidence) = query.min_confidence {
+        select = select.filter(bank_match::Column::Confidence.gte(min_confidence));
+    }
+    select = select
+        .order_by_desc(bank_match::Column::Confidence)
+        .order_by_desc(bank_match::Column::Id);
+
+    let page = fetch_page(&state.db, select, &paging).await?;
+    let items = match_responses(&state.db, page.items).await?;
+    Ok(Json(Page {
+        items,
+        total: page.total,
+        page: page.page,
+        per_page: page.per_page,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/bank-matches/{id}/accept",
+    responses(
+        (status = 200, description = "Match accepted; for invoices the payment is recorded", body = BankMatchResponse),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 404, description = "Match not found", body = ErrorResponse),
+        (status = 409, description = "Already decided, or the transaction or expense is already reconciled", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "banking"
+)]
+pub async fn accept_bank_match(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<BankMatchResponse>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::BankingWrite).await?;
+    let id = Uuid::parse_str(&id).map_err(|_| AppError::bad_request("Invalid id"))?;
+
+    let txn = state.db.begin().await?;
+    let proposal = find_proposal(&txn, access.workspace_id, id).await?;
+    // Locking the transaction serializes concurrent accepts of its proposals, so the check
+    // below sees a match accepted in the meantime.
+    let transaction = bank_transaction::Entity::find_by_id(proposal.bank_transaction_id)
+        .lock_exclusive()
+        .one(&txn)
+        .await?
+        .ok_or_else(|| AppError::not_found("Bank transaction not found"))?;
+    if accepted_exists(&txn, bank_match::Column::BankTransactionId, transaction.id).await? {
+        return Err(AppError::conflict("The transaction is already reconciled"));
+    }
+
+    if let Some(invoice_id) = proposal.invoice_id {
+        let invoice = invoice::Entity::find_by_id(invoice_id)
+            .one(&txn)
+            .await?
+            .ok_or_else(|| AppError::not_found("Invoice not found"))?;
+        if invoice.status == "cancelled" {
+            return Err(AppError::conflict("The invoice is cancelled"));
+        }
+        if record_payment(&txn, &invoice, &transaction).await? {
+            // A paid invoice is no longer a candidate for other transactions.
+            bank_match::Entity::delete_many()
+                .filter(bank_match::Column::InvoiceId.eq(invoice.id))
+                .filter(bank_match::Column::Status.eq(MatchStatus::Proposed.as_str()))
+                .filter(bank_match::Column::Id.ne(proposal.id))
+                .exec(&txn)
+                .await?;
+        }
+    }
+    if let Some(expense_id) = proposal.expense_id {
+        if accepted_exists(&txn, bank_match::Column::ExpenseId, expense_id).await? {
+            return Err(AppError::conflict("The expense is already reconciled"));
+        }
+        bank_match::Entity::delete_many()
+            .filter(bank_match::Column::ExpenseId.eq(expense_id))
+            .filter(bank_match::Column::Status.eq(MatchStatus::Proposed.as_str()))
+            .filter(bank_match::Column::Id.ne(proposal.id))
+            .exec(&txn)
+            .await?;
+    }
+    bank_match::Entity::delete_many()
+        .filter(bank_match::Column::BankTransactionId.eq(transaction.id))
+        .filter(bank_match::Column::Status.eq(MatchStatus::Proposed.as_str()))
+        .filter(bank_match::Column::Id.ne(proposal.id))
+        .exec(&txn)
+        .await?;
+
+    // Two transactions accepted for one expense at once only collide on the unique index.
+    let accepted = decide(&txn, proposal, MatchStatus::Accepted, access.user.id)
+        .await
+        .map_err(|error| match error.sql_err() {
+            Some(SqlErr::UniqueConstraintViolation(_)) => {
+                AppError::conflict("The transaction or expense is already reconciled")
+            }
+            _ => AppError::from(error),
+        })?;
+    txn.commit().await?;
+
+    let response = match_responses(&state.db, vec![accepted]).await?.pop();
+    response
+        .map(Json)
+        .ok_or_else(|| AppError::internal("Accepted match vanished"))
+}
+
+#[utoipa::path(
+    post,
+    path = "/bank-matches/{id}/reject",
+    responses(
+        (status = 200, description = "Match rejected; it will not be proposed again", body = BankMatchResponse),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 404, description = "Match not found", body = ErrorResponse),
+        (status = 409, description = "Already decided", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "banking"
+)]
+pub async fn reject_bank_match(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<BankMatchResponse>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::BankingWrite).await?;
+    let id = Uuid::parse_str(&id).map_err(|_| AppError::bad_request("Invalid id"))?;
+    let proposal = find_proposal(&state.db, access.workspace_id, id).await?;
+    let rejected = decide(&state.db, proposal, MatchStatus::Rejected, access.user.id).await?;
+
+    let response = match_responses(&state.db, vec![rejected]).await?.pop();
+    response
+        .map(Json)
+        .ok_or_else(|| AppError::internal("Rejected match vanished"))
+}
+
+#[utoipa::path(
+    get,
+    path = "/invoices/{id}/payments",
+    responses(
+        (status = 200, description = "Payments recorded for the invoice", body = [InvoicePaymentResponse]),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 404, description = "Invoice not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "invoices"
+)]
+pub async fn list_invoice_payments(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<InvoicePaymentResponse>>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::InvoicesRead).await?;
+    let id = Uuid::parse_str(&id).map_err(|_| AppError::bad_request("Invalid id"))?;
+    let invoice = invoice::Entity::find_by_id(id)
+        .filter(invoice::Column::WorkspaceId.eq(access.workspace_id))
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Invoice not found"))?;
+    let payments = invoice_payment::Entity::find()
+        .filter(invoice_payment::Column::InvoiceId.eq(invoice.id))
+        .order_by_asc(invoice_payment::Column::PaidOn)
+        .all(&state.db)
+        .await?;
+    Ok(Json(payments.into_iter().map(InvoicePaymentResponse::from).collect()))
+}
+
+/// Replaces the open proposals of the workspace's unreconciled transactions (all of them, or
+/// those in `transaction_ids`) with fresh ones. Rejected pairs are not proposed again.
+pub(crate) async fn propose_matches<C: ConnectionTrait>(
+    db: &C,
+    workspace_id: Uuid,
+    transaction_ids: Option<&[Uuid]>,
+) -> Result<ReconciliationRunResponse, AppError> {
+    let mut select = bank_transaction::Entity::find()
+        .filter(bank_transaction::Column::WorkspaceId.eq(workspace_id))
+        .filter(bank_transaction::Column::Id.not_in_subquery(accepted_subquery(
+            bank_match::Column::BankTransactionId,
+        )));
+    if let Some(ids) = transaction_ids {
+        select = select.filter(bank_transaction::Column::Id.is_in(ids.iter().copied()));
+    }
+    let transactions = select.all(db).await?;
+    if transactions.is_empty() {
+        return Ok(ReconciliationRunResponse {
+            transactions: 0,
+            proposed: 0,
+        });
+    }
+    let transaction_ids: Vec<Uuid> = transactions.iter().map(|t| t.id).collect();
+
+    let rejected: HashSet<(Uuid, Option<Uuid>, Option<Uuid>)> = bank_match::Entity::find()
+        .filter(bank_match::Column::BankTransactionId.is_in(transaction_ids.clone()))
+        .filter(bank_match::Column::Status.eq(MatchStatus::Rejected.as_str()))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|m| (m.bank_transaction_id, m.invoice_id, m.expense_id))
+        .collect();
+    bank_match::Entity::delete_many()
+        .filter(bank_match::Column::BankTransactionId.is_in(transaction_ids))
+        .filter(bank_match::Column::Status.eq(MatchStatus::Proposed.as_str()))
+        .exec(db)
+        .await?;
+
+    let invoices = if transactions.iter().any(|t| t.amount > 0.0) {
+        invoice::Entity::find()
+            .filter(invoice::Column::WorkspaceId.eq(workspace_id))
+            // Invoices marked paid by hand still await the bank booking that paid them.
+            .filter(invoice::Column::Status.is_in(["sent", "paid"]))
+            .all(db)
+            .await?
+    } else {
+        Vec::new()
+    };
+    let paid = paid_amounts(db, invoices.iter().map(|invoice| invoice.id)).await?;
+
+    let expenses = match transactions
+        .iter()
+        .filter(|t| t.amount < 0.0)
+        .map(|t| t.booking_date)
+        .fold(None, |range: Option<(NaiveDate, NaiveDate)>, date| match range {
+            Some((from, to)) => Some((from.min(date), to.max(date))),
+            None => Some((date, date)),
+        }) {
+        Some((from, to)) => {
+            let window = Duration::days(EXPENSE_DATE_WINDOW_DAYS);
+            expense::Entity::find()
+                .filter(expense::Column::WorkspaceId.eq(workspace_id))
+                .filter(expense::Column::Date.between(from - window, to + window))
+                .filter(
+                    expense::Column::Id
+                        .not_in_subquery(accepted_subquery(bank_match::Column::ExpenseId)),
+                )
+                .all(db)
+                .await?
+        }
+        None => Vec::new(),
+    };
+
+    let now = Utc::now();
+    let mut proposals = Vec::new();
+    for transaction in &transactions {
+        let mut candidates: Vec<Candidate> = if transaction.amount > 0.0 {
+            invoices
+                .iter()
+                .filter_map(|invoice| {
+                    let open = invoice.total_amount - paid.get(&invoice.id).unwrap_or(&0.0);
+                    if open < AMOUNT_TOLERANCE {
+                        return None;
+                    }
+                    score_invoice(transaction, invoice, open).map(|(score, reasons)| Candidate {
+                        score,
+                        reasons,
+                        invoice_id: Some(invoice.id),
+                        expense_id: None,
+                    })
+                })
+                .collect()
+        } else {
+            expenses
+                .iter()
+                .filter_map(|expense| {
+                    score_expense(transaction, expense).map(|(score, reasons)| Candidate {
+                        score,
+                        reasons,
+                        invoice_id: None,
+                        expense_id: Some(expense.id),
+                    })
+                })
+                .collect()
+        };
+        candidates.retain(|candidate| {
+            candidate.score >= MIN_CONFIDENCE
+                && !rejected.contains(&(
+                    transaction.id,
+                    candidate.invoice_id,
+                    candidate.expense_id,
+                ))
+        });
+        candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+        candidates.truncate(MAX_PROPOSALS);
+
+        proposals.extend(candidates.into_iter().map(|candidate| bank_match::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            workspace_id: Set(workspace_id),
+            bank_transaction_id: Set(transaction.id),
+            invoice_id: Set(candidate.invoice_id),
+            expense_id: Set(candidate.expense_id),
+            confidence: Set((candidate.score.min(1.0) * 100.0).round() / 100.0),
+            reasons: Set(serde_json::json!(candidate.reasons)),
+            status: Set(MatchStatus::Proposed.as_str().to_string()),
+            decided_by: Set(None),
+            decided_at: Set(None),
+            created_at: Set(now),
+        }));
+    }
+
+    let proposed = proposals.len();
+    if !proposals.is_empty() {
+        bank_match::Entity::insert_many(proposals).exec(db).await?;
+    }
+    Ok(ReconciliationRunResponse {
+        transactions: transactions.len(),
+        proposed,
+    })
+}
+
+/// Scores an incoming transaction against an open invoice: the amount, the invoice number
+/// in the purpose of payment and the payer's name.
+fn score_invoice(
+    transaction: &bank_transaction::Model,
+    invoice: &invoice::Model,
+    open_amount: f64,
+) -> Option<(f64, Vec<String>)> {
+    if transaction.currency != invoice.currency || transaction.booking_date < invoice.date {
+        return None;
+    }
+    let mut score = 0.0;
+    let mut reasons = Vec::new();
+
+    let amount_score = amount_score(transaction.amount, open_amount);
+    if amount_score > 0.0 {
+        score += amount_score;
+        reasons.push(if amount_score >= 0.5 {
+            "Amount equals the open amount".to_string()
+        } else {
+            format!("Amount is close to the open amount of {open_amount:.2}")
+        });
+    }
+
+    let text = [
+        transaction.remittance_info.as_deref(),
+        transaction.reference.as_deref(),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>()
+    .join(" ");
+    let number_found = mentions_number(&text, &invoice.invoice_number);
+    if number_found {
+        score += 0.4;
+        reasons.push(format!(
+            "Invoice number {} in the purpose of payment",
+            invoice.invoice_number
+        ));
+    }
+    if amount_score == 0.0 && !number_found {
+        return None;
+    }
+
+    let similarity = transaction
+        .counterparty_name
+        .as_deref()
+        .map(|name| name_similarity(name, &invoice.client_name))
+        .unwrap_or(0.0);
+    if similarity >= 0.5 {
+        score += 0.2 * similarity;
+        reasons.push(format!("Payer resembles client {}", invoice.client_name));
+    }
+    Some((score, reasons))
+}
+
+/// Scores an outgoing transaction against an expense: the amount, how far apart the dates
+/// are and the payee's name.
+fn score_expense(
+    transaction: &bank_transaction::Model,
+    expense: &expense::Model,
+) -> Option<(f64, Vec<String>)> {
+    let days = (transaction.booking_date - expense.date).num_days().abs();
+    if transaction.currency != expense.currency || days > EXPENSE_DATE_WINDOW_DAYS {
+        return None;
+    }
+    let amount_score = amount_score(-transaction.amount, expense.amount);
+    if amount_score == 0.0 {
+        return None;
+    }
+    let mut score = amount_score;
+    let mut reasons = vec![if amount_score >= 0.5 {
+        "Amount equals the expense".to_string()
+    } else {
+        format!("Amount is close to the expense amount of {:.2}", expense.amount)
+    }];
+
+    score += match days {
+        0..=3 => 0.25,
+        4..=10 => 0.15,
+        _ => 0.05,
+    };
+    reasons.push(match days {
+        0 => "Booked on the expense date".to_string(),
+        1 => "Booked 1 day from the expense date".to_string(),
+        _ => format!("Booked {days} days from the expense date"),
+    });
+
+    let similarity = [
+        transaction.counterparty_name.as_deref(),
+        transaction.remittance_info.as_deref(),
+    ]
+    .into_iter()
+    .flatten()
+    .map(|text| name_similarity(text, &expense.vendor))
+    .fold(0.0, f64::max);
+    if similarity >= 0.5 {
+        score += 0.25 * similarity;
+        reasons.push(format!("Payee resembles vendor {}", expense.vendor));
+    }
+    Some((score, reasons))
+}
+
+/// 0.5 for the same amount, 0.25 for a near match, 0 otherwise.
+fn amount_score(paid: f64, expected: f64) -> f64 {
+    if expected <= 0.0 {
+        return 0.0;
+    }
+    let difference = (paid - expected).abs();
+    if difference < AMOUNT_TOLERANCE {
+        0.5
+    } else if difference / expected <= NEAR_AMOUNT_SHARE {
+        0.25
+    } else {
+        0.0
+    }
+}
+
+/// Whether `text` mentions the invoice number, ignoring case, spaces and punctuation (payers
+/// write `RE-2024-001` as `RE 2024 001` or `RE2024001`). A number directly followed by
+/// another digit, such as `1001` in `10012`, does not count.
+fn mentions_number(text: &str, number: &str) -> bool {
+    let compact = |value: &str| -> String {
+        value
+            .chars()
+            .filter(|c| c.is_alphanumeric())
+            .flat_map(char::to_uppercase)
+            .collect()
+    };
+    let number = compact(number);
+    if number.len() < 3 || !number.chars().any(|c| c.is_ascii_digit()) {
+        return false;
+    }
+    let text = compact(text);
+    text.match_indices(&number).any(|(start, _)| {
+        let before = text[..start].chars().next_back();
+        let after = text[start + number.len()..].chars().next();
+        let starts_with_digit = number.starts_with(|c: char| c.is_ascii_digit());
+        let digit_after = after.is_some_and(|c| c.is_ascii_digit());
+        let digit_before = starts_with_digit && before.is_some_and(|c| c.is_ascii_digit());
+        !digit_after && !digit_before
+    })
+}
+
+/// Share of the shorter name's words found in the other name, ignoring legal forms; banks
+/// often truncate or reorder names, and spell umlauts out.
+fn name_similarity(a: &str, b: &str) -> f64 {
+    fn words(value: &str) -> HashSet<String> {
+        value
+            .to_lowercase()
+            .replace('ä', "ae")
+            .replace('ö', "oe")
+            .replace('ü', "ue")
+            .replace('ß', "ss")
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| word.len() >= 2 && !NAME_STOPWORDS.contains(word))
+            .map(str::to_string)
+            .collect()
+    }
+    let a = words(a);
+    let b = words(b);
+    let shorter = a.len().min(b.len());
+    if shorter == 0 {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f64 / shorter as f64
+}
+
+/// Records the transaction as a payment of the invoice and marks the invoice paid once the
+/// payments cover its total. Returns whether the invoice is now paid.
+async fn record_payment<C: ConnectionTrait>(
+    db: &C,
+    invoice: &invoice::Model,
+    transaction: &bank_transaction::Model,
+) -> Result<bool, AppError> {
+    invoice_payment::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        workspace_id: Set(transaction.workspace_id),
+        invoice_id: Set(invoice.id),
+        bank_transaction_id: Set(Some(transaction.id)),
+        amount: Set(transaction.amount),
+        currency: Set(transaction.currency.clone()),
+        paid_on: Set(transaction.booking_date),
+        created_at: Set(Utc::now()),
+    }
+    .insert(db)
+    .await?;
+
+    let paid = paid_amounts(db, [invoice.id]).await?;
+    let fully_paid =
+        paid.get(&invoice.id).copied().unwrap_or(0.0) >= invoice.total_amount - AMOUNT_TOLERANCE;
+    if fully_paid && invoice.status != "paid" {
+        let mut active: invoice::ActiveModel = invoice.clone().into();
+        active.status = Set("paid".to_string());
+        active.update(db).await?;
+    }
+    Ok(fully_paid)
+}
+
+/// Sum of the recorded payments per invoice.
+async fn paid_amounts<C: ConnectionTrait>(
+    db: &C,
+    invoice_ids: impl IntoIterator<Item = Uuid>,
+) -> Result<HashMap<Uuid, f64>, AppError> {
+    let invoice_ids: Vec<Uuid> = invoice_ids.into_iter().collect();
+    if invoice_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let payments = invoice_payment::Entity::find()
+        .filter(invoice_payment::Column::InvoiceId.is_in(invoice_ids))
+        .all(db)
+        .await?;
+    let mut paid = HashMap::new();
+    for payment in payments {
+        *paid.entry(payment.invoice_id).or_insert(0.0) += payment.amount;
+    }
+    Ok(paid)
+}
+
+/// Ids in `column` of accepted matches.
+pub(crate) fn accepted_subquery(column: bank_match::Column) -> SelectStatement {
+    SqlQuery::select()
+        .column(column)
+        .from(bank_match::Entity)
+        .and_where(bank_match::Column::Status.eq(MatchStatus::Accepted.as_str()))
+        .and_where(column.is_not_null())
+        .to_owned()
+}
+
+async fn accepted_exists<C: ConnectionTrait>(
+    db: &C,
+    column: bank_match::Column,
+    id: Uuid,
+) -> Result<bool, AppError> {
+    Ok(bank_match::Entity::find()
+        .filter(column.eq(id))
+        .filter(bank_match::Column::Status.eq(MatchStatus::Accepted.as_str()))
+        .one(db)
+        .await?
+        .is_some())
+}
+
+async fn find_proposal<C: ConnectionTrait>(
+    db: &C,
+    workspace_id: Uuid,
+    id: Uuid,
+) -> Result<bank_match::Model, AppError> {
+    let found = bank_match::Entity::find_by_id(id)
+        .filter(bank_match::Column::WorkspaceId.eq(workspace_id))
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Match not found"))?;
+    if found.status != MatchStatus::Proposed.as_str() {
+        return Err(AppError::conflict(format!("The match is already {}", found.status)));
+    }
+    Ok(found)
+}
+
+async fn decide<C: ConnectionTrait>(
+    db: &C,
+    proposal: bank_match::Model,
+    status: MatchStatus,
+    user_id: Uuid,
+) -> Result<bank_match::Model, DbErr> {
+    let mut active: bank_match::ActiveModel = proposal.into();
+    active.status = Set(status.as_str().to_string());
+    active.decided_by = Set(Some(user_id));
+    active.decided_at = Set(Some(Utc::now()));
+    active.update(db).await
+}
+
+/// Loads the transactions, invoices and expenses the matches refer to.
+async fn match_responses<C: ConnectionTrait>(
+    db: &C,
+    matches: Vec<bank_match::Model>,
+) -> Result<Vec<BankMatchResponse>, AppError> {
+    let transactions: HashMap<Uuid, bank_transaction::Model> =
+        bank_transaction::Entity::find()
+            .filter(
+                bank_transaction::Column::Id
+                    .is_in(matches.iter().map(|m| m.bank_transaction_id)),
+            )
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|transaction| (transaction.id, transaction))
+            .collect();
+    let invoices: HashMap<Uuid, invoice::Model> = invoice::Entity::find()
+        .filter(invoice::Column::Id.is_in(matches.iter().filter_map(|m| m.invoice_id)))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|invoice| (invoice.id, invoice))
+        .collect();
+    let paid = paid_amounts(db, invoices.keys().copied()).await?;
+    let expenses: HashMap<Uuid, expense::Model> = expense::Entity::find()
+        .filter(expense::Column::Id.is_in(matches.iter().filter_map(|m| m.expense_id)))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|expense| (expense.id, expense))
+        .collect();
+
+    let mut responses = Vec::with_capacity(matches.len());
+    for found in matches {
+        let Some(transaction) = transactions.get(&found.bank_transaction_id).cloned() else {
+            continue;
+        };
+        responses.push(BankMatchResponse {
+            id: found.id,
+            status: match found.status.as_str() {
+                "accepted" => MatchStatus::Accepted,
+                "rejected" => MatchStatus::Rejected,
+                _ => MatchStatus::Proposed,
+            },
+            confidence: found.confidence,
+            reasons: serde_json::from_value(found.reasons).unwrap_or_default(),
+            transaction: BankTransactionResponse::from(transaction),
+            invoice: found
+                .invoice_id
+                .and_then(|id| invoices.get(&id))
+                .map(|invoice| MatchedInvoice {
+                    id: invoice.id,
+                    invoice_number: invoice.invoice_number.clone(),
+                    client_name: invoice.client_name.clone(),
+                    total_amount: invoice.total_amount,
+                    open_amount: invoice.total_amount
+                        - paid.get(&invoice.id).copied().unwrap_or(0.0),
+                    currency: invoice.currency.clone(),
+                    date: invoice.date,
+                    due_date: invoice.due_date,
+                    status: invoice.status.clone(),
+                }),
+            expense: found
+                .expense_id
+                .and_then(|id| expenses.get(&id))
+                .map(|expense| MatchedExpense {
+                    id: expense.id,
+                    vendor: expense.vendor.clone(),
+                    description: expense.description.clone(),
+                    amount: expense.amount,
+                    currency: expense.currency.clone(),
+                    date: expense.date,
+                }),
+            decided_at: found.decided_at,
+            created_at: found.created_at,
+        });
+    }
+    Ok(responses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult, Transaction};
+
+    fn incoming(amount: f64) -> bank_transaction::Model {
+        bank_transaction::Model {
+            id: Uuid::new_v4(),
+            workspace_id: Uuid::nil(),
+            bank_account_id: Uuid::new_v4(),
+            import_id: Uuid::new_v4(),
+            booking_date: NaiveDate::from_ymd_opt(2026, 3, 2).unwrap(),
+            value_date: None,
+            amount,
+            currency: "EUR".to_string(),
+            counterparty_name: Some("Client GmbH".to_string()),
+            counterparty_iban: None,
+            remittance_info: Some("Invoices RE-1 RE-2".to_string()),
+            reference: None,
+            fingerprint: "fingerprint".to_string(),
+            created_at: Utc::now(),
+        }
+    }
+
+    fn issued(number: &str, status: &str) -> invoice::Model {
+        invoice::Model {
+            id: Uuid::new_v4(),
+            invoice_number: number.to_string(),
+            user_id: None,
+            workspace_id: Some(Uuid::nil()),
+            company_id: None,
+            template_id: None,
+            contact_id: None,
+            client_name: "Client GmbH".to_string(),
+            client_address: "Street 1".to_string(),
+            description: "Work".to_string(),
+            amount: 100.0,
+            currency: "EUR".to_string(),
+            user_address: "Home 1".to_string(),
+            total_amount: 119.0,
+            date: NaiveDate::from_ymd_opt(2026, 2, 1).unwrap(),
+            due_date: None,
+            status: status.to_string(),
+            sent_at: None,
+        }
+    }
+
+    fn payment(invoice: &invoice::Model, amount: f64) -> invoice_payment::Model {
+        invoice_payment::Model {
+            id: Uuid::new_v4(),
+            workspace_id: Uuid::nil(),
+            invoice_id: invoice.id,
+            bank_transaction_id: None,
+            amount,
+            currency: "EUR".to_string(),
+            paid_on: NaiveDate::from_ymd_opt(2026, 2, 15).unwrap(),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn proposes_invoices_marked_paid_but_not_fully_paid_ones() {
+        let transaction = incoming(119.0);
+        // Marked paid by hand, awaiting its bank booking.
+        let marked_paid = issued("RE-1", "paid");
+        // Still sent, but its payments already cover the total.
+        let covered = issued("RE-2", "sent");
+        let proposal = bank_match::Model {
+            id: Uuid::new_v4(),
+            workspace_id: Uuid::nil(),
+            bank_transaction_id: transaction.id,
+            invoice_id: Some(marked_paid.id),
+            expense_id: None,
+            confidence: 0.7,
+            reasons: serde_json::json!([]),
+            status: MatchStatus::Proposed.as_str().to_string(),
+            decided_by: None,
+            decided_at: None,
+            created_at: Utc::now(),
+        };
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([[transaction.clone()]])
+            .append_query_results([Vec::<bank_match::Model>::new()])
+            .append_query_results([[marked_paid.clone(), covered.clone()]])
+            .append_query_results([[payment(&covered, 100.0), payment(&covered, 19.0)]])
+            .append_query_results([[proposal]])
+            .append_exec_results([MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 0,
+            }])
+            .into_connection();
+
+        let run = propose_matches(&db, Uuid::nil(), None).await.ok().unwrap();
+        // Both invoices are named in the purpose; only the one still open is proposed.
+        assert_eq!((run.transactions, run.proposed), (1, 1));
+        assert_eq!(
+            db.into_transaction_log()[3],
+            Transaction::from_sql_and_values(
+                DatabaseBackend::Postgres,
+                r#"SELECT "invoice"."id", "invoice"."invoice_number", "invoice"."user_id", "invoice"."workspace_id", "invoice"."company_id", "invoice"."template_id", "invoice"."contact_id", "invoice"."client_name", "invoice"."client_address", "invoice"."description", "invoice"."amount", "invoice"."currency", "invoice"."user_address", "invoice"."total_amount", "invoice"."date", "invoice"."due_date", "invoice"."status", "invoice"."sent_at" FROM "invoice" WHERE "invoice"."workspace_id" = $1 AND "invoice"."status" IN ($2, $3)"#,
+                [Uuid::nil().into(), "sent".into(), "paid".into()],
+            )
+        );
+    }
+}
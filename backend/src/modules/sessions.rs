@@ -0,0 +1,279 @@
+use crate::entity::session;
+use crate::modules::account::end_all_sessions;
+use crate::modules::auth::{require_session, session_cookie};
+use crate::modules::shared::{AppError, AppState};
+use crate::modules::validation::Path;
+use axum::{
+    extract::State,
+    http::{header::SET_COOKIE, HeaderMap, StatusCode},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder};
+use serde::Serialize;
+use std::time::Duration;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Serialize, ToSchema)]
+pub struct ActiveSessionResponse {
+    pub id: Uuid,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    /// Whether this is the session making the request
+    pub current: bool,
+}
+
+#[utoipa::path(
+    get,
+    path = "/auth/sessions",
+    responses(
+        (status = 200, description = "Active sessions, most recently used first", body = [ActiveSessionResponse]),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Requires a browser session", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "auth"
+)]
+pub async fn list_sessions(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<ActiveSessionResponse>>, AppError> {
+    let (current_user, current_session) = require_session(&state, &headers).await?;
+    let sessions = session::Entity::find()
+        .filter(session::Column::UserId.eq(current_user.id))
+        .filter(session::Column::ExpiresAt.gt(Utc::now()))
+        .order_by_desc(session::Column::LastSeenAt)
+        .all(&state.db)
+        .await?;
+
+    Ok(Json(
+        sessions
+            .into_iter()
+            .map(|session| ActiveSessionResponse {
+                current: session.id == current_session.id,
+                id: session.id,
+                user_agent: session.user_agent,
+                ip_address: session.ip_address,
+                created_at: session.created_at,
+                last_seen_at: session.last_seen_at,
+                expires_at: session.expires_at,
+            })
+            .collect(),
+    ))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/auth/sessions/{id}",
+    params(
+        ("id" = String, Path, description = "Session id (UUID)")
+    ),
+    responses(
+        (status = 204, description = "Session signed out"),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Requires a browser session", body = ErrorResponse),
+        (status = 404, description = "Session not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "auth"
+)]
+pub async fn revoke_session(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    let (current_user, _) = require_session(&state, &headers).await?;
+    let id = Uuid::parse_str(&id).map_err(|_| AppError::bad_request("Invalid id"))?;
+    let result = session::Entity::delete_many()
+        .filter(session::Column::Id.eq(id))
+        .filter(session::Column::UserId.eq(current_user.id))
+        .exec(&state.db)
+        .await?;
+    if result.rows_affected == 0 {
+        return Err(AppError::not_found("Session not found"));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/sessions/revoke-others",
+    responses(
+        (status = 204, description = "All other sessions signed out"),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Requires a browser session", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "auth"
+)]
+pub async fn revoke_other_sessions(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<StatusCode, AppError> {
+    let (current_user, current_session) = require_session(&state, &headers).await?;
+    session::Entity::delete_many()
+        .filter(session::Column::UserId.eq(current_user.id))
+        .filter(session::Column::Id.ne(current_session.id))
+        .exec(&state.db)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/logout-all",
+    responses(
+        (status = 204, description = "Signed out everywhere, including this browser"),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Requires a browser session", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "auth"
+)]
+pub async fn logout_everywhere(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<(HeaderMap, StatusCode), AppError> {
+    let (current_user, _) = require_session(&state, &headers).await?;
+    end_all_sessions(&state.db, current_user.id).await?;
+
+    let mut response_headers = HeaderMap::new();
+    response_headers.insert(SET_COOKIE, session_cookie("", 0)?);
+    Ok((response_headers, StatusCode::NO_CONTENT))
+}
+
+/// Deletes expired sessions every `SESSION_PURGE_INTERVAL_MINUTES` (default 60).
+pub fn spawn_session_purge_job(db: DatabaseConnection) {
+    let minutes = std::env::var("SESSION_PURGE_INTERVAL_MINUTES")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(60)
+        .max(1);
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(minutes * 60));
+        loop {
+            interval.tick().await;
+            match purge_expired_sessions(&db).await {
+                Ok(0) => {}
+                Ok(count) => println!("Sessions: purged {count} expired session(s)"),
+                Err(error) => eprintln!("Session purge failed: {error}"),
+            }
+        }
+    });
+}
+
+pub async fn purge_expired_sessions(db: &DatabaseConnection) -> Result<u64, AppError> {
+    let result = session::Entity::delete_many()
+        .filter(session::Column::ExpiresAt.lt(Utc::now()))
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::test_support::{self, session_headers};
+    use axum::http::{header::COOKIE, HeaderValue};
+    use sea_orm::{DatabaseBackend, MockExecResult, Transaction};
+
+    fn user_id() -> Uuid {
+        Uuid::from_u128(1)
+    }
+
+    fn current_session() -> session::Model {
+        test_support::session(Uuid::from_u128(2), user_id())
+    }
+
+    /// A signed-in browser whose request deletes `rows_affected` sessions.
+    fn signed_in(rows_affected: u64) -> (AppState, HeaderMap) {
+        let db = test_support::signed_in(
+            current_session(),
+            test_support::user(user_id(), "owner@example.com"),
+        )
+        .append_exec_results([MockExecResult {
+            last_insert_id: 0,
+            rows_affected,
+        }])
+        .into_connection();
+        (AppState::for_tests(db), session_headers())
+    }
+    fn statement(state: AppState, index: usize) -> Transaction {
+        let AppState { db, .. } = state;
+        db.into_transaction_log().remove(index)
+    }
+
+    #[tokio::test]
+    async fn revoking_other_sessions_keeps_the_current_one() {
+        let (state, headers) = signed_in(3);
+        let status = revoke_other_sessions(State(state.clone()), headers).await.ok().unwrap();
+
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        assert_eq!(
+            statement(state, 2),
+            Transaction::from_sql_and_values(
+                DatabaseBackend::Postgres,
+                r#"DELETE FROM "session" WHERE "session"."user_id" = $1 AND "session"."id" <> $2"#,
+                [user_id().into(), current_session().id.into()],
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn logging_out_everywhere_ends_every_session_and_clears_the_cookie() {
+        let (state, headers) = signed_in(4);
+        let (response_headers, status) =
+            logout_everywhere(State(state.clone()), headers).await.ok().unwrap();
+
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let cookie = response_headers.get(SET_COOKIE).unwrap().to_str().unwrap();
+        assert!(cookie.starts_with("session_id=;"), "{cookie}");
+        assert!(cookie.contains("Max-Age=0"), "{cookie}");
+        assert_eq!(
+            statement(state, 2),
+            Transaction::from_sql_and_values(
+                DatabaseBackend::Postgres,
+                r#"DELETE FROM "session" WHERE "session"."user_id" = $1"#,
+                [user_id().into()],
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn a_session_of_another_user_is_not_found() {
+        let (state, headers) = signed_in(0);
+        let other = Uuid::from_u128(9);
+        let error = revoke_session(State(state.clone()), headers, Path(other.to_string()))
+            .await
+            .err()
+            .unwrap();
+
+        assert_eq!(error.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            statement(state, 2),
+            Transaction::from_sql_and_values(
+                DatabaseBackend::Postgres,
+                r#"DELETE FROM "session" WHERE "session"."id" = $1 AND "session"."user_id" = $2"#,
+                [other.into(), user_id().into()],
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn requests_without_a_valid_session_cookie_are_rejected() {
+        let (state, _) = signed_in(1);
+        let mut headers = HeaderMap::new();
+        headers.insert(COOKIE, HeaderValue::from_static("session_id=session-token.00"));
+        let error = revoke_other_sessions(State(state), headers).await.err().unwrap();
+
+        assert_eq!(error.status(), StatusCode::UNAUTHORIZED);
+    }
+}
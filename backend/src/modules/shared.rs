@@ -1,6 +1,468 @@
-use sea_orm::DatabaseConnection;
+use crate::modules::banking::BankTransactionResponse;
+use crate::modules::company::CompanyResponse;
+use crate::modules::expenses::ExpenseResponse;
+use crate::modules::invoices::{InvoiceResponse, TemplateResponse};
+use crate::modules::mail::Mailer;
+use crate::modules::ocr::OcrEngine;
+use crate::modules::rate_limit::RateLimiter;
+use crate::modules::reconciliation::BankMatchResponse;
+use crate::modules::storage::ReceiptStorage;
+use axum::{
+    extract::{ConnectInfo, Request},
+    http::{header::RETRY_AFTER, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use sea_orm::{DatabaseConnection, EntityTrait, PaginatorTrait, Select};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+pub const DEFAULT_PAGE_SIZE: u64 = 50;
+pub const MAX_PAGE_SIZE: u64 = 200;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
 
-#[derive(Clone)]
 pub struct AppState {
     pub db: DatabaseConnection,
+    pub mailer: Mailer,
+    pub rate_limiter: RateLimiter,
+    pub receipts: Arc<dyn ReceiptStorage>,
+    pub ocr: Arc<dyn OcrEngine>,
+}
+
+impl Clone for AppState {
+    fn clone(&self) -> Self {
+        Self {
+            db: clone_db(&self.db),
+            mailer: self.mailer.clone(),
+            rate_limiter: self.rate_limiter.clone(),
+            receipts: self.receipts.clone(),
+            ocr: self.ocr.clone(),
+        }
+    }
+}
+
+impl AppState {
+    /// State over `db` with an in-process mailer, no rate limits and receipts in the
+    /// temp directory, for tests.
+    #[cfg(test)]
+    pub(crate) fn for_tests(db: DatabaseConnection) -> Self {
+        Self {
+            db,
+            mailer: Mailer::memory(),
+            rate_limiter: RateLimiter::disabled(),
+            receipts: Arc::new(crate::modules::storage::LocalStorage::new(
+                std::env::temp_dir().join("freelance-forge-test-receipts"),
+            )),
+            ocr: Arc::new(crate::modules::ocr::TesseractOcr::from_env()),
+        }
+    }
+}
+
+/// Clones the pool handle. `DatabaseConnection` itself is only `Clone` while
+/// sea-orm's `mock` feature is off, and the tests turn it on.
+pub fn clone_db(db: &DatabaseConnection) -> DatabaseConnection {
+    match db {
+        DatabaseConnection::SqlxPostgresPoolConnection(pool) => {
+            DatabaseConnection::SqlxPostgresPoolConnection(pool.clone())
+        }
+        #[cfg(test)]
+        DatabaseConnection::MockDatabaseConnection(mock) => {
+            DatabaseConnection::MockDatabaseConnection(mock.clone())
+        }
+        _ => DatabaseConnection::Disconnected,
+    }
+}
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+    static CLIENT_IP: Option<String>;
+}
+
+/// Error body returned by every endpoint.
+#[derive(Serialize, ToSchema)]
+pub struct ErrorResponse {
+    /// Machine-readable error code, e.g. `not_found` or `validation_failed`
+    pub code: String,
+    /// Human-readable message, safe to show to users
+    pub message: String,
+    /// Per-field problems for `validation_failed` errors
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldError>,
+    /// Id of the request, also sent as the `x-request-id` header
+    pub request_id: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    /// Machine-readable reason, e.g. `required` or `invalid`
+    pub code: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, code: &str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.to_string(),
+            code: code.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum AppError {
+    BadRequest(String),
+    Validation(Vec<FieldError>),
+    Unauthorized(String),
+    Forbidden(String),
+    NotFound(String),
+    Conflict(String),
+    PayloadTooLarge(String),
+    UnsupportedMediaType(String),
+    /// Rate limit or lockout; the second field is the `Retry-After` delay in seconds.
+    TooManyRequests(String, u64),
+    /// An upstream service (mail server, AI provider) failed. The detail is logged.
+    BadGateway(String),
+    /// Unexpected failure. The detail is logged and never sent to the client.
+    Internal(String),
+}
+
+impl AppError {
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self::BadRequest(message.into())
+    }
+
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        Self::Unauthorized(message.into())
+    }
+
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        Self::Forbidden(message.into())
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::NotFound(message.into())
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::Conflict(message.into())
+    }
+
+    pub fn payload_too_large(message: impl Into<String>) -> Self {
+        Self::PayloadTooLarge(message.into())
+    }
+
+    pub fn unsupported_media_type(message: impl Into<String>) -> Self {
+        Self::UnsupportedMediaType(message.into())
+    }
+
+    pub fn too_many_requests(message: impl Into<String>, retry_after_secs: u64) -> Self {
+        Self::TooManyRequests(message.into(), retry_after_secs.max(1))
+    }
+
+    pub fn bad_gateway(detail: impl fmt::Display) -> Self {
+        Self::BadGateway(detail.to_string())
+    }
+
+    pub fn internal(detail: impl fmt::Display) -> Self {
+        Self::Internal(detail.to_string())
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::BadRequest(_) => StatusCode::BAD_REQUEST,
+            Self::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            Self::Forbidden(_) => StatusCode::FORBIDDEN,
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::Conflict(_) => StatusCode::CONFLICT,
+            Self::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::UnsupportedMediaType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Self::TooManyRequests(..) => StatusCode::TOO_MANY_REQUESTS,
+            Self::BadGateway(_) => StatusCode::BAD_GATEWAY,
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::BadRequest(_) => "bad_request",
+            Self::Validation(_) => "validation_failed",
+            Self::Unauthorized(_) => "unauthorized",
+            Self::Forbidden(_) => "forbidden",
+            Self::NotFound(_) => "not_found",
+            Self::Conflict(_) => "conflict",
+            Self::PayloadTooLarge(_) => "payload_too_large",
+            Self::UnsupportedMediaType(_) => "unsupported_media_type",
+            Self::TooManyRequests(..) => "rate_limited",
+            Self::BadGateway(_) => "upstream_failed",
+            Self::Internal(_) => "internal_error",
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Validation(fields) => {
+                let fields: Vec<&str> = fields.iter().map(|field| field.field.as_str()).collect();
+                write!(f, "invalid fields: {}", fields.join(", "))
+            }
+            Self::BadRequest(message)
+            | Self::Unauthorized(message)
+            | Self::Forbidden(message)
+            | Self::NotFound(message)
+            | Self::Conflict(message)
+            | Self::PayloadTooLarge(message)
+            | Self::UnsupportedMediaType(message)
+            | Self::TooManyRequests(message, _)
+            | Self::BadGateway(message)
+            | Self::Internal(message) => f.write_str(message),
+        }
+    }
+}
+
+impl From<sea_orm::DbErr> for AppError {
+    fn from(error: sea_orm::DbErr) -> Self {
+        Self::internal(error)
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let request_id = current_request_id();
+        let status = self.status();
+        let code = self.code().to_string();
+        let retry_after = match &self {
+            Self::TooManyRequests(_, seconds) => Some(*seconds),
+            _ => None,
+        };
+        let (message, fields) = match self {
+            Self::Validation(fields) => ("Validation failed".to_string(), fields),
+            Self::BadGateway(detail) => {
+                eprintln!(
+                    "[{}] upstream error: {detail}",
+                    request_id.as_deref().unwrap_or("-")
+                );
+                ("Upstream service failed".to_string(), Vec::new())
+            }
+            Self::Internal(detail) => {
+                eprintln!(
+                    "[{}] internal error: {detail}",
+                    request_id.as_deref().unwrap_or("-")
+                );
+                ("Internal server error".to_string(), Vec::new())
+            }
+            Self::BadRequest(message)
+            | Self::Unauthorized(message)
+            | Self::Forbidden(message)
+            | Self::NotFound(message)
+            | Self::Conflict(message)
+            | Self::PayloadTooLarge(message)
+            | Self::UnsupportedMediaType(message)
+            | Self::TooManyRequests(message, _) => (message, Vec::new()),
+        };
+
+        let body = ErrorResponse {
+            code,
+            message,
+            fields,
+            request_id,
+        };
+        let mut response = (status, Json(body)).into_response();
+        if let Some(seconds) = retry_after {
+            response.headers_mut().insert(RETRY_AFTER, HeaderValue::from(seconds));
+        }
+        response
+    }
+}
+
+/// Id of the request currently being handled, if it passed through [`request_id`].
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+/// Assigns every request an id (reusing a sane incoming `x-request-id`), makes it
+/// available to error responses and echoes it in the response headers.
+pub async fn request_id(request: Request, next: Next) -> Response {
+    let id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| {
+            !value.is_empty()
+                && value.len() <= 64
+                && value
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        })
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+    let mut response = REQUEST_ID.scope(id.clone(), next.run(request)).await;
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+/// IP address of the caller of the current request, if it passed through [`client_ip`].
+pub fn current_client_ip() -> Option<String> {
+    CLIENT_IP.try_with(|ip| ip.clone()).ok().flatten()
+}
+
+/// Records the caller's IP address for the request. Behind a reverse proxy set
+/// `TRUST_PROXY=true` so the first `X-Forwarded-For` entry is used instead of the peer.
+pub async fn client_ip(request: Request, next: Next) -> Response {
+    let trust_proxy = std::env::var("TRUST_PROXY")
+        .map(|v| v == "true")
+        .unwrap_or(false);
+    let forwarded = request
+        .headers()
+        .get("x-forwarded-for")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(',').next())
+        .map(str::trim)
+        .filter(|value| !value.is_empty());
+    let ip = match forwarded {
+        Some(forwarded) if trust_proxy => Some(forwarded.to_string()),
+        _ => request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip().to_string()),
+    };
+
+    CLIENT_IP.scope(ip, next.run(request)).await
+}
+
+/// Origin of the web frontend, from `CORS_ORIGIN` or `FRONTEND_ORIGIN`.
+pub fn frontend_origin() -> String {
+    std::env::var("CORS_ORIGIN")
+        .or_else(|_| std::env::var("FRONTEND_ORIGIN"))
+        .unwrap_or_else(|_| "http://localhost:5173".to_string())
+}
+
+/// `SameSite` attribute of the session cookie: `COOKIE_SAMESITE`, defaulting to `None`
+/// for secure cookies and `Lax` otherwise.
+pub fn cookie_same_site() -> String {
+    std::env::var("COOKIE_SAMESITE").unwrap_or_else(|_| {
+        let secure = std::env::var("COOKIE_SECURE").map(|v| v == "true").unwrap_or(false);
+        if secure { "None" } else { "Lax" }.to_string()
+    })
+}
+
+/// Offset pagination shared by all list endpoints. Pages are 1-based.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PageParams {
+    /// Page number, starting at 1 (default 1)
+    pub page: Option<u64>,
+    /// Items per page (default 50, max 200)
+    pub per_page: Option<u64>,
+}
+
+impl PageParams {
+    pub fn resolve(&self) -> Result<(u64, u64), AppError> {
+        let page = self.page.unwrap_or(1);
+        let per_page = self.per_page.unwrap_or(DEFAULT_PAGE_SIZE);
+        if page == 0 {
+            return Err(AppError::bad_request("page must be at least 1"));
+        }
+        if per_page == 0 || per_page > MAX_PAGE_SIZE {
+            return Err(AppError::bad_request(format!(
+                "per_page must be between 1 and {}",
+                MAX_PAGE_SIZE
+            )));
+        }
+        Ok((page, per_page))
+    }
+}
+
+#[derive(Clone, Copy, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl From<SortOrder> for sea_orm::Order {
+    fn from(order: SortOrder) -> Self {
+        match order {
+            SortOrder::Asc => sea_orm::Order::Asc,
+            SortOrder::Desc => sea_orm::Order::Desc,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[aliases(
+    InvoicePage = Page<InvoiceResponse>,
+    ExpensePage = Page<ExpenseResponse>,
+    CompanyPage = Page<CompanyResponse>,
+    TemplatePage = Page<TemplateResponse>,
+    BankTransactionPage = Page<BankTransactionResponse>,
+    BankMatchPage = Page<BankMatchResponse>
+)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Number of items matching the filters across all pages
+    pub total: u64,
+    pub page: u64,
+    pub per_page: u64,
+}
+
+impl<T> Page<T> {
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            total: self.total,
+            page: self.page,
+            per_page: self.per_page,
+        }
+    }
+}
+
+/// Runs a filtered and ordered select one page at a time, returning the page
+/// together with the total match count.
+pub async fn fetch_page<E>(
+    db: &DatabaseConnection,
+    select: Select<E>,
+    params: &PageParams,
+) -> Result<Page<E::Model>, AppError>
+where
+    E: EntityTrait,
+    E::Model: Send + Sync,
+{
+    let (page, per_page) = params.resolve()?;
+    let paginator = select.paginate(db, per_page);
+    let total = paginator.num_items().await?;
+    let items = paginator.fetch_page(page - 1).await?;
+
+    Ok(Page {
+        items,
+        total,
+        page,
+        per_page,
+    })
+}
+
+/// Builds an ILIKE pattern for a free-text search term, escaping wildcards.
+pub fn search_pattern(term: &str) -> Option<String> {
+    let term = term.trim();
+    if term.is_empty() {
+        return None;
+    }
+    let escaped = term
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    Some(format!("%{}%", escaped))
 }
@@ -0,0 +1,420 @@
+use crate::modules::shared::AppError;
+use async_trait::async_trait;
+use aws_sdk_s3::config::{Credentials, Region};
+use aws_sdk_s3::presigning::PresigningConfig;
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::Client;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Private object storage for receipt files. Keys are relative paths such as
+/// `<workspace id>/<receipt id>.pdf`; nothing stored here is publicly readable.
+#[async_trait]
+pub trait ReceiptStorage: Send + Sync {
+    /// Short backend name for logs.
+    fn name(&self) -> &'static str;
+
+    async fn put(&self, key: &str, content_type: &str, body: Vec<u8>) -> Result<(), AppError>;
+
+    /// The stored bytes, or `None` when the key does not exist.
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, AppError>;
+
+    /// Size of the object in bytes, or `None` when the key does not exist.
+    async fn size(&self, key: &str) -> Result<Option<u64>, AppError>;
+
+    /// Removes the object; deleting a missing key is not an error.
+    async fn delete(&self, key: &str) -> Result<(), AppError>;
+
+    /// A time-limited URL that downloads the object directly from the backend, if it can
+    /// issue one. Otherwise downloads go through the API with a signed link.
+    async fn presigned_get(
+        &self,
+        _key: &str,
+        _content_type: &str,
+        _content_disposition: &str,
+        _expires_in: Duration,
+    ) -> Result<Option<String>, AppError> {
+        Ok(None)
+    }
+
+    /// A time-limited URL the client can `PUT` the file to, if the backend supports direct
+    /// uploads.
+    async fn presigned_put(
+        &self,
+        _key: &str,
+        _content_type: &str,
+        _expires_in: Duration,
+    ) -> Result<Option<String>, AppError> {
+        Ok(None)
+    }
+}
+
+/// Picks the backend from `RECEIPT_STORAGE` (`s3` or `local`). Without it, S3 is used when
+/// `R2_BUCKET` is set and the local filesystem otherwise.
+pub fn storage_from_env() -> anyhow::Result<Arc<dyn ReceiptStorage>> {
+    let default_backend = if std::env::var("R2_BUCKET").is_ok() {
+        "s3"
+    } else {
+        "local"
+    };
+    match std::env::var("RECEIPT_STORAGE")
+        .unwrap_or_else(|_| default_backend.to_string())
+        .as_str()
+    {
+        "s3" => Ok(Arc::new(S3Storage::from_env()?)),
+        "local" => {
+            let root = std::env::var("RECEIPT_STORAGE_DIR")
+                .unwrap_or_else(|_| "data/receipts".to_string());
+            Ok(Arc::new(LocalStorage::new(root)))
+        }
+        other => anyhow::bail!("Unknown RECEIPT_STORAGE {other:?}, expected s3 or local"),
+    }
+}
+
+/// Stores receipts below a directory on the server's filesystem.
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Keys are generated by us, but never let one escape the storage directory.
+    fn path(&self, key: &str) -> Result<PathBuf, AppError> {
+        let relative = Path::new(key);
+        if key.is_empty()
+            || !relative
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return Err(AppError::internal(format!("Invalid storage key {key:?}")));
+        }
+        Ok(self.root.join(relative))
+    }
+}
+
+#[async_trait]
+impl ReceiptStorage for LocalStorage {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    async fn put(&self, key: &str, _content_type: &str, body: Vec<u8>) -> Result<(), AppError> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(AppError::internal)?;
+        }
+        // Write next to the target and rename, so readers never see a partial file.
+        let partial = path.with_extension("partial");
+        tokio::fs::write(&partial, body)
+            .await
+            .map_err(AppError::internal)?;
+        tokio::fs::rename(&partial, &path)
+            .await
+            .map_err(AppError::internal)
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, AppError> {
+        match tokio::fs::read(self.path(key)?).await {
+            Ok(body) => Ok(Some(body)),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(AppError::internal(error)),
+        }
+    }
+
+    async fn size(&self, key: &str) -> Result<Option<u64>, AppError> {
+        match tokio::fs::metadata(self.path(key)?).await {
+            Ok(metadata) => Ok(Some(metadata.len())),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(AppError::internal(error)),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), AppError> {
+        match tokio::fs::remove_file(self.path(key)?).await {
+            Ok(()) => Ok(()),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(error) => Err(AppError::internal(error)),
+        }
+    }
+}
+
+/// Stores receipts in an S3-compatible bucket (Cloudflare R2, MinIO, AWS).
+pub struct S3Storage {
+    client: Client,
+    bucket: String,
+}
+
+impl S3Storage {
+    /// Reads `R2_ENDPOINT`, `R2_ACCESS_KEY_ID`, `R2_SECRET_ACCESS_KEY`, `R2_BUCKET` and
+    /// optionally `R2_REGION`. Set `R2_FORCE_PATH_STYLE=true` for MinIO.
+    pub fn from_env() -> anyhow::Result<Self> {
+        let endpoint = std::env::var("R2_ENDPOINT")
+            .map_err(|_| anyhow::anyhow!("R2_ENDPOINT missing"))?;
+        let access_key = std::env::var("R2_ACCESS_KEY_ID")
+            .map_err(|_| anyhow::anyhow!("R2_ACCESS_KEY_ID missing"))?;
+        let secret_key = std::env::var("R2_SECRET_ACCESS_KEY")
+            .map_err(|_| anyhow::anyhow!("R2_SECRET_ACCESS_KEY missing"))?;
+        let bucket =
+            std::env::var("R2_BUCKET").map_err(|_| anyhow::anyhow!("R2_BUCKET missing"))?;
+        let region = std::env::var("R2_REGION").unwrap_or_else(|_| "auto".to_string());
+        let force_path_style = std::env::var("R2_FORCE_PATH_STYLE")
+            .map(|value| value == "true" || value == "1")
+            .unwrap_or(false);
+        Ok(Self::new(endpoint, access_key, secret_key, bucket, region, force_path_style))
+    }
+
+    pub fn new(
+        endpoint: String,
+        access_key: String,
+        secret_key: String,
+        bucket: String,
+        region: String,
+        force_path_style: bool,
+    ) -> Self {
+        let config = aws_sdk_s3::config::Builder::new()
+            .behavior_version_latest()
+            .credentials_provider(Credentials::new(access_key, secret_key, None, None, "r2"))
+            .region(Region::new(region))
+            .endpoint_url(endpoint)
+            .force_path_style(force_path_style)
+            .build();
+        Self {
+            client: Client::from_conf(config),
+            bucket,
+        }
+    }
+}
+
+fn presigning(expires_in: Duration) -> Result<PresigningConfig, AppError> {
+    PresigningConfig::expires_in(expires_in)
+        .map_err(|e| AppError::internal(format!("Invalid expiry: {}", e)))
+}
+
+#[async_trait]
+impl ReceiptStorage for S3Storage {
+    fn name(&self) -> &'static str {
+        "s3"
+    }
+
+    async fn put(&self, key: &str, content_type: &str, body: Vec<u8>) -> Result<(), AppError> {
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .content_type(content_type)
+            .body(ByteStream::from(body))
+            .send()
+            .await
+            .map_err(AppError::bad_gateway)?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, AppError> {
+        let object = match self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+        {
+            Ok(object) => object,
+            Err(error) => {
+                let error = error.into_service_error();
+                if error.is_no_such_key() {
+                    return Ok(None);
+                }
+                return Err(AppError::bad_gateway(error));
+            }
+        };
+        let body = object.body.collect().await.map_err(AppError::bad_gateway)?;
+        Ok(Some(body.into_bytes().to_vec()))
+    }
+
+    async fn size(&self, key: &str) -> Result<Option<u64>, AppError> {
+        match self
+            .client
+            .head_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+        {
+            Ok(head) => Ok(Some(head.content_length().unwrap_or_default().max(0) as u64)),
+            Err(error) => {
+                let error = error.into_service_error();
+                if error.is_not_found() {
+                    return Ok(None);
+                }
+                Err(AppError::bad_gateway(error))
+            }
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), AppError> {
+        self.client
+            .delete_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(AppError::bad_gateway)?;
+        Ok(())
+    }
+
+    async fn presigned_get(
+        &self,
+        key: &str,
+        content_type: &str,
+        content_disposition: &str,
+        expires_in: Duration,
+    ) -> Result<Option<String>, AppError> {
+        let presigned = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .response_content_type(content_type)
+            .response_content_disposition(content_disposition)
+            .presigned(presigning(expires_in)?)
+            .await
+            .map_err(AppError::internal)?;
+        Ok(Some(presigned.uri().to_string()))
+    }
+
+    async fn presigned_put(
+        &self,
+        key: &str,
+        content_type: &str,
+        expires_in: Duration,
+    ) -> Result<Option<String>, AppError> {
+        let presigned = self
+            .client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .content_type(content_type)
+            .presigned(presigning(expires_in)?)
+            .await
+            .map_err(AppError::internal)?;
+        Ok(Some(presigned.uri().to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_storage() -> (LocalStorage, PathBuf) {
+        let root = std::env::temp_dir().join(format!("receipts-{}", uuid::Uuid::new_v4()));
+        (LocalStorage::new(&root), root)
+    }
+
+    #[test]
+    fn keys_stay_inside_the_storage_directory() {
+        let storage = LocalStorage::new("/srv/receipts");
+        assert_eq!(
+            storage.path("workspace/receipt.pdf").ok(),
+            Some(PathBuf::from("/srv/receipts/workspace/receipt.pdf")),
+        );
+        for key in ["", "../secret", "workspace/../../secret", "/etc/passwd", "./receipt.pdf", ".."] {
+            assert!(storage.path(key).is_err(), "{key:?} was accepted");
+        }
+    }
+
+    #[tokio::test]
+    async fn local_storage_round_trip() {
+        let (storage, root) = temp_storage();
+        let key = "workspace/receipt.pdf";
+        assert_eq!(storage.get(key).await.ok(), Some(None));
+        assert_eq!(storage.size(key).await.ok(), Some(None));
+
+        storage.put(key, "application/pdf", b"%PDF-1.7".to_vec()).await.ok().unwrap();
+        assert_eq!(storage.get(key).await.ok(), Some(Some(b"%PDF-1.7".to_vec())));
+        assert_eq!(storage.size(key).await.ok(), Some(Some(8)));
+        assert!(!root.join("workspace/receipt.partial").exists());
+        // Downloads go through the API's signed links instead.
+        let presigned = storage
+            .presigned_get(key, "application/pdf", "inline", Duration::from_secs(60))
+            .await;
+        assert_eq!(presigned.ok(), Some(None));
+
+        storage.delete(key).await.ok().unwrap();
+        assert_eq!(storage.get(key).await.ok(), Some(None));
+        assert!(storage.delete(key).await.is_ok());
+        assert!(storage.put("../escape.pdf", "application/pdf", Vec::new()).await.is_err());
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    /// Runs against a MinIO server when `MINIO_ENDPOINT`, `MINIO_ACCESS_KEY`,
+    /// `MINIO_SECRET_KEY` and `MINIO_BUCKET` (an existing bucket) are set, e.g. with
+    /// `docker run -p 9000:9000 minio/minio server /data`.
+    #[tokio::test]
+    async fn s3_storage_against_minio() {
+        let env = |name| std::env::var(name).ok();
+        let (Some(endpoint), Some(access_key), Some(secret_key), Some(bucket)) = (
+            env("MINIO_ENDPOINT"),
+            env("MINIO_ACCESS_KEY"),
+            env("MINIO_SECRET_KEY"),
+            env("MINIO_BUCKET"),
+        ) else {
+            eprintln!("MINIO_* not set, skipping the S3 storage test");
+            return;
+        };
+        let storage =
+            S3Storage::new(endpoint, access_key, secret_key, bucket, "us-east-1".to_string(), true);
+        let key = format!("test/{}.pdf", uuid::Uuid::new_v4());
+        let http = reqwest::Client::new();
+
+        assert_eq!(storage.get(&key).await.ok(), Some(None));
+        assert_eq!(storage.size(&key).await.ok(), Some(None));
+
+        let upload_url = storage
+            .presigned_put(&key, "application/pdf", Duration::from_secs(60))
+            .await
+            .ok()
+            .flatten()
+            .unwrap();
+        let uploaded = http
+            .put(&upload_url)
+            .header("content-type", "application/pdf")
+            .body(b"%PDF-1.7 direct".to_vec())
+            .send()
+            .await
+            .unwrap();
+        assert!(uploaded.status().is_success(), "{}", uploaded.status());
+        assert_eq!(storage.size(&key).await.ok(), Some(Some(15)));
+
+        storage.put(&key, "application/pdf", b"%PDF-1.7".to_vec()).await.ok().unwrap();
+        assert_eq!(storage.get(&key).await.ok(), Some(Some(b"%PDF-1.7".to_vec())));
+
+        let download_url = storage
+            .presigned_get(&key, "application/pdf", "inline", Duration::from_secs(60))
+            .await
+            .ok()
+            .flatten()
+            .unwrap();
+        let downloaded = http.get(&download_url).send().await.unwrap();
+        assert_eq!(downloaded.headers()["content-type"], "application/pdf");
+        assert_eq!(downloaded.bytes().await.unwrap().as_ref(), b"%PDF-1.7");
+
+        let expiring_url = storage
+            .presigned_get(&key, "application/pdf", "inline", Duration::from_secs(1))
+            .await
+            .ok()
+            .flatten()
+            .unwrap();
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        let expired = http.get(&expiring_url).send().await.unwrap();
+        assert_eq!(expired.status(), reqwest::StatusCode::FORBIDDEN);
+
+        storage.delete(&key).await.ok().unwrap();
+        assert_eq!(storage.get(&key).await.ok(), Some(None));
+    }
+}
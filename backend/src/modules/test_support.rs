@@ -0,0 +1,58 @@
+//! Fixtures shared by handler tests that run against a `MockDatabase`.
+
+use crate::entity::{session, user};
+use crate::modules::auth::{hash_token, sign_session_token};
+use axum::http::{header::COOKIE, HeaderMap, HeaderValue};
+use chrono::{Duration, Utc};
+use sea_orm::{DatabaseBackend, MockDatabase};
+use uuid::Uuid;
+
+/// Token of the browser session created by [`signed_in`].
+pub(crate) const SESSION_TOKEN: &str = "session-token";
+
+pub(crate) fn user(id: Uuid, email: &str) -> user::Model {
+    user::Model {
+        id,
+        email: email.to_string(),
+        password_hash: String::new(),
+        address: None,
+        company_id: None,
+        created_at: Utc::now(),
+        email_verified_at: Some(Utc::now()),
+        totp_secret: None,
+        totp_enabled_at: None,
+        totp_last_step: None,
+        deleted_at: None,
+    }
+}
+
+/// A session fresh enough that authenticating neither renews nor touches it.
+pub(crate) fn session(id: Uuid, user_id: Uuid) -> session::Model {
+    let now = Utc::now();
+    session::Model {
+        id,
+        user_id,
+        token_hash: hash_token(SESSION_TOKEN),
+        created_at: now,
+        expires_at: now + Duration::days(7),
+        user_agent: None,
+        ip_address: None,
+        last_seen_at: now,
+    }
+}
+
+/// Request headers carrying the signed cookie for [`SESSION_TOKEN`].
+pub(crate) fn session_headers() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    let cookie = format!("session_id={}", sign_session_token(SESSION_TOKEN));
+    headers.insert(COOKIE, HeaderValue::from_str(&cookie).unwrap());
+    headers
+}
+
+/// A Postgres mock whose first two queries authenticate `user` through `session`;
+/// append the handler's own results after it.
+pub(crate) fn signed_in(session: session::Model, user: user::Model) -> MockDatabase {
+    MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([[session]])
+        .append_query_results([[user]])
+}
@@ -0,0 +1,900 @@
+use crate::entity::{expense, mileage_rate, per_diem_rate};
+use crate::modules::api_tokens::ApiScope;
+use crate::modules::auth::require_access;
+use crate::modules::expense_categories::resolve_category;
+use crate::modules::expenses::{ExpenseKind, ExpenseResponse};
+use crate::modules::shared::{AppError, AppState, FieldError};
+use crate::modules::validation::{
+    country_code, currency_code, field_error, not_blank, positive, Path, ValidatedJson,
+};
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder, Set,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+/// Mileage rates every new workspace starts with: vehicle, rate per km in EUR and the year
+/// from whose 1 January it applies (German travel expense law). Keep in sync with the travel
+/// expenses migration.
+const DEFAULT_MILEAGE_RATES: &[(&str, f64, i32)] =
+    &[("car", 0.30, 2001), ("motorcycle", 0.20, 2014)];
+
+/// Domestic per diem rates new workspaces start with: country, full-day and partial-day
+/// allowance in EUR and the year from whose 1 January they apply.
+const DEFAULT_PER_DIEM_RATES: &[(&str, f64, f64, i32)] =
+    &[("DE", 24.0, 12.0, 2014), ("DE", 28.0, 14.0, 2020)];
+
+/// A single-day trip earns the partial-day allowance only when away for longer than this.
+const MIN_SINGLE_DAY_HOURS: i64 = 8;
+
+/// Share of the full-day allowance withheld for each meal that was provided.
+const BREAKFAST_SHARE: f64 = 0.2;
+const MAIN_MEAL_SHARE: f64 = 0.4;
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct MileageRateCreateRequest {
+    #[validate(custom(function = not_blank), length(max = 50))]
+    pub vehicle: String,
+    #[validate(custom(function = positive))]
+    pub rate_per_km: f64,
+    #[validate(custom(function = currency_code))]
+    pub currency: String,
+    /// The rate applies to trips from this day until the next rate for the vehicle
+    pub valid_from: NaiveDate,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct MileageRateResponse {
+    pub id: Uuid,
+    pub vehicle: String,
+    pub rate_per_km: f64,
+    pub currency: String,
+    pub valid_from: NaiveDate,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<mileage_rate::Model> for MileageRateResponse {
+    fn from(rate: mileage_rate::Model) -> Self {
+        Self {
+            id: rate.id,
+            vehicle: rate.vehicle,
+            rate_per_km: rate.rate_per_km,
+            currency: rate.currency,
+            valid_from: rate.valid_from,
+            created_at: rate.created_at,
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct PerDiemRateCreateRequest {
+    /// ISO 3166 alpha-2 code of the destination, e.g. `DE`
+    #[validate(custom(function = country_code))]
+    pub country: String,
+    /// Allowance for a day away for the full 24 hours
+    #[validate(custom(function = positive))]
+    pub full_day: f64,
+    /// Allowance for arrival and departure days and single-day trips over 8 hours
+    #[validate(custom(function = positive))]
+    pub partial_day: f64,
+    #[validate(custom(function = currency_code))]
+    pub currency: String,
+    /// The rates apply to trips starting on this day until the next rate for the country
+    pub valid_from: NaiveDate,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PerDiemRateResponse {
+    pub id: Uuid,
+    pub country: String,
+    pub full_day: f64,
+    pub partial_day: f64,
+    pub currency: String,
+    pub valid_from: NaiveDate,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<per_diem_rate::Model> for PerDiemRateResponse {
+    fn from(rate: per_diem_rate::Model) -> Self {
+        Self {
+            id: rate.id,
+            country: rate.country,
+            full_day: rate.full_day,
+            partial_day: rate.partial_day,
+            currency: rate.currency,
+            valid_from: rate.valid_from,
+            created_at: rate.created_at,
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct MileageExpenseRequest {
+    /// Day of the trip; picks the rate valid on that day
+    pub date: NaiveDate,
+    #[validate(custom(function = not_blank), length(max = 200))]
+    pub trip_from: String,
+    #[validate(custom(function = not_blank), length(max = 200))]
+    pub trip_to: String,
+    #[validate(custom(function = positive), range(max = 100000.0))]
+    pub distance_km: f64,
+    /// A vehicle with a mileage rate in the workspace (default `car`)
+    #[validate(length(max = 50))]
+    pub vehicle: Option<String>,
+    /// Purpose of the trip
+    #[serde(default)]
+    #[validate(length(max = 1000))]
+    pub description: String,
+    /// Name of an expense category of the workspace (default `Travel` when it exists)
+    #[validate(length(max = 100))]
+    pub category: Option<String>,
+    /// Takes precedence over `category`
+    pub category_id: Option<Uuid>,
+}
+
+fn validate_per_diem_times(payload: &PerDiemExpenseRequest) -> Result<(), ValidationError> {
+    if payload.return_at <= payload.departure_at {
+        return Err(field_error(
+            "return_at",
+            "before_departure",
+            "must be after the departure",
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+#[validate(schema(function = validate_per_diem_times, skip_on_field_errors = false))]
+pub struct PerDiemExpenseRequest {
+    /// ISO 3166 alpha-2 code of the destination, e.g. `DE`
+    #[validate(custom(function = country_code))]
+    pub country: String,
+    /// Local time of leaving home or the office; its day is the expense date
+    pub departure_at: NaiveDateTime,
+    /// Local time of coming back
+    pub return_at: NaiveDateTime,
+    /// Meals provided by an employer or host over the whole trip, at most one of each kind
+    /// per day
+    #[serde(default)]
+    #[validate(range(min = 0, max = 1000))]
+    pub breakfasts_provided: i32,
+    #[serde(default)]
+    #[validate(range(min = 0, max = 1000))]
+    pub lunches_provided: i32,
+    #[serde(default)]
+    #[validate(range(min = 0, max = 1000))]
+    pub dinners_provided: i32,
+    /// Purpose of the trip
+    #[serde(default)]
+    #[validate(length(max = 1000))]
+    pub description: String,
+    /// Name of an expense category of the workspace (default `Travel` when it exists)
+    #[validate(length(max = 100))]
+    pub category: Option<String>,
+    /// Takes precedence over `category`
+    pub category_id: Option<Uuid>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct MileageDetails {
+    pub trip_from: String,
+    pub trip_to: String,
+    pub distance_km: f64,
+    pub vehicle: String,
+    pub rate_per_km: f64,
+}
+
+impl MileageDetails {
+    pub(crate) fn from_expense(expense: &expense::Model) -> Option<Self> {
+        Some(Self {
+            trip_from: expense.trip_from.clone()?,
+            trip_to: expense.trip_to.clone()?,
+            distance_km: expense.distance_km?,
+            vehicle: expense.vehicle.clone()?,
+            rate_per_km: expense.rate_per_km?,
+        })
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PerDiemDetails {
+    pub country: String,
+    pub departure_at: NaiveDateTime,
+    pub return_at: NaiveDateTime,
+    pub full_days: i32,
+    pub partial_days: i32,
+    pub breakfasts_provided: i32,
+    pub lunches_provided: i32,
+    pub dinners_provided: i32,
+}
+
+impl PerDiemDetails {
+    pub(crate) fn from_expense(expense: &expense::Model) -> Option<Self> {
+        Some(Self {
+            country: expense.country.clone()?,
+            departure_at: expense.departure_at?,
+            return_at: expense.return_at?,
+            full_days: expense.full_days?,
+            partial_days: expense.partial_days?,
+            breakfasts_provided: expense.breakfasts_provided.unwrap_or(0),
+            lunches_provided: expense.lunches_provided.unwrap_or(0),
+            dinners_provided: expense.dinners_provided.unwrap_or(0),
+        })
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/travel-rates/mileage",
+    responses(
+        (status = 200, description = "Mileage rates of the workspace", body = [MileageRateResponse]),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "expenses"
+)]
+pub async fn list_mileage_rates(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<MileageRateResponse>>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::ExpensesRead).await?;
+    let rates = mileage_rate::Entity::find()
+        .filter(mileage_rate::Column::WorkspaceId.eq(access.workspace_id))
+        .order_by_asc(mileage_rate::Column::Vehicle)
+        .order_by_desc(mileage_rate::Column::ValidFrom)
+        .all(&state.db)
+        .await?;
+    Ok(Json(rates.into_iter().map(MileageRateResponse::from).collect()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/travel-rates/mileage",
+    request_body = MileageRateCreateRequest,
+    responses(
+        (status = 200, description = "Mileage rate created", body = MileageRateResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 409, description = "The vehicle already has a rate from this day", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "expenses"
+)]
+pub async fn create_mileage_rate(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidatedJson(payload): ValidatedJson<MileageRateCreateRequest>,
+) -> Result<Json<MileageRateResponse>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::ExpensesWrite).await?;
+    let vehicle = normalize_vehicle(&payload.vehicle);
+    let existing = mileage_rate::Entity::find()
+        .filter(mileage_rate::Column::WorkspaceId.eq(access.workspace_id))
+        .filter(mileage_rate::Column::Vehicle.eq(vehicle.as_str()))
+        .filter(mileage_rate::Column::ValidFrom.eq(payload.valid_from))
+        .one(&state.db)
+        .await?;
+    if existing.is_some() {
+        return Err(AppError::conflict(
+            "The vehicle already has a rate from this day; delete it first",
+        ));
+    }
+
+    let created = mileage_rate::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        workspace_id: Set(access.workspace_id),
+        vehicle: Set(vehicle),
+        rate_per_km: Set(payload.rate_per_km),
+        currency: Set(payload.currency),
+        valid_from: Set(payload.valid_from),
+        created_at: Set(Utc::now()),
+    }
+    .insert(&state.db)
+    .await?;
+
+    Ok(Json(MileageRateResponse::from(created)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/travel-rates/mileage/{id}",
+    responses(
+        (status = 204, description = "Mileage rate deleted; recorded trips keep their amounts"),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 404, description = "Mileage rate not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "expenses"
+)]
+pub async fn delete_mileage_rate(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    let access = require_access(&state, &headers, ApiScope::ExpensesWrite).await?;
+    let id = Uuid::parse_str(&id).map_err(|_| AppError::bad_request("Invalid id"))?;
+    let deleted = mileage_rate::Entity::delete_many()
+        .filter(mileage_rate::Column::Id.eq(id))
+        .filter(mileage_rate::Column::WorkspaceId.eq(access.workspace_id))
+        .exec(&state.db)
+        .await?;
+    if deleted.rows_affected == 0 {
+        return Err(AppError::not_found("Mileage rate not found"));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/travel-rates/per-diem",
+    responses(
+        (status = 200, description = "Per diem rates of the workspace", body = [PerDiemRateResponse]),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "expenses"
+)]
+pub async fn list_per_diem_rates(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<PerDiemRateResponse>>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::ExpensesRead).await?;
+    let rates = per_diem_rate::Entity::find()
+        .filter(per_diem_rate::Column::WorkspaceId.eq(access.workspace_id))
+        .order_by_asc(per_diem_rate::Column::Country)
+        .order_by_desc(per_diem_rate::Column::ValidFrom)
+        .all(&state.db)
+        .await?;
+    Ok(Json(rates.into_iter().map(PerDiemRateResponse::from).collect()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/travel-rates/per-diem",
+    request_body = PerDiemRateCreateRequest,
+    responses(
+        (status = 200, description = "Per diem rate created", body = PerDiemRateResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 409, description = "The country already has a rate from this day", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "expenses"
+)]
+pub async fn create_per_diem_rate(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidatedJson(payload): ValidatedJson<PerDiemRateCreateRequest>,
+) -> Result<Json<PerDiemRateResponse>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::ExpensesWrite).await?;
+    if payload.partial_day > payload.full_day {
+        return Err(AppError::Validation(vec![FieldError::new(
+            "partial_day",
+            "above_full_day",
+            "must not exceed the full-day allowance",
+        )]));
+    }
+    let existing = per_diem_rate::Entity::find()
+        .filter(per_diem_rate::Column::WorkspaceId.eq(access.workspace_id))
+        .filter(per_diem_rate::Column::Country.eq(payload.country.as_str()))
+        .filter(per_diem_rate::Column::ValidFrom.eq(payload.valid_from))
+        .one(&state.db)
+        .await?;
+    if existing.is_some() {
+        return Err(AppError::conflict(
+            "The country already has a rate from this day; delete it first",
+        ));
+    }
+
+    let created = per_diem_rate::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        workspace_id: Set(access.workspace_id),
+        country: Set(payload.country),
+        full_day: Set(payload.full_day),
+        partial_day: Set(payload.partial_day),
+        currency: Set(payload.currency),
+        valid_from: Set(payload.valid_from),
+        created_at: Set(Utc::now()),
+    }
+    .insert(&state.db)
+    .await?;
+
+    Ok(Json(PerDiemRateResponse::from(created)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/travel-rates/per-diem/{id}",
+    responses(
+        (status = 204, description = "Per diem rate deleted; recorded trips keep their amounts"),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 404, description = "Per diem rate not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "expenses"
+)]
+pub async fn delete_per_diem_rate(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    let access = require_access(&state, &headers, ApiScope::ExpensesWrite).await?;
+    let id = Uuid::parse_str(&id).map_err(|_| AppError::bad_request("Invalid id"))?;
+    let deleted = per_diem_rate::Entity::delete_many()
+        .filter(per_diem_rate::Column::Id.eq(id))
+        .filter(per_diem_rate::Column::WorkspaceId.eq(access.workspace_id))
+        .exec(&state.db)
+        .await?;
+    if deleted.rows_affected == 0 {
+        return Err(AppError::not_found("Per diem rate not found"));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/expenses/mileage",
+    request_body = MileageExpenseRequest,
+    responses(
+        (status = 200, description = "Mileage expense created", body = ExpenseResponse),
+        (status = 422, description = "Validation failed or no rate for the vehicle on that day", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "expenses"
+)]
+pub async fn create_mileage_expense(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidatedJson(payload): ValidatedJson<MileageExpenseRequest>,
+) -> Result<Json<ExpenseResponse>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::ExpensesWrite).await?;
+    let mut active = new_travel_expense(access.user.id, access.workspace_id, ExpenseKind::Mileage);
+    apply_mileage(&state.db, access.workspace_id, &mut active, payload).await?;
+    let saved = active.insert(&state.db).await?;
+    Ok(Json(ExpenseResponse::from(saved)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/expenses/{id}/mileage",
+    request_body = MileageExpenseRequest,
+    responses(
+        (status = 200, description = "Trip replaced and amount recomputed", body = ExpenseResponse),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 422, description = "Validation failed or no rate for the vehicle on that day", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 404, description = "Expense not found", body = ErrorResponse),
+        (status = 409, description = "Expense is not a mileage entry", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "expenses"
+)]
+pub async fn update_mileage_expense(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    ValidatedJson(payload): ValidatedJson<MileageExpenseRequest>,
+) -> Result<Json<ExpenseResponse>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::ExpensesWrite).await?;
+    let existing = find_travel_expense(&state, access.workspace_id, &id, ExpenseKind::Mileage).await?;
+    let mut active: expense::ActiveModel = existing.into();
+    apply_mileage(&state.db, access.workspace_id, &mut active, payload).await?;
+    let updated = active.update(&state.db).await?;
+    Ok(Json(ExpenseResponse::from(updated)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/expenses/per-diem",
+    request_body = PerDiemExpenseRequest,
+    responses(
+        (status = 200, description = "Per diem expense created", body = ExpenseResponse),
+        (status = 422, description = "Validation failed, no allowance for the trip or no rate for the country", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "expenses"
+)]
+pub async fn create_per_diem_expense(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidatedJson(payload): ValidatedJson<PerDiemExpenseRequest>,
+) -> Result<Json<ExpenseResponse>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::ExpensesWrite).await?;
+    let mut active = new_travel_expense(access.user.id, access.workspace_id, ExpenseKind::PerDiem);
+    apply_per_diem(&state.db, access.workspace_id, &mut active, payload).await?;
+    let saved = active.insert(&state.db).await?;
+    Ok(Json(ExpenseResponse::from(saved)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/expenses/{id}/per-diem",
+    request_body = PerDiemExpenseRequest,
+    responses(
+        (status = 200, description = "Trip replaced and allowance recomputed", body = ExpenseResponse),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 422, description = "Validation failed, no allowance for the trip or no rate for the country", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Missing API token scope or workspace access", body = ErrorResponse),
+        (status = 404, description = "Expense not found", body = ErrorResponse),
+        (status = 409, description = "Expense is not a per diem entry", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "expenses"
+)]
+pub async fn update_per_diem_expense(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    ValidatedJson(payload): ValidatedJson<PerDiemExpenseRequest>,
+) -> Result<Json<ExpenseResponse>, AppError> {
+    let access = require_access(&state, &headers, ApiScope::ExpensesWrite).await?;
+    let existing = find_travel_expense(&state, access.workspace_id, &id, ExpenseKind::PerDiem).await?;
+    let mut active: expense::ActiveModel = existing.into();
+    apply_per_diem(&state.db, access.workspace_id, &mut active, payload).await?;
+    let updated = active.update(&state.db).await?;
+    Ok(Json(ExpenseResponse::from(updated)))
+}
+
+/// Gives a new workspace the default mileage and per diem rates.
+pub(crate) async fn seed_default_travel_rates<C: ConnectionTrait>(
+    db: &C,
+    workspace_id: Uuid,
+) -> Result<(), AppError> {
+    let now = Utc::now();
+    let mileage = DEFAULT_MILEAGE_RATES.iter().map(|(vehicle, rate, valid_from)| {
+        mileage_rate::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            workspace_id: Set(workspace_id),
+            vehicle: Set(vehicle.to_string()),
+            rate_per_km: Set(*rate),
+            currency: Set("EUR".to_string()),
+            valid_from: Set(default_date(*valid_from)),
+            created_at: Set(now),
+        }
+    });
+    mileage_rate::Entity::insert_many(mileage).exec(db).await?;
+    let per_diem = DEFAULT_PER_DIEM_RATES.iter().map(|(country, full, partial, valid_from)| {
+        per_diem_rate::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            workspace_id: Set(workspace_id),
+            country: Set(country.to_string()),
+            full_day: Set(*full),
+            partial_day: Set(*partial),
+            currency: Set("EUR".to_string()),
+            valid_from: Set(default_date(*valid_from)),
+            created_at: Set(now),
+        }
+    });
+    per_diem_rate::Entity::insert_many(per_diem).exec(db).await?;
+    Ok(())
+}
+
+fn default_date(year: i32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, 1, 1).expect("valid default rate year")
+}
+
+fn normalize_vehicle(vehicle: &str) -> String {
+    vehicle.trim().to_lowercase()
+}
+
+/// A travel expense without trip details; VAT is not tracked for allowances.
+fn new_travel_expense(user_id: Uuid, workspace_id: Uuid, kind: ExpenseKind) -> expense::ActiveModel {
+    expense::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        user_id: Set(user_id),
+        workspace_id: Set(workspace_id),
+        net_amount: Set(None),
+        vat_rate: Set(None),
+        vat_amount: Set(None),
+        input_tax_deductible: Set(false),
+        reverse_charge: Set(false),
+        receipt_id: Set(None),
+        receipt_url: Set(None),
+        kind: Set(kind.as_str().to_string()),
+        trip_from: Set(None),
+        trip_to: Set(None),
+        distance_km: Set(None),
+        vehicle: Set(None),
+        rate_per_km: Set(None),
+        country: Set(None),
+        departure_at: Set(None),
+        return_at: Set(None),
+        full_days: Set(None),
+        partial_days: Set(None),
+        breakfasts_provided: Set(None),
+        lunches_provided: Set(None),
+        dinners_provided: Set(None),
+        created_at: Set(Utc::now()),
+        ..Default::default()
+    }
+}
+
+async fn find_travel_expense(
+    state: &AppState,
+    workspace_id: Uuid,
+    id: &str,
+    kind: ExpenseKind,
+) -> Result<expense::Model, AppError> {
+    let id = Uuid::parse_str(id).map_err(|_| AppError::bad_request("Invalid id"))?;
+    let existing = expense::Entity::find_by_id(id)
+        .filter(expense::Column::WorkspaceId.eq(workspace_id))
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Expense not found"))?;
+    if existing.kind != kind.as_str() {
+        return Err(AppError::conflict(format!(
+            "Expense is not a {} entry",
+            kind.as_str().replace('_', " ")
+        )));
+    }
+    Ok(existing)
+}
+
+async fn apply_mileage<C: ConnectionTrait>(
+    db: &C,
+    workspace_id: Uuid,
+    active: &mut expense::ActiveModel,
+    payload: MileageExpenseRequest,
+) -> Result<(), AppError> {
+    let vehicle = payload
+        .vehicle
+        .as_deref()
+        .map(normalize_vehicle)
+        .filter(|vehicle| !vehicle.is_empty())
+        .unwrap_or_else(|| "car".to_string());
+    let rate = mileage_rate::Entity::find()
+        .filter(mileage_rate::Column::WorkspaceId.eq(workspace_id))
+        .filter(mileage_rate::Column::Vehicle.eq(vehicle.as_str()))
+        .filter(mileage_rate::Column::ValidFrom.lte(payload.date))
+        .order_by_desc(mileage_rate::Column::ValidFrom)
+        .one(db)
+        .await?
+        .ok_or_else(|| {
+            AppError::Validation(vec![FieldError::new(
+                "vehicle",
+                "no_rate",
+                format!("No mileage rate for \"{vehicle}\" on {}", payload.date),
+            )])
+        })?;
+    let trip_from = payload.trip_from.trim().to_string();
+    let trip_to = payload.trip_to.trim().to_string();
+    let category =
+        travel_category(db, workspace_id, payload.category_id, payload.category.as_deref())
+            .await?;
+
+    active.vendor = Set(format!("{trip_from} – {trip_to}"));
+    active.description = Set(payload.description);
+    active.amount = Set(round_cents(payload.distance_km * rate.rate_per_km));
+    active.currency = Set(rate.currency);
+    active.date = Set(payload.date);
+    active.category = Set(category.as_ref().map(|category| category.name.clone()));
+    active.category_id = Set(category.map(|category| category.id));
+    active.trip_from = Set(Some(trip_from));
+    active.trip_to = Set(Some(trip_to));
+    active.distance_km = Set(Some(payload.distance_km));
+    active.vehicle = Set(Some(vehicle));
+    active.rate_per_km = Set(Some(rate.rate_per_km));
+    Ok(())
+}
+
+async fn apply_per_diem<C: ConnectionTrait>(
+    db: &C,
+    workspace_id: Uuid,
+    active: &mut expense::ActiveModel,
+    payload: PerDiemExpenseRequest,
+) -> Result<(), AppError> {
+    let date = payload.departure_at.date();
+    let rate = per_diem_rate::Entity::find()
+        .filter(per_diem_rate::Column::WorkspaceId.eq(workspace_id))
+        .filter(per_diem_rate::Column::Country.eq(payload.country.as_str()))
+        .filter(per_diem_rate::Column::ValidFrom.lte(date))
+        .order_by_desc(per_diem_rate::Column::ValidFrom)
+        .one(db)
+        .await?
+        .ok_or_else(|| {
+            AppError::Validation(vec![FieldError::new(
+                "country",
+                "no_rate",
+                format!("No per diem rate for {} on {date}", payload.country),
+            )])
+        })?;
+    let (full_days, partial_days) = allowance_days(payload.departure_at, payload.return_at)
+        .ok_or_else(|| {
+            AppError::Validation(vec![FieldError::new(
+                "return_at",
+                "too_short",
+                format!("A single-day trip needs more than {MIN_SINGLE_DAY_HOURS} hours away"),
+            )])
+        })?;
+    let days = full_days + partial_days;
+    let too_many: Vec<FieldError> = [
+        ("breakfasts_provided", payload.breakfasts_provided),
+        ("lunches_provided", payload.lunches_provided),
+        ("dinners_provided", payload.dinners_provided),
+    ]
+    .into_iter()
+    .filter(|(_, count)| *count > days)
+    .map(|(field, _)| {
+        FieldError::new(field, "too_many", format!("At most one per day of the trip ({days})"))
+    })
+    .collect();
+    if !too_many.is_empty() {
+        return Err(AppError::Validation(too_many));
+    }
+    let allowance = f64::from(full_days) * rate.full_day + f64::from(partial_days) * rate.partial_day;
+    let withheld = meal_deduction(
+        (full_days, partial_days),
+        (rate.full_day, rate.partial_day),
+        [
+            payload.breakfasts_provided,
+            payload.lunches_provided,
+            payload.dinners_provided,
+        ],
+    );
+    let category =
+        travel_category(db, workspace_id, payload.category_id, payload.category.as_deref())
+            .await?;
+
+    active.vendor = Set(format!("Per diem {}", payload.country));
+    active.description = Set(payload.description);
+    active.amount = Set(round_cents((allowance - withheld).max(0.0)));
+    active.currency = Set(rate.currency);
+    active.date = Set(date);
+    active.category = Set(category.as_ref().map(|category| category.name.clone()));
+    active.category_id = Set(category.map(|category| category.id));
+    active.country = Set(Some(payload.country));
+    active.departure_at = Set(Some(payload.departure_at));
+    active.return_at = Set(Some(payload.return_at));
+    active.full_days = Set(Some(full_days));
+    active.partial_days = Set(Some(partial_days));
+    active.breakfasts_provided = Set(Some(payload.breakfasts_provided));
+    active.lunches_provided = Set(Some(payload.lunches_provided));
+    active.dinners_provided = Set(Some(payload.dinners_provided));
+    Ok(())
+}
+
+/// Full and partial allowance days of a trip: arrival and departure day are partial days with
+/// full days in between, and a trip within one day is a partial day when it lasts more than
+/// eight hours. `None` when the trip earns no allowance.
+fn allowance_days(departure_at: NaiveDateTime, return_at: NaiveDateTime) -> Option<(i32, i32)> {
+    let nights = (return_at.date() - departure_at.date()).num_days();
+    if nights == 0 {
+        let away = return_at - departure_at;
+        return (away.num_minutes() > MIN_SINGLE_DAY_HOURS * 60).then_some((0, 1));
+    }
+    let full_days = i32::try_from(nights - 1).ok()?;
+    Some((full_days, 2))
+}
+
+/// Amount withheld for provided `[breakfasts, lunches, dinners]`, none of them more than one
+/// per day. A day's reduction is capped at that day's allowance. Only totals per trip are
+/// known, so each kind of meal goes to the days with the most allowance left, which caps no
+/// more than the totals force.
+fn meal_deduction(
+    (full_days, partial_days): (i32, i32),
+    (full_day, partial_day): (f64, f64),
+    [breakfasts, lunches, dinners]: [i32; 3],
+) -> f64 {
+    // Allowance and reduction of each day, full days first so they win ties.
+    let mut days: Vec<(f64, f64)> = std::iter::repeat_n((full_day, 0.0), full_days.max(0) as usize)
+        .chain(std::iter::repeat_n((partial_day, 0.0), partial_days.max(0) as usize))
+        .collect();
+    for (count, share) in [
+        (lunches, MAIN_MEAL_SHARE),
+        (dinners, MAIN_MEAL_SHARE),
+        (breakfasts, BREAKFAST_SHARE),
+    ] {
+        let mut order: Vec<usize> = (0..days.len()).collect();
+        order.sort_by(|a, b| {
+            let left = |index: usize| days[index].0 - days[index].1;
+            left(*b).total_cmp(&left(*a))
+        });
+        for index in order.into_iter().take(count.max(0) as usize) {
+            days[index].1 += share * full_day;
+        }
+    }
+    days.iter()
+        .map(|(allowance, withheld)| withheld.min(*allowance))
+        .sum()
+}
+
+/// The requested category, or else the workspace's `Travel` category when it has one.
+async fn travel_category<C: ConnectionTrait>(
+    db: &C,
+    workspace_id: Uuid,
+    category_id: Option<Uuid>,
+    name: Option<&str>,
+) -> Result<Option<crate::entity::expense_category::Model>, AppError> {
+    if category_id.is_some() || name.is_some() {
+        return resolve_category(db, workspace_id, category_id, name).await;
+    }
+    match resolve_category(db, workspace_id, None, Some("Travel")).await {
+        Err(AppError::Validation(_)) => Ok(None),
+        found => found,
+    }
+}
+
+fn round_cents(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// German domestic rates since 2020.
+    const RATES: (f64, f64) = (28.0, 14.0);
+
+    fn at(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn withheld(days: (i32, i32), meals: [i32; 3]) -> f64 {
+        round_cents(meal_deduction(days, RATES, meals))
+    }
+
+    #[test]
+    fn single_day_trips_need_more_than_eight_hours() {
+        assert_eq!(allowance_days(at("2026-03-02 08:00"), at("2026-03-02 16:00")), None);
+        assert_eq!(allowance_days(at("2026-03-02 08:00"), at("2026-03-02 16:01")), Some((0, 1)));
+        assert_eq!(allowance_days(at("2026-03-02 00:00"), at("2026-03-02 23:59")), Some((0, 1)));
+    }
+
+    #[test]
+    fn overnight_trips_earn_partial_arrival_and_departure_days() {
+        // Arrival and departure days count however short they are.
+        assert_eq!(allowance_days(at("2026-03-02 22:00"), at("2026-03-03 06:00")), Some((0, 2)));
+        assert_eq!(allowance_days(at("2026-03-02 08:00"), at("2026-03-05 18:00")), Some((2, 2)));
+        assert_eq!(allowance_days(at("2025-12-31 18:00"), at("2026-01-02 09:00")), Some((1, 2)));
+    }
+
+    #[test]
+    fn withholds_a_share_of_the_full_day_rate_per_meal() {
+        assert_eq!(withheld((0, 1), [1, 0, 0]), 5.6);
+        assert_eq!(withheld((0, 1), [0, 1, 0]), 11.2);
+        // Hotel breakfast the next morning and a dinner on the way: 28 - 16.80.
+        assert_eq!(withheld((0, 2), [1, 0, 1]), 16.8);
+        assert_eq!(withheld((1, 2), [1, 2, 2]), 50.4);
+        assert_eq!(withheld((2, 2), [0, 0, 0]), 0.0);
+    }
+
+    #[test]
+    fn caps_each_days_reduction_at_its_allowance() {
+        // Lunch and dinner on a single day withhold 22.40 but the day earns only 14.
+        assert_eq!(withheld((0, 1), [0, 1, 1]), 14.0);
+        // Every meal provided: nothing is left, and nothing is owed either.
+        assert_eq!(withheld((1, 2), [3, 3, 3]), 56.0);
+        // Two main meals on every day: the full day loses 22.40 and each partial day its 14,
+        // so 5.60 of the 56 remain. Capping only the total would have left nothing.
+        assert_eq!(withheld((1, 2), [0, 3, 3]), 50.4);
+    }
+
+    #[test]
+    fn spreads_meals_before_capping() {
+        // One lunch and one dinner over two partial days need not share a day.
+        assert_eq!(withheld((0, 2), [0, 1, 1]), 22.4);
+        // A full day takes all three meals without reaching the cap.
+        assert_eq!(withheld((1, 2), [1, 1, 1]), 28.0);
+    }
+}
@@ -0,0 +1,580 @@
+use crate::entity::{recovery_code, user};
+use crate::modules::account::{consume_token, find_valid_token, issue_token};
+use crate::modules::auth::{
+    create_session, hash_token, require_session_user, verify_password, SessionResponse,
+};
+use crate::modules::rate_limit::{clear_login_failures, login_lockout, record_login_failure};
+use crate::modules::shared::{AppError, AppState};
+use crate::modules::validation::{not_blank, ValidatedJson};
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, EntityTrait, QueryFilter, Set,
+    TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+const TOTP_DIGITS: u32 = 6;
+const TOTP_PERIOD_SECS: i64 = 30;
+/// Codes from the neighbouring time steps are accepted to tolerate clock drift.
+const TOTP_SKEW_STEPS: i64 = 1;
+const TOTP_SECRET_BYTES: usize = 20;
+const RECOVERY_CODE_COUNT: usize = 10;
+const RECOVERY_CODE_LENGTH: usize = 10;
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+const LOGIN_CHALLENGE_PURPOSE: &str = "two_factor_login";
+const LOGIN_CHALLENGE_TTL_MINUTES: i64 = 5;
+
+#[derive(Serialize, ToSchema)]
+pub struct TotpSetupResponse {
+    /// Base32 secret for manual entry in an authenticator app
+    pub secret: String,
+    /// `otpauth://` URI to render as a QR code
+    pub otpauth_uri: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RecoveryCodesResponse {
+    /// One-time codes that replace an authenticator code. They are only shown once.
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TwoFactorChallengeResponse {
+    /// Pass this to `POST /auth/login/2fa` together with an authenticator or recovery code
+    pub challenge: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct TwoFactorCodeRequest {
+    /// Current authenticator code, or a recovery code where accepted
+    #[validate(custom(function = not_blank), length(max = 32))]
+    pub code: String,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct TwoFactorLoginRequest {
+    #[validate(custom(function = not_blank))]
+    pub challenge: String,
+    /// Authenticator code or unused recovery code
+    #[validate(custom(function = not_blank), length(max = 32))]
+    pub code: String,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct DisableTwoFactorRequest {
+    #[validate(custom(function = not_blank))]
+    pub password: String,
+    /// Authenticator code or unused recovery code
+    #[validate(custom(function = not_blank), length(max = 32))]
+    pub code: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/2fa/setup",
+    responses(
+        (status = 200, description = "New secret; confirm it with /auth/2fa/enable", body = TotpSetupResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Requires a browser session", body = ErrorResponse),
+        (status = 409, description = "Two-factor authentication already enabled", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "auth"
+)]
+pub async fn setup_two_factor(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<TotpSetupResponse>, AppError> {
+    let current_user = require_session_user(&state, &headers).await?;
+    if current_user.totp_enabled_at.is_some() {
+        return Err(AppError::conflict("Two-factor authentication is already enabled"));
+    }
+
+    let mut secret = [0u8; TOTP_SECRET_BYTES];
+    OsRng.fill_bytes(&mut secret);
+    let email = current_user.email.clone();
+    let mut active: user::ActiveModel = current_user.into();
+    active.totp_secret = Set(Some(hex::encode(secret)));
+    active.totp_last_step = Set(None);
+    active.update(&state.db).await?;
+
+    let secret = base32_encode(&secret);
+    Ok(Json(TotpSetupResponse {
+        otpauth_uri: otpauth_uri(&secret, &email),
+        secret,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/2fa/enable",
+    request_body = TwoFactorCodeRequest,
+    responses(
+        (status = 200, description = "Two-factor authentication enabled", body = RecoveryCodesResponse),
+        (status = 400, description = "Invalid code or setup not started", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Requires a browser session", body = ErrorResponse),
+        (status = 409, description = "Two-factor authentication already enabled", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "auth"
+)]
+pub async fn enable_two_factor(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidatedJson(payload): ValidatedJson<TwoFactorCodeRequest>,
+) -> Result<Json<RecoveryCodesResponse>, AppError> {
+    let current_user = require_session_user(&state, &headers).await?;
+    if current_user.totp_enabled_at.is_some() {
+        return Err(AppError::conflict("Two-factor authentication is already enabled"));
+    }
+    let secret = user_secret(&current_user)?
+        .ok_or_else(|| AppError::bad_request("Start two-factor setup first"))?;
+    let now = Utc::now();
+    let step = matching_step(&secret, payload.code.trim(), now)
+        .ok_or_else(|| AppError::bad_request("Invalid authentication code"))?;
+
+    let txn = state.db.begin().await?;
+    let user_id = current_user.id;
+    let mut active: user::ActiveModel = current_user.into();
+    active.totp_enabled_at = Set(Some(now));
+    active.totp_last_step = Set(Some(step));
+    active.update(&txn).await?;
+    let recovery_codes = replace_recovery_codes(&txn, user_id, now).await?;
+    txn.commit().await?;
+
+    Ok(Json(RecoveryCodesResponse { recovery_codes }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/2fa/disable",
+    request_body = DisableTwoFactorRequest,
+    responses(
+        (status = 204, description = "Two-factor authentication disabled"),
+        (status = 400, description = "Wrong password or code", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Requires a browser session", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "auth"
+)]
+pub async fn disable_two_factor(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidatedJson(payload): ValidatedJson<DisableTwoFactorRequest>,
+) -> Result<StatusCode, AppError> {
+    let current_user = require_session_user(&state, &headers).await?;
+    if current_user.totp_enabled_at.is_none() {
+        return Err(AppError::bad_request("Two-factor authentication is not enabled"));
+    }
+    verify_password(&payload.password, &current_user.password_hash)
+        .map_err(|_| AppError::bad_request("Current password is wrong"))?;
+    if !verify_second_factor(&state.db, &current_user, &payload.code, Utc::now()).await? {
+        return Err(AppError::bad_request("Invalid authentication code"));
+    }
+
+    let txn = state.db.begin().await?;
+    let user_id = current_user.id;
+    let mut active: user::ActiveModel = current_user.into();
+    active.totp_secret = Set(None);
+    active.totp_enabled_at = Set(None);
+    active.totp_last_step = Set(None);
+    active.update(&txn).await?;
+    recovery_code::Entity::delete_many()
+        .filter(recovery_code::Column::UserId.eq(user_id))
+        .exec(&txn)
+        .await?;
+    txn.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/2fa/recovery-codes",
+    request_body = TwoFactorCodeRequest,
+    responses(
+        (status = 200, description = "New recovery codes; previous ones stop working", body = RecoveryCodesResponse),
+        (status = 400, description = "Invalid code or 2FA not enabled", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Requires a browser session", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "auth"
+)]
+pub async fn regenerate_recovery_codes(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidatedJson(payload): ValidatedJson<TwoFactorCodeRequest>,
+) -> Result<Json<RecoveryCodesResponse>, AppError> {
+    let current_user = require_session_user(&state, &headers).await?;
+    if current_user.totp_enabled_at.is_none() {
+        return Err(AppError::bad_request("Two-factor authentication is not enabled"));
+    }
+    let now = Utc::now();
+    if !verify_second_factor(&state.db, &current_user, &payload.code, now).await? {
+        return Err(AppError::bad_request("Invalid authentication code"));
+    }
+
+    let txn = state.db.begin().await?;
+    let recovery_codes = replace_recovery_codes(&txn, current_user.id, now).await?;
+    txn.commit().await?;
+
+    Ok(Json(RecoveryCodesResponse { recovery_codes }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/login/2fa",
+    request_body = TwoFactorLoginRequest,
+    responses(
+        (status = 200, description = "Logged in", body = SessionResponse),
+        (status = 401, description = "Invalid code or expired challenge", body = ErrorResponse),
+        (status = 429, description = "Too many attempts; see Retry-After", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "auth"
+)]
+pub async fn complete_two_factor_login(
+    State(state): State<AppState>,
+    request_headers: HeaderMap,
+    ValidatedJson(payload): ValidatedJson<TwoFactorLoginRequest>,
+) -> Result<(HeaderMap, Json<SessionResponse>), AppError> {
+    let expired = || AppError::unauthorized("Login challenge is invalid or expired");
+    let challenge = find_valid_token(&state.db, LOGIN_CHALLENGE_PURPOSE, &payload.challenge)
+        .await
+        .map_err(|_| expired())?;
+    let user = user::Entity::find_by_id(challenge.user_id)
+        .one(&state.db)
+        .await?
+        .filter(|user| user.totp_enabled_at.is_some())
+        .ok_or_else(expired)?;
+
+    let locked = login_lockout(&state.db, &user.email).await?.is_some();
+    if !verify_second_factor(&state.db, &user, &payload.code, Utc::now()).await? {
+        if locked {
+            // During a lockout every code guess needs the password again.
+            consume_token(&state.db, LOGIN_CHALLENGE_PURPOSE, &payload.challenge)
+                .await
+                .map_err(|_| expired())?;
+        } else {
+            record_login_failure(&state.db, &user.email, user.id).await?;
+        }
+        return Err(AppError::unauthorized("Invalid authentication code"));
+    }
+    consume_token(&state.db, LOGIN_CHALLENGE_PURPOSE, &payload.challenge)
+        .await
+        .map_err(|_| expired())?;
+    clear_login_failures(&state.db, &user.email).await?;
+
+    let (_session, cookie) = create_session(&state.db, user.id, &request_headers).await?;
+    let mut headers = HeaderMap::new();
+    headers.insert(axum::http::header::SET_COOKIE, cookie);
+
+    Ok((headers, Json(SessionResponse { user: user.into() })))
+}
+
+/// Issues the short-lived challenge that stands in for a session until the second
+/// factor has been checked.
+pub(crate) async fn start_login_challenge<C: ConnectionTrait>(
+    db: &C,
+    user_id: Uuid,
+) -> Result<TwoFactorChallengeResponse, AppError> {
+    let ttl = Duration::minutes(LOGIN_CHALLENGE_TTL_MINUTES);
+    let expires_at = Utc::now() + ttl;
+    let challenge = issue_token(db, user_id, LOGIN_CHALLENGE_PURPOSE, ttl).await?;
+    Ok(TwoFactorChallengeResponse {
+        challenge,
+        expires_at,
+    })
+}
+
+/// Checks an authenticator code (rejecting replays of an already used time step)
+/// or redeems an unused recovery code.
+pub(crate) async fn verify_second_factor<C: ConnectionTrait>(
+    db: &C,
+    user: &user::Model,
+    code: &str,
+    now: DateTime<Utc>,
+) -> Result<bool, AppError> {
+    let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+
+    if code.len() == TOTP_DIGITS as usize && code.chars().all(|c| c.is_ascii_digit()) {
+        let Some(secret) = user_secret(user)? else {
+            return Ok(false);
+        };
+        let Some(step) = matching_step(&secret, &code, now) else {
+            return Ok(false);
+        };
+        let result = user::Entity::update_many()
+            .col_expr(user::Column::TotpLastStep, Expr::value(step))
+            .filter(user::Column::Id.eq(user.id))
+            .filter(
+                Condition::any()
+                    .add(user::Column::TotpLastStep.is_null())
+                    .add(user::Column::TotpLastStep.lt(step)),
+            )
+            .exec(db)
+            .await?;
+        return Ok(result.rows_affected == 1);
+    }
+
+    let result = recovery_code::Entity::update_many()
+        .col_expr(recovery_code::Column::UsedAt, Expr::value(now))
+        .filter(recovery_code::Column::UserId.eq(user.id))
+        .filter(recovery_code::Column::CodeHash.eq(hash_token(&normalize_recovery_code(&code))))
+        .filter(recovery_code::Column::UsedAt.is_null())
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected == 1)
+}
+
+/// RFC 6238 code for the given 30-second time step (HMAC-SHA1, 6 digits).
+pub(crate) fn totp_code(secret: &[u8], step: i64) -> String {
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(&(step as u64).to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+
+    // Dynamic truncation as described in RFC 4226, section 5.3.
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+    format!(
+        "{:0width$}",
+        binary % 10u32.pow(TOTP_DIGITS),
+        width = TOTP_DIGITS as usize
+    )
+}
+
+pub(crate) fn time_step(now: DateTime<Utc>) -> i64 {
+    now.timestamp().div_euclid(TOTP_PERIOD_SECS)
+}
+
+/// Returns the time step `code` belongs to if it is valid at `now`.
+pub(crate) fn matching_step(secret: &[u8], code: &str, now: DateTime<Utc>) -> Option<i64> {
+    let current = time_step(now);
+    (current - TOTP_SKEW_STEPS..=current + TOTP_SKEW_STEPS)
+        .find(|step| totp_code(secret, *step) == code)
+}
+
+fn user_secret(user: &user::Model) -> Result<Option<Vec<u8>>, AppError> {
+    user.totp_secret
+        .as_deref()
+        .map(hex::decode)
+        .transpose()
+        .map_err(AppError::internal)
+}
+
+async fn replace_recovery_codes<C: ConnectionTrait>(
+    db: &C,
+    user_id: Uuid,
+    now: DateTime<Utc>,
+) -> Result<Vec<String>, AppError> {
+    recovery_code::Entity::delete_many()
+        .filter(recovery_code::Column::UserId.eq(user_id))
+        .exec(db)
+        .await?;
+
+    let codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+        .map(|_| generate_recovery_code())
+        .collect();
+    let models = codes.iter().map(|code| recovery_code::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        user_id: Set(user_id),
+        code_hash: Set(hash_token(&normalize_recovery_code(code))),
+        used_at: Set(None),
+        created_at: Set(now),
+    });
+    recovery_code::Entity::insert_many(models).exec(db).await?;
+
+    Ok(codes)
+}
+
+/// Ten base32 characters (50 bits), grouped as `xxxxx-xxxxx` for readability.
+fn generate_recovery_code() -> String {
+    let mut bytes = [0u8; RECOVERY_CODE_LENGTH];
+    OsRng.fill_bytes(&mut bytes);
+    let chars: String = bytes
+        .iter()
+        .map(|byte| BASE32_ALPHABET[(byte % 32) as usize].to_ascii_lowercase() as char)
+        .collect();
+    format!("{}-{}", &chars[..5], &chars[5..])
+}
+
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// RFC 4648 base32 without padding, as expected by authenticator apps.
+fn base32_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for &byte in bytes {
+        buffer = (buffer << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(BASE32_ALPHABET[((buffer >> bits) & 31) as usize] as char);
+        }
+        buffer &= (1 << bits) - 1;
+    }
+    if bits > 0 {
+        out.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 31) as usize] as char);
+    }
+    out
+}
+
+fn otpauth_uri(secret: &str, email: &str) -> String {
+    let issuer = std::env::var("TOTP_ISSUER").unwrap_or_else(|_| "Freelance Forge".to_string());
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        urlencoding::encode(&issuer),
+        urlencoding::encode(email),
+        secret,
+        urlencoding::encode(&issuer),
+        TOTP_DIGITS,
+        TOTP_PERIOD_SECS
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult, Transaction};
+
+    /// Shared secret of the RFC 6238 SHA-1 test vectors.
+    const RFC_SECRET: &[u8] = b"12345678901234567890";
+
+    fn at(timestamp: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(timestamp, 0).unwrap()
+    }
+
+    fn enrolled_user() -> user::Model {
+        user::Model {
+            id: Uuid::new_v4(),
+            email: "user@example.com".to_string(),
+            password_hash: String::new(),
+            address: None,
+            company_id: None,
+            created_at: at(0),
+            email_verified_at: None,
+            totp_secret: Some(hex::encode(RFC_SECRET)),
+            totp_enabled_at: Some(at(0)),
+            totp_last_step: None,
+            deleted_at: None,
+        }
+    }
+
+    fn exec_results(rows: &[u64]) -> sea_orm::DatabaseConnection {
+        MockDatabase::new(DatabaseBackend::Postgres)
+            .append_exec_results(rows.iter().map(|rows_affected| MockExecResult {
+                last_insert_id: 0,
+                rows_affected: *rows_affected,
+            }))
+            .into_connection()
+    }
+
+    #[test]
+    fn matches_rfc_6238_vectors() {
+        // Appendix B lists 8-digit codes; 6-digit codes are their last six digits.
+        let vectors = [
+            (59, "287082"),
+            (1_111_111_109, "081804"),
+            (1_111_111_111, "050471"),
+            (1_234_567_890, "005924"),
+            (2_000_000_000, "279037"),
+            (20_000_000_000, "353130"),
+        ];
+        for (timestamp, code) in vectors {
+            assert_eq!(totp_code(RFC_SECRET, time_step(at(timestamp))), code, "T = {timestamp}");
+        }
+    }
+
+    #[test]
+    fn accepts_one_step_of_clock_skew() {
+        let now = at(1_111_111_111);
+        let step = time_step(now);
+
+        for offset in [-1, 0, 1] {
+            let code = totp_code(RFC_SECRET, step + offset);
+            assert_eq!(matching_step(RFC_SECRET, &code, now), Some(step + offset));
+        }
+        for offset in [-2, 2] {
+            let code = totp_code(RFC_SECRET, step + offset);
+            assert_eq!(matching_step(RFC_SECRET, &code, now), None);
+        }
+    }
+
+    #[tokio::test]
+    async fn accepted_step_is_recorded_and_cannot_be_replayed() {
+        let now = at(1_111_111_111);
+        let code = totp_code(RFC_SECRET, time_step(now));
+        // The guarded update matches once; a replay finds totp_last_step already at the step.
+        let db = exec_results(&[1, 0]);
+        let user = enrolled_user();
+
+        assert!(verify_second_factor(&db, &user, &code, now).await.unwrap());
+        assert!(!verify_second_factor(&db, &user, &code, now).await.unwrap());
+
+        let step = time_step(now);
+        let expected = Transaction::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            r#"UPDATE "user" SET "totp_last_step" = $1 WHERE "user"."id" = $2 AND ("user"."totp_last_step" IS NULL OR "user"."totp_last_step" < $3)"#,
+            [step.into(), user.id.into(), step.into()],
+        );
+        assert_eq!(db.into_transaction_log(), vec![expected.clone(), expected]);
+    }
+
+    #[tokio::test]
+    async fn wrong_code_does_not_touch_the_database() {
+        let now = at(1_111_111_111);
+        let db = exec_results(&[]);
+
+        assert!(!verify_second_factor(&db, &enrolled_user(), "000000", now).await.unwrap());
+        assert!(db.into_transaction_log().is_empty());
+    }
+
+    #[tokio::test]
+    async fn recovery_codes_are_single_use() {
+        let now = at(1_111_111_111);
+        let db = exec_results(&[1, 0]);
+        let user = enrolled_user();
+
+        assert!(verify_second_factor(&db, &user, "ABCDE-FGHIJ", now).await.unwrap());
+        assert!(!verify_second_factor(&db, &user, "abcde fghij", now).await.unwrap());
+
+        // Both spellings look up the same normalized code, and only while it is unused.
+        let expected = Transaction::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            r#"UPDATE "recovery_code" SET "used_at" = $1 WHERE "recovery_code"."user_id" = $2 AND "recovery_code"."code_hash" = $3 AND "recovery_code"."used_at" IS NULL"#,
+            [now.into(), user.id.into(), hash_token("abcdefghij").into()],
+        );
+        assert_eq!(db.into_transaction_log(), vec![expected.clone(), expected]);
+    }
+}
@@ -0,0 +1,371 @@
+use crate::modules::shared::{AppError, FieldError};
+use axum::{
+    async_trait,
+    extract::{FromRequest, FromRequestParts, Request},
+    http::request::Parts,
+    Json,
+};
+use serde::de::DeserializeOwned;
+use std::borrow::Cow;
+use validator::{Validate, ValidateEmail, ValidationError, ValidationErrors, ValidationErrorsKind};
+
+/// Active ISO 4217 currency codes accepted on invoices and expenses.
+const ISO_4217: &str = "AED AFN ALL AMD ANG AOA ARS AUD AWG AZN BAM BBD BDT BGN BHD BIF BMD BND \
+    BOB BRL BSD BTN BWP BYN BZD CAD CDF CHF CLP CNY COP CRC CUP CVE CZK DJF DKK DOP DZD EGP ERN \
+    ETB EUR FJD FKP GBP GEL GHS GIP GMD GNF GTQ GYD HKD HNL HTG HUF IDR ILS INR IQD IRR ISK JMD \
+    JOD JPY KES KGS KHR KMF KPW KRW KWD KYD KZT LAK LBP LKR LRD LSL LYD MAD MDL MGA MKD MMK MNT \
+    MOP MRU MUR MVR MWK MXN MYR MZN NAD NGN NIO NOK NPR NZD OMR PAB PEN PGK PHP PKR PLN PYG QAR \
+    RON RSD RUB RWF SAR SBD SCR SDG SEK SGD SHP SLE SOS SRD SSP STN SVC SYP SZL THB TJS TMT TND \
+    TOP TRY TTD TWD TZS UAH UGX USD UYU UZS VES VND VUV WST XAF XCD XOF XPF YER ZAR ZMW ZWL";
+
+/// JSON body extractor that runs the payload's `Validate` rules after deserializing,
+/// so every handler reports all invalid fields at once in the same shape.
+pub struct ValidatedJson<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(request, state)
+            .await
+            .map_err(|rejection| AppError::bad_request(rejection.body_text()))?;
+        value.validate()?;
+        Ok(Self(value))
+    }
+}
+
+/// Query string extractor that reports unparsable parameters (`?page=x`, an unknown
+/// sort field, a malformed date) as an `AppError` instead of axum's plain-text rejection.
+pub struct Query<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for Query<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let axum::extract::Query(value) =
+            axum::extract::Query::<T>::from_request_parts(parts, state)
+                .await
+                .map_err(|rejection| AppError::bad_request(rejection.body_text()))?;
+        Ok(Self(value))
+    }
+}
+
+/// Path parameter extractor with the same error shape as [`Query`].
+pub struct Path<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for Path<T>
+where
+    T: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let axum::extract::Path(value) =
+            axum::extract::Path::<T>::from_request_parts(parts, state)
+                .await
+                .map_err(|rejection| {
+                    // A missing parameter is a routing bug rather than a bad request.
+                    if rejection.status().is_server_error() {
+                        AppError::internal(rejection.body_text())
+                    } else {
+                        AppError::bad_request(rejection.body_text())
+                    }
+                })?;
+        Ok(Self(value))
+    }
+}
+
+impl From<ValidationErrors> for AppError {
+    fn from(errors: ValidationErrors) -> Self {
+        let mut fields = Vec::new();
+        collect_field_errors("", &errors, &mut fields);
+        fields.sort_by(|a, b| a.field.cmp(&b.field));
+        AppError::Validation(fields)
+    }
+}
+
+fn collect_field_errors(prefix: &str, errors: &ValidationErrors, out: &mut Vec<FieldError>) {
+    for (field, kind) in errors.errors() {
+        // Top-level lists (e.g. a `Vec<T>` body) report their items under a placeholder key.
+        let path = if field == "_tmp_validator" {
+            prefix.to_string()
+        } else {
+            join_path(prefix, field)
+        };
+        match kind {
+            ValidationErrorsKind::Field(list) => {
+                for error in list {
+                    // Struct-level checks name the field they concern in a "field" param.
+                    let path = match error.params.get("field").and_then(|v| v.as_str()) {
+                        Some(field) => join_path(prefix, field),
+                        None => path.clone(),
+                    };
+                    let message = error
+                        .message
+                        .as_ref()
+                        .map(|message| message.to_string())
+                        .unwrap_or_else(|| format!("{} is invalid", path));
+                    out.push(FieldError::new(&path, &error.code, message));
+                }
+            }
+            ValidationErrorsKind::Struct(inner) => collect_field_errors(&path, inner, out),
+            ValidationErrorsKind::List(items) => {
+                for (index, inner) in items {
+                    collect_field_errors(&format!("{}[{}]", path, index), inner, out);
+                }
+            }
+        }
+    }
+}
+
+fn join_path(prefix: &str, field: &str) -> String {
+    if prefix.is_empty() {
+        field.to_string()
+    } else {
+        format!("{}.{}", prefix, field)
+    }
+}
+
+fn error(code: &'static str, message: &'static str) -> ValidationError {
+    ValidationError::new(code).with_message(Cow::Borrowed(message))
+}
+
+/// Builds a struct-level error attributed to `field`.
+pub fn field_error(field: &'static str, code: &'static str, message: &'static str) -> ValidationError {
+    let mut error = error(code, message);
+    error.add_param(Cow::Borrowed("field"), &field);
+    error
+}
+
+pub fn not_blank(value: &str) -> Result<(), ValidationError> {
+    if value.trim().is_empty() {
+        return Err(error("required", "must not be empty"));
+    }
+    Ok(())
+}
+
+pub fn positive(value: f64) -> Result<(), ValidationError> {
+    if !value.is_finite() || value <= 0.0 {
+        return Err(error("positive", "must be greater than zero"));
+    }
+    Ok(())
+}
+
+pub fn non_negative(value: f64) -> Result<(), ValidationError> {
+    if !value.is_finite() || value < 0.0 {
+        return Err(error("non_negative", "must not be negative"));
+    }
+    Ok(())
+}
+
+pub fn currency_code(value: &str) -> Result<(), ValidationError> {
+    if value.len() != 3 || !ISO_4217.split_whitespace().any(|code| code == value) {
+        return Err(error("currency", "must be an ISO 4217 currency code such as EUR"));
+    }
+    Ok(())
+}
+
+/// Two uppercase letters, as in ISO 3166-1 alpha-2 (e.g. `DE`).
+pub fn country_code(value: &str) -> Result<(), ValidationError> {
+    if value.len() != 2 || !value.chars().all(|c| c.is_ascii_uppercase()) {
+        return Err(error("country", "must be an ISO 3166 country code such as DE"));
+    }
+    Ok(())
+}
+
+/// A general ledger account number such as `4930` (SKR03/SKR04 use four digits).
+pub fn ledger_account(value: &str) -> Result<(), ValidationError> {
+    if !(4..=8).contains(&value.len()) || !value.chars().all(|c| c.is_ascii_digit()) {
+        return Err(error("ledger_account", "must be an account number of 4 to 8 digits"));
+    }
+    Ok(())
+}
+
+/// Accepts a blank value (treated as "not set") or a valid email address.
+pub fn blank_or_email(value: &str) -> Result<(), ValidationError> {
+    if !value.trim().is_empty() && !value.trim().validate_email() {
+        return Err(error("email", "must be a valid email address"));
+    }
+    Ok(())
+}
+
+pub fn email_list(values: &[String]) -> Result<(), ValidationError> {
+    if values.iter().any(|value| !value.trim().validate_email()) {
+        return Err(error("email", "must only contain valid email addresses"));
+    }
+    Ok(())
+}
+
+/// An IBAN with a valid ISO 13616 check sum; spaces are ignored.
+pub fn iban(value: &str) -> Result<(), ValidationError> {
+    let compact: String = value.chars().filter(|c| !c.is_whitespace()).collect();
+    let compact = compact.to_uppercase();
+    let well_formed = (15..=34).contains(&compact.len())
+        && compact.chars().all(|c| c.is_ascii_alphanumeric())
+        && compact[..2].chars().all(|c| c.is_ascii_alphabetic())
+        && compact[2..4].chars().all(|c| c.is_ascii_digit());
+    // Move country code and check digits to the end, map letters to 10..35, then mod 97.
+    let remainder = well_formed.then(|| {
+        compact[4..]
+            .chars()
+            .chain(compact[..4].chars())
+            .fold(0u32, |remainder, c| {
+                let digit = c.to_digit(36).unwrap_or(0);
+                if digit >= 10 {
+                    (remainder * 100 + digit) % 97
+                } else {
+                    (remainder * 10 + digit) % 97
+                }
+            })
+    });
+    if remainder != Some(1) {
+        return Err(error("iban", "must be a valid IBAN"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::invoices::{InvoiceListQuery, NewInvoice};
+    use crate::modules::shared::PageParams;
+    use axum::http::Request as HttpRequest;
+
+    async fn query<T: DeserializeOwned>(uri: &str) -> Result<T, AppError> {
+        let (mut parts, _) = HttpRequest::builder().uri(uri).body(()).unwrap().into_parts();
+        Query::<T>::from_request_parts(&mut parts, &()).await.map(|Query(value)| value)
+    }
+
+    fn assert_bad_request<T>(result: Result<T, AppError>) {
+        match result {
+            Err(AppError::BadRequest(message)) => {
+                assert!(message.starts_with("Failed to deserialize query string"), "{message}")
+            }
+            Err(other) => panic!("expected bad_request, got {}", other.code()),
+            Ok(_) => panic!("expected bad_request, got a value"),
+        }
+    }
+
+    #[tokio::test]
+    async fn malformed_query_parameters_are_bad_requests() {
+        assert_bad_request(query::<PageParams>("/invoices?page=x").await);
+        assert_bad_request(query::<InvoiceListQuery>("/invoices?sort=bogus").await);
+        assert_bad_request(query::<InvoiceListQuery>("/invoices?date_from=2026-13-01").await);
+    }
+
+    #[tokio::test]
+    async fn valid_query_parameters_are_parsed() {
+        let params = query::<PageParams>("/invoices?page=2&per_page=10").await.ok().unwrap();
+        assert_eq!((params.page, params.per_page), (Some(2), Some(10)));
+    }
+
+    fn codes(result: Result<(), ValidationError>) -> Option<String> {
+        result.err().map(|error| error.code.to_string())
+    }
+
+    #[test]
+    fn not_blank_rejects_whitespace_only_values() {
+        assert_eq!(codes(not_blank("Design work")), None);
+        assert_eq!(codes(not_blank("")).as_deref(), Some("required"));
+        assert_eq!(codes(not_blank(" \t\n")).as_deref(), Some("required"));
+    }
+
+    #[test]
+    fn currency_code_accepts_only_active_iso_4217_codes() {
+        for code in ["EUR", "USD", "CHF", "JPY"] {
+            assert_eq!(codes(currency_code(code)), None, "{code}");
+        }
+        for code in ["eur", "EURO", "EU", "", "XYZ", "DEM"] {
+            assert_eq!(codes(currency_code(code)).as_deref(), Some("currency"), "{code}");
+        }
+    }
+
+    #[test]
+    fn iban_checks_format_and_check_digits() {
+        assert_eq!(codes(iban("DE89370400440532013000")), None);
+        assert_eq!(codes(iban("de89 3704 0044 0532 0130 00")), None);
+        assert_eq!(codes(iban("GB82 WEST 1234 5698 7654 32")), None);
+        for value in [
+            "DE88370400440532013000",
+            "DE8937040044",
+            "1289370400440532013000",
+            "DE89-3704-0044-0532-0130-00",
+            "",
+        ] {
+            assert_eq!(codes(iban(value)).as_deref(), Some("iban"), "{value}");
+        }
+    }
+
+    #[test]
+    fn email_list_rejects_any_invalid_address() {
+        let list = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+        assert_eq!(codes(email_list(&[])), None);
+        assert_eq!(codes(email_list(&list(&["a@example.com", " b@example.org "]))), None);
+        assert_eq!(
+            codes(email_list(&list(&["a@example.com", "not an address"]))).as_deref(),
+            Some("email")
+        );
+        assert_eq!(codes(email_list(&list(&[""]))).as_deref(), Some("email"));
+    }
+
+    fn field_errors(body: serde_json::Value) -> Vec<(String, String)> {
+        let invoice: NewInvoice = serde_json::from_value(body).unwrap();
+        match AppError::from(invoice.validate().err().unwrap()) {
+            AppError::Validation(fields) => {
+                fields.into_iter().map(|error| (error.field, error.code)).collect()
+            }
+            other => panic!("expected validation error, got {}", other.code()),
+        }
+    }
+
+    #[test]
+    fn all_invalid_fields_are_reported_with_nested_item_paths() {
+        let errors = field_errors(serde_json::json!({
+            "company_id": "00000000-0000-0000-0000-000000000000",
+            "currency": "euro",
+            "date": "2026-03-10",
+            "due_date": "2026-03-01",
+            "items": [
+                { "description": "Design", "quantity": 0.0, "unit_price": 100.0 },
+                { "description": "Hosting", "quantity": 1.0, "unit_price": 20.0 },
+                { "description": " ", "quantity": 1.0, "unit_price": -5.0 }
+            ]
+        }));
+
+        assert_eq!(
+            errors,
+            [
+                ("currency", "currency"),
+                ("due_date", "before_invoice_date"),
+                ("items[0].quantity", "positive"),
+                ("items[2].description", "required"),
+                ("items[2].unit_price", "non_negative"),
+            ]
+            .map(|(field, code)| (field.to_string(), code.to_string()))
+        );
+    }
+
+    #[test]
+    fn an_empty_item_list_is_reported_on_the_list() {
+        let errors = field_errors(serde_json::json!({
+            "company_id": "00000000-0000-0000-0000-000000000000",
+            "currency": "EUR",
+            "date": "2026-03-10",
+            "items": []
+        }));
+
+        assert_eq!(errors, [("items".to_string(), "length".to_string())]);
+    }
+}
@@ -0,0 +1,1009 @@
+use crate::entity::{user, workspace, workspace_invitation, workspace_member};
+use crate::modules::account::app_url;
+use crate::modules::auth::{generate_token, hash_token, require_session_user};
+use crate::modules::expense_categories::seed_default_categories;
+use crate::modules::mail::{render_account_email, OutgoingMail, WORKSPACE_INVITATION_EMAIL};
+use crate::modules::shared::{AppError, AppState};
+use crate::modules::travel::seed_default_travel_rates;
+use crate::modules::validation::{not_blank, Path, ValidatedJson};
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait,
+    QueryFilter, QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fmt;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+/// Selects the workspace a request acts on. Without it, the workspace the user joined
+/// first (normally their personal one) is used.
+pub const WORKSPACE_HEADER: &str = "x-workspace-id";
+const INVITATION_TTL_DAYS: i64 = 7;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum WorkspaceRole {
+    /// Full control, including managing owners
+    Owner,
+    /// Manages members and invitations, edits all data
+    Admin,
+    /// Reads everything, changes nothing
+    Accountant,
+    /// Creates and edits invoices, clients, expenses and templates
+    Member,
+}
+
+impl WorkspaceRole {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WorkspaceRole::Owner => "owner",
+            WorkspaceRole::Admin => "admin",
+            WorkspaceRole::Accountant => "accountant",
+            WorkspaceRole::Member => "member",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "owner" => Some(WorkspaceRole::Owner),
+            "admin" => Some(WorkspaceRole::Admin),
+            "accountant" => Some(WorkspaceRole::Accountant),
+            "member" => Some(WorkspaceRole::Member),
+            _ => None,
+        }
+    }
+
+    pub fn can_write(self) -> bool {
+        self != WorkspaceRole::Accountant
+    }
+
+    pub fn can_manage_members(self) -> bool {
+        matches!(self, WorkspaceRole::Owner | WorkspaceRole::Admin)
+    }
+}
+
+impl fmt::Display for WorkspaceRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The caller of a resource endpoint and the workspace the request acts on.
+pub struct WorkspaceAccess {
+    pub user: user::Model,
+    pub workspace_id: Uuid,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct WorkspaceRequest {
+    #[validate(custom(function = not_blank), length(max = 200))]
+    pub name: String,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct InviteMemberRequest {
+    #[validate(email(message = "must be a valid email address"))]
+    pub email: String,
+    pub role: WorkspaceRole,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct UpdateMemberRequest {
+    pub role: WorkspaceRole,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct AcceptInvitationRequest {
+    #[validate(custom(function = not_blank))]
+    pub token: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct WorkspaceResponse {
+    pub id: Uuid,
+    pub name: String,
+    /// Role of the current user in this workspace
+    pub role: WorkspaceRole,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct WorkspaceMemberResponse {
+    pub user_id: Uuid,
+    pub email: String,
+    pub role: WorkspaceRole,
+    pub joined_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct WorkspaceInvitationResponse {
+    pub id: Uuid,
+    pub email: String,
+    pub role: WorkspaceRole,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<workspace_invitation::Model> for WorkspaceInvitationResponse {
+    fn from(model: workspace_invitation::Model) -> Self {
+        Self {
+            role: invitation_role(&model),
+            id: model.id,
+            email: model.email,
+            expires_at: model.expires_at,
+            created_at: model.created_at,
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/workspaces",
+    responses(
+        (status = 200, description = "Workspaces the current user belongs to", body = [WorkspaceResponse]),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Requires a browser session", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "workspaces"
+)]
+pub async fn list_workspaces(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<WorkspaceResponse>>, AppError> {
+    let current_user = require_session_user(&state, &headers).await?;
+    let memberships = workspace_member::Entity::find()
+        .filter(workspace_member::Column::UserId.eq(current_user.id))
+        .order_by_asc(workspace_member::Column::CreatedAt)
+        .all(&state.db)
+        .await?;
+    let workspaces = workspace::Entity::find()
+        .filter(workspace::Column::Id.is_in(memberships.iter().map(|m| m.workspace_id)))
+        .all(&state.db)
+        .await?;
+
+    Ok(Json(
+        memberships
+            .iter()
+            .filter_map(|membership| {
+                let workspace = workspaces.iter().find(|w| w.id == membership.workspace_id)?;
+                Some(WorkspaceResponse {
+                    id: workspace.id,
+                    name: workspace.name.clone(),
+                    role: member_role(membership),
+                    created_at: workspace.created_at,
+                })
+            })
+            .collect(),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/workspaces",
+    request_body = WorkspaceRequest,
+    responses(
+        (status = 201, description = "Workspace created with the current user as owner", body = WorkspaceResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Requires a browser session", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "workspaces"
+)]
+pub async fn create_workspace(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidatedJson(payload): ValidatedJson<WorkspaceRequest>,
+) -> Result<(StatusCode, Json<WorkspaceResponse>), AppError> {
+    let current_user = require_session_user(&state, &headers).await?;
+    let txn = state.db.begin().await?;
+    let workspace = create_workspace_with_owner(&txn, payload.name.trim(), current_user.id).await?;
+    txn.commit().await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(WorkspaceResponse {
+            id: workspace.id,
+            name: workspace.name,
+            role: WorkspaceRole::Owner,
+            created_at: workspace.created_at,
+        }),
+    ))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/workspaces/{id}",
+    request_body = WorkspaceRequest,
+    params(
+        ("id" = String, Path, description = "Workspace id (UUID)")
+    ),
+    responses(
+        (status = 200, description = "Workspace renamed", body = WorkspaceResponse),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Requires the owner or admin role", body = ErrorResponse),
+        (status = 404, description = "Workspace not found", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "workspaces"
+)]
+pub async fn update_workspace(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    ValidatedJson(payload): ValidatedJson<WorkspaceRequest>,
+) -> Result<Json<WorkspaceResponse>, AppError> {
+    let current_user = require_session_user(&state, &headers).await?;
+    let id = Uuid::parse_str(&id).map_err(|_| AppError::bad_request("Invalid id"))?;
+    let role = require_manager(&state.db, id, current_user.id).await?;
+
+    let workspace = workspace::Entity::find_by_id(id)
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Workspace not found"))?;
+    let mut active: workspace::ActiveModel = workspace.into();
+    active.name = Set(payload.name.trim().to_string());
+    let workspace = active.update(&state.db).await?;
+
+    Ok(Json(WorkspaceResponse {
+        id: workspace.id,
+        name: workspace.name,
+        role,
+        created_at: workspace.created_at,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/workspaces/{id}/members",
+    params(
+        ("id" = String, Path, description = "Workspace id (UUID)")
+    ),
+    responses(
+        (status = 200, description = "Members of the workspace", body = [WorkspaceMemberResponse]),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 404, description = "Workspace not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "workspaces"
+)]
+pub async fn list_members(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<WorkspaceMemberResponse>>, AppError> {
+    let current_user = require_session_user(&state, &headers).await?;
+    let id = Uuid::parse_str(&id).map_err(|_| AppError::bad_request("Invalid id"))?;
+    find_membership(&state.db, id, current_user.id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Workspace not found"))?;
+
+    let members = workspace_member::Entity::find()
+        .filter(workspace_member::Column::WorkspaceId.eq(id))
+        .order_by_asc(workspace_member::Column::CreatedAt)
+        .all(&state.db)
+        .await?;
+    let users = user::Entity::find()
+        .filter(user::Column::Id.is_in(members.iter().map(|m| m.user_id)))
+        .all(&state.db)
+        .await?;
+
+    Ok(Json(
+        members
+            .iter()
+            .filter_map(|member| {
+                let user = users.iter().find(|u| u.id == member.user_id)?;
+                Some(WorkspaceMemberResponse {
+                    user_id: user.id,
+                    email: user.email.clone(),
+                    role: member_role(member),
+                    joined_at: member.created_at,
+                })
+            })
+            .collect(),
+    ))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/workspaces/{id}/members/{user_id}",
+    request_body = UpdateMemberRequest,
+    params(
+        ("id" = String, Path, description = "Workspace id (UUID)"),
+        ("user_id" = String, Path, description = "User id of the member (UUID)")
+    ),
+    responses(
+        (status = 200, description = "Role changed", body = WorkspaceMemberResponse),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Not allowed to change this member", body = ErrorResponse),
+        (status = 404, description = "Member not found", body = ErrorResponse),
+        (status = 409, description = "The workspace needs at least one owner", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "workspaces"
+)]
+pub async fn update_member(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((id, user_id)): Path<(String, String)>,
+    ValidatedJson(payload): ValidatedJson<UpdateMemberRequest>,
+) -> Result<Json<WorkspaceMemberResponse>, AppError> {
+    let current_user = require_session_user(&state, &headers).await?;
+    let id = Uuid::parse_str(&id).map_err(|_| AppError::bad_request("Invalid id"))?;
+    let user_id = Uuid::parse_str(&user_id).map_err(|_| AppError::bad_request("Invalid id"))?;
+    let role = require_manager(&state.db, id, current_user.id).await?;
+
+    let txn = state.db.begin().await?;
+    let member = find_membership(&txn, id, user_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Member not found"))?;
+    let current_role = member_role(&member);
+    let touches_owner = current_role == WorkspaceRole::Owner || payload.role == WorkspaceRole::Owner;
+    if touches_owner && role != WorkspaceRole::Owner {
+        return Err(AppError::forbidden("Only owners can grant or change the owner role"));
+    }
+    if current_role == WorkspaceRole::Owner && payload.role != WorkspaceRole::Owner {
+        ensure_other_owner(&txn, id, user_id).await?;
+    }
+
+    let mut active: workspace_member::ActiveModel = member.into();
+    active.role = Set(payload.role.as_str().to_string());
+    let member = active.update(&txn).await?;
+    txn.commit().await?;
+
+    let user = user::Entity::find_by_id(user_id)
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Member not found"))?;
+    Ok(Json(WorkspaceMemberResponse {
+        user_id: user.id,
+        email: user.email,
+        role: payload.role,
+        joined_at: member.created_at,
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/workspaces/{id}/members/{user_id}",
+    params(
+        ("id" = String, Path, description = "Workspace id (UUID)"),
+        ("user_id" = String, Path, description = "User id of the member (UUID); your own id to leave")
+    ),
+    responses(
+        (status = 204, description = "Member removed"),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Not allowed to remove this member", body = ErrorResponse),
+        (status = 404, description = "Member not found", body = ErrorResponse),
+        (status = 409, description = "The workspace needs at least one owner", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "workspaces"
+)]
+pub async fn remove_member(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((id, user_id)): Path<(String, String)>,
+) -> Result<StatusCode, AppError> {
+    let current_user = require_session_user(&state, &headers).await?;
+    let id = Uuid::parse_str(&id).map_err(|_| AppError::bad_request("Invalid id"))?;
+    let user_id = Uuid::parse_str(&user_id).map_err(|_| AppError::bad_request("Invalid id"))?;
+
+    let txn = state.db.begin().await?;
+    let caller = find_membership(&txn, id, current_user.id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Workspace not found"))?;
+    let member = find_membership(&txn, id, user_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Member not found"))?;
+    let caller_role = member_role(&caller);
+    let member_role = member_role(&member);
+    if user_id != current_user.id {
+        if !caller_role.can_manage_members() {
+            return Err(AppError::forbidden("Requires the owner or admin role"));
+        }
+        if member_role == WorkspaceRole::Owner && caller_role != WorkspaceRole::Owner {
+            return Err(AppError::forbidden("Only owners can remove an owner"));
+        }
+    }
+    if member_role == WorkspaceRole::Owner {
+        ensure_other_owner(&txn, id, user_id).await?;
+    }
+
+    workspace_member::Entity::delete_by_id(member.id)
+        .exec(&txn)
+        .await?;
+    txn.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/workspaces/{id}/invitations",
+    request_body = InviteMemberRequest,
+    params(
+        ("id" = String, Path, description = "Workspace id (UUID)")
+    ),
+    responses(
+        (status = 201, description = "Invitation emailed", body = WorkspaceInvitationResponse),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Requires the owner or admin role", body = ErrorResponse),
+        (status = 409, description = "Already a member", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 502, description = "Mail delivery failed", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "workspaces"
+)]
+pub async fn invite_member(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    ValidatedJson(payload): ValidatedJson<InviteMemberRequest>,
+) -> Result<(StatusCode, Json<WorkspaceInvitationResponse>), AppError> {
+    let current_user = require_session_user(&state, &headers).await?;
+    let id = Uuid::parse_str(&id).map_err(|_| AppError::bad_request("Invalid id"))?;
+    let role = require_manager(&state.db, id, current_user.id).await?;
+    if payload.role == WorkspaceRole::Owner && role != WorkspaceRole::Owner {
+        return Err(AppError::forbidden("Only owners can invite owners"));
+    }
+
+    let email = payload.email.trim().to_lowercase();
+    let existing_user = user::Entity::find()
+        .filter(user::Column::Email.eq(email.clone()))
+        .one(&state.db)
+        .await?;
+    if let Some(existing_user) = existing_user
+        && find_membership(&state.db, id, existing_user.id).await?.is_some()
+    {
+        return Err(AppError::conflict("Already a member of this workspace"));
+    }
+    let workspace = workspace::Entity::find_by_id(id)
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Workspace not found"))?;
+
+    // A new invitation replaces any pending one for the same address.
+    workspace_invitation::Entity::delete_many()
+        .filter(workspace_invitation::Column::WorkspaceId.eq(id))
+        .filter(workspace_invitation::Column::Email.eq(email.clone()))
+        .filter(workspace_invitation::Column::AcceptedAt.is_null())
+        .exec(&state.db)
+        .await?;
+
+    let token = generate_token();
+    let now = Utc::now();
+    let invitation = workspace_invitation::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        workspace_id: Set(id),
+        email: Set(email.clone()),
+        role: Set(payload.role.as_str().to_string()),
+        token_hash: Set(hash_token(&token)),
+        invited_by: Set(Some(current_user.id)),
+        expires_at: Set(now + Duration::days(INVITATION_TTL_DAYS)),
+        accepted_at: Set(None),
+        created_at: Set(now),
+    }
+    .insert(&state.db)
+    .await?;
+
+    let ctx = json!({
+        "email": email,
+        "inviter": current_user.email,
+        "workspace": workspace.name,
+        "role": payload.role.as_str(),
+        "link": format!("{}/invitations/accept?token={}", app_url(), token),
+        "valid_days": INVITATION_TTL_DAYS,
+    });
+    let rendered = render_account_email(WORKSPACE_INVITATION_EMAIL, &ctx)?;
+    state
+        .mailer
+        .send(OutgoingMail {
+            to: vec![email],
+            subject: rendered.subject,
+            html: rendered.html,
+            text: rendered.text,
+            attachments: Vec::new(),
+        })
+        .await
+        .map_err(AppError::bad_gateway)?;
+
+    Ok((StatusCode::CREATED, Json(invitation.into())))
+}
+
+#[utoipa::path(
+    get,
+    path = "/workspaces/{id}/invitations",
+    params(
+        ("id" = String, Path, description = "Workspace id (UUID)")
+    ),
+    responses(
+        (status = 200, description = "Pending invitations", body = [WorkspaceInvitationResponse]),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Requires the owner or admin role", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "workspaces"
+)]
+pub async fn list_invitations(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<WorkspaceInvitationResponse>>, AppError> {
+    let current_user = require_session_user(&state, &headers).await?;
+    let id = Uuid::parse_str(&id).map_err(|_| AppError::bad_request("Invalid id"))?;
+    require_manager(&state.db, id, current_user.id).await?;
+
+    let invitations = workspace_invitation::Entity::find()
+        .filter(workspace_invitation::Column::WorkspaceId.eq(id))
+        .filter(workspace_invitation::Column::AcceptedAt.is_null())
+        .filter(workspace_invitation::Column::ExpiresAt.gt(Utc::now()))
+        .order_by_desc(workspace_invitation::Column::CreatedAt)
+        .all(&state.db)
+        .await?;
+
+    Ok(Json(
+        invitations
+            .into_iter()
+            .map(WorkspaceInvitationResponse::from)
+            .collect(),
+    ))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/workspaces/{id}/invitations/{invitation_id}",
+    params(
+        ("id" = String, Path, description = "Workspace id (UUID)"),
+        ("invitation_id" = String, Path, description = "Invitation id (UUID)")
+    ),
+    responses(
+        (status = 204, description = "Invitation revoked"),
+        (status = 400, description = "Invalid id", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Requires the owner or admin role", body = ErrorResponse),
+        (status = 404, description = "Invitation not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "workspaces"
+)]
+pub async fn revoke_invitation(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((id, invitation_id)): Path<(String, String)>,
+) -> Result<StatusCode, AppError> {
+    let current_user = require_session_user(&state, &headers).await?;
+    let id = Uuid::parse_str(&id).map_err(|_| AppError::bad_request("Invalid id"))?;
+    let invitation_id =
+        Uuid::parse_str(&invitation_id).map_err(|_| AppError::bad_request("Invalid id"))?;
+    require_manager(&state.db, id, current_user.id).await?;
+
+    let result = workspace_invitation::Entity::delete_many()
+        .filter(workspace_invitation::Column::Id.eq(invitation_id))
+        .filter(workspace_invitation::Column::WorkspaceId.eq(id))
+        .filter(workspace_invitation::Column::AcceptedAt.is_null())
+        .exec(&state.db)
+        .await?;
+    if result.rows_affected == 0 {
+        return Err(AppError::not_found("Invitation not found"));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/workspaces/invitations/accept",
+    request_body = AcceptInvitationRequest,
+    responses(
+        (status = 200, description = "Joined the workspace", body = WorkspaceResponse),
+        (status = 400, description = "Invalid or expired invitation", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Invitation was sent to another email address", body = ErrorResponse),
+        (status = 422, description = "Validation failed", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "workspaces"
+)]
+pub async fn accept_invitation(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidatedJson(payload): ValidatedJson<AcceptInvitationRequest>,
+) -> Result<Json<WorkspaceResponse>, AppError> {
+    let current_user = require_session_user(&state, &headers).await?;
+    let now = Utc::now();
+
+    let txn = state.db.begin().await?;
+    let invitation = workspace_invitation::Entity::find()
+        .filter(workspace_invitation::Column::TokenHash.eq(hash_token(payload.token.trim())))
+        .one(&txn)
+        .await?
+        .filter(|invitation| invitation.accepted_at.is_none() && invitation.expires_at > now)
+        .ok_or_else(|| AppError::bad_request("Invalid or expired invitation"))?;
+    if invitation.email != current_user.email {
+        return Err(AppError::forbidden(
+            "This invitation was sent to a different email address",
+        ));
+    }
+
+    let result = workspace_invitation::Entity::update_many()
+        .col_expr(workspace_invitation::Column::AcceptedAt, Expr::value(now))
+        .filter(workspace_invitation::Column::Id.eq(invitation.id))
+        .filter(workspace_invitation::Column::AcceptedAt.is_null())
+        .exec(&txn)
+        .await?;
+    if result.rows_affected != 1 {
+        return Err(AppError::bad_request("Invalid or expired invitation"));
+    }
+
+    let role = invitation_role(&invitation);
+    if find_membership(&txn, invitation.workspace_id, current_user.id)
+        .await?
+        .is_none()
+    {
+        workspace_member::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            workspace_id: Set(invitation.workspace_id),
+            user_id: Set(current_user.id),
+            role: Set(role.as_str().to_string()),
+            created_at: Set(now),
+        }
+        .insert(&txn)
+        .await?;
+    }
+    let workspace = workspace::Entity::find_by_id(invitation.workspace_id)
+        .one(&txn)
+        .await?
+        .ok_or_else(|| AppError::bad_request("Invalid or expired invitation"))?;
+    txn.commit().await?;
+
+    Ok(Json(WorkspaceResponse {
+        id: workspace.id,
+        name: workspace.name,
+        role,
+        created_at: workspace.created_at,
+    }))
+}
+
+/// Creates the personal workspace every account starts with.
+pub(crate) async fn create_personal_workspace<C: ConnectionTrait>(
+    db: &C,
+    user: &user::Model,
+) -> Result<workspace::Model, AppError> {
+    create_workspace_with_owner(db, &user.email, user.id).await
+}
+
+/// Resolves the workspace a request acts on from [`WORKSPACE_HEADER`], falling back to
+/// the user's first workspace, together with the user's role in it.
+pub(crate) async fn resolve_workspace(
+    db: &DatabaseConnection,
+    user_id: Uuid,
+    headers: &HeaderMap,
+) -> Result<(Uuid, WorkspaceRole), AppError> {
+    let requested = headers
+        .get(WORKSPACE_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|value| !value.is_empty());
+
+    let membership = match requested {
+        Some(value) => {
+            let id = Uuid::parse_str(value)
+                .map_err(|_| AppError::bad_request("Invalid workspace id"))?;
+            find_membership(db, id, user_id)
+                .await?
+                .ok_or_else(|| AppError::forbidden("Not a member of this workspace"))?
+        }
+        None => workspace_member::Entity::find()
+            .filter(workspace_member::Column::UserId.eq(user_id))
+            .order_by_asc(workspace_member::Column::CreatedAt)
+            .one(db)
+            .await?
+            .ok_or_else(|| AppError::forbidden("Not a member of any workspace"))?,
+    };
+
+    Ok((membership.workspace_id, member_role(&membership)))
+}
+
+async fn create_workspace_with_owner<C: ConnectionTrait>(
+    db: &C,
+    name: &str,
+    owner_id: Uuid,
+) -> Result<workspace::Model, AppError> {
+    let now = Utc::now();
+    let workspace = workspace::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        name: Set(name.to_string()),
+        created_at: Set(now),
+        archived_at: Set(None),
+        retain_until: Set(None),
+    }
+    .insert(db)
+    .await?;
+
+    workspace_member::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        workspace_id: Set(workspace.id),
+        user_id: Set(owner_id),
+        role: Set(WorkspaceRole::Owner.as_str().to_string()),
+        created_at: Set(now),
+    }
+    .insert(db)
+    .await?;
+    seed_default_categories(db, workspace.id).await?;
+    seed_default_travel_rates(db, workspace.id).await?;
+
+    Ok(workspace)
+}
+
+async fn find_membership<C: ConnectionTrait>(
+    db: &C,
+    workspace_id: Uuid,
+    user_id: Uuid,
+) -> Result<Option<workspace_member::Model>, AppError> {
+    Ok(workspace_member::Entity::find()
+        .filter(workspace_member::Column::WorkspaceId.eq(workspace_id))
+        .filter(workspace_member::Column::UserId.eq(user_id))
+        .one(db)
+        .await?)
+}
+
+/// The caller's role in the workspace, which must allow managing members.
+pub(crate) async fn require_manager(
+    db: &DatabaseConnection,
+    workspace_id: Uuid,
+    user_id: Uuid,
+) -> Result<WorkspaceRole, AppError> {
+    let membership = find_membership(db, workspace_id, user_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Workspace not found"))?;
+    let role = member_role(&membership);
+    if !role.can_manage_members() {
+        return Err(AppError::forbidden("Requires the owner or admin role"));
+    }
+    Ok(role)
+}
+
+/// Locks the workspace's owner rows until the transaction ends, so two owners cannot
+/// step down at the same time and leave the workspace without one.
+async fn ensure_other_owner<C: ConnectionTrait>(
+    db: &C,
+    workspace_id: Uuid,
+    user_id: Uuid,
+) -> Result<(), AppError> {
+    let owners = workspace_member::Entity::find()
+        .filter(workspace_member::Column::WorkspaceId.eq(workspace_id))
+        .filter(workspace_member::Column::Role.eq(WorkspaceRole::Owner.as_str()))
+        .lock_exclusive()
+        .all(db)
+        .await?;
+    if !owners.iter().any(|owner| owner.user_id != user_id) {
+        return Err(AppError::conflict("The workspace needs at least one owner"));
+    }
+    Ok(())
+}
+
+fn member_role(membership: &workspace_member::Model) -> WorkspaceRole {
+    // Unknown roles fall back to the most restrictive one.
+    WorkspaceRole::parse(&membership.role).unwrap_or(WorkspaceRole::Accountant)
+}
+
+fn invitation_role(invitation: &workspace_invitation::Model) -> WorkspaceRole {
+    WorkspaceRole::parse(&invitation.role).unwrap_or(WorkspaceRole::Accountant)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::test_support::{self, session_headers};
+    use sea_orm::{DatabaseBackend, MockDatabase, Statement, Transaction};
+
+    fn workspace_id() -> Uuid {
+        Uuid::from_u128(10)
+    }
+
+    fn caller_id() -> Uuid {
+        Uuid::from_u128(1)
+    }
+
+    fn member(user_id: Uuid, role: WorkspaceRole) -> workspace_member::Model {
+        workspace_member::Model {
+            id: Uuid::from_u128(user_id.as_u128() + 100),
+            workspace_id: workspace_id(),
+            user_id,
+            role: role.as_str().to_string(),
+            created_at: Utc::now(),
+        }
+    }
+
+    fn signed_in() -> MockDatabase {
+        test_support::signed_in(
+            test_support::session(Uuid::from_u128(2), caller_id()),
+            test_support::user(caller_id(), "caller@example.com"),
+        )
+    }
+
+    async fn change_role(
+        db: MockDatabase,
+        user_id: Uuid,
+        role: WorkspaceRole,
+    ) -> (Result<Json<WorkspaceMemberResponse>, AppError>, Vec<Transaction>) {
+        let state = AppState::for_tests(db.into_connection());
+        let result = update_member(
+            State(state.clone()),
+            session_headers(),
+            Path((workspace_id().to_string(), user_id.to_string())),
+            ValidatedJson(UpdateMemberRequest { role }),
+        )
+        .await;
+        let AppState { db, .. } = state;
+        (result, db.into_transaction_log())
+    }
+
+    const MEMBER_COLUMNS: &str = r#"SELECT "workspace_member"."id", "workspace_member"."workspace_id", "workspace_member"."user_id", "workspace_member"."role", "workspace_member"."created_at" FROM "workspace_member""#;
+
+    fn statement(sql: &str, values: Vec<sea_orm::Value>) -> Statement {
+        Statement::from_sql_and_values(DatabaseBackend::Postgres, sql, values)
+    }
+
+    #[tokio::test]
+    async fn only_owners_can_change_an_owner() {
+        let owner = Uuid::from_u128(3);
+        let db = signed_in()
+            .append_query_results([[member(caller_id(), WorkspaceRole::Admin)]])
+            .append_query_results([[member(owner, WorkspaceRole::Owner)]]);
+        let (result, _) = change_role(db, owner, WorkspaceRole::Member).await;
+
+        let error = result.err().unwrap();
+        assert_eq!(error.status(), StatusCode::FORBIDDEN);
+        assert_eq!(error.to_string(), "Only owners can grant or change the owner role");
+    }
+
+    #[tokio::test]
+    async fn admins_cannot_promote_to_owner_and_members_cannot_manage() {
+        let member_id = Uuid::from_u128(3);
+        let db = signed_in()
+            .append_query_results([[member(caller_id(), WorkspaceRole::Admin)]])
+            .append_query_results([[member(member_id, WorkspaceRole::Member)]]);
+        let (result, _) = change_role(db, member_id, WorkspaceRole::Owner).await;
+        assert_eq!(result.err().unwrap().status(), StatusCode::FORBIDDEN);
+
+        let db = signed_in().append_query_results([[member(caller_id(), WorkspaceRole::Member)]]);
+        let (result, _) = change_role(db, member_id, WorkspaceRole::Accountant).await;
+        let error = result.err().unwrap();
+        assert_eq!(error.status(), StatusCode::FORBIDDEN);
+        assert_eq!(error.to_string(), "Requires the owner or admin role");
+    }
+
+    #[tokio::test]
+    async fn the_last_owner_cannot_step_down() {
+        let me = member(caller_id(), WorkspaceRole::Owner);
+        let db = signed_in()
+            .append_query_results([[me.clone()]])
+            .append_query_results([[me.clone()]])
+            .append_query_results([[me]]);
+        let (result, log) = change_role(db, caller_id(), WorkspaceRole::Admin).await;
+
+        let error = result.err().unwrap();
+        assert_eq!(error.status(), StatusCode::CONFLICT);
+        assert_eq!(error.to_string(), "The workspace needs at least one owner");
+        assert_eq!(
+            log.last().unwrap(),
+            &Transaction::many([
+                Statement::from_string(DatabaseBackend::Postgres, "BEGIN"),
+                statement(
+                    &format!(
+                        r#"{MEMBER_COLUMNS} WHERE "workspace_member"."workspace_id" = $1 AND "workspace_member"."user_id" = $2 LIMIT $3"#
+                    ),
+                    vec![workspace_id().into(), caller_id().into(), 1u64.into()],
+                ),
+                statement(
+                    &format!(
+                        r#"{MEMBER_COLUMNS} WHERE "workspace_member"."workspace_id" = $1 AND "workspace_member"."role" = $2 FOR UPDATE"#
+                    ),
+                    vec![workspace_id().into(), "owner".into()],
+                ),
+                Statement::from_string(DatabaseBackend::Postgres, "ROLLBACK"),
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn an_owner_can_step_down_while_another_owner_remains() {
+        let me = member(caller_id(), WorkspaceRole::Owner);
+        let other = member(Uuid::from_u128(3), WorkspaceRole::Owner);
+        let db = signed_in()
+            .append_query_results([[me.clone()]])
+            .append_query_results([[me.clone()]])
+            .append_query_results([[me.clone(), other]])
+            .append_query_results([[workspace_member::Model {
+                role: "admin".to_string(),
+                ..me
+            }]])
+            .append_query_results([[test_support::user(caller_id(), "caller@example.com")]]);
+        let (result, _) = change_role(db, caller_id(), WorkspaceRole::Admin).await;
+
+        assert_eq!(result.ok().unwrap().role, WorkspaceRole::Admin);
+    }
+
+    #[tokio::test]
+    async fn members_can_leave_but_not_remove_others() {
+        let other = Uuid::from_u128(3);
+        let db = signed_in()
+            .append_query_results([[member(caller_id(), WorkspaceRole::Member)]])
+            .append_query_results([[member(other, WorkspaceRole::Member)]])
+            .into_connection();
+        let error = remove_member(
+            State(AppState::for_tests(db)),
+            session_headers(),
+            Path((workspace_id().to_string(), other.to_string())),
+        )
+        .await
+        .err()
+        .unwrap();
+        assert_eq!(error.status(), StatusCode::FORBIDDEN);
+        assert_eq!(error.to_string(), "Requires the owner or admin role");
+
+        let me = member(caller_id(), WorkspaceRole::Member);
+        let db = signed_in()
+            .append_query_results([[me.clone()]])
+            .append_query_results([[me]])
+            .append_exec_results([sea_orm::MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            }])
+            .into_connection();
+        let status = remove_member(
+            State(AppState::for_tests(db)),
+            session_headers(),
+            Path((workspace_id().to_string(), caller_id().to_string())),
+        )
+        .await
+        .ok()
+        .unwrap();
+        assert_eq!(status, StatusCode::NO_CONTENT);
+    }
+
+    #[test]
+    fn unknown_roles_fall_back_to_the_read_only_accountant() {
+        let invitation = workspace_invitation::Model {
+            id: Uuid::from_u128(20),
+            workspace_id: workspace_id(),
+            email: "new@example.com".to_string(),
+            role: "superuser".to_string(),
+            token_hash: String::new(),
+            invited_by: Some(caller_id()),
+            expires_at: Utc::now(),
+            accepted_at: None,
+            created_at: Utc::now(),
+        };
+        assert_eq!(invitation_role(&invitation), WorkspaceRole::Accountant);
+        assert_eq!(
+            WorkspaceInvitationResponse::from(invitation).role,
+            WorkspaceRole::Accountant
+        );
+
+        let mut membership = member(caller_id(), WorkspaceRole::Owner);
+        membership.role = "superuser".to_string();
+        assert_eq!(member_role(&membership), WorkspaceRole::Accountant);
+    }
+}
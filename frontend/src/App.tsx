@@ -1,4 +1,6 @@
 import { Navigate, Route, Routes } from "react-router-dom";
+import AcceptInvitation from "./routes/AcceptInvitation";
+import Accountant from "./routes/Accountant";
 import Auth from "./routes/Auth";
 import Dashboard from "./routes/Dashboard";
 import NotFound from "./routes/NotFound";
@@ -6,11 +8,17 @@ import Templates from "./routes/Templates";
 import Invoices from "./routes/Invoices";
 import Reports from "./routes/Reports";
 import Expenses from "./routes/Expenses";
+import ResetPassword from "./routes/ResetPassword";
+import VerifyEmail from "./routes/VerifyEmail";
 
 export default function App() {
   return (
     <Routes>
       <Route path="/" element={<Auth />} />
+      <Route path="/reset-password" element={<ResetPassword />} />
+      <Route path="/verify-email" element={<VerifyEmail />} />
+      <Route path="/invitations/accept" element={<AcceptInvitation />} />
+      <Route path="/accountant" element={<Accountant />} />
       <Route path="/app" element={<Dashboard />} />
       <Route path="/app/invoices" element={<Invoices />} />
       <Route path="/app/reports" element={<Reports />} />
@@ -31,7 +31,7 @@ export default function InvoicePreview({
           <div className="rounded-2xl border border-ink/10 bg-white/80 p-3">
             <p className="text-xs uppercase tracking-[0.2em] text-haze">Line items</p>
             <div className="mt-3 space-y-2 text-sm text-slate">
-              {invoice.items.map((row) => (
+              {(invoice.items ?? []).map((row) => (
                 <div key={row.id || row.description} className="flex items-center justify-between">
                   <span>{row.description}</span>
                   <span>
@@ -1,14 +1,21 @@
+import type { ReactNode } from "react";
 import type { Invoice } from "../lib/api";
 import { currencySymbol } from "../lib/currency";
 
 export default function InvoiceTable({
   invoices,
+  total,
+  children,
   onView,
   onEdit,
   onDuplicate,
   onDownload,
 }: {
   invoices: Invoice[];
+  /** Invoices matching the filters across all pages. */
+  total: number;
+  /** Rendered below the table, e.g. pagination. */
+  children?: ReactNode;
   onView: (invoice: Invoice) => void;
   onEdit: (invoice: Invoice) => void;
   onDuplicate: (invoice: Invoice) => void;
@@ -18,7 +25,7 @@ export default function InvoiceTable({
     <div className="rounded-3xl border border-white/70 bg-white/80 p-6 shadow-lift">
       <div className="flex items-center justify-between">
         <h3 className="font-display text-xl">Invoices</h3>
-        <span className="text-xs text-haze">{invoices.length} total</span>
+        <span className="text-xs text-haze">{total} total</span>
       </div>
       <div className="mt-4 overflow-x-auto">
         <table className="w-full text-left text-sm">
@@ -86,6 +93,7 @@ export default function InvoiceTable({
           </tbody>
         </table>
       </div>
+      {children}
     </div>
   );
 }
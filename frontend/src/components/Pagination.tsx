@@ -0,0 +1,39 @@
+export default function Pagination({
+  page,
+  perPage,
+  total,
+  onChange,
+}: {
+  page: number;
+  perPage: number;
+  total: number;
+  onChange: (page: number) => void;
+}) {
+  const pages = Math.max(1, Math.ceil(total / perPage));
+  if (pages <= 1) {
+    return null;
+  }
+  return (
+    <div className="mt-4 flex items-center justify-between text-xs text-slate">
+      <button
+        className="rounded-lg border border-ink/10 px-3 py-1 font-semibold disabled:opacity-40"
+        onClick={() => onChange(page - 1)}
+        type="button"
+        disabled={page <= 1}
+      >
+        Previous
+      </button>
+      <span>
+        Page {page} of {pages}
+      </span>
+      <button
+        className="rounded-lg border border-ink/10 px-3 py-1 font-semibold disabled:opacity-40"
+        onClick={() => onChange(page + 1)}
+        type="button"
+        disabled={page >= pages}
+      >
+        Next
+      </button>
+    </div>
+  );
+}
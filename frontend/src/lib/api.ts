@@ -4,6 +4,20 @@ export type User = {
   address?: string | null;
   company_id?: string | null;
   created_at: string;
+  email_verified?: boolean;
+  two_factor_enabled?: boolean;
+};
+
+export type TwoFactorChallenge = {
+  challenge: string;
+  expires_at: string;
+};
+
+export type Workspace = {
+  id: string;
+  name: string;
+  role: "owner" | "admin" | "accountant" | "member";
+  created_at: string;
 };
 
 export type Company = {
@@ -28,7 +42,10 @@ export type Invoice = {
   user_address: string;
   total_amount: number;
   date: string;
-  items: LineItem[];
+  due_date?: string | null;
+  status: string;
+  // Missing when the list was requested with include_items=false.
+  items?: LineItem[];
 };
 
 export type LineItem = {
@@ -45,15 +62,92 @@ export type Expense = {
   vendor: string;
   description: string;
   amount: number;
+  net_amount?: number | null;
+  vat_rate?: number | null;
+  vat_amount?: number | null;
+  input_tax_deductible: boolean;
+  reverse_charge: boolean;
   currency: string;
   date: string;
   category?: string | null;
+  category_id?: string | null;
+  receipt_id?: string | null;
   receipt_url?: string | null;
+  kind: 'receipt' | 'mileage' | 'per_diem';
+  mileage?: {
+    trip_from: string;
+    trip_to: string;
+    distance_km: number;
+    vehicle: string;
+    rate_per_km: number;
+  } | null;
+  per_diem?: {
+    country: string;
+    departure_at: string;
+    return_at: string;
+    full_days: number;
+    partial_days: number;
+    breakfasts_provided: number;
+    lunches_provided: number;
+    dinners_provided: number;
+  } | null;
+};
+
+export type ExpenseCategory = {
+  id: string;
+  name: string;
+  skr03_account?: string | null;
+  skr04_account?: string | null;
+  deductible_percent: number;
+  created_at: string;
+};
+
+export type Receipt = {
+  id: string;
+  filename: string;
+  content_type: string;
+  size_bytes: number;
+  sha256: string;
+  created_at: string;
+  download_url: string;
+  download_url_expires_at: string;
 };
 
 export type ReceiptUpload = {
+  receipt_id: string;
   upload_url: string;
-  receipt_url: string;
+  download_url: string;
+  download_url_expires_at: string;
+};
+
+export type ExtractedValue<T> = {
+  value: T;
+  confidence: number;
+};
+
+export type ReceiptExtraction = {
+  receipt_id: string;
+  engine: string;
+  text: string;
+  fields: {
+    vendor?: ExtractedValue<string> | null;
+    date?: ExtractedValue<string> | null;
+    amount?: ExtractedValue<number> | null;
+    net_amount?: ExtractedValue<number> | null;
+    vat_amount?: ExtractedValue<number> | null;
+    vat_rate?: ExtractedValue<number> | null;
+    currency?: ExtractedValue<string> | null;
+  };
+  draft: {
+    vendor: string;
+    description: string;
+    amount?: number | null;
+    net_amount?: number | null;
+    vat_rate?: number | null;
+    currency: string;
+    date: string;
+    receipt_id?: string | null;
+  };
 };
 
 export type LineItemImprove = {
@@ -72,22 +166,92 @@ export type InvoiceTemplate = {
 };
 type ApiResult<T> = { ok: true; data: T } | { ok: false; error: string };
 
+export type AccountantGrant = {
+  workspace: string;
+  email: string;
+  period_start: string;
+  period_end: string;
+  expires_at: string;
+};
+
+export type AccountantTotal = {
+  currency: string;
+  count: number;
+  total: number;
+};
+
+export type AccountantReport = {
+  period_start: string;
+  period_end: string;
+  invoices: AccountantTotal[];
+  expenses: AccountantTotal[];
+  expenses_by_category: Array<
+    AccountantTotal & {
+      category: string | null;
+      skr03_account: string | null;
+      skr04_account: string | null;
+      deductible_percent: number | null;
+    }
+  >;
+  expenses_by_kind: Array<AccountantTotal & { kind: Expense["kind"] }>;
+};
+
+export type Page<T> = {
+  items: T[];
+  total: number;
+  page: number;
+  per_page: number;
+};
+
+/** Paging, sorting, search and filters of a list endpoint; empty values are left out. */
+export type ListParams = {
+  page?: number;
+  per_page?: number;
+  q?: string;
+  sort?: string;
+  order?: "asc" | "desc";
+  [filter: string]: string | number | boolean | null | undefined;
+};
+
 const API_BASE = import.meta.env.VITE_API_URL || "http://localhost:3000";
 
+export type ApiError = {
+  code: string;
+  message: string;
+  fields?: Array<{ field: string; code: string; message: string }>;
+  request_id?: string | null;
+};
+
+async function readError(res: Response): Promise<string> {
+  const text = await res.text();
+  try {
+    const body = JSON.parse(text) as ApiError;
+    if (body.fields && body.fields.length > 0) {
+      return body.fields.map((field) => field.message).join(", ");
+    }
+    if (body.message) {
+      return body.message;
+    }
+  } catch {
+    // Not a structured error body; fall back to the raw text.
+  }
+  return text || `Request failed (${res.status})`;
+}
+
 async function fetchJson<T>(path: string, options?: RequestInit): Promise<ApiResult<T>> {
   try {
     const res = await fetch(`${API_BASE}${path}`, {
       credentials: "include",
+      ...options,
       headers: {
-        "Content-Type": "application/json",
+        // Let the browser set the multipart boundary for form uploads.
+        ...(options?.body instanceof FormData ? {} : { "Content-Type": "application/json" }),
         ...(options?.headers || {}),
       },
-      ...options,
     });
 
     if (!res.ok) {
-      const message = await res.text();
-      return { ok: false, error: message || `Request failed (${res.status})` };
+      return { ok: false, error: await readError(res) };
     }
 
     const contentType = res.headers.get("content-type") || "";
@@ -105,10 +269,68 @@ async function fetchJson<T>(path: string, options?: RequestInit): Promise<ApiRes
   }
 }
 
+async function fetchBlob(path: string, options?: RequestInit): Promise<ApiResult<Blob>> {
+  try {
+    const res = await fetch(`${API_BASE}${path}`, options);
+    if (!res.ok) {
+      return { ok: false, error: await readError(res) };
+    }
+    return { ok: true, data: await res.blob() };
+  } catch (error) {
+    return {
+      ok: false,
+      error: error instanceof Error ? error.message : "Network error",
+    };
+  }
+}
+
+/** Accountant access links authenticate on their own, without the browser session. */
+function bearer(token: string): RequestInit {
+  return { credentials: "omit", headers: { Authorization: `Bearer ${token}` } };
+}
+
+function withQuery(path: string, params?: ListParams) {
+  const search = new URLSearchParams();
+  Object.entries(params || {}).forEach(([key, value]) => {
+    if (value !== undefined && value !== null && value !== "") {
+      search.set(key, String(value));
+    }
+  });
+  const query = search.toString();
+  return query ? `${path}?${query}` : path;
+}
+
+function fetchPage<T>(path: string, params?: ListParams) {
+  return fetchJson<Page<T>>(withQuery(path, params));
+}
+
+/**
+ * Every item matching `params`, for reports that aggregate over a date range. List views
+ * use `fetchPage` instead.
+ */
+async function fetchRange<T>(path: string, params: ListParams): Promise<ApiResult<T[]>> {
+  const items: T[] = [];
+  for (let page = 1; ; page += 1) {
+    const result = await fetchPage<T>(path, { ...params, page, per_page: 200 });
+    if (!result.ok) {
+      return result;
+    }
+    items.push(...result.data.items);
+    if (items.length >= result.data.total || result.data.items.length === 0) {
+      return { ok: true, data: items };
+    }
+  }
+}
+
 export const api = {
   me: () => fetchJson<User>("/auth/me"),
   login: (payload: { email: string; password: string }) =>
-    fetchJson<{ user: User }>("/auth/login", {
+    fetchJson<{ user: User } | TwoFactorChallenge>("/auth/login", {
+      method: "POST",
+      body: JSON.stringify(payload),
+    }),
+  completeTwoFactorLogin: (payload: { challenge: string; code: string }) =>
+    fetchJson<{ user: User }>("/auth/login/2fa", {
       method: "POST",
       body: JSON.stringify(payload),
     }),
@@ -123,8 +345,23 @@ export const api = {
       method: "PATCH",
       body: JSON.stringify(payload),
     }),
+  verifyEmail: (payload: { token: string }) =>
+    fetchJson<void>("/auth/verify-email", {
+      method: "POST",
+      body: JSON.stringify(payload),
+    }),
+  resetPassword: (payload: { token: string; password: string }) =>
+    fetchJson<void>("/auth/password-reset", {
+      method: "POST",
+      body: JSON.stringify(payload),
+    }),
+  acceptInvitation: (payload: { token: string }) =>
+    fetchJson<Workspace>("/workspaces/invitations/accept", {
+      method: "POST",
+      body: JSON.stringify(payload),
+    }),
   myCompany: () => fetchJson<Company>("/company/me"),
-  listCompanies: () => fetchJson<Company[]>("/company"),
+  listCompanies: (params?: ListParams) => fetchPage<Company>("/company", params),
   createCompany: (payload: { name: string; address: string; registration_number: string }) =>
     fetchJson<Company>("/company", {
       method: "POST",
@@ -142,8 +379,8 @@ export const api = {
   createInvoice: (payload: {
     company_id: string;
     template_id?: string | null;
-    client_name: string;
-    client_address: string;
+    client_name?: string;
+    client_address?: string;
     currency: string;
     date: string;
     items: Array<{
@@ -157,7 +394,10 @@ export const api = {
       method: "POST",
       body: JSON.stringify(payload),
     }),
-  listInvoices: () => fetchJson<Invoice[]>("/invoices"),
+  listInvoices: (params?: ListParams) => fetchPage<Invoice>("/invoices", params),
+  /** Invoices dated within the range, without line items. */
+  reportInvoices: (range: { date_from: string; date_to: string }) =>
+    fetchRange<Invoice>("/invoices", { ...range, include_items: false }),
   updateInvoice: (
     id: string,
     payload: Partial<{
@@ -180,7 +420,8 @@ export const api = {
       body: JSON.stringify(payload),
     }),
   getInvoice: (id: string) => fetchJson<Invoice>(`/invoices/${id}`),
-  listTemplates: () => fetchJson<InvoiceTemplate[]>("/invoice-templates"),
+  listTemplates: (params?: ListParams) =>
+    fetchPage<InvoiceTemplate>("/invoice-templates", params),
   createTemplate: (payload: { name: string; html: string }) =>
     fetchJson<InvoiceTemplate>("/invoice-templates", {
       method: "POST",
@@ -195,14 +436,22 @@ export const api = {
     fetchJson<void>(`/invoice-templates/${id}`, {
       method: "DELETE",
     }),
-  listExpenses: () => fetchJson<Expense[]>("/expenses"),
+  listExpenses: (params?: ListParams) => fetchPage<Expense>("/expenses", params),
+  reportExpenses: (range: { date_from: string; date_to: string }) =>
+    fetchRange<Expense>("/expenses", range),
+  listExpenseCategories: () => fetchJson<ExpenseCategory[]>("/expense-categories"),
   createExpense: (payload: {
     vendor: string;
     description: string;
-    amount: number;
+    amount?: number | null;
+    net_amount?: number | null;
+    vat_rate?: number | null;
+    input_tax_deductible?: boolean;
+    reverse_charge?: boolean;
     currency: string;
     date: string;
     category?: string | null;
+    receipt_id?: string | null;
     receipt_url?: string | null;
   }) =>
     fetchJson<Expense>("/expenses", {
@@ -215,9 +464,14 @@ export const api = {
       vendor: string;
       description: string;
       amount: number;
+      net_amount: number;
+      vat_rate: number;
+      input_tax_deductible: boolean;
+      reverse_charge: boolean;
       currency: string;
       date: string;
       category?: string | null;
+      receipt_id?: string | null;
       receipt_url?: string | null;
     }>
   ) =>
@@ -229,15 +483,37 @@ export const api = {
     fetchJson<void>(`/expenses/${id}`, {
       method: "DELETE",
     }),
+  uploadReceipt: (file: File) => {
+    const form = new FormData();
+    form.append("file", file);
+    return fetchJson<Receipt>("/receipts", { method: "POST", body: form });
+  },
+  getReceipt: (id: string) => fetchJson<Receipt>(`/receipts/${id}`),
   createReceiptUpload: (payload: { filename: string; content_type: string }) =>
     fetchJson<ReceiptUpload>("/expenses/receipt-url", {
       method: "POST",
       body: JSON.stringify(payload),
     }),
+  extractReceipt: (id: string) =>
+    fetchJson<ReceiptExtraction>(`/receipts/${id}/extract`, { method: "POST" }),
+  confirmReceipt: (id: string) =>
+    fetchJson<Receipt>(`/receipts/${id}/confirm`, { method: "POST" }),
   improveLineItem: (payload: { description: string }) =>
     fetchJson<LineItemImprove>("/ai/line-item-improve", {
       method: "POST",
       body: JSON.stringify(payload),
     }),
   lastLineItem: () => fetchJson<LastLineItem>("/ai/line-item-last"),
+  accountantGrant: (token: string) =>
+    fetchJson<AccountantGrant>("/accountant/grant", bearer(token)),
+  accountantReport: (token: string) =>
+    fetchJson<AccountantReport>("/accountant/report", bearer(token)),
+  accountantInvoices: (token: string, params?: ListParams) =>
+    fetchJson<Page<Invoice>>(withQuery("/accountant/invoices", params), bearer(token)),
+  accountantInvoicePdf: (token: string, id: string) =>
+    fetchBlob(`/accountant/invoices/${id}/pdf`, bearer(token)),
+  accountantExpenses: (token: string, params?: ListParams) =>
+    fetchJson<Page<Expense>>(withQuery("/accountant/expenses", params), bearer(token)),
+  accountantReceipt: (token: string, id: string) =>
+    fetchJson<Receipt>(`/accountant/receipts/${id}`, bearer(token)),
 };
@@ -0,0 +1,11 @@
+import { useEffect, useState } from "react";
+
+/** `value` once it has stopped changing for `delay` ms, e.g. for search-as-you-type. */
+export function useDebounced<T>(value: T, delay = 300) {
+  const [debounced, setDebounced] = useState(value);
+  useEffect(() => {
+    const timer = window.setTimeout(() => setDebounced(value), delay);
+    return () => window.clearTimeout(timer);
+  }, [value, delay]);
+  return debounced;
+}
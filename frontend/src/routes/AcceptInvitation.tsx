@@ -0,0 +1,77 @@
+import { useState } from "react";
+import { Link, useLocation, useSearchParams } from "react-router-dom";
+import { api } from "../lib/api";
+import type { Workspace } from "../lib/api";
+
+export default function AcceptInvitation() {
+  const location = useLocation();
+  const [searchParams] = useSearchParams();
+  const token = searchParams.get("token") || "";
+  const [loading, setLoading] = useState(false);
+  const [workspace, setWorkspace] = useState<Workspace | null>(null);
+  const [needsLogin, setNeedsLogin] = useState(false);
+  const [status, setStatus] = useState<string | null>(token ? null : "This link has no token.");
+
+  async function handleAccept() {
+    setLoading(true);
+    setStatus(null);
+    const session = await api.me();
+    if (!session.ok) {
+      setLoading(false);
+      setNeedsLogin(true);
+      setStatus("Log in or register with the invited email address, then accept again.");
+      return;
+    }
+    const result = await api.acceptInvitation({ token });
+    setLoading(false);
+    if (!result.ok) {
+      setStatus(result.error);
+      return;
+    }
+    setWorkspace(result.data);
+  }
+
+  const loginLink = `/?next=${encodeURIComponent(location.pathname + location.search)}`;
+
+  return (
+    <div className="flex min-h-screen items-center justify-center bg-cloud text-ink">
+      <div className="w-full max-w-md rounded-3xl border border-ink/10 bg-white/80 p-10 text-center shadow-lift">
+        <p className="text-xs uppercase tracking-[0.2em] text-haze">Workspace invitation</p>
+        <h1 className="mt-4 font-display text-3xl">
+          {workspace ? `Welcome to ${workspace.name}` : "Join a workspace"}
+        </h1>
+        <p className="mt-2 text-sm text-slate">
+          {workspace
+            ? `You joined as ${workspace.role}.`
+            : status || "Accept the invitation to work in the shared workspace."}
+        </p>
+        {workspace ? (
+          <Link
+            to="/app"
+            className="mt-6 inline-flex rounded-xl bg-ink px-4 py-2 text-sm font-semibold text-white"
+          >
+            Continue
+          </Link>
+        ) : needsLogin ? (
+          <Link
+            to={loginLink}
+            className="mt-6 inline-flex rounded-xl bg-ink px-4 py-2 text-sm font-semibold text-white"
+          >
+            Log in
+          </Link>
+        ) : (
+          token && (
+            <button
+              className="mt-6 inline-flex rounded-xl bg-ink px-4 py-2 text-sm font-semibold text-white"
+              onClick={handleAccept}
+              type="button"
+              disabled={loading}
+            >
+              {loading ? "Working..." : "Accept invitation"}
+            </button>
+          )
+        )}
+      </div>
+    </div>
+  );
+}
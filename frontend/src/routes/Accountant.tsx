@@ -0,0 +1,278 @@
+import { useEffect, useState } from "react";
+import { useNavigate, useSearchParams } from "react-router-dom";
+import { api } from "../lib/api";
+import type { AccountantGrant, AccountantReport, Expense, Invoice } from "../lib/api";
+import Pagination from "../components/Pagination";
+import { currencySymbol } from "../lib/currency";
+
+const PER_PAGE = 25;
+const TOKEN_KEY = "accountant_token";
+
+function formatTotal(currency: string, total: number) {
+  return `${currencySymbol(currency)} ${total.toFixed(2)}`;
+}
+
+export default function Accountant() {
+  const navigate = useNavigate();
+  const [searchParams] = useSearchParams();
+  const linkToken = searchParams.get("token");
+  const token = linkToken || sessionStorage.getItem(TOKEN_KEY) || "";
+  const [grant, setGrant] = useState<AccountantGrant | null>(null);
+  const [report, setReport] = useState<AccountantReport | null>(null);
+  const [invoices, setInvoices] = useState<Invoice[]>([]);
+  const [invoiceTotal, setInvoiceTotal] = useState(0);
+  const [invoicePage, setInvoicePage] = useState(1);
+  const [expenses, setExpenses] = useState<Expense[]>([]);
+  const [expenseTotal, setExpenseTotal] = useState(0);
+  const [expensePage, setExpensePage] = useState(1);
+  const [status, setStatus] = useState<string | null>(token ? null : "This link has no token.");
+
+  // Keep the token for reloads but out of the address bar and browser history.
+  useEffect(() => {
+    if (linkToken) {
+      sessionStorage.setItem(TOKEN_KEY, linkToken);
+      navigate("/accountant", { replace: true });
+    }
+  }, [linkToken, navigate]);
+
+  useEffect(() => {
+    if (token) {
+      void loadOverview();
+    }
+  }, [token]);
+
+  useEffect(() => {
+    if (grant) {
+      void loadInvoices();
+    }
+  }, [grant, invoicePage]);
+
+  useEffect(() => {
+    if (grant) {
+      void loadExpenses();
+    }
+  }, [grant, expensePage]);
+
+  async function loadOverview() {
+    const grantResult = await api.accountantGrant(token);
+    if (!grantResult.ok) {
+      sessionStorage.removeItem(TOKEN_KEY);
+      setStatus(grantResult.error);
+      return;
+    }
+    setGrant(grantResult.data);
+    const reportResult = await api.accountantReport(token);
+    if (reportResult.ok) {
+      setReport(reportResult.data);
+    } else {
+      setStatus(reportResult.error);
+    }
+  }
+
+  async function loadInvoices() {
+    const result = await api.accountantInvoices(token, { page: invoicePage, per_page: PER_PAGE });
+    if (!result.ok) {
+      setStatus(result.error);
+      return;
+    }
+    setInvoices(result.data.items);
+    setInvoiceTotal(result.data.total);
+  }
+
+  async function loadExpenses() {
+    const result = await api.accountantExpenses(token, { page: expensePage, per_page: PER_PAGE });
+    if (!result.ok) {
+      setStatus(result.error);
+      return;
+    }
+    setExpenses(result.data.items);
+    setExpenseTotal(result.data.total);
+  }
+
+  async function handleInvoicePdf(invoice: Invoice) {
+    const result = await api.accountantInvoicePdf(token, invoice.id);
+    if (!result.ok) {
+      setStatus(result.error);
+      return;
+    }
+    const url = window.URL.createObjectURL(result.data);
+    const link = document.createElement("a");
+    link.href = url;
+    link.download = `invoice-${invoice.invoice_number}.pdf`;
+    document.body.appendChild(link);
+    link.click();
+    link.remove();
+    window.URL.revokeObjectURL(url);
+  }
+
+  async function handleReceipt(expense: Expense) {
+    if (!expense.receipt_id) {
+      return;
+    }
+    const result = await api.accountantReceipt(token, expense.receipt_id);
+    if (!result.ok) {
+      setStatus(result.error);
+      return;
+    }
+    window.open(result.data.download_url, "_blank", "noopener");
+  }
+
+  return (
+    <div className="min-h-screen bg-gradient-to-br from-cloud via-white to-[#E6F6F5] text-ink">
+      <main className="mx-auto flex w-full max-w-6xl flex-col gap-8 px-6 py-10">
+        <header className="space-y-2">
+          <p className="text-sm font-semibold uppercase tracking-[0.2em] text-ember">
+            Accountant access
+          </p>
+          <h1 className="font-display text-3xl font-semibold text-ink">
+            {grant ? grant.workspace : "Shared bookkeeping"}
+          </h1>
+          {grant && (
+            <p className="text-sm text-slate">
+              {grant.period_start} → {grant.period_end} · shared with {grant.email} · link
+              expires {new Date(grant.expires_at).toLocaleDateString()}
+            </p>
+          )}
+        </header>
+
+        {status && (
+          <div className="rounded-2xl border border-ember/30 bg-white/70 px-6 py-4 text-sm text-slate shadow-glow">
+            {status}
+          </div>
+        )}
+
+        {report && (
+          <section className="grid gap-6 md:grid-cols-2">
+            <div className="rounded-3xl border border-white/70 bg-white/80 p-6 shadow-lift">
+              <p className="text-xs uppercase tracking-[0.2em] text-haze">Invoiced</p>
+              {report.invoices.length === 0 && <p className="mt-3 text-sm text-slate">None.</p>}
+              {report.invoices.map((row) => (
+                <p key={row.currency} className="mt-3 text-2xl font-semibold text-ink">
+                  {formatTotal(row.currency, row.total)}
+                  <span className="ml-2 text-sm font-normal text-slate">{row.count} invoices</span>
+                </p>
+              ))}
+            </div>
+            <div className="rounded-3xl border border-white/70 bg-white/80 p-6 shadow-lift">
+              <p className="text-xs uppercase tracking-[0.2em] text-haze">Expenses</p>
+              {report.expenses.length === 0 && <p className="mt-3 text-sm text-slate">None.</p>}
+              {report.expenses.map((row) => (
+                <p key={row.currency} className="mt-3 text-2xl font-semibold text-ink">
+                  {formatTotal(row.currency, row.total)}
+                  <span className="ml-2 text-sm font-normal text-slate">{row.count} expenses</span>
+                </p>
+              ))}
+              <ul className="mt-4 space-y-1 text-sm text-slate">
+                {report.expenses_by_category.map((row) => (
+                  <li key={`${row.category ?? "none"}-${row.currency}`}>
+                    {row.category ?? "Uncategorised"}
+                    {row.skr03_account && ` (SKR03 ${row.skr03_account})`}:{" "}
+                    {formatTotal(row.currency, row.total)}
+                  </li>
+                ))}
+              </ul>
+            </div>
+          </section>
+        )}
+
+        {grant && (
+          <section className="rounded-3xl border border-white/70 bg-white/80 p-6 shadow-lift">
+            <div className="flex items-center justify-between">
+              <h2 className="font-display text-2xl">Invoices</h2>
+              <span className="text-sm text-slate">{invoiceTotal} total</span>
+            </div>
+            <table className="mt-4 w-full text-left text-sm">
+              <thead className="text-xs uppercase tracking-[0.2em] text-haze">
+                <tr>
+                  <th className="py-2">Number</th>
+                  <th className="py-2">Date</th>
+                  <th className="py-2">Client</th>
+                  <th className="py-2">Status</th>
+                  <th className="py-2 text-right">Total</th>
+                  <th className="py-2" />
+                </tr>
+              </thead>
+              <tbody>
+                {invoices.map((invoice) => (
+                  <tr key={invoice.id} className="border-t border-ink/5">
+                    <td className="py-2">{invoice.invoice_number}</td>
+                    <td className="py-2">{invoice.date}</td>
+                    <td className="py-2">{invoice.client_name}</td>
+                    <td className="py-2">{invoice.status}</td>
+                    <td className="py-2 text-right">
+                      {formatTotal(invoice.currency, invoice.total_amount)}
+                    </td>
+                    <td className="py-2 text-right">
+                      <button
+                        className="rounded-full border border-ink/10 px-3 py-1 text-xs font-semibold"
+                        onClick={() => handleInvoicePdf(invoice)}
+                        type="button"
+                      >
+                        PDF
+                      </button>
+                    </td>
+                  </tr>
+                ))}
+              </tbody>
+            </table>
+            <Pagination
+              page={invoicePage}
+              perPage={PER_PAGE}
+              total={invoiceTotal}
+              onChange={setInvoicePage}
+            />
+          </section>
+        )}
+
+        {grant && (
+          <section className="rounded-3xl border border-white/70 bg-white/80 p-6 shadow-lift">
+            <div className="flex items-center justify-between">
+              <h2 className="font-display text-2xl">Expenses</h2>
+              <span className="text-sm text-slate">{expenseTotal} total</span>
+            </div>
+            <table className="mt-4 w-full text-left text-sm">
+              <thead className="text-xs uppercase tracking-[0.2em] text-haze">
+                <tr>
+                  <th className="py-2">Date</th>
+                  <th className="py-2">Vendor</th>
+                  <th className="py-2">Category</th>
+                  <th className="py-2 text-right">Amount</th>
+                  <th className="py-2" />
+                </tr>
+              </thead>
+              <tbody>
+                {expenses.map((expense) => (
+                  <tr key={expense.id} className="border-t border-ink/5">
+                    <td className="py-2">{expense.date}</td>
+                    <td className="py-2">{expense.vendor}</td>
+                    <td className="py-2">{expense.category ?? "—"}</td>
+                    <td className="py-2 text-right">
+                      {formatTotal(expense.currency, expense.amount)}
+                    </td>
+                    <td className="py-2 text-right">
+                      {expense.receipt_id && (
+                        <button
+                          className="rounded-full border border-ink/10 px-3 py-1 text-xs font-semibold"
+                          onClick={() => handleReceipt(expense)}
+                          type="button"
+                        >
+                          Receipt
+                        </button>
+                      )}
+                    </td>
+                  </tr>
+                ))}
+              </tbody>
+            </table>
+            <Pagination
+              page={expensePage}
+              perPage={PER_PAGE}
+              total={expenseTotal}
+              onChange={setExpensePage}
+            />
+          </section>
+        )}
+      </main>
+    </div>
+  );
+}
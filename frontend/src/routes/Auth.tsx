@@ -1,12 +1,39 @@
 import { useState } from "react";
-import { useNavigate } from "react-router-dom";
+import { useNavigate, useSearchParams } from "react-router-dom";
 import { api } from "../lib/api";
 
+/** Messages for the `sso_error` codes single sign-on redirects back with. */
+const SSO_ERRORS: Record<string, string> = {
+  unauthorized: "Single sign-on failed or was cancelled. Please try again.",
+  conflict:
+    "An account with this email exists but is not verified. Log in with its password and verify the email first.",
+  forbidden: "This account has been deleted.",
+  not_found: "This sign-in provider is not configured.",
+  upstream_failed: "The sign-in provider could not be reached. Please try again.",
+};
+
+/** Challenge handed over by single sign-on for accounts with 2FA (`#two_factor=...`). */
+function ssoChallenge() {
+  const match = window.location.hash.match(/^#two_factor=([0-9a-f]+)$/);
+  return match ? match[1] : null;
+}
+
 export default function Auth() {
   const navigate = useNavigate();
+  const [searchParams] = useSearchParams();
+  // Pages that need a session (e.g. accepting an invitation) send people here with ?next=.
+  const next = searchParams.get("next");
+  const destination =
+    next && next !== "/" && next.startsWith("/") && !next.startsWith("//") ? next : "/app";
   const [activeTab, setActiveTab] = useState<"login" | "register">("login");
   const [loading, setLoading] = useState(false);
-  const [status, setStatus] = useState<string | null>(null);
+  const ssoError = searchParams.get("sso_error");
+  const [status, setStatus] = useState<string | null>(() => {
+    if (ssoChallenge()) {
+      return "Enter the code from your authenticator app or a recovery code.";
+    }
+    return ssoError ? SSO_ERRORS[ssoError] || "Single sign-on failed. Please try again." : null;
+  });
 
   const [registerForm, setRegisterForm] = useState({
     email: "",
@@ -14,6 +41,8 @@ export default function Auth() {
     address: "",
   });
   const [loginForm, setLoginForm] = useState({ email: "", password: "" });
+  const [challenge, setChallenge] = useState<string | null>(ssoChallenge);
+  const [twoFactorCode, setTwoFactorCode] = useState("");
 
   async function handleRegister() {
     setLoading(true);
@@ -29,7 +58,7 @@ export default function Auth() {
       return;
     }
     setStatus("Welcome aboard. Session started.");
-    navigate("/app", { replace: true });
+    navigate(destination, { replace: true });
   }
 
   async function handleLogin() {
@@ -41,8 +70,29 @@ export default function Auth() {
       setStatus(result.error);
       return;
     }
+    if ("challenge" in result.data) {
+      setChallenge(result.data.challenge);
+      setStatus("Enter the code from your authenticator app or a recovery code.");
+      return;
+    }
+    setStatus("Logged in.");
+    navigate(destination, { replace: true });
+  }
+
+  async function handleTwoFactor() {
+    if (!challenge) {
+      return;
+    }
+    setLoading(true);
+    setStatus(null);
+    const result = await api.completeTwoFactorLogin({ challenge, code: twoFactorCode });
+    setLoading(false);
+    if (!result.ok) {
+      setStatus(result.error);
+      return;
+    }
     setStatus("Logged in.");
-    navigate("/app", { replace: true });
+    navigate(destination, { replace: true });
   }
 
   return (
@@ -127,13 +177,27 @@ export default function Auth() {
                       type="password"
                     />
                   </div>
+                  {challenge && (
+                    <div>
+                      <label className="text-xs uppercase tracking-[0.2em] text-haze">
+                        Authentication code
+                      </label>
+                      <input
+                        className="mt-2 w-full rounded-xl border border-ink/10 bg-white/80 px-4 py-3"
+                        value={twoFactorCode}
+                        onChange={(event) => setTwoFactorCode(event.target.value)}
+                        placeholder="123456"
+                        autoComplete="one-time-code"
+                      />
+                    </div>
+                  )}
                   <button
                     className="w-full rounded-xl bg-ink px-4 py-3 font-semibold text-white shadow-glow transition hover:translate-y-[-1px]"
-                    onClick={handleLogin}
+                    onClick={challenge ? handleTwoFactor : handleLogin}
                     type="button"
                     disabled={loading}
                   >
-                    {loading ? "Working..." : "Login"}
+                    {loading ? "Working..." : challenge ? "Verify" : "Login"}
                   </button>
                 </div>
               ) : (
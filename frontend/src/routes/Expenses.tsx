@@ -1,18 +1,28 @@
 import { useEffect, useMemo, useState } from "react";
 import { useNavigate } from "react-router-dom";
 import { api } from "../lib/api";
-import type { Expense, User } from "../lib/api";
+import type { Expense, ExpenseCategory, User } from "../lib/api";
 import DashboardHeader from "../components/DashboardHeader";
 import DashboardNav from "../components/DashboardNav";
+import Pagination from "../components/Pagination";
 import { currencySymbol } from "../lib/currency";
+import { useDebounced } from "../lib/useDebounced";
+
+const PER_PAGE = 25;
 
 export default function Expenses() {
   const navigate = useNavigate();
   const [user, setUser] = useState<User | null>(null);
   const [expenses, setExpenses] = useState<Expense[]>([]);
+  const [total, setTotal] = useState(0);
+  const [page, setPage] = useState(1);
+  const [search, setSearch] = useState("");
+  const query = useDebounced(search.trim());
+  const [categories, setCategories] = useState<ExpenseCategory[]>([]);
   const [status, setStatus] = useState<string | null>(null);
   const [loading, setLoading] = useState(false);
   const [uploading, setUploading] = useState(false);
+  const [extracting, setExtracting] = useState(false);
   const [editingId, setEditingId] = useState<string | null>(null);
   const [form, setForm] = useState({
     vendor: "",
@@ -21,6 +31,7 @@ export default function Expenses() {
     currency: "EUR",
     date: new Date().toISOString().slice(0, 10),
     category: "",
+    receipt_id: "",
     receipt_url: "",
   });
 
@@ -28,6 +39,16 @@ export default function Expenses() {
     void loadSession();
   }, []);
 
+  useEffect(() => {
+    if (user) {
+      void loadExpenses();
+    }
+  }, [user, page, query]);
+
+  useEffect(() => {
+    setPage(1);
+  }, [query]);
+
   async function loadSession() {
     const result = await api.me();
     if (!result.ok) {
@@ -35,19 +56,27 @@ export default function Expenses() {
       return;
     }
     setUser(result.data);
-    void loadExpenses();
+    void loadCategories();
   }
 
   async function loadExpenses() {
-    const result = await api.listExpenses();
+    const result = await api.listExpenses({ page, per_page: PER_PAGE, q: query });
     if (result.ok) {
-      setExpenses(result.data);
+      setExpenses(result.data.items);
+      setTotal(result.data.total);
     } else {
       setStatus(result.error);
     }
   }
 
-  const totalExpenses = useMemo(
+  async function loadCategories() {
+    const result = await api.listExpenseCategories();
+    if (result.ok) {
+      setCategories(result.data);
+    }
+  }
+
+  const pageTotal = useMemo(
     () => expenses.reduce((sum, exp) => sum + exp.amount, 0),
     [expenses]
   );
@@ -61,7 +90,9 @@ export default function Expenses() {
       amount: Number(form.amount || 0),
       currency: form.currency,
       date: form.date,
-      category: form.category || null,
+      // An empty string clears the category when editing.
+      category: form.category,
+      receipt_id: form.receipt_id || null,
       receipt_url: form.receipt_url || null,
     };
     const result = editingId
@@ -80,6 +111,7 @@ export default function Expenses() {
       currency: "EUR",
       date: new Date().toISOString().slice(0, 10),
       category: "",
+      receipt_id: "",
       receipt_url: "",
     });
     setEditingId(null);
@@ -95,6 +127,7 @@ export default function Expenses() {
       currency: expense.currency,
       date: expense.date,
       category: expense.category || "",
+      receipt_id: expense.receipt_id || "",
       receipt_url: expense.receipt_url || "",
     });
   }
@@ -116,32 +149,44 @@ export default function Expenses() {
   }
 
   async function handleReceiptUpload(file: File) {
-    if (file.size > 5 * 1024 * 1024) {
-      setStatus("Receipt must be 5MB or less.");
+    if (file.size > 10 * 1024 * 1024) {
+      setStatus("Receipt must be 10MB or less.");
       return;
     }
     setUploading(true);
     setStatus(null);
-    const upload = await api.createReceiptUpload({
-      filename: file.name,
-      content_type: file.type || "application/octet-stream",
-    });
+    const upload = await api.uploadReceipt(file);
+    setUploading(false);
     if (!upload.ok) {
-      setUploading(false);
       setStatus(upload.error);
       return;
     }
-    const res = await fetch(upload.data.upload_url, {
-      method: "PUT",
-      headers: { "Content-Type": file.type || "application/octet-stream" },
-      body: file,
-    });
-    setUploading(false);
-    if (!res.ok) {
-      setStatus("Upload failed.");
+    setForm((prev) => ({ ...prev, receipt_id: upload.data.id }));
+
+    setExtracting(true);
+    const extraction = await api.extractReceipt(upload.data.id);
+    setExtracting(false);
+    if (!extraction.ok) {
+      return;
+    }
+    const { draft, fields } = extraction.data;
+    // Only fill what the user has not typed yet.
+    setForm((prev) => ({
+      ...prev,
+      vendor: prev.vendor || draft.vendor,
+      amount: prev.amount || (draft.amount != null ? String(draft.amount) : ""),
+      currency: fields.currency ? draft.currency : prev.currency,
+      date: fields.date ? draft.date : prev.date,
+    }));
+  }
+
+  async function openReceipt(id: string) {
+    const result = await api.getReceipt(id);
+    if (!result.ok) {
+      setStatus(result.error);
       return;
     }
-    setForm((prev) => ({ ...prev, receipt_url: upload.data.receipt_url }));
+    window.open(result.data.download_url, "_blank", "noreferrer");
   }
 
   return (
@@ -168,7 +213,7 @@ export default function Expenses() {
                 <div className="flex items-center justify-between">
                   <h3 className="font-display text-xl">Add expense</h3>
                   <span className="text-xs text-haze">
-                    Total {currencySymbol("EUR")} {totalExpenses.toFixed(2)}
+                    This page {currencySymbol("EUR")} {pageTotal.toFixed(2)}
                   </span>
                 </div>
                 <div className="mt-4 grid gap-3 md:grid-cols-2">
@@ -184,13 +229,11 @@ export default function Expenses() {
                     onChange={(event) => setForm({ ...form, category: event.target.value })}
                   >
                     <option value="">Select category</option>
-                    <option value="Travel">Travel</option>
-                    <option value="Software">Software</option>
-                    <option value="Hardware">Hardware</option>
-                    <option value="Office">Office</option>
-                    <option value="Marketing">Marketing</option>
-                    <option value="Services">Services</option>
-                    <option value="Other">Other</option>
+                    {categories.map((category) => (
+                      <option key={category.id} value={category.name}>
+                        {category.name}
+                      </option>
+                    ))}
                   </select>
                   <textarea
                     className="md:col-span-2 rounded-xl border border-ink/10 bg-white/80 px-4 py-3"
@@ -226,7 +269,7 @@ export default function Expenses() {
                     <div className="mt-3 flex flex-wrap items-center gap-3">
                       <input
                         type="file"
-                        accept="image/*,application/pdf"
+                        accept="application/pdf,image/png,image/jpeg,image/webp"
                         onChange={(event) => {
                           const file = event.target.files?.[0];
                           if (file) {
@@ -235,7 +278,16 @@ export default function Expenses() {
                         }}
                       />
                       {uploading && <span className="text-xs text-slate">Uploading…</span>}
-                      {form.receipt_url && (
+                      {extracting && <span className="text-xs text-slate">Reading receipt…</span>}
+                      {form.receipt_id ? (
+                        <button
+                          className="text-xs font-semibold text-ember"
+                          onClick={() => void openReceipt(form.receipt_id)}
+                          type="button"
+                        >
+                          View receipt
+                        </button>
+                      ) : form.receipt_url && (
                         <a
                           className="text-xs font-semibold text-ember"
                           href={form.receipt_url}
@@ -262,8 +314,14 @@ export default function Expenses() {
             <div className="rounded-3xl border border-white/70 bg-white/80 p-6 shadow-lift">
               <div className="flex items-center justify-between">
                 <h3 className="font-display text-xl">Expenses</h3>
-                <span className="text-xs text-haze">{expenses.length} total</span>
+                <span className="text-xs text-haze">{total} total</span>
               </div>
+              <input
+                className="mt-4 w-full rounded-xl border border-ink/10 bg-white/80 px-4 py-2 text-sm"
+                placeholder="Search vendor or description"
+                value={search}
+                onChange={(event) => setSearch(event.target.value)}
+              />
               <div className="mt-4 overflow-x-auto">
                 <table className="w-full text-left text-sm">
                   <thead className="text-xs uppercase tracking-[0.2em] text-haze">
@@ -294,7 +352,15 @@ export default function Expenses() {
                         <td className="py-3">{expense.date}</td>
                         <td className="py-3 text-right">
                           <div className="flex items-center justify-end gap-2">
-                            {expense.receipt_url && (
+                            {expense.receipt_id ? (
+                              <button
+                                className="rounded-lg border border-ink/10 px-3 py-1 text-xs font-semibold"
+                                onClick={() => void openReceipt(expense.receipt_id as string)}
+                                type="button"
+                              >
+                                Receipt
+                              </button>
+                            ) : expense.receipt_url && (
                               <a
                                 className="rounded-lg border border-ink/10 px-3 py-1 text-xs font-semibold"
                                 href={expense.receipt_url}
@@ -325,13 +391,14 @@ export default function Expenses() {
                     {expenses.length === 0 && (
                       <tr>
                         <td className="py-4 text-sm text-haze" colSpan={5}>
-                          No expenses yet.
+                          {query ? "No matching expenses." : "No expenses yet."}
                         </td>
                       </tr>
                     )}
                   </tbody>
                 </table>
               </div>
+              <Pagination page={page} perPage={PER_PAGE} total={total} onChange={setPage} />
             </div>
           </section>
         </main>
@@ -7,12 +7,32 @@ import DashboardNav from "../components/DashboardNav";
 import InvoiceForm from "../components/InvoiceForm";
 import InvoiceTable from "../components/InvoiceTable";
 import InvoicePreview from "../components/InvoicePreview";
+import Pagination from "../components/Pagination";
+import { useDebounced } from "../lib/useDebounced";
+
+const PER_PAGE = 25;
+/** Clients and templates are offered in dropdowns; the API caps a page at 200. */
+const OPTIONS_PER_PAGE = 200;
+
+/** Sort choices of the invoice list, mapped to the API's `sort` and `order`. */
+const SORTS: Record<string, { sort: string; order: "asc" | "desc" }> = {
+  date_desc: { sort: "date", order: "desc" },
+  date_asc: { sort: "date", order: "asc" },
+  amount_desc: { sort: "total_amount", order: "desc" },
+  amount_asc: { sort: "total_amount", order: "asc" },
+  client_asc: { sort: "client_name", order: "asc" },
+  client_desc: { sort: "client_name", order: "desc" },
+};
 
 export default function Invoices() {
   const navigate = useNavigate();
   const [user, setUser] = useState<User | null>(null);
   const [companies, setCompanies] = useState<Company[]>([]);
   const [invoices, setInvoices] = useState<Invoice[]>([]);
+  const [invoiceTotalCount, setInvoiceTotalCount] = useState(0);
+  const [page, setPage] = useState(1);
+  const [search, setSearch] = useState("");
+  const query = useDebounced(search.trim());
   const [templates, setTemplates] = useState<InvoiceTemplate[]>([]);
   const [status, setStatus] = useState<string | null>(null);
   const [loading, setLoading] = useState(false);
@@ -64,47 +84,24 @@ export default function Invoices() {
     [invoiceForm.items]
   );
 
-  const availableYears = useMemo(() => {
-    const years = new Set<number>();
-    invoices.forEach((invoice) => years.add(new Date(invoice.date).getFullYear()));
-    years.add(currentYear);
-    return Array.from(years).sort((a, b) => b - a);
-  }, [invoices, currentYear]);
+  const availableYears = useMemo(
+    () => Array.from({ length: 10 }, (_, index) => currentYear - index),
+    [currentYear]
+  );
 
-  const filteredInvoices = useMemo(() => {
-    const year = Number(filterYear);
-    return invoices.filter((invoice) => new Date(invoice.date).getFullYear() === year);
-  }, [invoices, filterYear]);
+  useEffect(() => {
+    void loadSession();
+  }, []);
 
-  const sortedInvoices = useMemo(() => {
-    const data = [...filteredInvoices];
-    switch (sortKey) {
-      case "date_asc":
-        data.sort((a, b) => new Date(a.date).getTime() - new Date(b.date).getTime());
-        break;
-      case "amount_desc":
-        data.sort((a, b) => (b.total_amount ?? b.amount) - (a.total_amount ?? a.amount));
-        break;
-      case "amount_asc":
-        data.sort((a, b) => (a.total_amount ?? a.amount) - (b.total_amount ?? b.amount));
-        break;
-      case "client_asc":
-        data.sort((a, b) => a.client_name.localeCompare(b.client_name));
-        break;
-      case "client_desc":
-        data.sort((a, b) => b.client_name.localeCompare(a.client_name));
-        break;
-      case "date_desc":
-      default:
-        data.sort((a, b) => new Date(b.date).getTime() - new Date(a.date).getTime());
-        break;
+  useEffect(() => {
+    if (user) {
+      void loadInvoices();
     }
-    return data;
-  }, [filteredInvoices, sortKey]);
+  }, [user, page, query, filterYear, sortKey]);
 
   useEffect(() => {
-    void loadSession();
-  }, []);
+    setPage(1);
+  }, [query, filterYear, sortKey]);
 
   async function loadSession() {
     const result = await api.me();
@@ -114,28 +111,37 @@ export default function Invoices() {
     }
     setUser(result.data);
     void loadCompanies();
-    void loadInvoices();
     void loadTemplates();
   }
 
   async function loadCompanies() {
-    const result = await api.listCompanies();
+    const result = await api.listCompanies({ per_page: OPTIONS_PER_PAGE });
     if (result.ok) {
-      setCompanies(result.data);
+      setCompanies(result.data.items);
     }
   }
 
   async function loadInvoices() {
-    const result = await api.listInvoices();
+    const result = await api.listInvoices({
+      page,
+      per_page: PER_PAGE,
+      q: query,
+      date_from: `${filterYear}-01-01`,
+      date_to: `${filterYear}-12-31`,
+      ...(SORTS[sortKey] ?? SORTS.date_desc),
+    });
     if (result.ok) {
-      setInvoices(result.data);
+      setInvoices(result.data.items);
+      setInvoiceTotalCount(result.data.total);
+    } else {
+      setStatus(result.error);
     }
   }
 
   async function loadTemplates() {
-    const result = await api.listTemplates();
+    const result = await api.listTemplates({ per_page: OPTIONS_PER_PAGE });
     if (result.ok) {
-      setTemplates(result.data);
+      setTemplates(result.data.items);
     }
   }
 
@@ -367,7 +373,13 @@ export default function Invoices() {
 
               <div className="flex flex-wrap items-center justify-between gap-3">
                 <h3 className="font-display text-xl">Invoice list</h3>
-                <div className="flex items-center gap-3 text-sm">
+                <div className="flex flex-wrap items-center gap-3 text-sm">
+                  <input
+                    className="rounded-xl border border-ink/10 bg-white/80 px-3 py-2"
+                    placeholder="Search number, client or item"
+                    value={search}
+                    onChange={(event) => setSearch(event.target.value)}
+                  />
                   <label className="text-xs uppercase tracking-[0.2em] text-haze">Year</label>
                   <select
                     className="rounded-xl border border-ink/10 bg-white/80 px-3 py-2"
@@ -396,12 +408,20 @@ export default function Invoices() {
                 </div>
               </div>
               <InvoiceTable
-                invoices={sortedInvoices}
+                invoices={invoices}
+                total={invoiceTotalCount}
                 onView={setInvoiceResult}
                 onEdit={handleInvoiceEdit}
                 onDuplicate={handleInvoiceDuplicate}
                 onDownload={handleInvoiceDownload}
-              />
+              >
+                <Pagination
+                  page={page}
+                  perPage={PER_PAGE}
+                  total={invoiceTotalCount}
+                  onChange={setPage}
+                />
+              </InvoiceTable>
             <InvoicePreview
               invoice={invoiceResult}
               loading={loading}
@@ -6,6 +6,8 @@ import DashboardHeader from "../components/DashboardHeader";
 import DashboardNav from "../components/DashboardNav";
 import { currencySymbol } from "../lib/currency";
 
+const ISO_DATE = /^\d{4}-\d{2}-\d{2}$/;
+
 const MONTHS = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
 
 export default function Reports() {
@@ -21,10 +23,29 @@ export default function Reports() {
   });
   const [summaryYear, setSummaryYear] = useState(String(currentYear));
 
+  // Only the dates the report actually shows are fetched: the selected range,
+  // the summary year and the current month.
+  const reportWindow = useMemo(() => {
+    const today = new Date().toISOString().slice(0, 10);
+    const dates = [range.start, range.end, `${summaryYear}-01-01`, `${summaryYear}-12-31`, today]
+      .filter((value) => ISO_DATE.test(value))
+      .sort();
+    return {
+      date_from: `${dates[0].slice(0, 7)}-01`,
+      date_to: dates[dates.length - 1],
+    };
+  }, [range, summaryYear]);
+
   useEffect(() => {
     void loadSession();
   }, []);
 
+  useEffect(() => {
+    if (user) {
+      void loadReport(reportWindow);
+    }
+  }, [user, reportWindow.date_from, reportWindow.date_to]);
+
   async function loadSession() {
     const result = await api.me();
     if (!result.ok) {
@@ -32,13 +53,16 @@ export default function Reports() {
       return;
     }
     setUser(result.data);
-    const invoiceResult = await api.listInvoices();
+  }
+
+  async function loadReport(dates: { date_from: string; date_to: string }) {
+    const invoiceResult = await api.reportInvoices(dates);
     if (invoiceResult.ok) {
       setInvoices(invoiceResult.data);
     } else {
       setStatus(invoiceResult.error);
     }
-    const expenseResult = await api.listExpenses();
+    const expenseResult = await api.reportExpenses(dates);
     if (expenseResult.ok) {
       setExpenses(expenseResult.data);
     } else {
@@ -79,6 +103,7 @@ export default function Reports() {
     return {
       totalRevenue,
       totalExpenses,
+      invoiceCount: rangedInvoices.length,
       avgInvoice,
       monthRevenue,
       monthExpenses,
@@ -111,13 +136,10 @@ export default function Reports() {
     return series;
   }, [invoices, range]);
 
-  const availableYears = useMemo(() => {
-    const years = new Set<number>();
-    invoices.forEach((invoice) => years.add(new Date(invoice.date).getFullYear()));
-    expenses.forEach((expense) => years.add(new Date(expense.date).getFullYear()));
-    years.add(currentYear);
-    return Array.from(years).sort((a, b) => b - a);
-  }, [invoices, expenses, currentYear]);
+  const availableYears = useMemo(
+    () => Array.from({ length: 10 }, (_, index) => currentYear - index),
+    [currentYear],
+  );
 
   const yearlyTotals = useMemo(() => {
     const year = Number(summaryYear);
@@ -184,7 +206,7 @@ export default function Reports() {
               <p className="mt-3 text-2xl font-semibold text-ink">
                 {currencySymbol("EUR")} {totals.totalRevenue.toFixed(2)}
               </p>
-              <p className="mt-2 text-sm text-slate">All invoices to date.</p>
+              <p className="mt-2 text-sm text-slate">Invoices in the selected range.</p>
             </div>
             <div className="rounded-3xl border border-white/70 bg-white/80 p-6 shadow-lift">
               <p className="text-xs uppercase tracking-[0.2em] text-haze">Total expenses</p>
@@ -195,7 +217,7 @@ export default function Reports() {
             </div>
             <div className="rounded-3xl border border-white/70 bg-white/80 p-6 shadow-lift">
               <p className="text-xs uppercase tracking-[0.2em] text-haze">Invoices</p>
-              <p className="mt-3 text-2xl font-semibold text-ink">{invoices.length}</p>
+              <p className="mt-3 text-2xl font-semibold text-ink">{totals.invoiceCount}</p>
               <p className="mt-2 text-sm text-slate">Average EUR {totals.avgInvoice.toFixed(2)}.</p>
             </div>
             <div className="rounded-3xl border border-white/70 bg-white/80 p-6 shadow-lift">
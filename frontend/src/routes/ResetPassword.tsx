@@ -0,0 +1,80 @@
+import { useState } from "react";
+import { Link, useSearchParams } from "react-router-dom";
+import { api } from "../lib/api";
+
+export default function ResetPassword() {
+  const [searchParams] = useSearchParams();
+  const token = searchParams.get("token") || "";
+  const [password, setPassword] = useState("");
+  const [confirmation, setConfirmation] = useState("");
+  const [loading, setLoading] = useState(false);
+  const [done, setDone] = useState(false);
+  const [status, setStatus] = useState<string | null>(token ? null : "This link has no token.");
+
+  async function handleSubmit() {
+    if (password !== confirmation) {
+      setStatus("The passwords do not match.");
+      return;
+    }
+    setLoading(true);
+    setStatus(null);
+    const result = await api.resetPassword({ token, password });
+    setLoading(false);
+    if (!result.ok) {
+      setStatus(result.error);
+      return;
+    }
+    setDone(true);
+    setStatus("Password changed. Every session was signed out, so log in with the new password.");
+  }
+
+  return (
+    <div className="flex min-h-screen items-center justify-center bg-cloud text-ink">
+      <div className="w-full max-w-md rounded-3xl border border-ink/10 bg-white/80 p-10 shadow-lift">
+        <p className="text-xs uppercase tracking-[0.2em] text-haze">Password reset</p>
+        <h1 className="mt-4 font-display text-3xl">Choose a new password</h1>
+        {status && <p className="mt-4 text-sm text-slate">{status}</p>}
+        {done || !token ? (
+          <Link
+            to="/"
+            className="mt-6 inline-flex rounded-xl bg-ink px-4 py-2 text-sm font-semibold text-white"
+          >
+            Back to login
+          </Link>
+        ) : (
+          <div className="mt-6 space-y-5">
+            <div>
+              <label className="text-xs uppercase tracking-[0.2em] text-haze">New password</label>
+              <input
+                className="mt-2 w-full rounded-xl border border-ink/10 bg-white/80 px-4 py-3"
+                value={password}
+                onChange={(event) => setPassword(event.target.value)}
+                placeholder="At least 8 characters"
+                type="password"
+                autoComplete="new-password"
+              />
+            </div>
+            <div>
+              <label className="text-xs uppercase tracking-[0.2em] text-haze">Repeat password</label>
+              <input
+                className="mt-2 w-full rounded-xl border border-ink/10 bg-white/80 px-4 py-3"
+                value={confirmation}
+                onChange={(event) => setConfirmation(event.target.value)}
+                type="password"
+                autoComplete="new-password"
+              />
+            </div>
+            <button
+              className="w-full rounded-xl bg-ink px-4 py-3 font-semibold text-white shadow-glow transition hover:translate-y-[-1px]"
+              onClick={handleSubmit}
+              type="button"
+              disabled={loading || !password}
+            >
+              {loading ? "Working..." : "Set password"}
+            </button>
+          </div>
+        )}
+      </div>
+    </div>
+  );
+}
@@ -3,26 +3,32 @@ import { Link, useNavigate } from "react-router-dom";
 import { api } from "../lib/api";
 import type { InvoiceTemplate } from "../lib/api";
 import logo from "../assets/logo.svg";
+import Pagination from "../components/Pagination";
+
+const PER_PAGE = 20;
 
 export default function Templates() {
   const navigate = useNavigate();
   const [templates, setTemplates] = useState<InvoiceTemplate[]>([]);
+  const [total, setTotal] = useState(0);
+  const [page, setPage] = useState(1);
   const [loading, setLoading] = useState(false);
   const [status, setStatus] = useState<string | null>(null);
   const [editingId, setEditingId] = useState<string | null>(null);
   const [form, setForm] = useState({ name: "", html: "" });
 
   useEffect(() => {
-    void loadTemplates();
-  }, []);
+    void loadTemplates(page);
+  }, [page]);
 
-  async function loadTemplates() {
-    const result = await api.listTemplates();
+  async function loadTemplates(target: number) {
+    const result = await api.listTemplates({ page: target, per_page: PER_PAGE });
     if (!result.ok) {
       navigate("/", { replace: true });
       return;
     }
-    setTemplates(result.data);
+    setTemplates(result.data.items);
+    setTotal(result.data.total);
   }
 
   async function handleSave() {
@@ -39,7 +45,7 @@ export default function Templates() {
     if (editingId) {
       setTemplates((prev) => prev.map((t) => (t.id === result.data.id ? result.data : t)));
     } else {
-      setTemplates((prev) => [result.data, ...prev]);
+      void loadTemplates(page);
     }
     setForm({ name: "", html: "" });
     setEditingId(null);
@@ -55,7 +61,7 @@ export default function Templates() {
       setStatus(result.error);
       return;
     }
-    setTemplates((prev) => prev.filter((t) => t.id !== id));
+    void loadTemplates(page);
     if (editingId === id) {
       setEditingId(null);
       setForm({ name: "", html: "" });
@@ -124,6 +130,7 @@ export default function Templates() {
                 </div>
               ))}
             </div>
+            <Pagination page={page} perPage={PER_PAGE} total={total} onChange={setPage} />
           </div>
 
           <div className="rounded-3xl border border-white/70 bg-white/80 p-6 shadow-lift">
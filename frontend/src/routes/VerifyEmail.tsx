@@ -0,0 +1,52 @@
+import { useEffect, useRef, useState } from "react";
+import { Link, useSearchParams } from "react-router-dom";
+import { api } from "../lib/api";
+
+export default function VerifyEmail() {
+  const [searchParams] = useSearchParams();
+  const token = searchParams.get("token") || "";
+  const [status, setStatus] = useState<"working" | "done" | "failed">(
+    token ? "working" : "failed",
+  );
+  const [message, setMessage] = useState(
+    token ? "Confirming your email..." : "This link has no token.",
+  );
+  // Tokens are single-use, so the request must not repeat when effects run twice.
+  const submitted = useRef(false);
+
+  useEffect(() => {
+    if (!token || submitted.current) {
+      return;
+    }
+    submitted.current = true;
+    void api.verifyEmail({ token }).then((result) => {
+      if (result.ok) {
+        setStatus("done");
+        setMessage("Your email address is verified.");
+      } else {
+        setStatus("failed");
+        setMessage(result.error);
+      }
+    });
+  }, [token]);
+
+  return (
+    <div className="flex min-h-screen items-center justify-center bg-cloud text-ink">
+      <div className="w-full max-w-md rounded-3xl border border-ink/10 bg-white/80 p-10 text-center shadow-lift">
+        <p className="text-xs uppercase tracking-[0.2em] text-haze">Email verification</p>
+        <h1 className="mt-4 font-display text-3xl">
+          {status === "done" ? "All set" : status === "failed" ? "Link not valid" : "One moment"}
+        </h1>
+        <p className="mt-2 text-sm text-slate">{message}</p>
+        {status !== "working" && (
+          <Link
+            to={status === "done" ? "/app" : "/"}
+            className="mt-6 inline-flex rounded-xl bg-ink px-4 py-2 text-sm font-semibold text-white"
+          >
+            {status === "done" ? "Continue" : "Back to login"}
+          </Link>
+        )}
+      </div>
+    </div>
+  );
+}